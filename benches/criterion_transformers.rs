@@ -1,10 +1,16 @@
 use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
 use pulldown_cmark::{CowStr, Event, LinkType, Tag, TagEnd};
 
-use ssg::transformer::{
-    WithTransformer, code_block::CodeHighlightTransformer,
-    footnote::convert_footnotes_to_sidenotes, image::ImageCaptionTransformer,
-    math::MathTransformer, toc::insert_toc_and_heading_ids,
+use ssg::{
+    diagnostics::DocumentSink,
+    transformer::{
+        WithTransformer,
+        code_block::CodeHighlightTransformer,
+        footnote::{SidenoteOptions, convert_footnotes_to_sidenotes},
+        image::ImageCaptionTransformer,
+        math::MathTransformer,
+        toc::{TocOptions, insert_toc_and_heading_ids},
+    },
 };
 
 mod fixtures;
@@ -60,7 +66,7 @@ fn bench_footnotes(c: &mut Criterion) {
 
     group.bench_function("inline_sidenotes", |b| {
         b.iter(|| {
-            let out = convert_footnotes_to_sidenotes(events.clone());
+            let out = convert_footnotes_to_sidenotes(events.clone(), &SidenoteOptions::default());
             black_box(out);
         })
     });
@@ -75,7 +81,11 @@ fn bench_toc(c: &mut Criterion) {
 
     group.bench_function("insert_toc_and_ids", |b| {
         b.iter(|| {
-            let out = insert_toc_and_heading_ids(events.clone());
+            let out = insert_toc_and_heading_ids(
+                events.clone(),
+                &DocumentSink::default(),
+                &TocOptions::default(),
+            );
             black_box(out);
         })
     });
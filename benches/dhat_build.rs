@@ -1,4 +1,4 @@
-use dhat::{DhatAlloc, Profiler};
+use dhat::{Alloc, Profiler};
 
 use ssg::pipeline::build_at;
 
@@ -6,7 +6,7 @@ mod fixtures;
 use fixtures::{SiteOptions, make_site};
 
 #[global_allocator]
-static ALLOC: DhatAlloc = DhatAlloc;
+static ALLOC: Alloc = Alloc;
 
 fn main() {
     let _prof = Profiler::builder().file_name("dhat-build.json").build();
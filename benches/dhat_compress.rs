@@ -1,14 +1,14 @@
 use std::io::Write;
 
 use brotli::CompressorWriter;
-use dhat::{DhatAlloc, Profiler};
+use dhat::{Alloc, Profiler};
 use flate2::{Compression, write::GzEncoder};
 
 mod fixtures;
 use fixtures::rust_snippet;
 
 #[global_allocator]
-static ALLOC: DhatAlloc = DhatAlloc;
+static ALLOC: Alloc = Alloc;
 
 fn main() {
     let _prof = Profiler::builder().file_name("dhat-compress.json").build();
@@ -26,5 +26,5 @@ fn main() {
     let br_out = br.into_inner();
 
     // Keep outputs alive to be counted.
-    dhat::md::black_box((gz_out.len(), br_out.len()));
+    std::hint::black_box((gz_out.len(), br_out.len()));
 }
@@ -1,4 +1,4 @@
-use dhat::{DhatAlloc, Profiler};
+use dhat::{Alloc, Profiler};
 use pulldown_cmark::Event;
 
 use ssg::transformer::{
@@ -10,7 +10,7 @@ mod fixtures;
 use fixtures::{code_block_events, footnote_events, heading_events, math_events, rust_snippet};
 
 #[global_allocator]
-static ALLOC: DhatAlloc = DhatAlloc;
+static ALLOC: Alloc = Alloc;
 
 fn main() {
     let _prof = Profiler::builder()
@@ -40,11 +40,11 @@ fn main() {
         .into_iter()
         .with_transformer::<CodeHighlightTransformer<_>>()
         .with_transformer::<MathTransformer<_>>()
-        .with_transformer::<FootnoteTransformer<_>>()
-        .with_transformer::<TocTransformer<'_, _>>()
+        .with_transformer::<FootnoteTransformer<'_>>()
+        .with_transformer::<TocTransformer<'_>>()
         .with_transformer::<ImageCaptionTransformer<_>>()
         .collect();
 
     // Ensure the transformed events stay alive until after the profile.
-    dhat::md::black_box(out.len());
+    std::hint::black_box(out.len());
 }
@@ -0,0 +1,50 @@
+//! Redirect pages left at a post's old locations, listed in its `aliases:`
+//! frontmatter, so links to a renamed or moved post keep working. Each is a
+//! tiny page that refreshes to the post at once and names it as canonical,
+//! so search engines carry the old address's standing over.
+use std::path::{Component, Path, PathBuf};
+
+use crate::{
+    config::SITE_LANGUAGE,
+    utils::{escape_attr, escape_text},
+};
+
+/// Where the redirect page for `alias`, a path relative to the site root such
+/// as `old/url.html` or `2019/post/`, is written inside the output directory.
+/// `None` if the alias would land outside it.
+pub fn alias_out_path(alias: &str) -> Option<PathBuf> {
+    let alias = alias.trim().trim_start_matches('/');
+    let mut path = PathBuf::from(alias);
+    if alias.is_empty()
+        || path
+            .components()
+            .any(|part| !matches!(part, Component::Normal(_)))
+    {
+        return None;
+    }
+    if alias.ends_with('/') || Path::new(alias).extension().is_none() {
+        path.push("index.html");
+    }
+    Some(path)
+}
+
+/// A page sending visitors to `url`, the post titled `title`.
+pub fn redirect_html(url: &str, title: &str) -> String {
+    let url_attr = escape_attr(url);
+    let title = escape_text(title);
+    format!(
+        r#"<!doctype html>
+<html lang="{SITE_LANGUAGE}">
+<meta charset="utf-8">
+<title>{title}</title>
+<link rel="canonical" href="{url_attr}">
+<meta name="robots" content="noindex">
+<meta http-equiv="refresh" content="0; url={url_attr}">
+<p>This page has moved to <a href="{url_attr}">{title}</a>.</p>
+</html>
+"#
+    )
+}
+
+#[cfg(test)]
+mod tests;
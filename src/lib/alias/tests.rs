@@ -0,0 +1,33 @@
+use std::path::PathBuf;
+
+use crate::{
+    alias::{alias_out_path, redirect_html},
+    config::SITE_LANGUAGE,
+};
+
+#[test]
+fn aliases_map_to_pages_inside_the_output() {
+    assert_eq!(
+        alias_out_path("old/url.html"),
+        Some(PathBuf::from("old/url.html"))
+    );
+    assert_eq!(
+        alias_out_path("/2019/post/"),
+        Some(PathBuf::from("2019/post/index.html"))
+    );
+    assert_eq!(
+        alias_out_path("2019/post"),
+        Some(PathBuf::from("2019/post/index.html"))
+    );
+    assert_eq!(alias_out_path("../outside.html"), None);
+    assert_eq!(alias_out_path(""), None);
+}
+
+#[test]
+fn redirects_refresh_to_the_canonical_url() {
+    let html = redirect_html("https://example.com/posts/new.html", "Fish & Chips");
+    assert!(html.contains(r#"<link rel="canonical" href="https://example.com/posts/new.html">"#));
+    assert!(html.contains(r#"content="0; url=https://example.com/posts/new.html""#));
+    assert!(html.contains("<title>Fish &amp; Chips</title>"));
+    assert!(html.contains(&format!(r#"<html lang="{SITE_LANGUAGE}">"#)));
+}
@@ -0,0 +1,121 @@
+//! Registry of the anchor ids each page defines, so `page#fragment` links
+//! (including resolved wikilinks) can be checked across the whole site once
+//! every page has been rendered.
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use lol_html::{HtmlRewriter, Settings, element};
+use pulldown_cmark::{Event, Tag};
+
+use crate::{backlinks::internal_href, transformer::raw_html::raw_hrefs, types::Href};
+
+/// Anchor ids defined by every rendered page, keyed by page href.
+#[derive(Debug, Default)]
+pub struct AnchorRegistry {
+    pages: HashMap<Href, HashSet<String>>,
+}
+
+impl AnchorRegistry {
+    /// Record the ids defined on `page`, adding to any already known.
+    pub fn insert(&mut self, page: &Href, ids: impl IntoIterator<Item = String>) {
+        self.pages.entry(page.clone()).or_default().extend(ids);
+    }
+
+    /// Anchor ids defined on `page`, or `None` for pages that were not built.
+    pub fn ids(&self, page: &Href) -> Option<&HashSet<String>> {
+        self.pages.get(page)
+    }
+
+    /// Whether `page#fragment` points at something. Links to pages the
+    /// registry knows nothing about (assets, listings) are given the benefit
+    /// of the doubt.
+    pub fn resolves(&self, page: &Href, fragment: &str) -> bool {
+        self.ids(page).is_none_or(|ids| ids.contains(fragment))
+    }
+
+    /// Every link in `links` whose fragment is missing from its target page.
+    pub fn broken<'a>(&self, links: &'a [FragmentLink]) -> Vec<&'a FragmentLink> {
+        links
+            .iter()
+            .filter(|link| !self.resolves(&link.target, &link.fragment))
+            .collect()
+    }
+}
+
+/// A link into a specific part of a page.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FragmentLink {
+    pub source: Href,
+    pub target: Href,
+    pub fragment: String,
+}
+
+impl fmt::Display for FragmentLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} links to missing anchor {}#{}",
+            self.source.as_str(),
+            self.target.as_str(),
+            self.fragment
+        )
+    }
+}
+
+/// Links with a fragment in a document, resolved against the page they
+/// appear on, including raw HTML `<a href>` tags. Bare `#fragment` links
+/// target the page itself.
+pub fn fragment_links(events: &[Event<'_>], page_href: &Href) -> Vec<FragmentLink> {
+    let raw = raw_hrefs(events);
+    events
+        .iter()
+        .filter_map(|e| match e {
+            Event::Start(Tag::Link { dest_url, .. }) => Some(dest_url.as_ref()),
+            _ => None,
+        })
+        .chain(raw.iter().map(String::as_str))
+        .filter_map(|dest| {
+            let (path, fragment) = dest.split_once('#')?;
+            if fragment.is_empty() {
+                return None;
+            }
+            let target = match path {
+                "" => page_href.clone(),
+                _ => internal_href(path, page_href)?,
+            };
+            Some(FragmentLink {
+                source: page_href.clone(),
+                target,
+                fragment: fragment.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Every `id` attribute in a fragment of rendered HTML.
+pub fn collect_ids(html: &str) -> HashSet<String> {
+    let ids = RefCell::new(HashSet::new());
+    let mut rewriter = HtmlRewriter::new(
+        Settings {
+            element_content_handlers: vec![element!("[id]", |el| {
+                if let Some(id) = el.get_attribute("id") {
+                    ids.borrow_mut().insert(id);
+                }
+                Ok(())
+            })],
+            ..Settings::new()
+        },
+        |_: &[u8]| {},
+    );
+    // Only the handlers matter; malformed markup just yields fewer ids.
+    let _ = rewriter
+        .write(html.as_bytes())
+        .and_then(|()| rewriter.end());
+    ids.into_inner()
+}
+
+#[cfg(test)]
+mod tests;
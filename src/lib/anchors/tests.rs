@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+
+use pulldown_cmark::{Options, Parser};
+
+use crate::{
+    anchors::{AnchorRegistry, FragmentLink, collect_ids, fragment_links},
+    types::{Href, RelPath},
+};
+
+fn href(s: &str) -> Href {
+    Href::from_rel(&RelPath::new(PathBuf::from(s)).unwrap())
+}
+
+#[test]
+fn ids_are_collected_from_rendered_html() {
+    let html = r##"<h2 id="intro">Intro</h2><p>x<sup id="fnref-1"><a href="#fn-1">1</a></sup></p>
+<ol><li id="fn-1">note</li></ol>"##;
+
+    let ids = collect_ids(html);
+
+    assert_eq!(ids.len(), 3);
+    assert!(ids.contains("intro") && ids.contains("fnref-1") && ids.contains("fn-1"));
+}
+
+#[test]
+fn fragment_links_resolve_against_the_page() {
+    let md =
+        "[a](#intro) [b](other.md#setup) [c](../posts/x.html#y) [d](https://e.org/#z) [e](x.html)";
+    let events: Vec<_> = Parser::new_ext(md, Options::empty()).collect();
+    let page = href("posts/a.html");
+
+    let links = fragment_links(&events, &page);
+
+    let targets: Vec<_> = links
+        .iter()
+        .map(|l| format!("{}#{}", l.target.as_str(), l.fragment))
+        .collect();
+    assert_eq!(
+        targets,
+        [
+            "posts/a.html#intro",
+            "posts/other.html#setup",
+            "posts/x.html#y"
+        ]
+    );
+}
+
+#[test]
+fn only_missing_anchors_on_known_pages_are_broken() {
+    let mut registry = AnchorRegistry::default();
+    registry.insert(&href("posts/a.html"), ["intro".to_string()]);
+    let link = |target: &str, fragment: &str| FragmentLink {
+        source: href("posts/b.html"),
+        target: href(target),
+        fragment: fragment.to_string(),
+    };
+    let links = [
+        link("posts/a.html", "intro"),
+        link("posts/a.html", "outro"),
+        link("tags/rust.html", "top"),
+    ];
+
+    let broken = registry.broken(&links);
+
+    assert_eq!(broken, [&links[1]]);
+    assert_eq!(
+        broken[0].to_string(),
+        "posts/b.html links to missing anchor posts/a.html#outro"
+    );
+}
@@ -0,0 +1,146 @@
+//! Static JSON API for external front-ends: the full article list split into
+//! fixed-size pages under `api/articles/`, plus one file per tag under
+//! `api/tags/`. Pages are filled newest first, so page 1 always holds the most
+//! recent posts and `next` walks back through the archive.
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::{article::Article, types::Tag};
+
+/// Directory (relative to the output root) holding the API files.
+pub const API_DIR: &str = "api";
+
+#[derive(Serialize, Debug)]
+struct ApiArticle<'a> {
+    title: &'a str,
+    /// Absolute URL of the article page.
+    url: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<&'a str>,
+    tags: Vec<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    link: Option<&'a str>,
+    /// Custom frontmatter fields.
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    extra: &'a Map<String, Value>,
+}
+
+#[derive(Serialize, Debug)]
+struct ApiPage<'a> {
+    page: usize,
+    total_pages: usize,
+    total_items: usize,
+    /// Absolute URLs of the neighbouring pages, if any.
+    prev: Option<String>,
+    next: Option<String>,
+    items: Vec<ApiArticle<'a>>,
+}
+
+#[derive(Serialize, Debug)]
+struct ApiTag<'a> {
+    tag: &'a str,
+    total_items: usize,
+    items: Vec<ApiArticle<'a>>,
+}
+
+impl<'a> ApiArticle<'a> {
+    fn new(article: &'a Article, base_url: &str) -> Self {
+        Self {
+            title: &article.title,
+            url: format!("{base_url}/{}", article.href.as_str()),
+            kind: article.kind.as_str(),
+            date: article.ctime.as_ref().map(|d| d.as_str()),
+            updated: article.updated.as_ref().map(|d| d.as_str()),
+            summary: article.summary.as_deref(),
+            tags: article.tags.iter().map(Tag::as_str).collect(),
+            link: article.link.as_deref(),
+            extra: &article.extra,
+        }
+    }
+}
+
+/// Path of an article page, relative to the output root.
+pub fn api_page_path(page: usize) -> PathBuf {
+    Path::new(API_DIR)
+        .join("articles")
+        .join(format!("page-{page}.json"))
+}
+
+/// Path of a tag's article list, relative to the output root.
+pub fn api_tag_path(tag: &Tag) -> PathBuf {
+    Path::new(API_DIR).join("tags").join(format!("{tag}.json"))
+}
+
+/// Serialise `articles` (already sorted newest first) into pages of
+/// `page_size`. An empty site still gets an empty first page so clients have
+/// a fixed entry point.
+pub fn build_article_pages(
+    articles: &[Article],
+    page_size: usize,
+    base_url: &str,
+) -> color_eyre::Result<Vec<(PathBuf, String)>> {
+    let base = base_url.trim_end_matches('/');
+    let page_size = page_size.max(1);
+    let total_pages = articles.len().div_ceil(page_size).max(1);
+    let page_url = |n: usize| format!("{base}/{}", api_page_path(n).display());
+
+    (1..=total_pages)
+        .map(|n| {
+            let items = articles
+                .iter()
+                .skip((n - 1) * page_size)
+                .take(page_size)
+                .map(|a| ApiArticle::new(a, base))
+                .collect();
+            let page = ApiPage {
+                page: n,
+                total_pages,
+                total_items: articles.len(),
+                prev: (n > 1).then(|| page_url(n - 1)),
+                next: (n < total_pages).then(|| page_url(n + 1)),
+                items,
+            };
+            Ok((api_page_path(n), serde_json::to_string(&page)?))
+        })
+        .collect()
+}
+
+/// Serialise one file per tag listing every article carrying it.
+pub fn build_tag_files(
+    articles: &[Article],
+    base_url: &str,
+) -> color_eyre::Result<Vec<(PathBuf, String)>> {
+    let base = base_url.trim_end_matches('/');
+    let mut by_tag: BTreeMap<&Tag, Vec<&Article>> = BTreeMap::new();
+    for a in articles {
+        for t in &a.tags {
+            by_tag.entry(t).or_default().push(a);
+        }
+    }
+
+    by_tag
+        .into_iter()
+        .map(|(tag, tagged)| {
+            let file = ApiTag {
+                tag: tag.as_str(),
+                total_items: tagged.len(),
+                items: tagged.iter().map(|a| ApiArticle::new(a, base)).collect(),
+            };
+            Ok((api_tag_path(tag), serde_json::to_string(&file)?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests;
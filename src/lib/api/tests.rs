@@ -0,0 +1,77 @@
+use std::path::Path;
+
+use crate::{
+    api::{build_article_pages, build_tag_files},
+    article::Article,
+};
+
+fn article(title: &str, tags: &[&str]) -> Article {
+    Article::test(title).dated("2024-01-01").tagged(tags)
+}
+
+#[test]
+fn article_pages_are_linked_in_order() {
+    let articles: Vec<Article> = (0..5).map(|i| article(&format!("p{i}"), &[])).collect();
+
+    let pages = build_article_pages(&articles, 2, "https://example.com/").unwrap();
+
+    assert_eq!(pages.len(), 3);
+    assert_eq!(pages[0].0, Path::new("api/articles/page-1.json"));
+    let first: serde_json::Value = serde_json::from_str(&pages[0].1).unwrap();
+    assert_eq!(first["total_pages"], 3);
+    assert_eq!(first["total_items"], 5);
+    assert_eq!(first["prev"], serde_json::Value::Null);
+    assert_eq!(
+        first["next"],
+        "https://example.com/api/articles/page-2.json"
+    );
+    assert_eq!(
+        first["items"][0]["url"],
+        "https://example.com/posts/p0.html"
+    );
+    assert_eq!(first["items"][0]["type"], "article");
+
+    let last: serde_json::Value = serde_json::from_str(&pages[2].1).unwrap();
+    assert_eq!(last["items"].as_array().unwrap().len(), 1);
+    assert_eq!(last["items"][0]["title"], "p4");
+    assert_eq!(last["next"], serde_json::Value::Null);
+}
+
+#[test]
+fn custom_frontmatter_fields_are_passed_through() {
+    let mut rated = article("rated", &[]);
+    rated
+        .extra
+        .insert("rating".to_string(), serde_json::json!(4));
+    let articles = vec![rated, article("plain", &[])];
+
+    let pages = build_article_pages(&articles, 10, "https://example.com").unwrap();
+
+    let page: serde_json::Value = serde_json::from_str(&pages[0].1).unwrap();
+    assert_eq!(page["items"][0]["extra"]["rating"], 4);
+    assert!(page["items"][1].get("extra").is_none());
+}
+
+#[test]
+fn empty_site_has_one_empty_page() {
+    let pages = build_article_pages(&[], 10, "https://example.com").unwrap();
+
+    assert_eq!(pages.len(), 1);
+    let page: serde_json::Value = serde_json::from_str(&pages[0].1).unwrap();
+    assert_eq!(page["total_pages"], 1);
+    assert!(page["items"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn tag_files_list_tagged_articles() {
+    let articles = vec![article("a", &["rust"]), article("b", &["rust", "web"])];
+
+    let files = build_tag_files(&articles, "https://example.com").unwrap();
+
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[0].0, Path::new("api/tags/rust.json"));
+    let rust: serde_json::Value = serde_json::from_str(&files[0].1).unwrap();
+    assert_eq!(rust["tag"], "rust");
+    assert_eq!(rust["total_items"], 2);
+    assert_eq!(rust["items"][1]["tags"][1], "web");
+}
@@ -0,0 +1,97 @@
+//! Date archive: a listing of each year's posts under `ARCHIVE_DIR/<year>/`
+//! and of each month's under `ARCHIVE_DIR/<year>/<month>/`. Undated posts
+//! are in neither.
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    article::Article,
+    config::ARCHIVE_DIR,
+    utils::{escape_attr, escape_text},
+};
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// One year of the archive.
+#[derive(Debug, Default)]
+pub struct ArchiveYear {
+    /// The year's posts, in the order given.
+    pub articles: Vec<Article>,
+    /// The same posts by month, 1 to 12.
+    pub months: BTreeMap<u8, Vec<Article>>,
+}
+
+/// The dated posts among `articles` by year, keeping their order.
+pub fn archive_years(articles: &[Article]) -> BTreeMap<i32, ArchiveYear> {
+    let mut years: BTreeMap<i32, ArchiveYear> = BTreeMap::new();
+    for a in articles {
+        let Some(ctime) = &a.ctime else {
+            continue;
+        };
+        let year = years.entry(ctime.year()).or_default();
+        year.articles.push(a.clone());
+        year.months
+            .entry(ctime.month())
+            .or_default()
+            .push(a.clone());
+    }
+    years
+}
+
+/// Output location of a year's archive page, or of one of its months'.
+pub fn archive_page_path(year: i32, month: Option<u8>) -> PathBuf {
+    let dir = Path::new(ARCHIVE_DIR).join(year.to_string());
+    match month {
+        Some(month) => dir.join(format!("{month:02}")).join("index.html"),
+        None => dir.join("index.html"),
+    }
+}
+
+/// "March 2024".
+pub fn month_label(year: i32, month: u8) -> String {
+    let name = MONTH_NAMES
+        .get(usize::from(month).wrapping_sub(1))
+        .copied()
+        .unwrap_or_default();
+    format!("{name} {year}")
+}
+
+/// Links to the months of a year's archive, for its page `href_prefix` away
+/// from the root.
+pub fn render_month_nav(year: i32, archive: &ArchiveYear, href_prefix: &str) -> String {
+    let links: Vec<String> = archive
+        .months
+        .iter()
+        .map(|(&month, articles)| {
+            let href = archive_page_path(year, Some(month));
+            format!(
+                r#"<a href="{}">{}</a> ({})"#,
+                escape_attr(&format!("{href_prefix}{}", href.display())),
+                escape_text(&month_label(year, month)),
+                articles.len()
+            )
+        })
+        .collect();
+    format!(
+        "<nav class=\"archive-months\" aria-label=\"Months\">{}</nav>\n",
+        links.join(r#"<span class="meta-sep">·</span>"#)
+    )
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+
+use crate::{
+    archive::{archive_page_path, archive_years, month_label, render_month_nav},
+    article::Article,
+};
+
+#[test]
+fn dated_posts_are_filed_by_year_and_month() {
+    let articles = vec![
+        Article::test("c").dated("2024-03-20"),
+        Article::test("b").dated("2024-03-02"),
+        Article::test("a").dated("2024-01-15"),
+        Article::test("old").dated("2023-12-31"),
+        Article::test("undated"),
+    ];
+
+    let years = archive_years(&articles);
+    let titles =
+        |listed: &[Article]| -> Vec<String> { listed.iter().map(|a| a.title.clone()).collect() };
+
+    assert_eq!(years.keys().copied().collect::<Vec<_>>(), [2023, 2024]);
+    assert_eq!(titles(&years[&2024].articles), ["c", "b", "a"]);
+    assert_eq!(titles(&years[&2024].months[&3]), ["c", "b"]);
+    assert_eq!(titles(&years[&2024].months[&1]), ["a"]);
+    assert_eq!(titles(&years[&2023].months[&12]), ["old"]);
+}
+
+#[test]
+fn pages_and_labels_name_the_period() {
+    assert_eq!(
+        archive_page_path(2024, None),
+        PathBuf::from("archive/2024/index.html")
+    );
+    assert_eq!(
+        archive_page_path(2024, Some(3)),
+        PathBuf::from("archive/2024/03/index.html")
+    );
+    assert_eq!(month_label(2024, 3), "March 2024");
+
+    let years = archive_years(&[Article::test("a").dated("2024-03-02")]);
+    let nav = render_month_nav(2024, &years[&2024], "../../");
+    assert!(
+        nav.contains(r#"<a href="../../archive/2024/03/index.html">March 2024</a> (1)"#),
+        "{nav}"
+    );
+}
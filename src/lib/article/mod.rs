@@ -1,6 +1,14 @@
+use std::sync::Arc;
+
+use serde_json::{Map, Value};
+
 use crate::{
-    types::{Href, IsoDate, Tag},
-    utils::{escape_attr, escape_text},
+    config::{LISTING_JUMP_NAV_THRESHOLD, LISTING_THUMBNAILS, ListingThumbnails},
+    series::SeriesEntry,
+    templates::{ListingContext, Templates},
+    thumbnail::thumbnail_img,
+    types::{Href, IsoDate, PostKind, Tag},
+    utils::{escape_attr, escape_text, slugify, tag_href},
 };
 
 #[derive(Clone, Debug)]
@@ -8,39 +16,241 @@ pub struct Article {
     pub title: String,
     pub ctime: Option<IsoDate>,
     pub updated: Option<IsoDate>,
+    /// Last day the post is current (see `EXPIRED_POSTS`).
+    pub expires: Option<IsoDate>,
     pub summary: Option<String>,
     /// Raw HTML for the article body (including header metadata) used for full-text feeds.
-    pub content_html: String,
+    /// Only kept for articles that feeds or the notes stream will show; see
+    /// [`feed_content_hrefs`](crate::feed::feed_content_hrefs).
+    pub content_html: Option<Arc<str>>,
+    /// Plain-text body used for the client-side search index.
+    pub text: String,
     pub href: Href,
     pub tags: Vec<Tag>,
+    /// Keys of the post's authors (see [`crate::author`]).
+    pub authors: Vec<String>,
+    pub series: Option<SeriesEntry>,
+    pub kind: PostKind,
+    /// Primary image of a photo post.
+    pub photo: Option<String>,
+    /// Square thumbnail of the post's primary image (its photo or `image`),
+    /// relative to the site root.
+    pub thumbnail: Option<String>,
+    /// Outbound target of a link post.
+    pub link: Option<String>,
+    /// Custom frontmatter fields (see [`Header::extra`](crate::header::Header::extra)).
+    pub extra: Map<String, Value>,
+}
+
+impl Article {
+    /// Whether the post's `expires:` date is before `today`.
+    pub fn is_expired(&self, today: &IsoDate) -> bool {
+        self.expires.as_ref().is_some_and(|expires| expires < today)
+    }
+}
+
+/// Articles for tests: an undated, untagged essay at `posts/<title>.html`,
+/// with setters for whatever a test cares about.
+#[cfg(test)]
+impl Article {
+    pub fn test(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            ctime: None,
+            updated: None,
+            expires: None,
+            summary: None,
+            content_html: None,
+            text: String::new(),
+            href: Href::from_rel(
+                &crate::types::RelPath::new(format!("posts/{title}.html").into())
+                    .expect("test titles make relative paths"),
+            ),
+            tags: Vec::new(),
+            authors: Vec::new(),
+            series: None,
+            kind: PostKind::Article,
+            photo: None,
+            thumbnail: None,
+            link: None,
+            extra: Map::new(),
+        }
+    }
+
+    /// Published at `href` instead.
+    pub fn at(mut self, href: &str) -> Self {
+        self.href = Href::from_rel(
+            &crate::types::RelPath::new(href.into()).expect("test hrefs are relative"),
+        );
+        self
+    }
+
+    pub fn dated(mut self, date: &str) -> Self {
+        self.ctime = Some(IsoDate::parse(date).expect("test dates are valid"));
+        self
+    }
+
+    pub fn expiring(mut self, date: &str) -> Self {
+        self.expires = Some(IsoDate::parse(date).expect("test dates are valid"));
+        self
+    }
+
+    pub fn tagged(mut self, tags: &[&str]) -> Self {
+        self.tags = tags
+            .iter()
+            .map(|t| Tag::parse(t).expect("test tags are valid"))
+            .collect();
+        self
+    }
+
+    pub fn of_kind(mut self, kind: PostKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn in_series(mut self, name: &str, part: Option<u32>) -> Self {
+        self.series = Some(SeriesEntry {
+            name: name.to_string(),
+            part,
+        });
+        self
+    }
+
+    pub fn summarised(mut self, summary: &str) -> Self {
+        self.summary = Some(summary.to_string());
+        self
+    }
+
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.text = text.to_string();
+        self
+    }
+}
+
+/// Heading of the group of posts without a date, listed after the years.
+const UNDATED_LABEL: &str = "Undated";
+
+/// Number of words of a note's text used as its stand-in title.
+const NOTE_TITLE_WORDS: usize = 8;
+
+/// Stand-in title for an untitled note, used in feeds, search, and `<title>`.
+pub fn note_title(text: &str) -> String {
+    let mut words = text.split_whitespace();
+    let mut title = words
+        .by_ref()
+        .take(NOTE_TITLE_WORDS)
+        .collect::<Vec<_>>()
+        .join(" ");
+    if words.next().is_some() {
+        title.push('…');
+    }
+    title
+}
+
+/// Anchor id of a jump-navigation group heading (a year or an initial).
+pub fn jump_anchor(label: &str) -> String {
+    format!("group-{}", slugify(label))
+}
+
+/// Row of links to the group headings of a long listing, in the given order.
+/// Each heading must carry the id from `jump_anchor`.
+pub fn render_jump_nav(labels: &[String]) -> String {
+    let links: Vec<String> = labels
+        .iter()
+        .map(|label| {
+            format!(
+                r##"<a href="#{}">{}</a>"##,
+                escape_attr(&jump_anchor(label)),
+                escape_text(label)
+            )
+        })
+        .collect();
+    format!(
+        "<nav class=\"jump-nav\" aria-label=\"Jump to\">{}</nav>\n",
+        links.join(" ")
+    )
 }
 
 pub fn render_listing_page(
+    templates: &Templates,
     page_title: &str,
     heading: &str,
+    intro: &str,
     articles: &[Article],
     head_includes: &str,
     href_prefix: &str,
+) -> color_eyre::Result<String> {
+    let body = render_listing_body(intro, articles, href_prefix, |_| None);
+    templates.listing(&ListingContext {
+        title: page_title,
+        heading,
+        articles,
+        href_prefix,
+        head: head_includes,
+        body: &body,
+    })
+}
+
+/// `intro`, then `articles` grouped under year headings. `group_href` gives
+/// the page a heading links to, if any, from its label.
+pub fn render_listing_body(
+    intro: &str,
+    articles: &[Article],
+    href_prefix: &str,
+    group_href: impl Fn(&str) -> Option<String>,
 ) -> String {
-    // Group by year purely for labelling, assuming "YYYY-MM-DD".
-    let mut body = String::new();
+    // Group by year purely for labelling, assuming "YYYY-MM-DD". Undated
+    // posts sort last and get a group of their own if any posts are dated.
+    let mut body = String::from(intro);
+    let any_dated = articles.iter().any(|a| a.ctime.is_some());
+    let group_of = |a: &Article| match &a.ctime {
+        Some(date) => Some(date.year().to_string()),
+        None if any_dated => Some(UNDATED_LABEL.to_owned()),
+        None => None,
+    };
+
+    if articles.len() > LISTING_JUMP_NAV_THRESHOLD {
+        let mut groups: Vec<String> = articles.iter().filter_map(group_of).collect();
+        groups.dedup();
+        if groups.len() > 1 {
+            body.push_str(&render_jump_nav(&groups));
+        }
+    }
 
-    let mut current_year: Option<i32> = None;
+    let mut current_group: Option<String> = None;
 
     for a in articles {
-        let year = a.ctime.as_ref().map(|d| d.year());
-        if year != current_year {
-            if let Some(y) = year {
-                body.push_str("<h2>");
-                body.push_str(&escape_text(&y.to_string()));
+        let group = group_of(a);
+        if group != current_group {
+            if let Some(label) = &group {
+                body.push_str(r#"<h2 id=""#);
+                body.push_str(&escape_attr(&jump_anchor(label)));
+                body.push_str(r#"">"#);
+                match group_href(label) {
+                    Some(href) => {
+                        body.push_str(r#"<a href=""#);
+                        body.push_str(&escape_attr(&href));
+                        body.push_str(r#"">"#);
+                        body.push_str(&escape_text(label));
+                        body.push_str("</a>");
+                    }
+                    None => body.push_str(&escape_text(label)),
+                }
                 body.push_str("</h2>\n");
-                current_year = year;
-            } else {
-                current_year = None;
             }
+            current_group = group;
         }
 
+        let thumb = a
+            .thumbnail
+            .as_deref()
+            .map(|thumb| thumbnail_img(href_prefix, thumb));
         body.push_str(r#"<p class="meta">"#);
+        if let Some(thumb) = &thumb
+            && LISTING_THUMBNAILS == ListingThumbnails::Leading
+        {
+            body.push_str(thumb);
+        }
         if let Some(ctime) = &a.ctime {
             let ctime_str = ctime.as_str();
             body.push_str(r#" <time datetime=""#);
@@ -53,14 +263,106 @@ pub fn render_listing_page(
 
         let full_href = format!("{href_prefix}{}", a.href.as_str());
         body.push_str(r#"<a href=""#);
-        body.push_str(&escape_attr(&full_href));
+        body.push_str(&escape_attr(a.link.as_deref().unwrap_or(&full_href)));
         body.push_str(r#"">"#);
         body.push_str(&escape_text(&a.title));
         body.push_str("</a>");
+        // Link posts point at their target; the commentary gets a permalink.
+        if a.link.is_some() {
+            body.push_str(r#" <a class="permalink" href=""#);
+            body.push_str(&escape_attr(&full_href));
+            body.push_str(r#"" title="Permalink">∞</a>"#);
+        }
+        if let Some(thumb) = &thumb
+            && LISTING_THUMBNAILS == ListingThumbnails::Trailing
+        {
+            body.push_str(thumb);
+        }
         body.push_str("</p>\n");
     }
 
-    crate::templates::listing_page(page_title, heading, &body, head_includes, href_prefix)
+    body
+}
+
+/// Body of the tags index, `href_prefix` away from the root: each tag of
+/// `counts` linked to its page, with its number of posts. As a `cloud`, tags
+/// are sized by that number instead.
+pub fn render_tag_index(counts: &[(Tag, usize)], cloud: bool, href_prefix: &str) -> String {
+    let most = counts.iter().map(|(_, n)| *n).max().unwrap_or(1) as f64;
+    let class = if cloud {
+        "tag-index tag-cloud"
+    } else {
+        "tag-index"
+    };
+    let mut body = format!("<ul class=\"{class}\">\n");
+    for (tag, count) in counts {
+        let link = format!(
+            r#"<a class="tag" href="{}">{}</a>"#,
+            escape_attr(&format!("{href_prefix}{}", tag_href(tag))),
+            escape_text(tag.as_str())
+        );
+        if cloud {
+            // Logarithmic, so one busy tag does not shrink the rest to nothing.
+            let weight = (*count as f64).ln_1p() / most.ln_1p();
+            let posts = if *count == 1 { "post" } else { "posts" };
+            body.push_str(&format!(
+                r#"<li style="font-size: {:.2}em" title="{count} {posts}">{link}</li>"#,
+                0.8 + 1.2 * weight
+            ));
+        } else {
+            body.push_str(&format!(
+                r#"<li>{link} <span class="count">{count}</span></li>"#
+            ));
+        }
+        body.push('\n');
+    }
+    body.push_str("</ul>\n");
+    body
+}
+
+/// Render the notes stream: each note in full, newest first, followed by a
+/// dated permalink instead of a title.
+pub fn render_note_stream(
+    templates: &Templates,
+    page_title: &str,
+    heading: &str,
+    intro: &str,
+    notes: &[Article],
+    head_includes: &str,
+    href_prefix: &str,
+) -> color_eyre::Result<String> {
+    let mut body = String::from(intro);
+
+    for n in notes {
+        let full_href = format!("{href_prefix}{}", n.href.as_str());
+        body.push_str(r#"<article class="note">"#);
+        body.push('\n');
+        body.push_str(n.content_html.as_deref().unwrap_or_default());
+        body.push_str(r#"<p class="meta"><a href=""#);
+        body.push_str(&escape_attr(&full_href));
+        body.push_str(r#"">"#);
+        match &n.ctime {
+            Some(ctime) => {
+                let ctime_str = ctime.as_str();
+                body.push_str(r#"<time datetime=""#);
+                body.push_str(&escape_attr(&ctime_str));
+                body.push_str(r#"">"#);
+                body.push_str(&escape_text(&ctime_str));
+                body.push_str("</time>");
+            }
+            None => body.push_str("Permalink"),
+        }
+        body.push_str("</a></p>\n</article>\n");
+    }
+
+    templates.listing(&ListingContext {
+        title: page_title,
+        heading,
+        articles: notes,
+        href_prefix,
+        head: head_includes,
+        body: &body,
+    })
 }
 
 #[cfg(test)]
@@ -1,13 +1,13 @@
-use std::path::PathBuf;
-
 use proptest::{
     prelude::*,
     test_runner::{Config, TestRunner},
 };
 
 use crate::{
-    article::Article,
-    types::{Href, IsoDate, RelPath},
+    article::{Article, render_tag_index},
+    config::{LISTING_JUMP_NAV_THRESHOLD, LISTING_THUMBNAILS, ListingThumbnails, site_meta},
+    templates::Templates,
+    types::{IsoDate, Tag},
 };
 
 #[test]
@@ -25,20 +25,16 @@ fn listing_groups_by_year() {
                 for (title, year) in items.iter() {
                     let date = IsoDate::parse(&format!("{year:04}-01-01")).unwrap();
                     articles.push(Article {
-                        title: title.clone(),
                         ctime: Some(date),
-                        updated: None,
-                        summary: None,
-                        content_html: String::new(),
-                        href: Href::from_rel(
-                            &RelPath::new(PathBuf::from(format!("{title}.html"))).unwrap(),
-                        ),
-                        tags: vec![],
+                        ..Article::test(title).at(&format!("{title}.html"))
                     });
                 }
                 articles.sort_by(|a, b| b.ctime.cmp(&a.ctime));
-                let body =
-                    crate::article::render_listing_page("Page", "Heading", &articles, "", "");
+                let templates = Templates::builtin(&site_meta());
+                let body = crate::article::render_listing_page(
+                    &templates, "Page", "Heading", "", &articles, "", "",
+                )
+                .unwrap();
                 for a in &articles {
                     let year_str = a.ctime.as_ref().unwrap().year().to_string();
                     prop_assert!(body.contains(&year_str));
@@ -49,3 +45,112 @@ fn listing_groups_by_year() {
         )
         .unwrap();
 }
+
+fn dated(title: &str, date: &str) -> Article {
+    Article {
+        ctime: IsoDate::parse(date),
+        ..Article::test(title).at(&format!("{title}.html"))
+    }
+}
+
+#[test]
+fn long_listings_get_year_jump_nav() {
+    let templates = Templates::builtin(&site_meta());
+    let mut articles: Vec<Article> = (0..=LISTING_JUMP_NAV_THRESHOLD)
+        .map(|i| {
+            let year = if i % 2 == 0 { 2024 } else { 2023 };
+            dated(&format!("post{i}"), &format!("{year}-01-01"))
+        })
+        .collect();
+    articles.sort_by(|a, b| b.ctime.cmp(&a.ctime));
+
+    let body =
+        crate::article::render_listing_page(&templates, "Page", "Heading", "", &articles, "", "")
+            .unwrap();
+
+    assert!(body.contains(r#"<nav class="jump-nav""#));
+    assert!(body.contains(r##"<a href="#group-2024">2024</a> <a href="#group-2023">2023</a>"##));
+    assert!(body.contains(r#"<h2 id="group-2023">2023</h2>"#));
+}
+
+#[test]
+fn undated_posts_are_grouped_last() {
+    let templates = Templates::builtin(&site_meta());
+    let articles = vec![dated("new", "2024-01-01"), dated("loose", "")];
+
+    let body =
+        crate::article::render_listing_page(&templates, "Page", "Heading", "", &articles, "", "")
+            .unwrap();
+
+    let year = body.find(r#"<h2 id="group-2024">2024</h2>"#).unwrap();
+    let undated = body.find(r#"<h2 id="group-undated">Undated</h2>"#).unwrap();
+    let loose = body.find(">loose</a>").unwrap();
+    assert!(year < undated && undated < loose);
+
+    let only_undated = crate::article::render_listing_page(
+        &templates,
+        "Page",
+        "Heading",
+        "",
+        &[dated("loose", "")],
+        "",
+        "",
+    )
+    .unwrap();
+    assert!(!only_undated.contains("Undated"));
+}
+
+#[test]
+fn short_listings_have_no_jump_nav() {
+    let templates = Templates::builtin(&site_meta());
+    let articles = vec![dated("new", "2024-01-01"), dated("old", "2020-01-01")];
+
+    let body =
+        crate::article::render_listing_page(&templates, "Page", "Heading", "", &articles, "", "")
+            .unwrap();
+
+    assert!(!body.contains("jump-nav"));
+    assert!(body.contains(r#"<h2 id="group-2020">2020</h2>"#));
+}
+
+#[test]
+fn listings_show_thumbnails_per_config() {
+    let templates = Templates::builtin(&site_meta());
+    let mut with_thumb = dated("pictured", "2024-01-01");
+    with_thumb.thumbnail = Some("thumbs/abc-160.jpg".to_string());
+    let articles = vec![with_thumb, dated("plain", "2023-01-01")];
+
+    let body = crate::article::render_listing_page(
+        &templates, "Page", "Heading", "", &articles, "", "../",
+    )
+    .unwrap();
+
+    let shown = body.contains(r#"<img class="thumb" src="../thumbs/abc-160.jpg""#);
+    assert_eq!(shown, LISTING_THUMBNAILS != ListingThumbnails::Off);
+    assert_eq!(body.matches(r#"class="thumb""#).count(), usize::from(shown));
+}
+
+#[test]
+fn tag_index_lists_counts_or_sizes_a_cloud() {
+    let counts = [
+        (Tag::parse("rust").unwrap(), 9),
+        (Tag::parse("life").unwrap(), 1),
+    ];
+
+    let list = render_tag_index(&counts, false, "../");
+    assert!(
+        list.contains(r#"<li><a class="tag" href="../tags/rust.html">rust</a> <span class="count">9</span></li>"#),
+        "{list}"
+    );
+
+    let cloud = render_tag_index(&counts, true, "../");
+    assert!(cloud.contains(r#"class="tag-index tag-cloud""#), "{cloud}");
+    assert!(
+        cloud.contains(r#"font-size: 2.00em" title="9 posts""#),
+        "{cloud}"
+    );
+    assert!(
+        cloud.contains(r#"font-size: 1.16em" title="1 post""#),
+        "{cloud}"
+    );
+}
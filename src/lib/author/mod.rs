@@ -0,0 +1,187 @@
+//! Post authors, for sites with guest posts. `AUTHORS_FILE` maps the keys
+//! posts name in `author:`/`authors:` to a name, bio, homepage, and avatar:
+//!
+//! ```yaml
+//! alice:
+//!   name: Alice Liddell
+//!   bio: Writes about compilers.
+//!   url: https://alice.example
+//!   avatar: assets/alice.jpg
+//! ```
+//!
+//! Each author who has posts gets a page under `AUTHORS_DIR` with an h-card
+//! and their posts, plus RSS and Atom feeds of just those posts.
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::Section;
+use gray_matter::{
+    Pod,
+    engine::{Engine, YAML},
+};
+use serde::Deserialize;
+
+use crate::{
+    config::{AUTHORS_DIR, PRETTY_URLS},
+    types::{Href, RelPath},
+    utils::{escape_attr, escape_text, page_out_path, slugify},
+};
+
+/// One entry of `AUTHORS_FILE`.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Author {
+    /// Key posts name the author by.
+    #[serde(skip)]
+    pub id: String,
+    /// Defaults to the key.
+    #[serde(default)]
+    pub name: String,
+    pub bio: Option<String>,
+    /// Homepage.
+    pub url: Option<String>,
+    /// Relative to the site root, or a full URL.
+    pub avatar: Option<String>,
+}
+
+impl Author {
+    /// Stand-in for an author the registry does not list.
+    fn unlisted(id: &str) -> Self {
+        Self {
+            id: id.to_owned(),
+            name: id.to_owned(),
+            ..Self::default()
+        }
+    }
+
+    /// Output location of the author's page, relative to the site root.
+    pub fn page_path(&self) -> PathBuf {
+        page_out_path(
+            &Path::new(AUTHORS_DIR).join(format!("{}.html", slugify(&self.id))),
+            PRETTY_URLS,
+        )
+    }
+
+    /// Root-relative link to the author's page.
+    pub fn href(&self) -> Href {
+        let rel = RelPath::new(self.page_path()).expect("author page path is relative");
+        Href::from_rel(&rel)
+    }
+
+    /// Location of one of the author's feeds relative to the site root, e.g.
+    /// `authors/alice.rss.xml`.
+    pub fn feed_path(&self, file: &str) -> PathBuf {
+        Path::new(AUTHORS_DIR).join(format!("{}.{file}", slugify(&self.id)))
+    }
+
+    /// The author as an h-card, for their page `href_prefix` away from the
+    /// root.
+    pub fn h_card(&self, href_prefix: &str) -> String {
+        let mut html = String::from(r#"<div class="h-card author-card">"#);
+        if let Some(avatar) = &self.avatar {
+            html.push_str(&format!(
+                r#"<img class="u-photo" src="{}" alt="">"#,
+                escape_attr(&resolve(avatar, href_prefix))
+            ));
+        }
+        let name = escape_text(&self.name);
+        match &self.url {
+            Some(url) => html.push_str(&format!(
+                r#"<p><a class="p-name u-url" href="{}" rel="me">{name}</a></p>"#,
+                escape_attr(url)
+            )),
+            None => html.push_str(&format!(r#"<p class="p-name">{name}</p>"#)),
+        }
+        if let Some(bio) = &self.bio {
+            html.push_str(&format!(r#"<p class="p-note">{}</p>"#, escape_text(bio)));
+        }
+        html.push_str("</div>\n");
+        html
+    }
+}
+
+/// The authors listed in `AUTHORS_FILE`, by key.
+#[derive(Debug, Default)]
+pub struct Authors {
+    listed: BTreeMap<String, Author>,
+}
+
+impl Authors {
+    /// Read the registry; a missing file lists nobody.
+    pub fn load(path: &Path) -> color_eyre::Result<Self> {
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let raw = std::fs::read_to_string(path)?;
+        Self::parse(&raw).with_note(|| format!("While parsing {}", path.display()))
+    }
+
+    pub fn parse(raw: &str) -> color_eyre::Result<Self> {
+        let pod = YAML::parse(raw)?;
+        if matches!(pod, Pod::Null) {
+            return Ok(Self::default());
+        }
+        let mut listed: BTreeMap<String, Author> = pod.deserialize()?;
+        for (id, author) in &mut listed {
+            author.id.clone_from(id);
+            if author.name.is_empty() {
+                author.name.clone_from(id);
+            }
+        }
+        Ok(Self { listed })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.listed.is_empty()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Author> {
+        self.listed.get(id)
+    }
+
+    /// The author `id`, or a stand-in named `id` if the registry lacks them.
+    pub fn resolve(&self, id: &str) -> Author {
+        self.get(id)
+            .cloned()
+            .unwrap_or_else(|| Author::unlisted(id))
+    }
+}
+
+/// "By …" line crediting `authors`, each linked to their page, for a post
+/// `href_prefix` away from the root. Empty without authors.
+pub fn byline(authors: &[Author], href_prefix: &str) -> String {
+    let links: Vec<String> = authors
+        .iter()
+        .map(|author| {
+            format!(
+                r#"<a class="p-author h-card" href="{}">{}</a>"#,
+                escape_attr(&format!("{href_prefix}{}", author.href())),
+                escape_text(&author.name)
+            )
+        })
+        .collect();
+    let names = match links.as_slice() {
+        [] => return String::new(),
+        [one] => one.clone(),
+        [first, second] => format!("{first} and {second}"),
+        [rest @ .., last] => format!("{}, and {last}", rest.join(", ")),
+    };
+    format!(
+        r#"<p class="byline">By {names}</p>
+"#
+    )
+}
+
+/// `src` as seen from a page `href_prefix` away from the root: paths
+/// relative to the root get the prefix; full and root-relative URLs do not.
+fn resolve(src: &str, href_prefix: &str) -> String {
+    if src.starts_with('/') || src.contains("://") {
+        src.to_owned()
+    } else {
+        format!("{href_prefix}{src}")
+    }
+}
+
+#[cfg(test)]
+mod tests;
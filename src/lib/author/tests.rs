@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+
+use crate::author::{Author, Authors, byline};
+
+#[test]
+fn registry_entries_default_their_name_to_the_key() {
+    let authors = Authors::parse(
+        "alice:\n  name: Alice Liddell\n  avatar: assets/alice.jpg\nbob:\n  bio: Guest.\n",
+    )
+    .unwrap();
+
+    let alice = authors.get("alice").unwrap();
+    assert_eq!(alice.id, "alice");
+    assert_eq!(alice.name, "Alice Liddell");
+    assert_eq!(authors.get("bob").unwrap().name, "bob");
+    assert!(authors.get("carol").is_none());
+    assert_eq!(authors.resolve("carol").name, "carol");
+    assert!(Authors::parse("").unwrap().is_empty());
+}
+
+#[test]
+fn author_paths_live_under_the_authors_directory() {
+    let author = Author {
+        id: "Alice L".into(),
+        ..Author::default()
+    };
+
+    assert_eq!(author.page_path(), PathBuf::from("authors/alice-l.html"));
+    assert_eq!(author.href().as_str(), "authors/alice-l.html");
+    assert_eq!(
+        author.feed_path("rss.xml"),
+        PathBuf::from("authors/alice-l.rss.xml")
+    );
+}
+
+#[test]
+fn h_card_marks_up_photo_name_and_note() {
+    let authors = Authors::parse(
+        "alice:\n  name: Alice & co\n  bio: Writes <code>.\n  url: https://alice.example\n  avatar: assets/alice.jpg\n",
+    )
+    .unwrap();
+
+    let card = authors.get("alice").unwrap().h_card("../");
+
+    assert!(
+        card.starts_with(r#"<div class="h-card author-card">"#),
+        "{card}"
+    );
+    assert!(
+        card.contains(r#"<img class="u-photo" src="../assets/alice.jpg""#),
+        "{card}"
+    );
+    assert!(
+        card.contains(
+            r#"<a class="p-name u-url" href="https://alice.example" rel="me">Alice &amp; co</a>"#
+        ),
+        "{card}"
+    );
+    assert!(
+        card.contains(r#"<p class="p-note">Writes &lt;code&gt;.</p>"#),
+        "{card}"
+    );
+}
+
+#[test]
+fn byline_joins_names_in_prose() {
+    let authors = Authors::default();
+    let names = |ids: &[&str]| {
+        let credited: Vec<Author> = ids.iter().map(|id| authors.resolve(id)).collect();
+        byline(&credited, "../")
+    };
+
+    assert_eq!(names(&[]), "");
+    assert!(
+        names(&["a"])
+            .contains(r#"By <a class="p-author h-card" href="../authors/a.html">a</a></p>"#)
+    );
+    assert!(names(&["a", "b"]).contains("</a> and <a"));
+    let three = names(&["a", "b", "c"]);
+    assert_eq!(three.matches("</a>, <a").count(), 1, "{three}");
+    assert!(three.contains("</a>, and <a"), "{three}");
+}
@@ -0,0 +1,441 @@
+//! Site-wide link graph: wikilink and cross-post reference resolution,
+//! outbound link collection, and the "Linked from" section rendered from it.
+//! Backlinks carry text fragments (`#:~:text=`) so they land on the passage
+//! that does the linking.
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use pulldown_cmark::{CowStr, Event, LinkType, Tag, TagEnd};
+
+use crate::{
+    article::Article,
+    config::{PRETTY_URLS, SMART_PUNCTUATION},
+    diagnostics::DocumentSink,
+    search::plain_text,
+    transformer::{
+        punctuation::smarten,
+        raw_html::{raw_hrefs, rewrite_event},
+    },
+    types::{Href, RelPath},
+    utils::{escape_attr, escape_text, page_out_path, slugify},
+};
+
+/// Words of context before a link's text used to tell repeats of it apart.
+const FRAGMENT_PREFIX_WORDS: usize = 3;
+
+/// Every page that a wikilink or `@/` reference may name, keyed by source
+/// path, file stem, and title slug.
+#[derive(Debug, Default)]
+pub struct PageIndex {
+    keys: HashMap<String, Href>,
+    titles: HashMap<Href, String>,
+}
+
+impl PageIndex {
+    /// Register a page under its source path (relative to the content
+    /// directory) and, if present, its title. Earlier pages win on clashes.
+    pub fn insert(&mut self, rel_src: &Path, title: Option<&str>, href: &Href) {
+        let path = rel_src
+            .with_extension("")
+            .to_string_lossy()
+            .replace('\\', "/")
+            .to_lowercase();
+        let stem = path.rsplit('/').next().unwrap_or(&path).to_string();
+
+        let mut keys = vec![path.clone(), stem.clone(), slugify(&stem)];
+        if let Some(title) = title {
+            keys.push(slugify(title));
+            self.titles
+                .entry(href.clone())
+                .or_insert_with(|| title.to_owned());
+        }
+        for key in keys {
+            self.keys.entry(key).or_insert_with(|| href.clone());
+        }
+    }
+
+    /// Title of the page at `href`, if it has one.
+    pub fn title(&self, href: &Href) -> Option<&str> {
+        self.titles.get(href).map(String::as_str)
+    }
+
+    /// Page named by a wikilink target such as `Some Page` or `dir/page`.
+    pub fn resolve(&self, target: &str) -> Option<&Href> {
+        let target = target.trim().trim_end_matches(".md").to_lowercase();
+        self.keys
+            .get(&target)
+            .or_else(|| self.keys.get(&slugify(&target)))
+    }
+}
+
+/// Point wikilinks at the pages they name. Unresolved wikilinks are reduced
+/// to their text so they never render as broken links.
+pub fn resolve_wikilinks<'a>(
+    events: Vec<Event<'a>>,
+    index: &PageIndex,
+    href_prefix: &str,
+) -> Vec<Event<'a>> {
+    let mut out = Vec::with_capacity(events.len());
+    let mut dropping = false;
+
+    for event in events {
+        match event {
+            Event::Start(Tag::Link {
+                link_type: link_type @ LinkType::WikiLink { .. },
+                dest_url,
+                title,
+                id,
+            }) => {
+                let (page, fragment) = match dest_url.split_once('#') {
+                    Some((page, fragment)) => (page, Some(fragment)),
+                    None => (dest_url.as_ref(), None),
+                };
+                match index.resolve(page) {
+                    Some(href) => {
+                        let mut dest = format!("{href_prefix}{href}");
+                        if let Some(fragment) = fragment {
+                            dest.push('#');
+                            dest.push_str(&slugify(fragment));
+                        }
+                        out.push(Event::Start(Tag::Link {
+                            link_type,
+                            dest_url: CowStr::from(dest),
+                            title,
+                            id,
+                        }));
+                    }
+                    None => dropping = true,
+                }
+            }
+            Event::End(TagEnd::Link) if dropping => dropping = false,
+            other => out.push(other),
+        }
+    }
+
+    out
+}
+
+/// Point cross-post references, links like `[](@/other-post.md)` or
+/// `[see this](@/notes/tea#brewing)`, at the page with that source path or
+/// slug. Links without text get the page's title. References to pages that
+/// do not exist are reported and reduced to their text (or target). Raw HTML
+/// `<a href="@/…">` tags are pointed the same way, or lose their `href`.
+pub fn resolve_post_refs<'a>(
+    events: Vec<Event<'a>>,
+    index: &PageIndex,
+    href_prefix: &str,
+    sink: &DocumentSink,
+) -> Vec<Event<'a>> {
+    let mut out = Vec::with_capacity(events.len());
+    let mut events = events.into_iter().peekable();
+    let mut dropping = false;
+
+    while let Some(event) = events.next() {
+        match event {
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) if dest_url.starts_with("@/") => {
+                let empty = matches!(events.peek(), Some(Event::End(TagEnd::Link)));
+                let page = post_ref_page(&dest_url);
+                let name = page.trim_end_matches(".md");
+                let Some((href, dest)) = resolve_post_ref(&dest_url, index, href_prefix, sink)
+                else {
+                    if empty {
+                        out.push(Event::Text(CowStr::from(name.to_owned())));
+                    }
+                    dropping = true;
+                    continue;
+                };
+
+                out.push(Event::Start(Tag::Link {
+                    link_type,
+                    dest_url: CowStr::from(dest),
+                    title,
+                    id,
+                }));
+                if empty {
+                    let text = index.title(href).unwrap_or(name);
+                    out.push(Event::Text(CowStr::from(text.to_owned())));
+                }
+            }
+            Event::End(TagEnd::Link) if dropping => dropping = false,
+            html @ (Event::Html(_) | Event::InlineHtml(_)) => {
+                out.push(rewrite_event(html, r#"a[href^="@/"]"#, |el| {
+                    let target = el.get_attribute("href").unwrap_or_default();
+                    match resolve_post_ref(&target, index, href_prefix, sink) {
+                        Some((_, dest)) => el.set_attribute("href", &dest).is_ok(),
+                        None => {
+                            el.remove_attribute("href");
+                            true
+                        }
+                    }
+                }));
+            }
+            other => out.push(other),
+        }
+    }
+
+    out
+}
+
+/// The page part of an `@/page#fragment` reference.
+fn post_ref_page(reference: &str) -> &str {
+    let target = reference.strip_prefix("@/").unwrap_or(reference);
+    target.split_once('#').map_or(target, |(page, _)| page)
+}
+
+/// The page an `@/` reference names and the link to it from a page
+/// `href_prefix` away from the root, or `None` (reported) if there is none.
+fn resolve_post_ref<'i>(
+    reference: &str,
+    index: &'i PageIndex,
+    href_prefix: &str,
+    sink: &DocumentSink,
+) -> Option<(&'i Href, String)> {
+    let page = post_ref_page(reference);
+    let Some(href) = index.resolve(page) else {
+        sink.warn(
+            "cross-reference",
+            reference,
+            format!("no post `{page}` to link to"),
+        );
+        return None;
+    };
+    let mut dest = format!("{href_prefix}{href}");
+    if let Some((_, fragment)) = reference.split_once('#') {
+        dest.push('#');
+        dest.push_str(fragment);
+    }
+    Some((href, dest))
+}
+
+/// Internal pages linked from a document, as root-relative hrefs, whether by
+/// Markdown links or raw HTML ones.
+pub fn outbound_links(events: &[Event<'_>], page_href: &Href) -> Vec<Href> {
+    let raw = raw_hrefs(events);
+    let mut links: Vec<Href> = events
+        .iter()
+        .filter_map(|e| match e {
+            Event::Start(Tag::Link { dest_url, .. }) => Some(dest_url.as_ref()),
+            _ => None,
+        })
+        .chain(raw.iter().map(String::as_str))
+        .filter_map(|dest| internal_href(dest, page_href))
+        .filter(|href| href != page_href)
+        .collect();
+    links.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    links.dedup();
+    links
+}
+
+/// Text fragment directives (`:~:text=...`) locating the first link from a
+/// document to each internal page, where the linking passage can be picked
+/// out of the document's text unambiguously.
+pub fn link_passages(events: &[Event<'_>], page_href: &Href) -> HashMap<Href, String> {
+    let page_text = plain_text(events);
+    let mut passages: HashMap<Href, String> = HashMap::new();
+    // Text of the current block before the link, and the open link's target
+    // and text.
+    let mut before = String::new();
+    let mut link: Option<(Option<Href>, String)> = None;
+
+    for event in events {
+        match event {
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                let target = internal_href(dest_url, page_href).filter(|h| h != page_href);
+                link = Some((target, String::new()));
+            }
+            Event::End(TagEnd::Link) => {
+                let Some((target, text)) = link.take() else {
+                    continue;
+                };
+                if let Some(target) = target
+                    && !passages.contains_key(&target)
+                    && let Some(directive) = text_fragment(&page_text, &before, &text)
+                {
+                    passages.insert(target, directive);
+                }
+                before.push_str(&text);
+            }
+            Event::Text(t) | Event::Code(t) => match link.as_mut() {
+                Some((_, text)) => text.push_str(t),
+                None => before.push_str(t),
+            },
+            Event::SoftBreak | Event::HardBreak => match link.as_mut() {
+                Some((_, text)) => text.push(' '),
+                None => before.push(' '),
+            },
+            // Inline markup does not interrupt the text.
+            Event::Start(
+                Tag::Emphasis
+                | Tag::Strong
+                | Tag::Strikethrough
+                | Tag::Superscript
+                | Tag::Subscript,
+            )
+            | Event::End(
+                TagEnd::Emphasis
+                | TagEnd::Strong
+                | TagEnd::Strikethrough
+                | TagEnd::Superscript
+                | TagEnd::Subscript,
+            ) => {}
+            // New blocks start afresh, and the rendered page shows something
+            // else for sidenotes, math, and raw HTML, so context cannot span
+            // them.
+            Event::FootnoteReference(_)
+            | Event::InlineMath(_)
+            | Event::InlineHtml(_)
+            | Event::Start(_)
+            | Event::End(_)
+                if link.is_none() =>
+            {
+                before.clear();
+            }
+            _ => {}
+        }
+    }
+
+    passages
+}
+
+/// Directive for `linked` text preceded by `before`, which must identify a
+/// single passage of `page_text`. Falls back from the linked text alone to
+/// it with a few words of context.
+fn text_fragment(page_text: &str, before: &str, linked: &str) -> Option<String> {
+    let linked = linked.split_whitespace().collect::<Vec<_>>().join(" ");
+    if linked.is_empty() {
+        return None;
+    }
+    if page_text.matches(linked.as_str()).count() == 1 {
+        return Some(format!(":~:text={}", fragment_encode(&linked)));
+    }
+
+    let words: Vec<&str> = before.split_whitespace().collect();
+    let prefix = words[words.len().saturating_sub(FRAGMENT_PREFIX_WORDS)..].join(" ");
+    if prefix.is_empty() || page_text.matches(&format!("{prefix} {linked}")).count() != 1 {
+        return None;
+    }
+    Some(format!(
+        ":~:text={}-,{}",
+        fragment_encode(&prefix),
+        fragment_encode(&linked)
+    ))
+}
+
+/// Percent-encode text for a text fragment, in which `-`, `,`, and `&` are
+/// syntax. Text is first typeset as the page renders it.
+fn fragment_encode(text: &str) -> String {
+    let typeset = if SMART_PUNCTUATION {
+        smarten(text, None).0
+    } else {
+        None
+    };
+    let mut out = String::new();
+    for byte in typeset.as_deref().unwrap_or(text).bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'.' | b'_' | b'~' => {
+                out.push(char::from(byte));
+            }
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    out
+}
+
+/// Resolve a link destination against the page it appears on. External
+/// links, bare fragments, and non-page schemes yield `None`.
+pub(crate) fn internal_href(dest: &str, page_href: &Href) -> Option<Href> {
+    if dest.starts_with('#') || dest.starts_with("//") || dest.contains(':') {
+        return None;
+    }
+
+    let path = dest.split(['#', '?']).next().unwrap_or_default();
+    if path.is_empty() {
+        return None;
+    }
+
+    let base = match path.strip_prefix('/') {
+        Some(_) => "",
+        None => page_href
+            .as_str()
+            .rsplit_once('/')
+            .map(|(dir, _)| dir)
+            .unwrap_or(""),
+    };
+
+    let mut segments: Vec<&str> = base.split('/').filter(|s| !s.is_empty()).collect();
+    for segment in path.trim_start_matches('/').split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop()?;
+            }
+            s => segments.push(s),
+        }
+    }
+    let mut joined = segments.join("/");
+    if path.ends_with('/') {
+        joined.push('/');
+    }
+
+    // Links between sources (`other.md`) name the page built from them.
+    if let Some(stem) = joined.strip_suffix(".md") {
+        let out = page_out_path(&PathBuf::from(format!("{stem}.html")), PRETTY_URLS);
+        return RelPath::new(out).map(|rel| Href::from_rel(&rel));
+    }
+
+    RelPath::new(PathBuf::from(joined)).map(|rel| Href::from_rel(&rel))
+}
+
+/// Invert outbound links into target → sources.
+pub fn backlink_graph(links: &[(Href, Vec<Href>)]) -> HashMap<Href, Vec<Href>> {
+    let mut graph: HashMap<Href, Vec<Href>> = HashMap::new();
+    for (source, targets) in links {
+        for target in targets {
+            graph
+                .entry(target.clone())
+                .or_default()
+                .push(source.clone());
+        }
+    }
+    graph
+}
+
+/// Render the pages linking here, or nothing when there are none. Each
+/// source may come with a text fragment directive for its linking passage.
+pub fn render_backlinks(sources: &[(&Article, Option<&str>)], href_prefix: &str) -> String {
+    if sources.is_empty() {
+        return String::new();
+    }
+
+    let mut html = String::from(
+        r#"<section class="backlinks">
+<h2>Linked from</h2>
+<ul>
+"#,
+    );
+    for (a, passage) in sources {
+        let mut full_href = format!("{href_prefix}{}", a.href.as_str());
+        if let Some(passage) = passage {
+            full_href.push('#');
+            full_href.push_str(passage);
+        }
+        html.push_str(&format!(
+            r#"<li><a href="{}">{}</a></li>
+"#,
+            escape_attr(&full_href),
+            escape_text(&a.title)
+        ));
+    }
+    html.push_str("</ul>\n</section>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,167 @@
+use std::path::{Path, PathBuf};
+
+use pulldown_cmark::{Options, Parser};
+
+use crate::{
+    article::Article,
+    backlinks::{
+        PageIndex, backlink_graph, link_passages, outbound_links, render_backlinks,
+        resolve_post_refs, resolve_wikilinks,
+    },
+    diagnostics::Diagnostics,
+    types::{Href, RelPath},
+};
+
+fn href(s: &str) -> Href {
+    Href::from_rel(&RelPath::new(PathBuf::from(s)).unwrap())
+}
+
+fn index() -> PageIndex {
+    let mut index = PageIndex::default();
+    index.insert(
+        Path::new("garden/Rust Tips.md"),
+        Some("Tips for Rust"),
+        &href("posts/garden/Rust Tips.html"),
+    );
+    index.insert(Path::new("about.md"), None, &href("posts/about.html"));
+    index
+}
+
+fn render(md: &str, prefix: &str) -> String {
+    let events: Vec<_> = Parser::new_ext(md, Options::ENABLE_WIKILINKS).collect();
+    let resolved = resolve_wikilinks(events, &index(), prefix);
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, resolved.into_iter());
+    html
+}
+
+#[test]
+fn wikilinks_resolve_by_stem_path_and_title() {
+    let html = render(
+        "[[about]] [[garden/rust tips]] [[Tips for Rust|tips]]",
+        "../",
+    );
+
+    assert!(html.contains(r#"<a href="../posts/about.html">about</a>"#));
+    assert!(html.contains(r#"<a href="../posts/garden/Rust%20Tips.html">garden/rust tips</a>"#));
+    assert!(html.contains(r#">tips</a>"#));
+}
+
+#[test]
+fn wikilink_fragments_become_slugs() {
+    let html = render("[[about#Contact Me]]", "");
+    assert!(html.contains(r#"href="posts/about.html#contact-me""#));
+}
+
+#[test]
+fn unresolved_wikilinks_become_text() {
+    let html = render("See [[Nowhere]].", "");
+    assert_eq!(html, "<p>See Nowhere.</p>\n");
+}
+
+#[test]
+fn post_refs_resolve_and_borrow_titles() {
+    let md = "[](<@/garden/Rust Tips.md>) [the page](@/about#contact) [](@/about.md) [](@/gone.md) [lost](@/gone)";
+    let diagnostics = Diagnostics::default();
+    let sink = diagnostics.for_document(Path::new("post.md"), md, Vec::new());
+    let events: Vec<_> = Parser::new(md).collect();
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(
+        &mut html,
+        resolve_post_refs(events, &index(), "../", &sink).into_iter(),
+    );
+
+    assert_eq!(
+        html,
+        concat!(
+            r#"<p><a href="../posts/garden/Rust%20Tips.html">Tips for Rust</a> "#,
+            r#"<a href="../posts/about.html#contact">the page</a> "#,
+            r#"<a href="../posts/about.html">about</a> gone lost</p>"#,
+            "
+",
+        )
+    );
+    assert_eq!(diagnostics.take().len(), 2);
+}
+
+#[test]
+fn post_refs_in_raw_html_resolve() {
+    let md = "<a href=\"@/about#contact\">contact</a> <a href=\"@/gone\">gone</a>\n";
+    let diagnostics = Diagnostics::default();
+    let sink = diagnostics.for_document(Path::new("post.md"), md, Vec::new());
+    let events: Vec<_> = Parser::new(md).collect();
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(
+        &mut html,
+        resolve_post_refs(events, &index(), "../", &sink).into_iter(),
+    );
+
+    assert_eq!(
+        html,
+        "<p><a href=\"../posts/about.html#contact\">contact</a> <a>gone</a></p>\n"
+    );
+    assert_eq!(diagnostics.take().len(), 1);
+}
+
+#[test]
+fn outbound_links_are_resolved_and_deduplicated() {
+    let md = "[a](other.md) [b](../posts/other.html#x) [c](/posts/third.html) \
+              [d](https://example.com) [e](#local) [f](mailto:me@example.com) [g](self.html)";
+    let events: Vec<_> = Parser::new(md).collect();
+
+    let links = outbound_links(&events, &href("posts/self.html"));
+    let links: Vec<_> = links.iter().map(Href::as_str).collect();
+
+    assert_eq!(links, vec!["posts/other.html", "posts/third.html"]);
+
+    let raw =
+        "<p><a href=\"fourth.html\">d</a></p>\n\n[a](other.md) <a href=\"other.html\">again</a>";
+    let events: Vec<_> = Parser::new(raw).collect();
+    let links = outbound_links(&events, &href("posts/self.html"));
+    let links: Vec<_> = links.iter().map(Href::as_str).collect();
+
+    assert_eq!(links, vec!["posts/fourth.html", "posts/other.html"]);
+}
+
+#[test]
+fn graph_inverts_outbound_links() {
+    let graph = backlink_graph(&[
+        (href("posts/a.html"), vec![href("posts/c.html")]),
+        (
+            href("posts/b.html"),
+            vec![href("posts/c.html"), href("posts/a.html")],
+        ),
+    ]);
+
+    assert_eq!(graph[&href("posts/c.html")].len(), 2);
+    assert_eq!(graph[&href("posts/a.html")], vec![href("posts/b.html")]);
+}
+
+#[test]
+fn link_passages_pick_out_the_linking_text() {
+    let md = "Read [the guide](other.md) first.\n\n\
+              Also [see](third.md) this, and *then* [see](fourth.md) that.\n\n\
+              [see](fifth.md) again.\n";
+    let events: Vec<_> = Parser::new(md).collect();
+    let passages = link_passages(&events, &href("posts/self.html"));
+
+    assert_eq!(
+        passages.get(&href("posts/other.html")).map(String::as_str),
+        Some(":~:text=the%20guide")
+    );
+    // Repeated link text is told apart by the words before it.
+    assert_eq!(
+        passages.get(&href("posts/fourth.html")).map(String::as_str),
+        Some(":~:text=this%2C%20and%20then-,see")
+    );
+    // Nothing before it in its block: no passage rather than a wrong one.
+    assert_eq!(passages.get(&href("posts/fifth.html")), None);
+}
+
+#[test]
+fn backlinks_link_to_their_passage() {
+    let source = Article::test("Source").at("posts/source.html");
+    let html = render_backlinks(&[(&source, Some(":~:text=the%20guide"))], "../");
+
+    assert!(html.contains(r#"<a href="../posts/source.html#:~:text=the%20guide">Source</a>"#));
+}
@@ -0,0 +1,403 @@
+//! The works pages cite with `[@key]`, loaded from the BibTeX (`.bib`) or
+//! CSL-JSON (`.json`) file named by `BIBLIOGRAPHY`. Only the fields an
+//! author-date reference needs are kept; BibTeX `@string` macros and
+//! cross-references are not expanded.
+use std::{collections::HashMap, fs, path::Path};
+
+use color_eyre::{
+    Section,
+    eyre::{bail, eyre},
+};
+use serde_json::Value;
+
+use crate::utils::{escape_attr, escape_text};
+
+/// An author or editor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Name {
+    pub family: String,
+    pub given: Option<String>,
+}
+
+impl Name {
+    /// `Family, Given` or `Given Family`, from BibTeX. A name wrapped in
+    /// braces, like `{World Health Organization}`, is kept whole.
+    fn parse_bibtex(raw: &str) -> Self {
+        let raw = raw.trim();
+        if raw.starts_with('{') && balanced_len(raw) == Some(raw.len()) {
+            return Self {
+                family: clean(raw),
+                given: None,
+            };
+        }
+        let raw = clean(raw);
+        if let Some((family, given)) = raw.split_once(',') {
+            return Self {
+                family: family.trim().to_owned(),
+                given: Some(given.trim().to_owned()).filter(|g| !g.is_empty()),
+            };
+        }
+        match raw.trim().rsplit_once(' ') {
+            Some((given, family)) => Self {
+                family: family.to_owned(),
+                given: Some(given.trim().to_owned()),
+            },
+            None => Self {
+                family: raw.trim().to_owned(),
+                given: None,
+            },
+        }
+    }
+
+    /// `Family, Given` when `inverted`, else `Given Family`.
+    fn display(&self, inverted: bool) -> String {
+        match (&self.given, inverted) {
+            (Some(given), true) => format!("{}, {given}", self.family),
+            (Some(given), false) => format!("{given} {}", self.family),
+            (None, _) => self.family.clone(),
+        }
+    }
+}
+
+/// One cited work.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Entry {
+    pub key: String,
+    pub authors: Vec<Name>,
+    pub year: Option<String>,
+    pub title: Option<String>,
+    /// Journal, proceedings, or book the work appeared in.
+    pub container: Option<String>,
+    pub publisher: Option<String>,
+    pub volume: Option<String>,
+    pub pages: Option<String>,
+    pub url: Option<String>,
+}
+
+impl Entry {
+    /// Short in-text form: `Knuth 1984`, `Knuth and Levy 1993`, or
+    /// `Knuth et al. 1989`.
+    pub fn short_label(&self) -> String {
+        let names = match &self.authors[..] {
+            [] => self.title.clone().unwrap_or_else(|| self.key.clone()),
+            [one] => one.family.clone(),
+            [first, second] => format!("{} and {}", first.family, second.family),
+            [first, ..] => format!("{} et al.", first.family),
+        };
+        match &self.year {
+            Some(year) => format!("{names} {year}"),
+            None => names,
+        }
+    }
+
+    /// The full reference as HTML, author-date style.
+    pub fn reference_html(&self) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        if !self.authors.is_empty() {
+            let names: Vec<String> = self
+                .authors
+                .iter()
+                .enumerate()
+                .map(|(i, name)| name.display(i == 0))
+                .collect();
+            let authors = match &names[..] {
+                [one] => one.clone(),
+                [init @ .., last] => format!("{} and {last}", init.join(", ")),
+                [] => unreachable!(),
+            };
+            parts.push(escape_text(authors.trim_end_matches('.')));
+        }
+        if let Some(year) = &self.year {
+            parts.push(escape_text(year));
+        }
+        match (&self.title, &self.container) {
+            (Some(title), Some(_)) => parts.push(format!("“{}.”", escape_text(title))),
+            (Some(title), None) => parts.push(format!("<em>{}</em>", escape_text(title))),
+            (None, _) => {}
+        }
+        if let Some(container) = &self.container {
+            let mut part = format!("<em>{}</em>", escape_text(container));
+            if let Some(volume) = &self.volume {
+                part.push_str(&format!(" {}", escape_text(volume)));
+            }
+            if let Some(pages) = &self.pages {
+                part.push_str(&format!(": {}", escape_text(pages)));
+            }
+            parts.push(part);
+        }
+        if let Some(publisher) = &self.publisher {
+            parts.push(escape_text(publisher));
+        }
+
+        let mut html = parts
+            .iter()
+            .map(|part| {
+                let text = part.trim_end_matches("</em>");
+                if text.ends_with(['.', '?', '!', '”']) {
+                    part.clone()
+                } else {
+                    format!("{part}.")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        if let Some(url) = &self.url {
+            html.push_str(&format!(
+                r#" <a href="{}">{}</a>"#,
+                escape_attr(url),
+                escape_text(url)
+            ));
+        }
+        html
+    }
+}
+
+/// Every work in the bibliography, by key.
+#[derive(Clone, Debug, Default)]
+pub struct Bibliography {
+    entries: HashMap<String, Entry>,
+}
+
+impl Bibliography {
+    /// The bibliography at `path`, or an empty one if there is no such file.
+    pub fn load(path: &Path) -> color_eyre::Result<Self> {
+        let Ok(source) = fs::read_to_string(path) else {
+            return Ok(Self::default());
+        };
+        let entries = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => parse_csl_json(&source),
+            _ => parse_bibtex(&source),
+        }
+        .with_note(|| format!("While loading the bibliography {}", path.display()))?;
+        Ok(Self::new(entries))
+    }
+
+    pub fn new(entries: Vec<Entry>) -> Self {
+        Self {
+            entries: entries
+                .into_iter()
+                .map(|entry| (entry.key.clone(), entry))
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Entry> {
+        self.entries.get(key)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Entries of a BibTeX file.
+pub fn parse_bibtex(source: &str) -> color_eyre::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    let mut rest = source;
+    while let Some(at) = rest.find('@') {
+        rest = &rest[at + 1..];
+        let open = rest
+            .find(['{', '('])
+            .ok_or_else(|| eyre!("entry `@{}` has no body", first_line(rest)))?;
+        let kind = rest[..open].trim().to_lowercase();
+        let body_len = balanced_len(&rest[open..])
+            .ok_or_else(|| eyre!("entry `@{}` is not closed", first_line(rest)))?;
+        let body = &rest[open + 1..open + body_len - 1];
+        rest = &rest[open + body_len..];
+        if matches!(kind.as_str(), "comment" | "preamble" | "string") {
+            continue;
+        }
+
+        let (key, fields) = body.split_once(',').unwrap_or((body, ""));
+        let fields = bibtex_fields(fields).with_note(|| format!("In entry {}", key.trim()))?;
+        let field = |name: &str| fields.get(name).cloned().filter(|v| !v.is_empty());
+        let authors = fields
+            .get("author")
+            .or_else(|| fields.get("editor"))
+            .map(|raw| {
+                split_names(raw)
+                    .iter()
+                    .map(|name| Name::parse_bibtex(name))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let clean_field = |name: &str| field(name).map(|v| clean(&v));
+        entries.push(Entry {
+            key: key.trim().to_owned(),
+            authors,
+            year: clean_field("year")
+                .or_else(|| field("date").map(|d| d.chars().take(4).collect())),
+            title: clean_field("title"),
+            container: clean_field("journal")
+                .or_else(|| clean_field("journaltitle"))
+                .or_else(|| clean_field("booktitle")),
+            publisher: clean_field("publisher"),
+            volume: clean_field("volume"),
+            pages: clean_field("pages"),
+            url: field("url").or_else(|| field("doi").map(|doi| format!("https://doi.org/{doi}"))),
+        });
+    }
+    Ok(entries)
+}
+
+/// The `name = value` fields of an entry body, names lowercased and values
+/// with their outer delimiters removed.
+fn bibtex_fields(body: &str) -> color_eyre::Result<HashMap<String, String>> {
+    let mut fields = HashMap::new();
+    let mut rest = body.trim_start();
+    while !rest.is_empty() {
+        let Some(eq) = rest.find('=') else {
+            if rest.trim_matches([',', ' ', '\n', '\r', '\t']).is_empty() {
+                break;
+            }
+            bail!("expected `name = value` at `{}`", first_line(rest));
+        };
+        let name = rest[..eq]
+            .trim()
+            .trim_start_matches(',')
+            .trim()
+            .to_lowercase();
+        rest = rest[eq + 1..].trim_start();
+
+        let mut value = String::new();
+        loop {
+            let (part, len) = match rest.chars().next() {
+                Some('{') => {
+                    let len =
+                        balanced_len(rest).ok_or_else(|| eyre!("field `{name}` is not closed"))?;
+                    (&rest[1..len - 1], len)
+                }
+                Some('"') => {
+                    let len = rest[1..]
+                        .find('"')
+                        .ok_or_else(|| eyre!("field `{name}` is not closed"))?
+                        + 2;
+                    (&rest[1..len - 1], len)
+                }
+                _ => {
+                    let len = rest.find([',', '#']).unwrap_or(rest.len());
+                    (rest[..len].trim(), len)
+                }
+            };
+            value.push_str(part);
+            rest = rest[len..].trim_start();
+            match rest.strip_prefix('#') {
+                Some(more) => rest = more.trim_start(),
+                None => break,
+            }
+        }
+        fields.insert(name, value);
+        rest = rest.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+    }
+    Ok(fields)
+}
+
+/// Length of the `{...}` or `(...)` group `s` starts with, delimiters
+/// included, or `None` if it is not closed.
+fn balanced_len(s: &str) -> Option<usize> {
+    let (open, close) = match s.chars().next()? {
+        '(' => ('(', ')'),
+        _ => ('{', '}'),
+    };
+    let mut depth = 0usize;
+    for (i, c) in s.char_indices() {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i + 1);
+            }
+        }
+    }
+    None
+}
+
+/// Names separated by `and` outside braces.
+fn split_names(raw: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    for word in raw.split_whitespace() {
+        if depth == 0 && word.eq_ignore_ascii_case("and") {
+            names.push(std::mem::take(&mut current));
+            continue;
+        }
+        depth += word.matches('{').count();
+        depth = depth.saturating_sub(word.matches('}').count());
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    names.push(current);
+    names.retain(|name| !name.is_empty());
+    names
+}
+
+/// A BibTeX value as plain text: braces dropped, common escapes and dashes
+/// resolved, whitespace collapsed.
+fn clean(value: &str) -> String {
+    let text = value
+        .replace(['{', '}'], "")
+        .replace("\\&", "&")
+        .replace("\\%", "%")
+        .replace("\\_", "_")
+        .replace("---", "—")
+        .replace("--", "–")
+        .replace('~', " ");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn first_line(s: &str) -> &str {
+    s.lines().next().unwrap_or_default().trim()
+}
+
+/// Entries of a CSL-JSON file: an array of items.
+pub fn parse_csl_json(source: &str) -> color_eyre::Result<Vec<Entry>> {
+    let items: Vec<Value> = serde_json::from_str(source)?;
+    items
+        .iter()
+        .map(|item| {
+            let text = |name: &str| match item.get(name)? {
+                Value::String(s) => Some(s.clone()),
+                Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            };
+            let key = text("id").ok_or_else(|| eyre!("CSL item without an `id`"))?;
+            let names = item
+                .get("author")
+                .or_else(|| item.get("editor"))
+                .and_then(Value::as_array);
+            let authors = names
+                .into_iter()
+                .flatten()
+                .filter_map(|name| {
+                    let part = |field: &str| name.get(field)?.as_str().map(str::to_owned);
+                    Some(Name {
+                        family: part("family").or_else(|| part("literal"))?,
+                        given: part("given"),
+                    })
+                })
+                .collect();
+            let year = item
+                .pointer("/issued/date-parts/0/0")
+                .map(|year| year.to_string().trim_matches('"').to_owned());
+            Ok(Entry {
+                key,
+                authors,
+                year,
+                title: text("title"),
+                container: text("container-title"),
+                publisher: text("publisher"),
+                volume: text("volume"),
+                pages: text("page"),
+                url: text("URL")
+                    .or_else(|| text("DOI").map(|doi| format!("https://doi.org/{doi}"))),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests;
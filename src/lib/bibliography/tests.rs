@@ -0,0 +1,94 @@
+use crate::bibliography::{Bibliography, Name, parse_bibtex, parse_csl_json};
+
+const BIBTEX: &str = r#"
+@comment{ generated by hand }
+@string{ tug = "TUGboat" }
+
+@article{knuth1984,
+  author = {Knuth, Donald E.},
+  title = {Literate {P}rogramming},
+  journal = "The Computer Journal",
+  volume = 27,
+  pages = {97--111},
+  year = 1984,
+  doi = {10.1093/comjnl/27.2.97},
+}
+
+@book{ms2000,
+  author = {Alice Mayer and {Barnes \& Noble} and Carl Szabo},
+  title = "Pages" # " and " # "Ink",
+  publisher = {Rag \& Bone},
+  year = {2000}
+}
+"#;
+
+#[test]
+fn bibtex_entries_are_parsed_and_cleaned() {
+    let entries = parse_bibtex(BIBTEX).unwrap();
+    assert_eq!(entries.len(), 2);
+
+    let knuth = &entries[0];
+    assert_eq!(knuth.key, "knuth1984");
+    assert_eq!(
+        knuth.authors,
+        [Name {
+            family: "Knuth".into(),
+            given: Some("Donald E.".into()),
+        }]
+    );
+    assert_eq!(knuth.title.as_deref(), Some("Literate Programming"));
+    assert_eq!(knuth.pages.as_deref(), Some("97–111"));
+    assert_eq!(knuth.year.as_deref(), Some("1984"));
+    assert_eq!(
+        knuth.url.as_deref(),
+        Some("https://doi.org/10.1093/comjnl/27.2.97")
+    );
+
+    let book = &entries[1];
+    assert_eq!(book.authors.len(), 3);
+    assert_eq!(book.authors[1].family, "Barnes & Noble");
+    assert_eq!(book.title.as_deref(), Some("Pages and Ink"));
+    assert_eq!(book.publisher.as_deref(), Some("Rag & Bone"));
+
+    assert!(parse_bibtex("@book{open, title = {x}").is_err());
+}
+
+#[test]
+fn labels_and_references_follow_author_date_style() {
+    let entries = parse_bibtex(BIBTEX).unwrap();
+    assert_eq!(entries[0].short_label(), "Knuth 1984");
+    assert_eq!(entries[1].short_label(), "Mayer et al. 2000");
+
+    assert_eq!(
+        entries[0].reference_html(),
+        concat!(
+            "Knuth, Donald E. 1984. “Literate Programming.” ",
+            "<em>The Computer Journal</em> 27: 97–111. ",
+            r#"<a href="https://doi.org/10.1093/comjnl/27.2.97">https://doi.org/10.1093/comjnl/27.2.97</a>"#,
+        )
+    );
+    assert_eq!(
+        entries[1].reference_html(),
+        "Mayer, Alice, Barnes &amp; Noble and Carl Szabo. 2000. <em>Pages and Ink</em>. Rag &amp; Bone."
+    );
+}
+
+#[test]
+fn csl_json_items_become_entries() {
+    let json = r#"[{
+        "id": "levy1993",
+        "type": "book",
+        "author": [{"family": "Levy", "given": "Silvio"}, {"literal": "CWEB Team"}],
+        "title": "The CWEB System",
+        "issued": {"date-parts": [[1993, 4]]},
+        "URL": "https://example.org/cweb"
+    }]"#;
+    let entries = parse_csl_json(json).unwrap();
+    assert_eq!(entries[0].short_label(), "Levy and CWEB Team 1993");
+    assert_eq!(entries[0].url.as_deref(), Some("https://example.org/cweb"));
+
+    let bibliography = Bibliography::new(entries);
+    assert!(bibliography.get("levy1993").is_some());
+    assert!(bibliography.get("knuth1984").is_none());
+    assert!(parse_csl_json(r#"[{"title": "no id"}]"#).is_err());
+}
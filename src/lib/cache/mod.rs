@@ -0,0 +1,160 @@
+//! Housekeeping for the on-disk build cache (`CACHE_DIR`): usage statistics,
+//! age- and size-based garbage collection, and clearing it outright.
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use color_eyre::Section;
+use walkdir::WalkDir;
+
+use crate::config::{CACHE_MAX_AGE_DAYS, CACHE_MAX_BYTES};
+
+pub mod remote;
+
+/// Limits enforced by `gc`.
+#[derive(Clone, Copy, Debug)]
+pub struct CacheLimits {
+    /// Entries not modified for this long are removed.
+    pub max_age: Duration,
+    /// Once younger entries exceed this many bytes, the oldest are removed
+    /// until they fit.
+    pub max_bytes: u64,
+}
+
+impl Default for CacheLimits {
+    fn default() -> Self {
+        Self {
+            max_age: Duration::from_secs(CACHE_MAX_AGE_DAYS * 24 * 60 * 60),
+            max_bytes: CACHE_MAX_BYTES,
+        }
+    }
+}
+
+/// Size of the cache, as reported by `ssg cache stats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub files: usize,
+    pub bytes: u64,
+    /// Modification time of the least recently written entry.
+    pub oldest: Option<SystemTime>,
+}
+
+/// What a `gc` run removed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GcReport {
+    pub removed: usize,
+    pub freed: u64,
+}
+
+struct Entry {
+    path: PathBuf,
+    bytes: u64,
+    modified: SystemTime,
+}
+
+fn entries(dir: &Path) -> Vec<Entry> {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            let meta = e.metadata().ok()?;
+            Some(Entry {
+                path: e.into_path(),
+                bytes: meta.len(),
+                modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            })
+        })
+        .collect()
+}
+
+/// Count the files under `dir`; a missing cache is empty.
+pub fn stats(dir: &Path) -> CacheStats {
+    entries(dir)
+        .into_iter()
+        .fold(CacheStats::default(), |acc, e| CacheStats {
+            files: acc.files + 1,
+            bytes: acc.bytes + e.bytes,
+            oldest: Some(acc.oldest.map_or(e.modified, |o| o.min(e.modified))),
+        })
+}
+
+/// Remove expired entries, then the oldest remaining ones until the cache
+/// fits in `limits.max_bytes`. Directories left empty are removed too.
+pub fn gc(dir: &Path, limits: CacheLimits, now: SystemTime) -> color_eyre::Result<GcReport> {
+    let mut entries = entries(dir);
+    entries.sort_by_key(|e| e.modified);
+
+    let mut total: u64 = entries.iter().map(|e| e.bytes).sum();
+    let mut report = GcReport::default();
+    for entry in entries {
+        let age = now.duration_since(entry.modified).unwrap_or_default();
+        if age <= limits.max_age && total <= limits.max_bytes {
+            // Sorted oldest first, so nothing after this qualifies either.
+            break;
+        }
+        remove_file(&entry.path)?;
+        total -= entry.bytes;
+        report.removed += 1;
+        report.freed += entry.bytes;
+    }
+
+    remove_empty_dirs(dir)?;
+    Ok(report)
+}
+
+/// Delete the whole cache directory.
+pub fn clear(dir: &Path) -> color_eyre::Result<()> {
+    match fs::remove_dir_all(dir) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => {
+            Err(e).with_note(|| format!("While removing {}", dir.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn remove_file(path: &Path) -> color_eyre::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => {
+            Err(e).with_note(|| format!("While removing {}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn remove_empty_dirs(dir: &Path) -> color_eyre::Result<()> {
+    // Children come before their parents, so nested empty directories
+    // collapse in one pass. The cache root itself is kept.
+    for entry in WalkDir::new(dir)
+        .min_depth(1)
+        .contents_first(true)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_dir())
+    {
+        if fs::read_dir(entry.path())?.next().is_none() {
+            fs::remove_dir(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+/// Human-readable byte count for reports.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests;
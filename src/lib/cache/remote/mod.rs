@@ -0,0 +1,149 @@
+//! Optional remote backing for the build cache, so CI builds can start warm
+//! without committing `CACHE_DIR`.
+//!
+//! The remote is any HTTP store that serves `GET <base>/<key>` and, when
+//! writes are enabled, accepts `PUT <base>/<key>`: a static file server, a
+//! WebDAV share, or an S3 bucket through its HTTP endpoint. Reads are
+//! read-through: an entry is only fetched when the local copy is missing.
+use std::{
+    collections::{BTreeMap, hash_map::DefaultHasher},
+    env, fs,
+    hash::{Hash, Hasher},
+    path::Path,
+    sync::Mutex,
+    time::Duration,
+};
+
+use color_eyre::{Section, eyre::eyre};
+
+use crate::config::{CACHE_REMOTE_URL, CACHE_REMOTE_WRITE};
+
+/// Overrides `CACHE_REMOTE_URL`.
+pub const REMOTE_URL_ENV: &str = "SSG_CACHE_REMOTE";
+/// Set to `1` to upload changed entries after a build.
+pub const REMOTE_WRITE_ENV: &str = "SSG_CACHE_REMOTE_WRITE";
+/// Bearer token sent with every request, if set.
+pub const REMOTE_TOKEN_ENV: &str = "SSG_CACHE_TOKEN";
+
+const REMOTE_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct RemoteCache {
+    base: String,
+    write: bool,
+    token: Option<String>,
+    agent: ureq::Agent,
+    /// Content hash of each entry as last seen on the remote, so unchanged
+    /// entries are not uploaded again.
+    seen: Mutex<BTreeMap<String, u64>>,
+}
+
+impl RemoteCache {
+    pub fn new(base: &str, write: bool, token: Option<String>) -> Self {
+        Self {
+            base: base.trim_end_matches('/').to_string(),
+            write,
+            token,
+            agent: ureq::Agent::config_builder()
+                .timeout_global(Some(REMOTE_TIMEOUT))
+                .http_status_as_error(false)
+                .build()
+                .into(),
+            seen: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// The remote configured through the environment or `CACHE_REMOTE_URL`,
+    /// if any.
+    pub fn from_env() -> Option<Self> {
+        let base = env::var(REMOTE_URL_ENV)
+            .ok()
+            .filter(|v| !v.is_empty())
+            .or(CACHE_REMOTE_URL.map(str::to_owned))?;
+        let write = env::var(REMOTE_WRITE_ENV).map_or(CACHE_REMOTE_WRITE, |v| v == "1");
+        let token = env::var(REMOTE_TOKEN_ENV).ok().filter(|v| !v.is_empty());
+        Some(Self::new(&base, write, token))
+    }
+
+    pub fn url_for(&self, key: &str) -> String {
+        format!("{}/{key}", self.base)
+    }
+
+    /// Copy `key` from the remote into `dir` unless it is already there.
+    /// Failures only cost a cold cache, so they are reported and ignored.
+    pub fn warm(&self, dir: &Path, key: &str) {
+        let path = dir.join(key);
+        if path.exists() {
+            return;
+        }
+        match self.get(key) {
+            Ok(Some(data)) => {
+                self.remember(key, &data);
+                if let Err(e) = fs::create_dir_all(dir).and_then(|_| fs::write(&path, &data)) {
+                    eprintln!("warning: could not write {}: {e}", path.display());
+                }
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("warning: remote cache read of {key} failed: {e}"),
+        }
+    }
+
+    /// Upload `key` from `dir` if writes are enabled and its content differs
+    /// from what the remote last returned.
+    pub fn publish(&self, dir: &Path, key: &str) -> color_eyre::Result<()> {
+        if !self.write {
+            return Ok(());
+        }
+        let Ok(data) = fs::read(dir.join(key)) else {
+            return Ok(());
+        };
+        if self.lock().get(key) == Some(&content_hash(&data)) {
+            return Ok(());
+        }
+        self.put(key, &data)
+            .with_note(|| format!("While uploading {key} to the remote cache"))?;
+        self.remember(key, &data);
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> color_eyre::Result<Option<Vec<u8>>> {
+        let mut request = self.agent.get(&self.url_for(key));
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", &format!("Bearer {token}"));
+        }
+        let mut response = request.call()?;
+        match response.status().as_u16() {
+            200 => Ok(Some(response.body_mut().read_to_vec()?)),
+            404 | 403 => Ok(None),
+            status => Err(eyre!("HTTP {status}")),
+        }
+    }
+
+    fn put(&self, key: &str, data: &[u8]) -> color_eyre::Result<()> {
+        let mut request = self.agent.put(&self.url_for(key));
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", &format!("Bearer {token}"));
+        }
+        let response = request.send(data)?;
+        if !response.status().is_success() {
+            return Err(eyre!("HTTP {}", response.status()));
+        }
+        Ok(())
+    }
+
+    fn remember(&self, key: &str, data: &[u8]) {
+        self.lock().insert(key.to_string(), content_hash(data));
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, u64>> {
+        self.seen.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn content_hash(data: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,39 @@
+use std::fs;
+
+use tempfile::TempDir;
+
+use crate::cache::remote::RemoteCache;
+
+#[test]
+fn keys_are_joined_onto_the_base_url() {
+    let remote = RemoteCache::new("https://cache.example.com/ssg/", false, None);
+
+    assert_eq!(
+        remote.url_for("link-titles.json"),
+        "https://cache.example.com/ssg/link-titles.json"
+    );
+}
+
+#[test]
+fn existing_local_entries_are_not_fetched() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("entry"), "local").unwrap();
+    // Unroutable, so any request would fail loudly rather than pass.
+    let remote = RemoteCache::new("http://127.0.0.1:9", false, None);
+
+    remote.warm(dir.path(), "entry");
+
+    assert_eq!(
+        fs::read_to_string(dir.path().join("entry")).unwrap(),
+        "local"
+    );
+}
+
+#[test]
+fn publishing_is_a_no_op_when_read_only() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("entry"), "local").unwrap();
+    let remote = RemoteCache::new("http://127.0.0.1:9", false, None);
+
+    remote.publish(dir.path(), "entry").unwrap();
+}
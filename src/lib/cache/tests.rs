@@ -0,0 +1,70 @@
+use std::{
+    fs,
+    time::{Duration, SystemTime},
+};
+
+use tempfile::TempDir;
+
+use crate::cache::{CacheLimits, CacheStats, clear, format_bytes, gc, stats};
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+fn write_aged(dir: &TempDir, name: &str, bytes: usize, age: Duration) {
+    let path = dir.path().join(name);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, vec![b'x'; bytes]).unwrap();
+    let file = fs::File::options().write(true).open(&path).unwrap();
+    file.set_modified(SystemTime::now() - age).unwrap();
+}
+
+#[test]
+fn stats_sum_every_file() {
+    let dir = TempDir::new().unwrap();
+    write_aged(&dir, "a.json", 10, DAY);
+    write_aged(&dir, "katex/b", 5, Duration::ZERO);
+
+    let s = stats(dir.path());
+
+    assert_eq!(s.files, 2);
+    assert_eq!(s.bytes, 15);
+    assert!(s.oldest.is_some());
+    assert_eq!(stats(&dir.path().join("missing")), CacheStats::default());
+}
+
+#[test]
+fn gc_removes_expired_then_oldest_over_budget() {
+    let dir = TempDir::new().unwrap();
+    write_aged(&dir, "expired/old", 10, 100 * DAY);
+    write_aged(&dir, "older", 10, 3 * DAY);
+    write_aged(&dir, "newer", 10, DAY);
+
+    let limits = CacheLimits {
+        max_age: 30 * DAY,
+        max_bytes: 15,
+    };
+    let report = gc(dir.path(), limits, SystemTime::now()).unwrap();
+
+    assert_eq!(report.removed, 2);
+    assert_eq!(report.freed, 20);
+    assert!(dir.path().join("newer").exists());
+    assert!(!dir.path().join("older").exists());
+    assert!(!dir.path().join("expired").exists());
+}
+
+#[test]
+fn clear_tolerates_missing_cache() {
+    let dir = TempDir::new().unwrap();
+    write_aged(&dir, "cache/a", 1, Duration::ZERO);
+
+    clear(&dir.path().join("cache")).unwrap();
+    clear(&dir.path().join("cache")).unwrap();
+
+    assert!(!dir.path().join("cache").exists());
+}
+
+#[test]
+fn bytes_are_formatted_with_binary_units() {
+    assert_eq!(format_bytes(512), "512 B");
+    assert_eq!(format_bytes(1536), "1.5 KiB");
+    assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MiB");
+}
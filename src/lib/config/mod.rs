@@ -1,35 +1,609 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
 pub const INPUT_DIR: &str = "contents";
 pub const OUTPUT_DIR: &str = "public";
 pub const POSTS_DIR: &str = "posts";
 pub const TAGS_DIR: &str = "tags";
 
+/// A directory of sources merged into the site.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ContentRoot {
+    /// Relative to the site root, or absolute for content kept elsewhere,
+    /// e.g. a notes repository checked out beside the site.
+    pub dir: &'static str,
+    /// Path under `POSTS_DIR` its pages are published at; empty for the top.
+    pub prefix: &'static str,
+}
+
+// Every directory whose sources make up the site. Two sources landing on the
+// same page fail the build.
+pub const CONTENT_ROOTS: &[ContentRoot] = &[ContentRoot {
+    dir: INPUT_DIR,
+    prefix: "",
+}];
+// Entries in a content root that are never sources, as gitignore-style
+// patterns relative to the root; `.gitignore` and `.ssgignore` files in the
+// root add more. Symlinked directories are followed, except back into
+// themselves.
+pub const DISCOVERY_EXCLUDES: &[&str] = &[".git/", "*~", ".#*", "#*#", ".*.sw?"];
+
+/// Where a post's date comes from when its frontmatter gives none.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DateSource {
+    /// The commit that added the source file.
+    Git,
+    /// The source file's modification time.
+    FileModified,
+}
+
+// Tried in order for posts without a `ctime`. Posts none of them can date are
+// listed last, under an "Undated" heading.
+pub const DATE_FALLBACKS: &[DateSource] = &[DateSource::Git, DateSource::FileModified];
+// Posts without an `mtime` take the date of the last commit touching their
+// source, when that is later than their `ctime`. Costs a `git log` per post.
+pub const GIT_MTIME: bool = false;
+
+// Stream page and feeds for `type: note` posts.
+pub const NOTES_DIR: &str = "notes";
+// Portfolio data and the optional Markdown detail pages for it, relative to
+// the site root; the rendered pages go to the same directory under the output.
+pub const PROJECTS_FILE: &str = "projects.yml";
+pub const PROJECTS_DIR: &str = "projects";
+// Registry of post authors, relative to the site root, and the directory their
+// bio pages and feeds go to. Without the file, authors are still credited by
+// the key they are named with.
+pub const AUTHORS_FILE: &str = "authors.yml";
+pub const AUTHORS_DIR: &str = "authors";
+// Titles and descriptions for tag pages and feeds, relative to the site root.
+// Tags it does not list keep "Tag: …" and "Posts tagged …".
+pub const TAGS_FILE: &str = "tags.yml";
+// Grid of every `type: photo` post.
+pub const GALLERY_DIR: &str = "gallery";
+// Per-year and per-month listings (`archive/2024/index.html`,
+// `archive/2024/03/index.html`), linked from the index's year headings.
+pub const ARCHIVE_DIR: &str = "archive";
+/// What becomes of a post once its `expires:` date has passed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExpiredPosts {
+    /// Its page stays up under an "archived" banner, but it is left out of
+    /// listings, feeds, and suggestions.
+    Archived,
+    /// It is not published at all.
+    Unpublished,
+}
+
+pub const EXPIRED_POSTS: ExpiredPosts = ExpiredPosts::Archived;
+
+// Landing pages of post series (`series:` in frontmatter).
+pub const SERIES_DIR: &str = "series";
+// Page for visitors who follow a feed link, explaining feeds and listing them.
+pub const FEEDS_DIR: &str = "feed";
+// Optional source of `404.html`, relative to INPUT_DIR. It is not published
+// as a post; sites without one get a plain "Page not found" page.
+pub const NOT_FOUND_PAGE: &str = "404.md";
+// Sources under this directory of INPUT_DIR, like those with `type: page`, are
+// standalone pages: published at the site root (`pages/about.md` as
+// `about.html`) and left out of listings, tag pages, and feeds.
+pub const PAGES_DIR: &str = "pages";
+// Build caches (fetched link titles, ...), kept between builds.
+pub const CACHE_DIR: &str = ".cache";
+// Lock file in the site root held while a build writes OUTPUT_DIR, so watch
+// mode and a manual build take turns; a build waits this long for another to
+// finish before giving up.
+pub const BUILD_LOCK_FILE: &str = ".ssg-lock";
+pub const BUILD_LOCK_WAIT: Duration = Duration::from_secs(60);
+// `serve` rebuilds once per burst of changes, after WATCH_DEBOUNCE passes
+// without another, and never for paths matching WATCH_IGNORE (patterns as in
+// DISCOVERY_EXCLUDES): editor swap and backup files, `.git`, and files a
+// build writes under a temporary name. WATCH_POLLING looks for changes every
+// WATCH_POLL_INTERVAL instead of relying on the platform's notifications,
+// for filesystems without them; it is also the fallback when they fail.
+pub const WATCH_IGNORE: &[&str] = &[
+    ".git/",
+    "*~",
+    ".#*",
+    "#*#",
+    ".*.sw?",
+    "4913",
+    "*___jb_tmp___",
+    "*___jb_old___",
+    ".DS_Store",
+    "*.partial",
+    ".ssg-lock",
+];
+pub const WATCH_DEBOUNCE: Duration = Duration::from_millis(200);
+pub const WATCH_POLLING: bool = false;
+pub const WATCH_POLL_INTERVAL: Duration = Duration::from_secs(1);
+// Under `--progress`, stages run on the worker pool show what each worker is
+// doing, one line per worker up to this many.
+pub const PROGRESS_WORKER_LINES: usize = 8;
+// Cache entries untouched for this many days are dropped after each build (and
+// by `ssg cache gc`), as are the oldest entries once the cache exceeds
+// CACHE_MAX_BYTES.
+pub const CACHE_MAX_AGE_DAYS: u64 = 90;
+pub const CACHE_MAX_BYTES: u64 = 256 * 1024 * 1024;
+// HTTP store backing the cache (see `cache::remote`); `SSG_CACHE_REMOTE`
+// overrides it. Uploading after a build is opt-in, or `SSG_CACHE_REMOTE_WRITE=1`.
+pub const CACHE_REMOTE_URL: Option<&str> = None;
+pub const CACHE_REMOTE_WRITE: bool = false;
+// Fetch the title of a link post's target when its frontmatter has none. A
+// target that has no title or cannot be fetched is not tried again for
+// LINK_TITLE_RETRY_DAYS.
+pub const FETCH_LINK_TITLES: bool = true;
+pub const LINK_TITLE_RETRY_DAYS: u64 = 7;
+// Download images and scripts that pages load from the hosts listed here (or
+// their subdomains) and serve copies from PROXIED_DIR, so pages make no
+// third-party requests for them. Other hosts are left alone.
+pub const PROXY_EXTERNAL_RESOURCES: bool = false;
+pub const PROXY_ALLOWED_HOSTS: &[&str] = &[];
+pub const PROXIED_DIR: &str = "external";
+// User templates overriding the built-in page shells.
+pub const TEMPLATES_DIR: &str = "templates";
+
+// Emit `posts/foo/index.html` instead of `posts/foo.html` so pages are linked
+// through extensionless, directory-style URLs.
+pub const PRETTY_URLS: bool = false;
+
+// Links to other sites get `rel="noopener noreferrer"`. They can also open in
+// a new tab and end in a small icon so readers know they are leaving. Links
+// to SITE_BASE_URL's host count as internal.
+pub const EXTERNAL_LINKS_NOREFERRER: bool = true;
+pub const EXTERNAL_LINKS_NEW_TAB: bool = false;
+pub const EXTERNAL_LINK_ICON: bool = false;
+
+// Curly quotes, dashes, and ellipses in prose. Applied by a transformer rather
+// than the parser so code, math, and epigraph markers see the raw text.
+pub const SMART_PUNCTUATION: bool = true;
+// Leading markers that introduce an epigraph attribution. Both the ASCII and
+// typographic dashes are listed so detection does not depend on whether smart
+// punctuation has already run.
+pub const EPIGRAPH_ATTRIBUTION_MARKERS: &[&str] = &["---", "--", "—", "–", "―"];
+
+/// How a blockquote is recognised as an epigraph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EpigraphDetection {
+    /// The final paragraph must begin with an attribution marker and follow
+    /// at least one quoted paragraph.
+    LeadingDash,
+    /// Any text run in the final paragraph beginning with a marker qualifies.
+    LastParagraph,
+    /// Only blockquotes opening with `EPIGRAPH_MARKER` are epigraphs.
+    Explicit,
+}
+
+pub const EPIGRAPH_DETECTION: EpigraphDetection = EpigraphDetection::LeadingDash;
+// Opening a blockquote with this marks it as an epigraph whatever the
+// detection mode, e.g. `> {.epigraph}` on a line of its own. Its final
+// paragraph is the attribution only if it begins with an attribution marker.
+pub const EPIGRAPH_MARKER: &str = "{.epigraph}";
+
+// Source extensions converted through pandoc instead of the Markdown parser,
+// with the pandoc reader used for each. The HTML pandoc produces goes into the
+// usual page shell; its metadata (`title`, `date`, `keywords`, ...) becomes the
+// page's frontmatter.
+pub const PANDOC_FORMATS: &[(&str, &str)] = &[("org", "org"), ("rst", "rst")];
+pub const PANDOC_COMMAND: &str = "pandoc";
+
+// `{{ exec "cmd args" }}` shortcodes run a command at build time and insert its
+// stdout into the page. Off by default; only commands named here may run, and
+// each is killed after EXEC_TIMEOUT.
+pub const EXEC_SHORTCODES: bool = false;
+pub const EXEC_ALLOWED_COMMANDS: &[&str] = &["figlet"];
+pub const EXEC_TIMEOUT: Duration = Duration::from_secs(10);
+
+// `{{ map lat lon zoom }}` shortcodes become a static map image, stitched at
+// build time from MAP_TILE_URL tiles ({z}, {x}, {y} filled in) and centred on
+// the point, that links to MAP_LINK_URL ({lat}, {lon}, {zoom}) for an
+// interactive view. Readers load nothing from the tile provider.
+pub const MAP_TILE_URL: &str = "https://tile.openstreetmap.org/{z}/{x}/{y}.png";
+pub const MAP_LINK_URL: &str =
+    "https://www.openstreetmap.org/?mlat={lat}&mlon={lon}#map={zoom}/{lat}/{lon}";
+pub const MAP_ATTRIBUTION: &str = "© OpenStreetMap contributors";
+// Size of the rendered map in pixels, and where maps go under the output
+// directory.
+pub const MAP_SIZE: (u32, u32) = (640, 360);
+pub const MAPS_DIR: &str = "maps";
+
+// Scripts and stylesheets a page lists under `scripts:` and `styles:` in its
+// frontmatter, for one-off experiments. Ignored unless PAGE_ASSETS is on.
+// Each page's scripts and styles must fit their byte budgets or the build
+// fails; every tag gets an integrity hash and scripts are deferred.
+pub const PAGE_ASSETS: bool = false;
+pub const PAGE_SCRIPT_BUDGET: u64 = 32 * 1024;
+pub const PAGE_STYLE_BUDGET: u64 = 16 * 1024;
+
+// Drop rules from style.css that need a class or id no emitted page uses.
+// Classes and ids only scripts add must match a glob in CSS_SAFELIST.
+pub const PRUNE_UNUSED_CSS: bool = true;
+pub const CSS_SAFELIST: &[&str] = &[];
+
+// Preload each post's stylesheet, first image, and the KaTeX fonts its math
+// uses, and list the same hints as `Link` headers in HEADERS_FILE (output
+// root, Netlify / Cloudflare Pages format) for hosts that send them early.
+pub const PRELOAD_HINTS: bool = true;
+pub const HEADERS_FILE: &str = "_headers";
+
+// Extra `.sublime-syntax` definitions, relative to the site root, added to the
+// built-in highlighting languages.
+pub const SYNTAXES_DIR: &str = "syntaxes";
+// Fence languages highlighted with a syntax their token does not name, as
+// (token, syntaxes). The first syntax available wins, so a mapping can name
+// one from SYNTAXES_DIR and fall back to a built-in.
+pub const FENCE_ALIASES: &[(&str, &[&str])] = &[
+    ("nix", &["Nix"]),
+    ("jsx", &["JavaScript (Babel)", "JavaScript"]),
+    ("tsx", &["TypeScriptReact", "TypeScript", "JavaScript"]),
+    ("ts", &["TypeScript", "JavaScript"]),
+    ("console", &["Shell Session", "Bourne Again Shell (bash)"]),
+    (
+        "shell-session",
+        &["Shell Session", "Bourne Again Shell (bash)"],
+    ),
+    ("zsh", &["Bourne Again Shell (bash)"]),
+];
+
+// Code highlighting theme: "bundled" (assets/theme.tmTheme), a syntect
+// built-in such as "InspiredGitHub" or "base16-ocean.dark", or a path to a
+// .tmTheme file relative to the site root.
+pub const HIGHLIGHT_THEME: &str = "bundled";
+
+// Number the lines of every highlighted code block. Individual fences can
+// override this with `linenos` / `nolinenos` after the language
+// (```` ```rs,linenos ````).
+pub const CODE_LINE_NUMBERS: bool = false;
+
+/// How `$…$` and `$$…$$` math is rendered. Pages can override this with
+/// `math: html` or `math: mathml` in their frontmatter.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MathOutput {
+    /// KaTeX HTML, which needs the KaTeX stylesheet and fonts, with MathML
+    /// alongside for screen readers.
+    #[default]
+    Html,
+    /// Native MathML; pages load no KaTeX assets.
+    Mathml,
+}
+
+impl MathOutput {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "html" | "katex" => Some(Self::Html),
+            "mathml" => Some(Self::Mathml),
+            _ => None,
+        }
+    }
+}
+
+pub const MATH_OUTPUT: MathOutput = MathOutput::Html;
+
+/// Which display-math blocks get an equation number. A `\label{eq:name}`
+/// inside a block makes it linkable with `\eqref{eq:name}` or `\ref{eq:name}`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EquationNumbering {
+    Off,
+    /// Only blocks with a `\label`.
+    Labelled,
+    All,
+}
+
+pub const EQUATION_NUMBERING: EquationNumbering = EquationNumbering::Labelled;
+
+/// What to do when two headings on a page would get the same id, or two tags
+/// would get the same page (tags are compared case-insensitively). Every
+/// clash is reported as a diagnostic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlugCollision {
+    /// Number the later ones: `setup`, `setup-2`, `setup-3`.
+    Suffix,
+    /// Share the first one's: tags are combined onto one page, headings keep
+    /// the same id (links land on the first).
+    Merge,
+    /// Fail the build.
+    Error,
+}
+
+pub const HEADING_SLUG_COLLISION: SlugCollision = SlugCollision::Suffix;
+pub const TAG_SLUG_COLLISION: SlugCollision = SlugCollision::Merge;
+
+/// Order of a post's tags in its meta row, listings, and feeds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TagOrder {
+    /// Alphabetically, ignoring case.
+    Name,
+    /// Most used across the site first, ties alphabetically.
+    Frequency,
+}
+
+pub const TAG_ORDER: TagOrder = TagOrder::Name;
+
+// Size the tags on `tags/index.html` by how many posts carry them, as a cloud,
+// instead of listing them with their counts.
+pub const TAG_CLOUD: bool = false;
+
+// Posts with more tags than this get a warning. `None` allows any number.
+pub const MAX_TAGS_PER_POST: Option<usize> = Some(8);
+
+// Deepest heading level in the table of contents, which starts at h2: `3`
+// lists h2–h3, `2` only h2. Pages override it with `toc_depth:`.
+pub const TOC_DEPTH: u8 = 3;
+
+/// How entries in the table of contents are numbered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TocNumbering {
+    /// `01`, `01.1`, `01.1.1`.
+    Decimal,
+    /// `I`, `I.i`, `I.i.i`.
+    Roman,
+    /// No numbers.
+    Off,
+}
+
+pub const TOC_NUMBERING: TocNumbering = TocNumbering::Decimal;
+
+// Also emit the TOC inline as a collapsed `<details class="toc-inline">`, for
+// narrow viewports where the margin column (and the margin TOC) collapses.
+// The stylesheet shows one copy or the other.
+pub const TOC_COLLAPSIBLE: bool = true;
+
+/// Which margin sidenotes sit in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SidenotePlacement {
+    /// All in the right margin.
+    Right,
+    /// Alternating right and left, starting on the right (`sidenote-left`
+    /// marks the left ones).
+    Alternate,
+}
+
+pub const SIDENOTE_PLACEMENT: SidenotePlacement = SidenotePlacement::Right;
+
+/// How sidenotes are marked in the text and the margin.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SidenoteMarkers {
+    /// `1`, `2`, `3`, ...
+    Numbers,
+    /// `*`, `†`, `‡`, `§`, `‖`, `¶`, then doubled: `**`, `††`, ...
+    Symbols,
+}
+
+pub const SIDENOTE_MARKERS: SidenoteMarkers = SidenoteMarkers::Numbers;
+
+// Restart sidenote markers at each section, i.e. each h2 on the page (`#` in
+// Markdown, before headings are demoted).
+pub const SIDENOTE_RESTART_PER_SECTION: bool = false;
+
+// BibTeX (`.bib`) or CSL-JSON (`.json`) file, relative to the site root, that
+// `[@key]` citations are looked up in. Sites without one cite nothing.
+pub const BIBLIOGRAPHY: &str = "references.bib";
+
+/// How `[@key]` citations appear in the text. Either way, the works a post
+/// cites are listed under "References" at its end.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CitationStyle {
+    /// As sidenotes, numbered along with the footnotes.
+    Sidenote,
+    /// In parentheses in the running text: `(Knuth 1984, p. 3)`.
+    Bracketed,
+}
+
+pub const CITATION_STYLE: CitationStyle = CitationStyle::Sidenote;
+
+// Give top-level paragraphs stable ids (a hash of their text) and a `¶` link
+// revealed on hover, so readers can link to a passage. Pages opt in or out
+// with `paragraph_links:`.
+pub const PARAGRAPH_PERMALINKS: bool = false;
+
+/// How ```` ```mermaid ```` fences become diagrams.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MermaidRendering {
+    /// Emit the source in a `<pre class="mermaid">` and load `MERMAID_SCRIPT`
+    /// on the page to draw it in the browser.
+    Client,
+    /// Render to inline SVG at build time with `MERMAID_CLI`, so pages need no
+    /// script. Falls back to `Client` markup if the CLI fails.
+    Build,
+}
+
+pub const MERMAID_RENDERING: MermaidRendering = MermaidRendering::Client;
+// ES module build of Mermaid loaded for client-side rendering.
+pub const MERMAID_SCRIPT: &str = "https://cdn.jsdelivr.net/npm/mermaid@11/dist/mermaid.esm.min.mjs";
+// Mermaid CLI (`@mermaid-js/mermaid-cli`) used for build-time rendering.
+pub const MERMAID_CLI: &str = "mmdc";
+
+// ImageMagick (`convert` on ImageMagick 6), used for placeholder colours and
+// thumbnails. Without it, images are left untinted and listings unillustrated.
+pub const IMAGE_CLI: &str = "magick";
+// Tint each local image's frame with its dominant colour so the space reserved
+// by width/height isn't blank while it loads.
+pub const IMAGE_PLACEHOLDER_COLORS: bool = true;
+// Edge of the square thumbnails cut from each post's primary image, in pixels,
+// and where they go under the output directory.
+pub const THUMBNAIL_SIZE: u32 = 160;
+pub const THUMBNAILS_DIR: &str = "thumbs";
+
+/// Where index and tag listings show a post's thumbnail.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ListingThumbnails {
+    Off,
+    /// Before the date and title.
+    Leading,
+    /// After the title.
+    Trailing,
+}
+
+pub const LISTING_THUMBNAILS: ListingThumbnails = ListingThumbnails::Leading;
+
+/// Design tokens, written as custom properties in a `:root` block at the top
+/// of the stylesheet so the theme can change here without touching the CSS.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Theme {
+    /// `--accent`, for links and highlights.
+    pub accent: &'static str,
+    /// `--font-body`, `--font-heading`, and `--font-mono` font stacks.
+    pub body_font: &'static str,
+    pub heading_font: &'static str,
+    pub mono_font: &'static str,
+    /// `--column-width`, the width of the text column.
+    pub column_width: &'static str,
+    /// `--column-width-narrow`, the text column on screens up to
+    /// `narrow_screen` pixels wide.
+    pub column_width_narrow: &'static str,
+    pub narrow_screen: u32,
+}
+
+impl Theme {
+    /// `sizes` attribute of images set in the text column.
+    pub fn image_sizes(&self) -> String {
+        format!(
+            "(max-width: {}px) {}, {}",
+            self.narrow_screen, self.column_width_narrow, self.column_width
+        )
+    }
+}
+
+pub const THEME: Theme = Theme {
+    accent: "#8b0000",
+    body_font: "\"Iowan Old Style\", \"Palatino Linotype\", Palatino, Georgia, serif",
+    heading_font: "\"Iowan Old Style\", \"Palatino Linotype\", Palatino, Georgia, serif",
+    mono_font: "ui-monospace, \"SF Mono\", Menlo, Consolas, monospace",
+    column_width: "55vw",
+    column_width_narrow: "92vw",
+    narrow_screen: 760,
+};
+
+/// Oldest browser releases the stylesheet has to work in, as major versions;
+/// `None` leaves a browser out. Newer CSS, such as nesting and `oklch()`
+/// colours, is lowered and vendor-prefixed for them when the stylesheet is
+/// built. With every browser left out, the CSS is published as written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BrowserTargets {
+    pub chrome: Option<u32>,
+    pub edge: Option<u32>,
+    pub firefox: Option<u32>,
+    pub safari: Option<u32>,
+    pub ios_safari: Option<u32>,
+    pub samsung: Option<u32>,
+}
+
+pub const BROWSER_TARGETS: BrowserTargets = BrowserTargets {
+    chrome: Some(109),
+    edge: Some(109),
+    firefox: Some(115),
+    safari: Some(15),
+    ios_safari: Some(15),
+    samsung: Some(20),
+};
+
 // Site-wide metadata used for feeds and absolute links.
 pub const SITE_TITLE: &str = "Dysthesis";
 pub const SITE_DESCRIPTION: &str = "Dysthesis' blog";
 pub const SITE_BASE_URL: &str = "https://dysthesis.com/";
 pub const SITE_AUTHOR: &str = "Dysthesis";
-// Fallback image for OpenGraph/Twitter cards.
+// Shape of `<title>` and og:title: `{page}` is the page's own title and
+// `{site}` is `SITE_TITLE`. A page's `seo_title:` replaces the whole title.
+pub const SITE_TITLE_TEMPLATE: &str = "{page} · {site}";
+// Language of the site's content (BCP 47), declared in feeds and pages.
+pub const SITE_LANGUAGE: &str = "en";
+// Copyright notice carried by feeds as RSS `<copyright>` / Atom `<rights>`.
+pub const SITE_RIGHTS: Option<&str> = None;
+// Feed icon (square) and logo (wider), relative to the site root.
+pub const SITE_ICON: Option<&str> = None;
+pub const SITE_LOGO: Option<&str> = None;
+// Fallback image for OpenGraph/Twitter cards, and its alt text.
 pub const SITE_DEFAULT_OG_IMAGE: Option<&str> = Some("assets/social-default.png");
+pub const SITE_DEFAULT_OG_IMAGE_ALT: Option<&str> = None;
+// Text macros: `@@name@@` in a post's prose, link targets, and link titles
+// becomes the value, so a handle or affiliate ID changes in one place. Code
+// is left alone.
+pub const TEXT_MACROS: &[(&str, &str)] = &[("site", SITE_TITLE), ("author", SITE_AUTHOR)];
+
+/// An entry in the site's navigation menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct NavLink {
+    pub label: &'static str,
+    /// Relative to the site root, or a full URL or root-relative path, which
+    /// are used as written.
+    pub href: &'static str,
+}
+
+// Menu rendered at the top of every page, in order; templates get it as
+// `nav`, and the raw entries as `site.nav`. Empty for no menu.
+pub const NAV: &[NavLink] = &[
+    NavLink {
+        label: "Home",
+        href: "index.html",
+    },
+    NavLink {
+        label: "Search",
+        href: "search/index.html",
+    },
+    NavLink {
+        label: "Feeds",
+        href: "feed/index.html",
+    },
+];
 
 /// Convenience container for site metadata used across rendering.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct SiteMeta {
     pub title: String,
+    pub title_template: String,
     pub description: String,
     pub base_url: String,
     pub author: String,
+    pub language: String,
     pub default_image: Option<String>,
+    pub default_image_alt: Option<String>,
+    pub nav: Vec<NavLink>,
+}
+
+impl SiteMeta {
+    /// Full title of a page titled `page`, per the title template. The site's
+    /// own title stands alone.
+    pub fn page_title(&self, page: &str) -> String {
+        if page == self.title {
+            return page.to_owned();
+        }
+        self.title_template
+            .replace("{site}", &self.title)
+            .replace("{page}", page)
+    }
 }
 
 pub fn site_meta() -> SiteMeta {
     SiteMeta {
         title: SITE_TITLE.to_string(),
+        title_template: SITE_TITLE_TEMPLATE.to_string(),
         description: SITE_DESCRIPTION.to_string(),
         base_url: SITE_BASE_URL.trim_end_matches('/').to_string(),
         author: SITE_AUTHOR.to_string(),
+        language: SITE_LANGUAGE.to_string(),
         default_image: SITE_DEFAULT_OG_IMAGE.map(|s| s.to_string()),
+        default_image_alt: SITE_DEFAULT_OG_IMAGE_ALT.map(|s| s.to_string()),
+        nav: NAV.to_vec(),
     }
 }
 
+// Listings with more entries than this get a row of links to their year
+// headings at the top.
+pub const LISTING_JUMP_NAV_THRESHOLD: usize = 20;
+
+// Maximum number of posts in a page's "Related" section; 0 disables it.
+pub const RELATED_POSTS_LIMIT: usize = 5;
+// Maximum number of posts in a page's "Similar" section, suggested by shared
+// vocabulary rather than tags (see `crate::terms`); 0 disables it.
+pub const CONTENT_RELATED_LIMIT: usize = 3;
+// Terms of a post listed in its `<meta name="keywords">`; 0 leaves it out.
+pub const KEYWORDS_PER_POST: usize = 6;
+// Page of the site's most used terms and the posts using them, and how many
+// terms it lists; 0 disables it.
+pub const VOCABULARY_DIR: &str = "vocabulary";
+pub const VOCABULARY_TERMS: usize = 100;
+
+// Number of articles per `api/articles/page-N.json` page.
+pub const API_PAGE_SIZE: usize = 20;
+
 // Maximum number of items to include in feeds.
 pub const FEED_ITEM_LIMIT: usize = 50;
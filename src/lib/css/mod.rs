@@ -1,5 +1,318 @@
-use std::{fs, path::Path};
+//! The site stylesheet: `style.css`, after a `:root` block of the `THEME`
+//! tokens as custom properties, lowered for `BROWSER_TARGETS`. With
+//! `PRUNE_UNUSED_CSS` on,
+//! rules whose selectors need a class or id that no emitted page uses are
+//! dropped once every page has been written. Element and attribute selectors
+//! are always kept, as are classes inside `:not()`, `:is()`, and the like;
+//! classes only scripts add belong in `CSS_SAFELIST`.
+//!
+//! Rules lightningcss cannot parse are dropped rather than failing the
+//! build; [`lint_css`] reports them, with unknown properties and repeated
+//! selectors, as diagnostics against `style.css`.
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+    sync::{Arc, RwLock},
+};
+
+use color_eyre::{Section, eyre::eyre};
+use lightningcss::{
+    properties::{Property, custom::CustomPropertyName},
+    rules::{CssRule, CssRuleList, Location},
+    selector::{Component, Selector},
+    stylesheet::{MinifyOptions, ParserOptions, PrinterOptions, StyleSheet},
+    targets::{Browsers, Targets},
+    traits::ToCss,
+};
+use lol_html::{HtmlRewriter, Settings, element};
+use walkdir::WalkDir;
+
+use crate::{
+    config::{BROWSER_TARGETS, BrowserTargets, THEME, Theme},
+    diagnostics::{DocumentSink, Severity},
+    ignore::glob_match,
+};
 
 pub fn build_css(css_path: &Path) -> color_eyre::Result<String> {
-    fs::read_to_string(css_path).map_err(Into::into)
+    let css = fs::read_to_string(css_path)?;
+    let css = format!("{}{css}", theme_tokens(&THEME));
+    lower_css(&css, &BROWSER_TARGETS).with_note(|| format!("While building {}", css_path.display()))
+}
+
+/// `css` with the features `targets` lack compiled away and the vendor
+/// prefixes they need added.
+pub fn lower_css(css: &str, targets: &BrowserTargets) -> color_eyre::Result<String> {
+    let browsers = [
+        targets.chrome,
+        targets.edge,
+        targets.firefox,
+        targets.safari,
+        targets.ios_safari,
+        targets.samsung,
+    ];
+    if browsers.iter().all(Option::is_none) {
+        return Ok(css.to_owned());
+    }
+    // lightningcss packs versions as major << 16 | minor << 8 | patch.
+    let version = |major: Option<u32>| major.map(|major| major << 16);
+    let targets = Targets::from(Browsers {
+        chrome: version(targets.chrome),
+        edge: version(targets.edge),
+        firefox: version(targets.firefox),
+        safari: version(targets.safari),
+        ios_saf: version(targets.ios_safari),
+        samsung: version(targets.samsung),
+        ..Browsers::default()
+    });
+
+    let mut sheet = StyleSheet::parse(css, recovering())
+        .map_err(|e| eyre!("could not parse the stylesheet: {e}"))?;
+    sheet
+        .minify(MinifyOptions {
+            targets,
+            ..MinifyOptions::default()
+        })
+        .map_err(|e| eyre!("could not lower the stylesheet: {e}"))?;
+    let printed = sheet
+        .to_css(PrinterOptions {
+            targets,
+            ..PrinterOptions::default()
+        })
+        .map_err(|e| eyre!("could not print the stylesheet: {e}"))?;
+    Ok(printed.code)
+}
+
+/// `theme` as custom properties on `:root`.
+pub fn theme_tokens(theme: &Theme) -> String {
+    format!(
+        ":root {{
+  --accent: {};
+  --font-body: {};
+  --font-heading: {};
+  --font-mono: {};
+  --column-width: {};
+  --column-width-narrow: {};
+  --narrow-screen: {}px;
+}}
+",
+        theme.accent,
+        theme.body_font,
+        theme.heading_font,
+        theme.mono_font,
+        theme.column_width,
+        theme.column_width_narrow,
+        theme.narrow_screen,
+    )
 }
+
+/// Parser options that skip invalid rules and declarations.
+fn recovering<'i>() -> ParserOptions<'i> {
+    ParserOptions {
+        error_recovery: true,
+        ..ParserOptions::default()
+    }
+}
+
+/// Report what is wrong with `css`, the source of the stylesheet `sink`
+/// reports against: rules and declarations that do not parse (and so are
+/// dropped), properties no browser knows, and selectors repeated within the
+/// same block.
+pub fn lint_css(css: &str, sink: &DocumentSink) {
+    let warnings = Arc::new(RwLock::new(Vec::new()));
+    let options = ParserOptions {
+        warnings: Some(Arc::clone(&warnings)),
+        ..recovering()
+    };
+    let sheet = match StyleSheet::parse(css, options) {
+        Ok(sheet) => sheet,
+        Err(e) => {
+            let offset = e
+                .loc
+                .as_ref()
+                .map_or(0, |loc| offset_of(css, loc.line, loc.column));
+            sink.report_at(Severity::Error, "css", offset, e.kind.to_string());
+            return;
+        }
+    };
+    for warning in warnings.read().map(|w| w.clone()).unwrap_or_default() {
+        let offset = warning
+            .loc
+            .as_ref()
+            .map_or(0, |loc| offset_of(css, loc.line, loc.column));
+        sink.report_at(Severity::Warning, "css", offset, warning.kind.to_string());
+    }
+    lint_rules(css, &sheet.rules, sink);
+}
+
+fn lint_rules(css: &str, rules: &CssRuleList<'_>, sink: &DocumentSink) {
+    let at = |loc: &Location| offset_of(css, loc.line, loc.column);
+    let mut seen: HashMap<String, Location> = HashMap::new();
+    for rule in &rules.0 {
+        match rule {
+            CssRule::Style(style) => {
+                let declarations = style
+                    .declarations
+                    .declarations
+                    .iter()
+                    .chain(&style.declarations.important_declarations);
+                for declaration in declarations {
+                    if let Property::Custom(custom) = declaration
+                        && let CustomPropertyName::Unknown(name) = &custom.name
+                        && !name.0.starts_with('-')
+                    {
+                        sink.report_at(
+                            Severity::Warning,
+                            "css",
+                            at(&style.loc),
+                            format!("unknown property `{}`", name.0),
+                        );
+                    }
+                }
+                if let Ok(selectors) = style.selectors.to_css_string(PrinterOptions::default()) {
+                    match seen.get(&selectors) {
+                        Some(first) => sink.report_at(
+                            Severity::Warning,
+                            "css",
+                            at(&style.loc),
+                            format!(
+                                "`{selectors}` is repeated from line {}; merge the rules",
+                                first.line + 1
+                            ),
+                        ),
+                        None => {
+                            seen.insert(selectors, style.loc);
+                        }
+                    }
+                }
+                lint_rules(css, &style.rules, sink);
+            }
+            CssRule::Media(media) => lint_rules(css, &media.rules, sink),
+            CssRule::Supports(supports) => lint_rules(css, &supports.rules, sink),
+            CssRule::LayerBlock(layer) => lint_rules(css, &layer.rules, sink),
+            CssRule::Container(container) => lint_rules(css, &container.rules, sink),
+            _ => {}
+        }
+    }
+}
+
+/// Byte offset of a lightningcss position: a 0-based line and a 1-based
+/// column in UTF-16 code units.
+fn offset_of(css: &str, line: u32, column: u32) -> usize {
+    let start: usize = css
+        .split_inclusive('\n')
+        .take(line as usize)
+        .map(str::len)
+        .sum();
+    let text = css[start..].lines().next().unwrap_or_default();
+    let mut units = 1;
+    for (i, c) in text.char_indices() {
+        if units >= column as usize {
+            return start + i;
+        }
+        units += c.len_utf16();
+    }
+    start + text.len()
+}
+
+/// Class names and ids found on a site's pages.
+#[derive(Debug, Default)]
+pub struct UsedSelectors {
+    classes: HashSet<String>,
+    ids: HashSet<String>,
+}
+
+impl UsedSelectors {
+    /// Everything used by the `.html` files under `dir`.
+    pub fn scan_dir(dir: &Path) -> Self {
+        let mut used = Self::default();
+        let pages = WalkDir::new(dir)
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "html"));
+        for page in pages {
+            if let Ok(html) = fs::read_to_string(page.path()) {
+                used.add_html(&html);
+            }
+        }
+        used
+    }
+
+    /// Record the classes and ids in `html`.
+    pub fn add_html(&mut self, html: &str) {
+        let used = RefCell::new(std::mem::take(self));
+        let mut rewriter = HtmlRewriter::new(
+            Settings {
+                element_content_handlers: vec![element!("[class], [id]", |el| {
+                    let mut used = used.borrow_mut();
+                    if let Some(classes) = el.get_attribute("class") {
+                        used.classes
+                            .extend(classes.split_whitespace().map(str::to_owned));
+                    }
+                    if let Some(id) = el.get_attribute("id") {
+                        used.ids.insert(id);
+                    }
+                    Ok(())
+                })],
+                ..Settings::new()
+            },
+            |_: &[u8]| {},
+        );
+        // Only the handlers matter; malformed markup just yields fewer names.
+        let _ = rewriter
+            .write(html.as_bytes())
+            .and_then(|()| rewriter.end());
+        *self = used.into_inner();
+    }
+}
+
+/// `css` without the rules no page can match. Names matching a `safelist`
+/// glob count as used.
+pub fn prune_css(css: &str, used: &UsedSelectors, safelist: &[&str]) -> color_eyre::Result<String> {
+    let mut sheet = StyleSheet::parse(css, ParserOptions::default())
+        .map_err(|e| eyre!("could not parse the stylesheet: {e}"))?;
+    let keep = |names: &HashSet<String>, name: &str| {
+        names.contains(name) || safelist.iter().any(|glob| glob_match(glob, name))
+    };
+    let matches = |selector: &Selector<'_>| {
+        selector
+            .iter_raw_match_order()
+            .all(|component| match component {
+                Component::Class(class) => keep(&used.classes, class),
+                Component::ID(id) => keep(&used.ids, id),
+                _ => true,
+            })
+    };
+    prune_rules(&mut sheet.rules, &matches);
+    let printed = sheet
+        .to_css(PrinterOptions::default())
+        .map_err(|e| eyre!("could not print the stylesheet: {e}"))?;
+    Ok(printed.code)
+}
+
+/// Drop the selectors `matches` rejects, then the rules left without any,
+/// and at-rules left empty.
+fn prune_rules(rules: &mut CssRuleList<'_>, matches: &impl Fn(&Selector<'_>) -> bool) {
+    rules.0.retain_mut(|rule| match rule {
+        CssRule::Style(style) => {
+            style.selectors.0.retain(|selector| matches(selector));
+            prune_rules(&mut style.rules, matches);
+            !style.selectors.0.is_empty()
+        }
+        CssRule::Media(media) => prune_nested(&mut media.rules, matches),
+        CssRule::Supports(supports) => prune_nested(&mut supports.rules, matches),
+        CssRule::LayerBlock(layer) => prune_nested(&mut layer.rules, matches),
+        CssRule::Container(container) => prune_nested(&mut container.rules, matches),
+        _ => true,
+    });
+}
+
+/// Prune the rules of an at-rule, returning whether any are left.
+fn prune_nested(rules: &mut CssRuleList<'_>, matches: &impl Fn(&Selector<'_>) -> bool) -> bool {
+    prune_rules(rules, matches);
+    !rules.0.is_empty()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,132 @@
+use std::path::Path;
+
+use crate::{
+    config::{BrowserTargets, THEME},
+    css::{UsedSelectors, lint_css, lower_css, prune_css, theme_tokens},
+    diagnostics::Diagnostics,
+};
+
+fn used(html: &str) -> UsedSelectors {
+    let mut used = UsedSelectors::default();
+    used.add_html(html);
+    used
+}
+
+#[test]
+fn rules_for_unused_classes_and_ids_are_dropped() {
+    let css = "body { color: black }\n.used { color: red }\n.unused { color: blue }\n#main { margin: 0 }\n#gone { margin: 1px }\n";
+    let used = used(r#"<main id=main><p class="used other">Hi</p></main>"#);
+
+    let pruned = prune_css(css, &used, &[]).unwrap();
+
+    assert!(pruned.contains("body"));
+    assert!(pruned.contains(".used"));
+    assert!(pruned.contains("#main"));
+    assert!(!pruned.contains(".unused"), "{pruned}");
+    assert!(!pruned.contains("#gone"), "{pruned}");
+}
+
+#[test]
+fn selector_lists_keep_only_the_selectors_in_use() {
+    let css = ".a, .b p { color: red }\n";
+    let pruned = prune_css(css, &used(r#"<div class="a"></div>"#), &[]).unwrap();
+
+    assert!(pruned.contains(".a"));
+    assert!(!pruned.contains(".b"), "{pruned}");
+}
+
+#[test]
+fn at_rules_are_pruned_and_dropped_when_empty() {
+    let css = "@media (width > 40em) { .gone { color: red } }\n@media print { .kept { color: red } }\n@font-face { font-family: X; src: url(x.woff2) }\n";
+    let pruned = prune_css(css, &used(r#"<p class="kept"></p>"#), &[]).unwrap();
+
+    assert!(!pruned.contains("40em"), "{pruned}");
+    assert!(pruned.contains("@media print"));
+    assert!(pruned.contains("@font-face"));
+}
+
+#[test]
+fn safelisted_and_negated_classes_are_kept() {
+    let css = ".is-open { display: block }\np:not(.lead) { margin: 0 }\n.menu { color: red }\n";
+    let pruned = prune_css(css, &used("<p></p>"), &["is-*"]).unwrap();
+
+    assert!(pruned.contains(".is-open"));
+    assert!(pruned.contains(":not(.lead)"));
+    assert!(!pruned.contains(".menu"), "{pruned}");
+}
+
+#[test]
+fn theme_tokens_become_root_custom_properties() {
+    let tokens = theme_tokens(&THEME);
+    assert!(tokens.starts_with(":root {\n"));
+    assert!(tokens.contains(&format!("--accent: {};", THEME.accent)));
+    assert!(tokens.contains(&format!("--font-mono: {};", THEME.mono_font)));
+    assert!(tokens.contains(&format!("--narrow-screen: {}px;", THEME.narrow_screen)));
+
+    // Tokens survive pruning, and the image sizes agree with the column.
+    let pruned = prune_css(&tokens, &used(""), &[]).unwrap();
+    assert!(pruned.contains("--column-width"));
+    assert!(THEME.image_sizes().ends_with(THEME.column_width));
+}
+
+#[test]
+fn modern_css_is_lowered_for_old_targets() {
+    let css = ".card { color: oklch(60% 0.15 30); & p { user-select: none } }\n";
+    let old = BrowserTargets {
+        chrome: Some(90),
+        edge: None,
+        firefox: None,
+        safari: Some(13),
+        ios_safari: None,
+        samsung: None,
+    };
+
+    let lowered = lower_css(css, &old).unwrap();
+    assert!(lowered.contains(".card p"), "{lowered}");
+    assert!(!lowered.contains('&'), "{lowered}");
+    assert!(lowered.contains("-webkit-user-select"), "{lowered}");
+    assert!(
+        lowered.contains("rgb") || lowered.contains('#'),
+        "{lowered}"
+    );
+
+    let untargeted = BrowserTargets {
+        chrome: None,
+        safari: None,
+        ..old
+    };
+    assert_eq!(lower_css(css, &untargeted).unwrap(), css);
+}
+
+#[test]
+fn stylesheet_problems_are_reported_with_positions() {
+    let css = "p { colr: red; color: blue }\n.a { margin: 0 }\n\n.a { padding: 0 }\n@media print { .a { color: black } }\n.b { color: red; ] }\n";
+    let diagnostics = Diagnostics::default();
+    let sink = diagnostics.for_document(Path::new("style.css"), css, Vec::new());
+
+    lint_css(css, &sink);
+
+    let reported: Vec<String> = diagnostics.take().iter().map(ToString::to_string).collect();
+    assert!(
+        reported.contains(&"warning: style.css:1:1: css: unknown property `colr`".to_owned()),
+        "{reported:#?}"
+    );
+    assert!(
+        reported.contains(
+            &"warning: style.css:4:1: css: `.a` is repeated from line 2; merge the rules"
+                .to_owned()
+        ),
+        "{reported:#?}"
+    );
+    assert!(
+        reported
+            .iter()
+            .any(|d| d.starts_with("warning: style.css:6:")),
+        "{reported:#?}"
+    );
+    // The `.a` inside `@media` is its own block, not a repeat.
+    assert_eq!(
+        reported.iter().filter(|d| d.contains("repeated")).count(),
+        1
+    );
+}
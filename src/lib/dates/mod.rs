@@ -0,0 +1,58 @@
+//! Dates for posts whose frontmatter has none, from the sources named in
+//! `DATE_FALLBACKS`: when git first saw the file, or when it was last
+//! modified on disk. With `GIT_MTIME`, the last commit touching a file also
+//! stands in for a missing `mtime`.
+use std::{fs, path::Path, process::Command};
+
+use time::OffsetDateTime;
+
+use crate::{config::DateSource, types::IsoDate};
+
+/// The first date any of `sources` gives for the file at `path`.
+pub fn fallback_date(path: &Path, sources: &[DateSource]) -> Option<IsoDate> {
+    sources.iter().find_map(|source| match source {
+        DateSource::Git => git_added(path),
+        DateSource::FileModified => file_modified(path),
+    })
+}
+
+/// Date of the commit that added `path`, following renames. `None` outside
+/// a repository, for untracked files, or without git.
+pub fn git_added(path: &Path) -> Option<IsoDate> {
+    // Newest first; the last line is the commit that added it.
+    let log = git_log(path, &["--follow"])?;
+    IsoDate::parse(log.lines().last()?)
+}
+
+/// Date of the last commit touching `path`. `None` outside a repository,
+/// for untracked files, or without git.
+pub fn git_modified(path: &Path) -> Option<IsoDate> {
+    let log = git_log(path, &["-1"])?;
+    IsoDate::parse(log.lines().next()?)
+}
+
+/// Author dates of the commits touching `path`, one per line, newest first.
+fn git_log(path: &Path, args: &[&str]) -> Option<String> {
+    let dir = path.parent()?;
+    let output = Command::new("git")
+        .current_dir(dir)
+        .arg("log")
+        .args(args)
+        .args(["--format=%as", "--"])
+        .arg(path.file_name()?)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+/// Date `path` was last modified, in UTC.
+pub fn file_modified(path: &Path) -> Option<IsoDate> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    Some(OffsetDateTime::from(modified).date().into())
+}
+
+#[cfg(test)]
+mod tests;
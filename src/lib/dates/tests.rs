@@ -0,0 +1,65 @@
+use std::{fs, process::Command};
+
+use tempfile::TempDir;
+use time::OffsetDateTime;
+
+use crate::{
+    config::DateSource,
+    dates::{fallback_date, file_modified, git_added, git_modified},
+    types::IsoDate,
+};
+
+#[test]
+fn files_are_dated_by_their_modification_time() {
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path().join("post.md");
+    fs::write(&path, "undated").unwrap();
+
+    let today = IsoDate::from(OffsetDateTime::now_utc().date());
+    assert_eq!(file_modified(&path), Some(today.clone()));
+    assert_eq!(
+        fallback_date(&path, &[DateSource::Git, DateSource::FileModified]),
+        Some(today)
+    );
+}
+
+#[test]
+fn sources_that_cannot_date_a_file_give_nothing() {
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path().join("post.md");
+    fs::write(&path, "undated").unwrap();
+
+    // Outside any repository.
+    assert_eq!(fallback_date(&path, &[DateSource::Git]), None);
+    assert_eq!(fallback_date(&path, &[]), None);
+    assert_eq!(file_modified(&tmp.path().join("missing.md")), None);
+}
+
+#[test]
+fn git_dates_a_file_by_its_first_and_last_commits() {
+    let tmp = TempDir::new().unwrap();
+    let git = |args: &[&str], date: &str| {
+        let status = Command::new("git")
+            .current_dir(tmp.path())
+            .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+            .args(args)
+            .env("GIT_AUTHOR_DATE", date)
+            .env("GIT_COMMITTER_DATE", date)
+            .status();
+        status.is_ok_and(|s| s.success())
+    };
+    if !git(&["init", "-q"], "2020-01-01T12:00:00Z") {
+        // No git to ask.
+        return;
+    }
+    let path = tmp.path().join("post.md");
+    fs::write(&path, "first").unwrap();
+    assert!(git(&["add", "post.md"], "2020-01-01T12:00:00Z"));
+    assert!(git(&["commit", "-qm", "add"], "2020-01-01T12:00:00Z"));
+    fs::write(&path, "second").unwrap();
+    assert!(git(&["commit", "-qam", "edit"], "2021-06-15T12:00:00Z"));
+
+    assert_eq!(git_added(&path), IsoDate::parse("2020-01-01"));
+    assert_eq!(git_modified(&path), IsoDate::parse("2021-06-15"));
+    assert_eq!(git_modified(&tmp.path().join("untracked.md")), None);
+}
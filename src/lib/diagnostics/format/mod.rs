@@ -0,0 +1,137 @@
+//! Diagnostics in forms CI systems turn into annotations on the changed
+//! lines: GitHub Actions workflow commands, a JSON array, or a SARIF log for
+//! code scanning. Documents should be named relative to the repository, as
+//! the files were checked in, for the annotations to land.
+use std::str::FromStr;
+
+use color_eyre::{Section, eyre::eyre};
+use serde_json::{Value, json};
+
+use crate::diagnostics::{Diagnostic, Severity};
+
+/// How a build reports its diagnostics.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// One line each on stderr, as `severity: file:line:column: origin: message`.
+    #[default]
+    Text,
+    /// GitHub Actions `::warning file=…::message` workflow commands.
+    Github,
+    /// A JSON array of objects with the fields of each diagnostic.
+    Json,
+    /// A SARIF 2.1.0 log, as code scanning uploads take.
+    Sarif,
+}
+
+impl FromStr for ReportFormat {
+    type Err = color_eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "github" => Ok(Self::Github),
+            "json" => Ok(Self::Json),
+            "sarif" => Ok(Self::Sarif),
+            other => Err(eyre!("unknown report format `{other}`"))
+                .with_note(|| "Use one of text, github, json, or sarif"),
+        }
+    }
+}
+
+impl ReportFormat {
+    /// `diagnostics` as the whole of the format's output.
+    pub fn render(self, diagnostics: &[Diagnostic]) -> String {
+        match self {
+            Self::Text => diagnostics.iter().map(|d| format!("{d}\n")).collect(),
+            Self::Github => diagnostics.iter().map(github_command).collect(),
+            Self::Json => {
+                let all: Vec<Value> = diagnostics.iter().map(json_object).collect();
+                format!("{:#}\n", Value::Array(all))
+            }
+            Self::Sarif => format!("{:#}\n", sarif_log(diagnostics)),
+        }
+    }
+}
+
+/// A workflow command annotating the diagnostic's line; TODO markers become
+/// notices.
+fn github_command(d: &Diagnostic) -> String {
+    let level = match d.severity {
+        Severity::Todo => "notice",
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    };
+    let mut properties = format!("file={}", escape_property(&d.document.to_string_lossy()));
+    if let Some((line, column)) = d.position {
+        properties.push_str(&format!(",line={line},col={column}"));
+    }
+    properties.push_str(&format!(",title={}", escape_property(d.origin)));
+    format!("::{level} {properties}::{}\n", escape_data(&d.message))
+}
+
+/// Workflow command messages end at a newline, so those are encoded, along
+/// with the `%` doing the encoding.
+fn escape_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Property values also end at `,` and `:`.
+fn escape_property(s: &str) -> String {
+    escape_data(s).replace(':', "%3A").replace(',', "%2C")
+}
+
+fn json_object(d: &Diagnostic) -> Value {
+    json!({
+        "severity": d.severity.to_string(),
+        "origin": d.origin,
+        "file": d.document.to_string_lossy(),
+        "line": d.position.map(|(line, _)| line),
+        "column": d.position.map(|(_, column)| column),
+        "message": d.message,
+    })
+}
+
+/// A log of one run of `ssg`, with a rule per origin.
+fn sarif_log(diagnostics: &[Diagnostic]) -> Value {
+    let mut origins: Vec<&str> = diagnostics.iter().map(|d| d.origin).collect();
+    origins.sort_unstable();
+    origins.dedup();
+    let rules: Vec<Value> = origins
+        .iter()
+        .map(|origin| json!({ "id": origin }))
+        .collect();
+    let results: Vec<Value> = diagnostics.iter().map(sarif_result).collect();
+    json!({
+        "version": "2.1.0",
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "runs": [{
+            "tool": { "driver": { "name": "ssg", "rules": rules } },
+            "results": results,
+        }],
+    })
+}
+
+fn sarif_result(d: &Diagnostic) -> Value {
+    let level = match d.severity {
+        Severity::Todo => "note",
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    };
+    let mut location = json!({
+        "artifactLocation": { "uri": d.document.to_string_lossy().replace('\\', "/") },
+    });
+    if let Some((line, column)) = d.position {
+        location["region"] = json!({ "startLine": line, "startColumn": column });
+    }
+    json!({
+        "ruleId": d.origin,
+        "level": level,
+        "message": { "text": d.message },
+        "locations": [{ "physicalLocation": location }],
+    })
+}
+
+#[cfg(test)]
+mod tests;
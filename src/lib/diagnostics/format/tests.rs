@@ -0,0 +1,79 @@
+use std::{path::Path, sync::Arc};
+
+use serde_json::Value;
+
+use crate::diagnostics::{
+    Diagnostic, Severity,
+    format::{ReportFormat, escape_property},
+};
+
+fn diagnostics() -> Vec<Diagnostic> {
+    vec![
+        Diagnostic {
+            severity: Severity::Warning,
+            origin: "cross-reference",
+            document: Arc::from(Path::new("contents/posts/a.md")),
+            offset: Some(12),
+            position: Some((3, 5)),
+            message: "no post `b` to link to".into(),
+        },
+        Diagnostic {
+            severity: Severity::Error,
+            origin: "css",
+            document: Arc::from(Path::new("style.css")),
+            offset: None,
+            position: None,
+            message: "100% broken,\nsee above".into(),
+        },
+    ]
+}
+
+#[test]
+fn formats_are_parsed_by_name() {
+    assert_eq!(
+        "github".parse::<ReportFormat>().unwrap(),
+        ReportFormat::Github
+    );
+    assert_eq!(
+        "sarif".parse::<ReportFormat>().unwrap(),
+        ReportFormat::Sarif
+    );
+    assert!("xml".parse::<ReportFormat>().is_err());
+}
+
+#[test]
+fn github_commands_annotate_lines_and_escape_values() {
+    assert_eq!(
+        ReportFormat::Github.render(&diagnostics()),
+        "::warning file=contents/posts/a.md,line=3,col=5,title=cross-reference::no post `b` to link to\n\
+         ::error file=style.css,title=css::100%25 broken,%0Asee above\n"
+    );
+    assert_eq!(escape_property("a:b,c"), "a%3Ab%2Cc");
+}
+
+#[test]
+fn json_lists_each_diagnostic() {
+    let all: Value = serde_json::from_str(&ReportFormat::Json.render(&diagnostics())).unwrap();
+
+    assert_eq!(all[0]["file"], "contents/posts/a.md");
+    assert_eq!(all[0]["line"], 3);
+    assert_eq!(all[0]["severity"], "warning");
+    assert_eq!(all[1]["line"], Value::Null);
+    assert_eq!(all[1]["origin"], "css");
+}
+
+#[test]
+fn sarif_has_a_rule_per_origin_and_located_results() {
+    let log: Value = serde_json::from_str(&ReportFormat::Sarif.render(&diagnostics())).unwrap();
+    let run = &log["runs"][0];
+
+    assert_eq!(log["version"], "2.1.0");
+    assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "cross-reference");
+    assert_eq!(run["tool"]["driver"]["rules"][1]["id"], "css");
+    let result = &run["results"][0];
+    assert_eq!(result["level"], "warning");
+    let location = &result["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "contents/posts/a.md");
+    assert_eq!(location["region"]["startLine"], 3);
+    assert!(run["results"][1]["locations"][0]["physicalLocation"]["region"].is_null());
+}
@@ -0,0 +1,237 @@
+//! Problems found while rendering that should be reported without stopping
+//! the build, such as math KaTeX rejects, fences in unknown languages, or
+//! typos in the stylesheet.
+use std::{
+    collections::HashSet,
+    fmt,
+    ops::Range,
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use pulldown_cmark::{Event, Options, Parser, Tag};
+
+pub mod format;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// A `TODO:` or `FIXME:` the author left; fatal only under
+    /// `--strict-todos`.
+    Todo,
+    Warning,
+    /// Fails the build once every page has been rendered.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Todo => "todo",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
+
+/// One problem in one document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Stage that found the problem, e.g. `math`.
+    pub origin: &'static str,
+    /// Source file, relative to the content directory.
+    pub document: Arc<Path>,
+    /// Byte offset into the document, when it could be located.
+    pub offset: Option<usize>,
+    /// 1-based line and column of `offset`.
+    pub position: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.document.display())?;
+        if let Some((line, column)) = self.position {
+            write!(f, ":{line}:{column}")?;
+        }
+        write!(f, ": {}: {}", self.origin, self.message)
+    }
+}
+
+/// Parse `source` as Markdown, keeping the byte ranges of the events a
+/// diagnostic may point at (text, code, math, and code blocks) so problems
+/// found after later stages reshape the events can still be placed.
+pub fn parse_with_spans(source: &str, options: Options) -> (Vec<Event<'_>>, Vec<Range<usize>>) {
+    let mut spans = Vec::new();
+    let events = Parser::new_ext(source, options)
+        .into_offset_iter()
+        .map(|(event, range)| {
+            if matches!(
+                event,
+                Event::Text(_)
+                    | Event::Code(_)
+                    | Event::InlineMath(_)
+                    | Event::DisplayMath(_)
+                    | Event::Start(Tag::CodeBlock(_))
+            ) {
+                spans.push(range);
+            }
+            event
+        })
+        .collect();
+    (events, spans)
+}
+
+/// 1-based line and column (in characters) of a byte offset.
+fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// Diagnostics collected from every document in a build. Clones share the
+/// same list, so one handle can be given to each rendering thread.
+#[derive(Clone, Debug, Default)]
+pub struct Diagnostics {
+    inner: Arc<Mutex<Vec<Diagnostic>>>,
+}
+
+impl Diagnostics {
+    pub fn push(&self, diagnostic: Diagnostic) {
+        self.lock().push(diagnostic);
+    }
+
+    /// Everything reported so far, ordered by document and position.
+    pub fn take(&self) -> Vec<Diagnostic> {
+        let mut all = std::mem::take(&mut *self.lock());
+        all.sort_by(|a, b| a.document.cmp(&b.document).then(a.offset.cmp(&b.offset)));
+        all
+    }
+
+    /// A sink for problems in `document`, whose text is `source`. `spans`
+    /// are the ranges from [`parse_with_spans`], or empty for documents that
+    /// are not Markdown.
+    pub fn for_document(
+        &self,
+        document: &Path,
+        source: &str,
+        spans: Vec<Range<usize>>,
+    ) -> DocumentSink {
+        DocumentSink {
+            diagnostics: Some(self.clone()),
+            document: Arc::from(document),
+            source: Arc::from(source),
+            spans: Arc::from(spans),
+            claimed: Arc::default(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<Diagnostic>> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Where a transformer reports problems in the document it is rendering.
+/// The default sink discards everything.
+#[derive(Clone, Debug)]
+pub struct DocumentSink {
+    diagnostics: Option<Diagnostics>,
+    document: Arc<Path>,
+    source: Arc<str>,
+    spans: Arc<[Range<usize>]>,
+    /// Spans already blamed, so repeated identical snippets are placed at
+    /// successive occurrences rather than all at the first.
+    claimed: Arc<Mutex<HashSet<usize>>>,
+}
+
+impl Default for DocumentSink {
+    fn default() -> Self {
+        Self {
+            diagnostics: None,
+            document: Arc::from(Path::new("")),
+            source: Arc::from(""),
+            spans: Arc::from([]),
+            claimed: Arc::default(),
+        }
+    }
+}
+
+impl DocumentSink {
+    /// Report a problem with `snippet`, which is located in the document to
+    /// give the diagnostic a position.
+    pub fn report(&self, severity: Severity, origin: &'static str, snippet: &str, message: String) {
+        if self.diagnostics.is_none() {
+            return;
+        }
+        self.push(severity, origin, self.locate(snippet), message);
+    }
+
+    /// Report a problem at byte `offset` of the document, for callers that
+    /// already know where it is.
+    pub fn report_at(
+        &self,
+        severity: Severity,
+        origin: &'static str,
+        offset: usize,
+        message: String,
+    ) {
+        let offset = offset.min(self.source.len());
+        self.push(severity, origin, Some(offset), message);
+    }
+
+    fn push(
+        &self,
+        severity: Severity,
+        origin: &'static str,
+        offset: Option<usize>,
+        message: String,
+    ) {
+        let Some(diagnostics) = &self.diagnostics else {
+            return;
+        };
+        diagnostics.push(Diagnostic {
+            severity,
+            origin,
+            document: Arc::clone(&self.document),
+            offset,
+            position: offset.map(|offset| line_col(&self.source, offset)),
+            message,
+        });
+    }
+
+    /// Offset of `snippet`: the first unclaimed span that is exactly the
+    /// snippet (give or take math delimiters), else the first unclaimed span
+    /// containing it, else its first occurrence anywhere in the document.
+    fn locate(&self, snippet: &str) -> Option<usize> {
+        if snippet.is_empty() {
+            return None;
+        }
+        let mut claimed = self.claimed.lock().unwrap_or_else(|e| e.into_inner());
+        let mut claim = |exact: bool| {
+            self.spans.iter().enumerate().find_map(|(i, span)| {
+                let text = self.source.get(span.clone())?;
+                if exact && text.trim_matches('$') != snippet {
+                    return None;
+                }
+                let found = text.find(snippet)?;
+                (!claimed.contains(&i)).then(|| {
+                    claimed.insert(i);
+                    span.start + found
+                })
+            })
+        };
+        claim(true)
+            .or_else(|| claim(false))
+            .or_else(|| self.source.find(snippet))
+    }
+
+    pub fn warn(&self, origin: &'static str, snippet: &str, message: String) {
+        self.report(Severity::Warning, origin, snippet, message);
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,79 @@
+use std::path::Path;
+
+use pulldown_cmark::{Event, Options};
+
+use crate::{
+    diagnostics::{Diagnostics, DocumentSink, Severity, parse_with_spans},
+    transformer::{WithTransformer, code_block::CodeHighlightTransformer, math::MathTransformer},
+};
+
+#[test]
+fn diagnostics_are_located_and_ordered() {
+    let diagnostics = Diagnostics::default();
+    let b = diagnostics.for_document(Path::new("b.md"), "one\ntwo", Vec::new());
+    let a = diagnostics.for_document(Path::new("a.md"), "alpha beta", Vec::new());
+    b.warn("test", "two", "second".into());
+    a.report(Severity::Error, "test", "beta", "first".into());
+    a.warn("test", "missing", "unplaced".into());
+
+    let all: Vec<String> = diagnostics.take().iter().map(ToString::to_string).collect();
+
+    assert_eq!(
+        all,
+        [
+            "warning: a.md: test: unplaced",
+            "error: a.md:1:7: test: first",
+            "warning: b.md:2:1: test: second",
+        ]
+    );
+    assert!(diagnostics.take().is_empty());
+}
+
+#[test]
+fn default_sink_discards() {
+    DocumentSink::default().warn("test", "", "ignored".into());
+}
+
+#[test]
+fn repeated_snippets_are_placed_at_successive_spans() {
+    // The first `x^` is prose, not math, so only the spans point at the math.
+    let source = "x^ in text, then $x^$\n\nand again:\n\n$$x^$$\n";
+    let diagnostics = Diagnostics::default();
+    let (events, spans) = parse_with_spans(source, Options::ENABLE_MATH);
+    let sink = diagnostics.for_document(Path::new("post.md"), source, spans);
+
+    let _: Vec<Event<'_>> = events
+        .into_iter()
+        .with_transformer_reporting::<MathTransformer<'_, _>>(&sink)
+        .collect();
+
+    let positions: Vec<_> = diagnostics.take().iter().map(|d| d.position).collect();
+    assert_eq!(positions, [Some((1, 19)), Some((5, 3))]);
+}
+
+#[test]
+fn transformers_report_without_aborting() {
+    let source = "Bad math: $x^$\n\n```nosuchlang\nx\n```\n";
+    let diagnostics = Diagnostics::default();
+    let (events, spans) = parse_with_spans(source, Options::ENABLE_MATH);
+    let sink = diagnostics.for_document(Path::new("post.md"), source, spans);
+
+    let events: Vec<Event<'_>> = events
+        .into_iter()
+        .with_transformer_reporting::<CodeHighlightTransformer<'_, _>>(&sink)
+        .with_transformer_reporting::<MathTransformer<'_, _>>(&sink)
+        .collect();
+
+    assert!(
+        events
+            .iter()
+            .any(|e| matches!(e, Event::InlineHtml(html) if html.as_ref() == "x^"))
+    );
+    let reported = diagnostics.take();
+    assert_eq!(reported.len(), 2);
+    assert_eq!(reported[0].origin, "math");
+    assert_eq!(reported[0].severity, Severity::Warning);
+    assert_eq!(reported[0].position, Some((1, 12)));
+    assert_eq!(reported[1].origin, "highlight");
+    assert_eq!(reported[1].position, Some((4, 1)));
+}
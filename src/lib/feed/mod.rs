@@ -1,21 +1,43 @@
-use std::{fs, path::Path};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
 
 use chrono::{DateTime, FixedOffset, Utc};
 use color_eyre::eyre::eyre;
-use rss::{Category, Channel, Guid, Item};
+use rss::{Category, Channel, Guid, Image, Item, extension::atom::AtomExtension};
+use serde::Serialize;
 
 use crate::{
     article::Article,
-    config::{FEED_ITEM_LIMIT, SITE_AUTHOR, SITE_BASE_URL, SITE_DESCRIPTION, SITE_TITLE},
-    types::{IsoDate, Tag},
+    author::Author,
+    config::{
+        FEED_ITEM_LIMIT, NOTES_DIR, SITE_AUTHOR, SITE_BASE_URL, SITE_DESCRIPTION, SITE_ICON,
+        SITE_LANGUAGE, SITE_LOGO, SITE_RIGHTS, SITE_TITLE, TAGS_DIR,
+    },
+    tag_info::TagInfos,
+    types::{Href, IsoDate, PostKind, Tag},
+    utils::{tag_href, write_atomic},
 };
 
+/// File name of the site-wide RSS feed; tag feeds use `<tag>.rss.xml`.
+pub const RSS_FILE: &str = "rss.xml";
+/// File name of the site-wide Atom feed; tag feeds use `<tag>.atom.xml`.
+pub const ATOM_FILE: &str = "atom.xml";
+/// File name of the site-wide JSON Feed.
+pub const JSON_FEED_FILE: &str = "feed.json";
+/// Name announced in the feeds' generator fields.
+const GENERATOR: &str = "ssg";
+
 /// Minimal site metadata used for feed generation.
 #[derive(Debug)]
 pub struct SiteMeta {
     pub title: String,
     pub description: String,
     pub base_url: String,
+    /// Page the feed describes (the site root, or a tag listing).
+    pub link: String,
     pub author: String,
 }
 
@@ -25,24 +47,228 @@ pub fn write_feeds(out_dir: &Path, articles: &[Article]) -> color_eyre::Result<(
         title: SITE_TITLE.to_string(),
         description: SITE_DESCRIPTION.to_string(),
         base_url: SITE_BASE_URL.to_string(),
+        link: SITE_BASE_URL.to_string(),
         author: SITE_AUTHOR.to_string(),
     };
 
-    let entries = articles
-        .iter()
-        .take(FEED_ITEM_LIMIT)
-        .map(|a| FeedEntry::from_article(a, &meta.base_url))
-        .collect::<Vec<_>>();
+    write_feed_pair(
+        out_dir,
+        Path::new(RSS_FILE),
+        Path::new(ATOM_FILE),
+        articles,
+        &meta,
+    )?;
+
+    let entries = feed_entries(articles, &meta);
+    let json = build_json_feed(&entries, &meta)?;
+    write_atomic(&out_dir.join(JSON_FEED_FILE), json.as_bytes())?;
+
+    Ok(())
+}
+
+/// Generate RSS and Atom feeds for a single tag, next to its listing page.
+/// `articles` should already be filtered to the tag and sorted newest first.
+pub fn write_tag_feeds(
+    out_dir: &Path,
+    tag: &Tag,
+    infos: &TagInfos,
+    articles: &[Article],
+) -> color_eyre::Result<()> {
+    let base = SITE_BASE_URL.trim_end_matches('/');
+    let name = infos
+        .get(tag)
+        .and_then(|info| info.title.as_deref())
+        .unwrap_or(tag.as_str());
+    let meta = SiteMeta {
+        title: format!("{SITE_TITLE}: {name}"),
+        description: infos.description(tag),
+        base_url: SITE_BASE_URL.to_string(),
+        link: format!("{base}/{}", tag_href(tag)),
+        author: SITE_AUTHOR.to_string(),
+    };
+
+    write_feed_pair(
+        out_dir,
+        &tag_feed_path(tag, RSS_FILE),
+        &tag_feed_path(tag, ATOM_FILE),
+        articles,
+        &meta,
+    )
+}
+
+/// Generate RSS and Atom feeds of one author's posts, next to their page.
+/// `articles` should already be filtered to the author and sorted newest
+/// first.
+pub fn write_author_feeds(
+    out_dir: &Path,
+    author: &Author,
+    articles: &[Article],
+) -> color_eyre::Result<()> {
+    let base = SITE_BASE_URL.trim_end_matches('/');
+    let meta = SiteMeta {
+        title: format!("{SITE_TITLE}: {}", author.name),
+        description: format!("Posts by {}", author.name),
+        base_url: SITE_BASE_URL.to_string(),
+        link: format!("{base}/{}", author.href()),
+        author: author.name.clone(),
+    };
+
+    write_feed_pair(
+        out_dir,
+        &author.feed_path(RSS_FILE),
+        &author.feed_path(ATOM_FILE),
+        articles,
+        &meta,
+    )
+}
+
+/// Generate RSS and Atom feeds for the notes stream under `notes/`.
+pub fn write_notes_feeds(out_dir: &Path, notes: &[Article]) -> color_eyre::Result<()> {
+    let base = SITE_BASE_URL.trim_end_matches('/');
+    let meta = SiteMeta {
+        title: format!("{SITE_TITLE}: notes"),
+        description: format!("Notes from {SITE_TITLE}"),
+        base_url: SITE_BASE_URL.to_string(),
+        link: format!("{base}/{NOTES_DIR}/index.html"),
+        author: SITE_AUTHOR.to_string(),
+    };
+
+    fs::create_dir_all(out_dir.join(NOTES_DIR))?;
+    write_feed_pair(
+        out_dir,
+        &Path::new(NOTES_DIR).join(RSS_FILE),
+        &Path::new(NOTES_DIR).join(ATOM_FILE),
+        notes,
+        &meta,
+    )
+}
+
+/// Location of a tag's feed relative to the site root, e.g. `tags/rust.rss.xml`.
+pub fn tag_feed_path(tag: &Tag, file: &str) -> PathBuf {
+    Path::new(TAGS_DIR).join(format!("{tag}.{file}"))
+}
+
+/// What [`feed_content_hrefs`] needs to know of a post, all of which is
+/// settled before the post is rendered.
+#[derive(Clone, Copy, Debug)]
+pub struct FeedCandidate<'a> {
+    pub href: &'a Href,
+    pub kind: PostKind,
+    pub ctime: Option<&'a IsoDate>,
+    pub expires: Option<&'a IsoDate>,
+    pub tags: &'a [Tag],
+    pub authors: &'a [String],
+}
+
+impl<'a> From<&'a Article> for FeedCandidate<'a> {
+    fn from(a: &'a Article) -> Self {
+        Self {
+            href: &a.href,
+            kind: a.kind,
+            ctime: a.ctime.as_ref(),
+            expires: a.expires.as_ref(),
+            tags: &a.tags,
+            authors: &a.authors,
+        }
+    }
+}
+
+/// Posts whose full content is shown somewhere: the newest
+/// `FEED_ITEM_LIMIT` essays and per-tag and per-author entries, and every
+/// note (the notes stream shows them all). `posts` must be sorted newest
+/// first. Standalone pages and posts expired by `today` are in no feed, so
+/// they take no place in one. Posts dated the same as the last one to fit a
+/// feed are kept too, since titles, which break such ties, are only known
+/// after rendering.
+pub fn feed_content_hrefs<'a>(
+    posts: impl IntoIterator<Item = FeedCandidate<'a>>,
+    today: &IsoDate,
+) -> HashSet<Href> {
+    /// Posts counted so far in one feed, and the date of the last.
+    #[derive(Default)]
+    struct Filled<'a> {
+        count: usize,
+        last: Option<Option<&'a IsoDate>>,
+    }
+    impl<'a> Filled<'a> {
+        fn admits(&mut self, ctime: Option<&'a IsoDate>) -> bool {
+            if self.count < FEED_ITEM_LIMIT || self.last == Some(ctime) {
+                self.count += 1;
+                self.last = Some(ctime);
+                return true;
+            }
+            false
+        }
+    }
+
+    let mut hrefs = HashSet::new();
+    let mut essays = Filled::default();
+    let mut per_tag: BTreeMap<&Tag, Filled<'a>> = BTreeMap::new();
+    let mut per_author: BTreeMap<&str, Filled<'a>> = BTreeMap::new();
+    let listed = posts
+        .into_iter()
+        .filter(|post| post.kind != PostKind::Page && post.expires.is_none_or(|e| e >= today));
+    for post in listed {
+        let mut needed = post.kind == PostKind::Note;
+        if post.kind != PostKind::Note && essays.admits(post.ctime) {
+            needed = true;
+        }
+        for tag in post.tags {
+            if per_tag.entry(tag).or_default().admits(post.ctime) {
+                needed = true;
+            }
+        }
+        for author in post.authors {
+            if per_author.entry(author).or_default().admits(post.ctime) {
+                needed = true;
+            }
+        }
+        if needed {
+            hrefs.insert(post.href.clone());
+        }
+    }
+    hrefs
+}
+
+/// Write an RSS/Atom pair to `rss_rel`/`atom_rel` (relative to `out_dir`,
+/// which is also where each feed's `rel="self"` link points).
+fn write_feed_pair(
+    out_dir: &Path,
+    rss_rel: &Path,
+    atom_rel: &Path,
+    articles: &[Article],
+    meta: &SiteMeta,
+) -> color_eyre::Result<()> {
+    let entries = feed_entries(articles, meta);
 
-    let rss_xml = build_rss(&entries, &meta)?;
-    fs::write(out_dir.join("rss.xml"), rss_xml)?;
+    let rss_xml = build_rss(&entries, meta, &site_url(meta, rss_rel))?;
+    write_atomic(&out_dir.join(rss_rel), rss_xml.as_bytes())?;
 
-    let atom_xml = build_atom(&entries, &meta)?;
-    fs::write(out_dir.join("atom.xml"), atom_xml)?;
+    let atom_xml = build_atom(&entries, meta, &site_url(meta, atom_rel))?;
+    write_atomic(&out_dir.join(atom_rel), atom_xml.as_bytes())?;
 
     Ok(())
 }
 
+/// Absolute URL of a file under the site root.
+fn site_url(meta: &SiteMeta, rel: &Path) -> String {
+    let base = meta.base_url.trim_end_matches('/');
+    let rel = rel
+        .iter()
+        .map(|c| c.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    format!("{base}/{rel}")
+}
+
+fn feed_entries(articles: &[Article], meta: &SiteMeta) -> Vec<FeedEntry> {
+    articles
+        .iter()
+        .take(FEED_ITEM_LIMIT)
+        .map(|a| FeedEntry::from_article(a, &meta.base_url))
+        .collect()
+}
+
 #[derive(Clone, Debug)]
 struct FeedEntry {
     title: String,
@@ -63,7 +289,11 @@ impl FeedEntry {
             title: article.title.clone(),
             url,
             summary: article.summary.clone(),
-            content_html: article.content_html.clone(),
+            content_html: article
+                .content_html
+                .as_deref()
+                .unwrap_or_default()
+                .to_owned(),
             tags: article.tags.clone(),
             published: article.ctime.clone(),
             updated: article.updated.clone().or_else(|| article.ctime.clone()),
@@ -71,11 +301,32 @@ impl FeedEntry {
     }
 }
 
-fn build_rss(entries: &[FeedEntry], meta: &SiteMeta) -> color_eyre::Result<String> {
+fn build_rss(entries: &[FeedEntry], meta: &SiteMeta, self_url: &str) -> color_eyre::Result<String> {
     let mut channel = Channel::default();
     channel.set_title(meta.title.clone());
-    channel.set_link(meta.base_url.clone());
+    channel.set_link(meta.link.clone());
     channel.set_description(meta.description.clone());
+    channel.set_language(Some(SITE_LANGUAGE.to_string()));
+    channel.set_copyright(SITE_RIGHTS.map(str::to_string));
+    channel.set_generator(Some(format!("{GENERATOR} {}", env!("CARGO_PKG_VERSION"))));
+
+    // RSS has no self link of its own; validators expect `atom:link`.
+    let mut self_link = atom_syndication::Link::default();
+    self_link.set_href(self_url.to_string());
+    self_link.set_rel("self".to_string());
+    self_link.set_mime_type(Some("application/rss+xml".to_string()));
+    channel.set_atom_ext(Some(AtomExtension {
+        links: vec![self_link],
+    }));
+
+    if let Some(image) = SITE_LOGO.or(SITE_ICON) {
+        channel.set_image(Some(Image {
+            url: site_url(meta, Path::new(image)),
+            title: meta.title.clone(),
+            link: meta.link.clone(),
+            ..Image::default()
+        }));
+    }
 
     let mut items = Vec::with_capacity(entries.len());
     for entry in entries {
@@ -118,10 +369,23 @@ fn build_rss(entries: &[FeedEntry], meta: &SiteMeta) -> color_eyre::Result<Strin
     Ok(channel.to_string())
 }
 
-fn build_atom(entries: &[FeedEntry], meta: &SiteMeta) -> color_eyre::Result<String> {
+fn build_atom(
+    entries: &[FeedEntry],
+    meta: &SiteMeta,
+    self_url: &str,
+) -> color_eyre::Result<String> {
     let mut feed = atom_syndication::Feed::default();
     feed.set_title(meta.title.clone());
-    feed.set_id(meta.base_url.clone());
+    feed.set_id(meta.link.clone());
+    feed.set_lang(Some(SITE_LANGUAGE.to_string()));
+    feed.set_rights(SITE_RIGHTS.map(atom_syndication::Text::plain));
+    feed.set_icon(SITE_ICON.map(|icon| site_url(meta, Path::new(icon))));
+    feed.set_logo(SITE_LOGO.map(|logo| site_url(meta, Path::new(logo))));
+    feed.set_generator(Some(atom_syndication::Generator {
+        value: GENERATOR.to_string(),
+        uri: None,
+        version: Some(env!("CARGO_PKG_VERSION").to_string()),
+    }));
 
     // Updated is required in Atom; use newest entry or fallback to now.
     let updated = entries
@@ -134,8 +398,12 @@ fn build_atom(entries: &[FeedEntry], meta: &SiteMeta) -> color_eyre::Result<Stri
 
     {
         let mut link = atom_syndication::Link::default();
-        link.set_href(meta.base_url.clone());
-        feed.set_links(vec![link]);
+        link.set_href(meta.link.clone());
+        let mut self_link = atom_syndication::Link::default();
+        self_link.set_href(self_url.to_string());
+        self_link.set_rel("self".to_string());
+        self_link.set_mime_type(Some("application/atom+xml".to_string()));
+        feed.set_links(vec![link, self_link]);
     }
 
     {
@@ -154,7 +422,7 @@ fn build_atom(entries: &[FeedEntry], meta: &SiteMeta) -> color_eyre::Result<Stri
             .as_ref()
             .map(to_chrono)
             .transpose()?
-            .unwrap_or_else(|| updated);
+            .unwrap_or(updated);
         e.set_updated(entry_updated);
 
         let mut link = atom_syndication::Link::default();
@@ -191,6 +459,77 @@ fn build_atom(entries: &[FeedEntry], meta: &SiteMeta) -> color_eyre::Result<Stri
     Ok(feed.to_string())
 }
 
+#[derive(Serialize)]
+struct JsonFeed<'a> {
+    version: &'static str,
+    title: &'a str,
+    home_page_url: &'a str,
+    feed_url: String,
+    description: &'a str,
+    authors: Vec<JsonAuthor<'a>>,
+    items: Vec<JsonItem<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonAuthor<'a> {
+    name: &'a str,
+}
+
+#[derive(Serialize)]
+struct JsonItem<'a> {
+    id: &'a str,
+    url: &'a str,
+    title: &'a str,
+    content_html: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    date_published: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    date_modified: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<&'a str>,
+}
+
+fn build_json_feed(entries: &[FeedEntry], meta: &SiteMeta) -> color_eyre::Result<String> {
+    let base = meta.base_url.trim_end_matches('/');
+    let items = entries
+        .iter()
+        .map(|entry| {
+            Ok(JsonItem {
+                id: &entry.url,
+                url: &entry.url,
+                title: &entry.title,
+                content_html: &entry.content_html,
+                summary: entry.summary.as_deref(),
+                date_published: entry
+                    .published
+                    .as_ref()
+                    .map(|d| to_chrono(d).map(|d| d.to_rfc3339()))
+                    .transpose()?,
+                date_modified: entry
+                    .updated
+                    .as_ref()
+                    .map(|d| to_chrono(d).map(|d| d.to_rfc3339()))
+                    .transpose()?,
+                tags: entry.tags.iter().map(Tag::as_str).collect(),
+            })
+        })
+        .collect::<color_eyre::Result<Vec<_>>>()?;
+
+    let feed = JsonFeed {
+        version: "https://jsonfeed.org/version/1.1",
+        title: &meta.title,
+        home_page_url: &meta.link,
+        feed_url: format!("{base}/{JSON_FEED_FILE}"),
+        description: &meta.description,
+        authors: vec![JsonAuthor { name: &meta.author }],
+        items,
+    };
+
+    Ok(serde_json::to_string_pretty(&feed)?)
+}
+
 fn to_chrono(date: &IsoDate) -> color_eyre::Result<DateTime<FixedOffset>> {
     let s = format!("{}T00:00:00+00:00", date.as_str());
     DateTime::parse_from_rfc3339(&s).map_err(|e| eyre!("parse date: {e}"))
@@ -199,3 +538,6 @@ fn to_chrono(date: &IsoDate) -> color_eyre::Result<DateTime<FixedOffset>> {
 fn to_rfc2822(date: &IsoDate) -> color_eyre::Result<String> {
     Ok(to_chrono(date)?.to_rfc2822())
 }
+
+#[cfg(test)]
+mod tests;
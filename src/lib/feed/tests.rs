@@ -0,0 +1,109 @@
+use crate::{
+    article::Article,
+    config::FEED_ITEM_LIMIT,
+    feed::{FeedCandidate, SiteMeta, build_atom, build_rss, feed_content_hrefs},
+    types::{IsoDate, PostKind},
+};
+
+fn kept_names(articles: &[Article]) -> impl Fn(&str) -> bool {
+    let today = IsoDate::parse("2025-06-01").unwrap();
+    let hrefs = feed_content_hrefs(articles.iter().map(FeedCandidate::from), &today);
+    move |name| {
+        hrefs
+            .iter()
+            .any(|h| h.as_str() == format!("posts/{name}.html"))
+    }
+}
+
+fn newest(date: &str) -> Vec<Article> {
+    (0..FEED_ITEM_LIMIT)
+        .map(|i| Article::test(&format!("new-{i}")).dated(date))
+        .collect()
+}
+
+#[test]
+fn content_is_kept_for_feed_entries_and_notes_only() {
+    let mut articles = newest("2025-02-01");
+    articles.push(Article::test("old").dated("2024-01-01"));
+    articles.push(
+        Article::test("old-tagged")
+            .dated("2024-01-01")
+            .tagged(&["rare"]),
+    );
+    articles.push(
+        Article::test("old-note")
+            .dated("2024-01-01")
+            .of_kind(PostKind::Note),
+    );
+
+    let kept = kept_names(&articles);
+
+    assert!(kept("new-0") && kept(&format!("new-{}", FEED_ITEM_LIMIT - 1)));
+    assert!(!kept("old"));
+    assert!(kept("old-tagged"));
+    assert!(kept("old-note"));
+}
+
+#[test]
+fn posts_tied_with_the_last_feed_entry_keep_their_content() {
+    let mut articles = newest("2025-02-01");
+    articles.push(Article::test("same-day").dated("2025-02-01"));
+    articles.push(Article::test("older").dated("2025-01-01"));
+
+    let kept = kept_names(&articles);
+
+    assert!(kept("same-day"));
+    assert!(!kept("older"));
+}
+
+#[test]
+fn pages_and_expired_posts_take_no_feed_place() {
+    let mut articles = vec![
+        Article::test("about")
+            .dated("2025-03-01")
+            .of_kind(PostKind::Page),
+        Article::test("expired")
+            .dated("2025-03-01")
+            .expiring("2025-04-01"),
+    ];
+    articles.extend(newest("2025-02-01").into_iter().skip(1));
+    articles.push(Article::test("last").dated("2025-01-01"));
+
+    let kept = kept_names(&articles);
+
+    assert!(kept("last"));
+    assert!(!kept("expired"));
+    assert!(!kept("about"));
+}
+
+fn meta() -> SiteMeta {
+    SiteMeta {
+        title: "Site".to_string(),
+        description: "A site".to_string(),
+        base_url: "https://example.com/".to_string(),
+        link: "https://example.com/".to_string(),
+        author: "Someone".to_string(),
+    }
+}
+
+#[test]
+fn feeds_carry_self_link_language_and_generator() {
+    let rss = build_rss(&[], &meta(), "https://example.com/rss.xml").unwrap();
+    assert!(rss.contains("xmlns:atom=\"http://www.w3.org/2005/Atom\""));
+    assert!(rss.contains("<atom:link href=\"https://example.com/rss.xml\" rel=\"self\" type=\"application/rss+xml\"/>"));
+    assert!(rss.contains("<language>en</language>"));
+    assert!(rss.contains(&format!(
+        "<generator>ssg {}</generator>",
+        env!("CARGO_PKG_VERSION")
+    )));
+
+    let atom = build_atom(&[], &meta(), "https://example.com/atom.xml").unwrap();
+    assert!(atom.contains(
+        "href=\"https://example.com/atom.xml\" rel=\"self\" type=\"application/atom+xml\""
+    ));
+    assert!(atom.contains("xml:lang=\"en\""));
+    assert!(atom.contains(&format!(
+        "<generator version=\"{}\">ssg</generator>",
+        env!("CARGO_PKG_VERSION")
+    )));
+}
@@ -0,0 +1,91 @@
+//! Line-by-line scanning of Markdown source that keeps track of code
+//! fences, for the preprocessors (includes, fragments, shortcodes) that must
+//! leave directives inside fences alone so they can be documented.
+use std::str::SplitInclusive;
+
+/// One line of the source, newline included.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Line<'a> {
+    /// Outside any code fence.
+    Text(&'a str),
+    /// Opens a code fence; `info` is what follows the marker, trimmed.
+    Open { line: &'a str, info: &'a str },
+    /// Inside a code fence, its closing line included.
+    Fenced(&'a str),
+}
+
+impl<'a> Line<'a> {
+    /// The line as it appears in the source.
+    pub fn raw(self) -> &'a str {
+        match self {
+            Self::Text(line) | Self::Open { line, .. } | Self::Fenced(line) => line,
+        }
+    }
+}
+
+/// The lines of `text`, each tagged with whether it is fenced.
+pub fn lines(text: &str) -> Lines<'_> {
+    Lines {
+        lines: text.split_inclusive('\n'),
+        fence: None,
+    }
+}
+
+/// Iterator returned by [`lines`].
+pub struct Lines<'a> {
+    lines: SplitInclusive<'a, char>,
+    /// Marker of the fence being read.
+    fence: Option<Marker>,
+}
+
+/// The run of backticks or tildes opening a fence.
+#[derive(Clone, Copy, Debug)]
+struct Marker {
+    char: char,
+    len: usize,
+}
+
+impl Marker {
+    /// Whether `line` closes this fence: a run of at least as many of the
+    /// same character, with nothing after it but whitespace.
+    fn closes(self, line: &str) -> bool {
+        let rest = line.trim_start_matches(self.char);
+        line.len() - rest.len() >= self.len && rest.trim().is_empty()
+    }
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = Line<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = self.lines.next()?;
+        let trimmed = line.trim_start();
+        if let Some(marker) = self.fence {
+            if marker.closes(trimmed) {
+                self.fence = None;
+            }
+            return Some(Line::Fenced(line));
+        }
+        Some(match code_fence(trimmed) {
+            Some(marker) => {
+                self.fence = Some(marker);
+                Line::Open {
+                    line,
+                    info: trimmed[marker.len..].trim(),
+                }
+            }
+            None => Line::Text(line),
+        })
+    }
+}
+
+/// The marker opening a code fence at the start of `line`: three or more
+/// backticks or tildes.
+fn code_fence(line: &str) -> Option<Marker> {
+    let char = line.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+    let len = line.len() - line.trim_start_matches(char).len();
+    (len >= 3).then_some(Marker { char, len })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,58 @@
+use crate::fence::{Line, lines};
+
+#[test]
+fn fences_are_tracked_until_their_own_marker_closes_them() {
+    let text = "a\n```rust\n~~~\nb\n```\nc\n  ~~~\nd";
+    let found: Vec<Line> = lines(text).collect();
+    assert_eq!(
+        found,
+        [
+            Line::Text("a\n"),
+            Line::Open {
+                line: "```rust\n",
+                info: "rust"
+            },
+            Line::Fenced("~~~\n"),
+            Line::Fenced("b\n"),
+            Line::Fenced("```\n"),
+            Line::Text("c\n"),
+            Line::Open {
+                line: "  ~~~\n",
+                info: ""
+            },
+            Line::Fenced("d"),
+        ]
+    );
+}
+
+#[test]
+fn raw_lines_rebuild_the_source() {
+    let text = "x\n```\ny\n```\nz\n";
+    assert_eq!(lines(text).map(Line::raw).collect::<String>(), text);
+}
+
+#[test]
+fn only_a_bare_run_as_long_as_the_opening_one_closes_a_fence() {
+    let text = "````md\n```rust\nx\n```\n````\n~~~\n~~~ not yet\n~~~~\nafter\n";
+    let found: Vec<Line> = lines(text).collect();
+    assert_eq!(
+        found,
+        [
+            Line::Open {
+                line: "````md\n",
+                info: "md"
+            },
+            Line::Fenced("```rust\n"),
+            Line::Fenced("x\n"),
+            Line::Fenced("```\n"),
+            Line::Fenced("````\n"),
+            Line::Open {
+                line: "~~~\n",
+                info: ""
+            },
+            Line::Fenced("~~~ not yet\n"),
+            Line::Fenced("~~~~\n"),
+            Line::Text("after\n"),
+        ]
+    );
+}
@@ -0,0 +1,217 @@
+//! Named fragments, for text several posts share, such as a disclaimer or a
+//! bio. Any document may define one:
+//!
+//! ```markdown
+//! ::: fragment bio
+//! I write about compilers.
+//! :::
+//! ```
+//!
+//! and any other embed it with a line holding only `{{ fragment "bio" }}`.
+//! A definition renders in place in its own document; fragments may embed
+//! other fragments. Definitions and embeds inside code fences are left alone
+//! so they can be documented.
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+};
+
+use color_eyre::{Section, eyre::eyre};
+
+use crate::fence::{self, Line};
+
+/// Fragments defined across the site, by name.
+#[derive(Debug, Default)]
+pub struct Fragments {
+    defined: HashMap<String, Fragment>,
+}
+
+#[derive(Debug)]
+struct Fragment {
+    /// The document defining it.
+    source: PathBuf,
+    body: String,
+}
+
+impl Fragments {
+    /// Take the fragment definitions out of `text`, the document `source`,
+    /// returning the text with each definition's body left in its place.
+    pub fn collect<'a>(
+        &mut self,
+        source: &Path,
+        text: &'a str,
+    ) -> color_eyre::Result<Cow<'a, str>> {
+        if !text.contains("fragment") {
+            return Ok(Cow::Borrowed(text));
+        }
+        let (out, found) = take_definitions(text)?;
+        for (name, body) in found {
+            if let Some(first) = self.defined.get(&name) {
+                return Err(eyre!(
+                    "fragment `{name}` is defined in both {} and {}",
+                    first.source.display(),
+                    source.display()
+                ))
+                .with_note(|| "Fragment names are shared by the whole site");
+            }
+            self.defined.insert(
+                name,
+                Fragment {
+                    source: source.to_path_buf(),
+                    body,
+                },
+            );
+        }
+        Ok(Cow::Owned(out))
+    }
+
+    /// `text` with every embed replaced by its fragment. The documents
+    /// defining the fragments used are added to `sources`.
+    pub fn expand<'a>(
+        &self,
+        text: &'a str,
+        sources: &mut BTreeSet<PathBuf>,
+    ) -> color_eyre::Result<Cow<'a, str>> {
+        if !text.contains("fragment") {
+            return Ok(Cow::Borrowed(text));
+        }
+        let mut stack = Vec::new();
+        Ok(match self.expand_nested(text, &mut stack, sources)? {
+            Some(out) => Cow::Owned(out),
+            None => Cow::Borrowed(text),
+        })
+    }
+
+    /// `text` with its embeds expanded, or `None` if it has none. `stack`
+    /// holds the fragments being expanded, outermost first.
+    fn expand_nested(
+        &self,
+        text: &str,
+        stack: &mut Vec<String>,
+        sources: &mut BTreeSet<PathBuf>,
+    ) -> color_eyre::Result<Option<String>> {
+        let mut out = String::with_capacity(text.len());
+        let mut changed = false;
+        for line in fence::lines(text) {
+            let embed = match line {
+                Line::Text(text) => parse_embed(text.trim()),
+                _ => None,
+            };
+            match embed {
+                Some(name) => {
+                    out.push_str(&self.embed(name, stack, sources)?);
+                    changed = true;
+                }
+                None => out.push_str(line.raw()),
+            }
+        }
+        Ok(changed.then_some(out))
+    }
+
+    /// The expanded body of the fragment `name`, ending in a newline.
+    fn embed(
+        &self,
+        name: &str,
+        stack: &mut Vec<String>,
+        sources: &mut BTreeSet<PathBuf>,
+    ) -> color_eyre::Result<String> {
+        let fragment = self
+            .defined
+            .get(name)
+            .ok_or_else(|| eyre!("no fragment named `{name}`"))
+            .with_note(|| "Define it in any document with `::: fragment <name>` … `:::`")?;
+        if stack.iter().any(|outer| outer == name) {
+            let chain: Vec<&str> = stack.iter().map(String::as_str).chain([name]).collect();
+            return Err(eyre!("fragment cycle: {}", chain.join(" → ")));
+        }
+        sources.insert(fragment.source.clone());
+
+        stack.push(name.to_owned());
+        let expanded = self.expand_nested(&fragment.body, stack, sources)?;
+        stack.pop();
+
+        let mut body = expanded.unwrap_or_else(|| fragment.body.clone());
+        if !body.ends_with('\n') {
+            body.push('\n');
+        }
+        Ok(body)
+    }
+}
+
+/// `text` without its definition markers, and the definitions, nested ones
+/// included, as `(name, body)`.
+fn take_definitions(text: &str) -> color_eyre::Result<(String, Vec<(String, String)>)> {
+    let mut out = String::with_capacity(text.len());
+    let mut found = Vec::new();
+    // The fragment being read: its name, body, and the `:::` containers
+    // opened inside it.
+    let mut open: Option<(&str, String, usize)> = None;
+
+    for line in fence::lines(text) {
+        let Line::Text(line) = line else {
+            match open.as_mut() {
+                Some((_, body, _)) => body.push_str(line.raw()),
+                None => out.push_str(line.raw()),
+            }
+            continue;
+        };
+        let trimmed = line.trim();
+        match open.as_mut() {
+            None => match parse_definition(trimmed) {
+                Some(name) => open = Some((name, String::new(), 0)),
+                None => out.push_str(line),
+            },
+            Some((_, body, depth)) => {
+                let container = trimmed.starts_with(":::");
+                if container && trimmed.trim_start_matches(':').trim().is_empty() {
+                    if *depth == 0 {
+                        let (name, body, _) = open.take().unwrap_or_default();
+                        let (body, nested) = take_definitions(&body)?;
+                        out.push_str(&body);
+                        found.push((name.to_owned(), body));
+                        found.extend(nested);
+                        continue;
+                    }
+                    *depth -= 1;
+                } else if container {
+                    *depth += 1;
+                }
+                body.push_str(line);
+            }
+        }
+    }
+    if let Some((name, _, _)) = open {
+        return Err(eyre!("fragment `{name}` is never closed"))
+            .with_note(|| "End it with a line holding only `:::`");
+    }
+    Ok((out, found))
+}
+
+/// The name in a `::: fragment name` line.
+pub fn parse_definition(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix(":::")?.trim_start_matches(':').trim();
+    let name = rest.strip_prefix("fragment")?;
+    if !name.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let name = name.trim();
+    (!name.is_empty() && !name.contains(char::is_whitespace)).then_some(name)
+}
+
+/// The name in a `{{ fragment "name" }}` line.
+pub fn parse_embed(line: &str) -> Option<&str> {
+    let inner = line.strip_prefix("{{")?.strip_suffix("}}")?.trim();
+    let quoted = inner.strip_prefix("fragment")?;
+    if !quoted.starts_with(char::is_whitespace) {
+        return None;
+    }
+    quoted
+        .trim()
+        .strip_prefix('"')?
+        .strip_suffix('"')
+        .filter(|name| !name.is_empty() && !name.contains('"'))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,96 @@
+use std::{
+    borrow::Cow,
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use crate::fragment::{Fragments, parse_definition, parse_embed};
+
+#[test]
+fn parses_definitions_and_embeds() {
+    assert_eq!(parse_definition("::: fragment bio"), Some("bio"));
+    assert_eq!(parse_definition(":::: fragment  bio "), Some("bio"));
+    assert!(parse_definition("::: fragments bio").is_none());
+    assert!(parse_definition("::: fragment").is_none());
+    assert!(parse_definition("::: fragment two words").is_none());
+
+    assert_eq!(parse_embed(r#"{{ fragment "bio" }}"#), Some("bio"));
+    assert_eq!(parse_embed(r#"{{fragment "bio"}}"#), Some("bio"));
+    assert!(parse_embed(r#"{{ fragment bio }}"#).is_none());
+    assert!(parse_embed(r#"{{ fragmentary "bio" }}"#).is_none());
+    assert!(parse_embed(r#"See {{ fragment "bio" }}"#).is_none());
+}
+
+#[test]
+fn definitions_render_in_place_and_embed_elsewhere() {
+    let mut fragments = Fragments::default();
+    let about = "# About\n\n::: fragment bio\nI write about compilers.\n\n{{ fragment \"contact\" }}\n:::\n\nThanks.\n";
+    let contact = "::: fragment contact\n::: aside\nMail me.\n:::\n:::\n";
+
+    let about = fragments.collect(Path::new("about.md"), about).unwrap();
+    let contact = fragments.collect(Path::new("contact.md"), contact).unwrap();
+    assert_eq!(
+        about,
+        "# About\n\nI write about compilers.\n\n{{ fragment \"contact\" }}\n\nThanks.\n"
+    );
+    assert_eq!(contact, "::: aside\nMail me.\n:::\n");
+
+    let mut sources = BTreeSet::new();
+    let post = fragments
+        .expand(
+            "Post.\n\n{{ fragment \"bio\" }}\n\n```md\n{{ fragment \"bio\" }}\n```\n",
+            &mut sources,
+        )
+        .unwrap();
+    assert_eq!(
+        post,
+        "Post.\n\nI write about compilers.\n\n::: aside\nMail me.\n:::\n\n```md\n{{ fragment \"bio\" }}\n```\n"
+    );
+    assert_eq!(
+        sources,
+        BTreeSet::from([PathBuf::from("about.md"), PathBuf::from("contact.md")])
+    );
+
+    let untouched = fragments
+        .expand("No fragments here.\n", &mut sources)
+        .unwrap();
+    assert!(matches!(untouched, Cow::Borrowed(_)));
+}
+
+#[test]
+fn duplicates_cycles_and_unknown_names_are_errors() {
+    let mut fragments = Fragments::default();
+    fragments
+        .collect(
+            Path::new("a.md"),
+            "::: fragment a\n{{ fragment \"b\" }}\n:::\n",
+        )
+        .unwrap();
+    fragments
+        .collect(
+            Path::new("b.md"),
+            "::: fragment b\n{{ fragment \"a\" }}\n:::\n",
+        )
+        .unwrap();
+
+    let err = fragments
+        .collect(Path::new("c.md"), "::: fragment a\nAgain.\n:::\n")
+        .unwrap_err();
+    assert!(err.to_string().contains("a.md and c.md"), "{err}");
+
+    let mut sources = BTreeSet::new();
+    let err = fragments
+        .expand("{{ fragment \"a\" }}\n", &mut sources)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "fragment cycle: a → b → a");
+
+    let err = fragments
+        .expand("{{ fragment \"missing\" }}\n", &mut sources)
+        .unwrap_err();
+    assert!(err.to_string().contains("missing"), "{err}");
+
+    let err = Fragments::default()
+        .collect(Path::new("d.md"), "::: fragment open\nNo end.\n")
+        .unwrap_err();
+    assert!(err.to_string().contains("never closed"), "{err}");
+}
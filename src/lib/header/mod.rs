@@ -1,21 +1,34 @@
 use color_eyre::Section;
 use gray_matter::{Matter, engine::YAML};
 use serde::Deserialize;
+use serde_json::{Map, Value};
 
 use crate::{
-    config::SiteMeta,
-    types::{IsoDate, Tag, Tags},
-    utils::{escape_attr, escape_text},
+    config::{MATH_OUTPUT, MathOutput, PARAGRAPH_PERMALINKS, SiteMeta},
+    recipe::{Recipe, RecipeInfo},
+    series::SeriesEntry,
+    transformer::{image::local_image_size, toc::TocOptions},
+    types::{IsoDate, PostKind, Tag, Tags},
+    utils::{escape_attr, escape_text, tag_href, tags_index_path},
 };
 
 #[derive(Deserialize, Default, Debug)]
 pub struct Header {
     title: Option<String>,
+    /// Full `<title>` and og:title, replacing the title template.
+    seo_title: Option<String>,
     subtitle: Option<String>,
     description: Option<String>,
     canonical: Option<String>,
     #[serde(alias = "og_image", alias = "image")]
     image: Option<String>,
+    /// Alt text of `image` for social cards.
+    #[serde(alias = "og_image_alt")]
+    image_alt: Option<String>,
+    /// Primary image of a photo post; falls back to `image`.
+    photo: Option<String>,
+    /// Outbound target of a link post.
+    link: Option<String>,
     #[serde(alias = "og_title")]
     og_title: Option<String>,
     #[serde(alias = "og_description")]
@@ -26,7 +39,38 @@ pub struct Header {
     twitter_creator: Option<String>,
     ctime: Option<String>,
     mtime: Option<String>,
+    /// Date after which the post is archived or unpublished (see
+    /// `EXPIRED_POSTS`).
+    expires: Option<String>,
     tags: Option<Vec<String>>,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    /// `html` or `mathml`, overriding `MATH_OUTPUT` for this page.
+    math: Option<String>,
+    /// `false` to leave the TOC out of this page.
+    toc: Option<bool>,
+    /// Deepest heading level in the TOC, overriding `TOC_DEPTH`.
+    toc_depth: Option<u8>,
+    /// Link to each paragraph, overriding `PARAGRAPH_PERMALINKS`.
+    paragraph_links: Option<bool>,
+    /// Scripts and stylesheets only this page loads (see `PAGE_ASSETS`).
+    scripts: Option<Vec<String>>,
+    styles: Option<Vec<String>>,
+    /// Old locations of this page, relative to the site root, which redirect
+    /// to it.
+    aliases: Option<Vec<String>>,
+    /// Keys of the post's authors in `AUTHORS_FILE`; `author` names one.
+    author: Option<String>,
+    authors: Option<Vec<String>>,
+    /// Name of the series the post is part of, and optionally its position.
+    series: Option<String>,
+    series_part: Option<u32>,
+    /// Ingredients, steps, and times of a recipe post.
+    #[serde(flatten)]
+    recipe: Recipe,
+    /// Every other key, for templates, shortcodes, and the JSON API.
+    #[serde(flatten)]
+    extra: Map<String, Value>,
 }
 
 impl TryFrom<&str> for Header {
@@ -50,10 +94,131 @@ impl Header {
         self.mtime.as_deref().and_then(IsoDate::parse)
     }
 
+    pub fn expires(&self) -> Option<IsoDate> {
+        self.expires.as_deref().and_then(IsoDate::parse)
+    }
+
     pub fn description(&self) -> Option<&str> {
         self.description.as_deref()
     }
 
+    pub fn kind(&self) -> PostKind {
+        self.kind
+            .as_deref()
+            .and_then(PostKind::parse)
+            .unwrap_or_default()
+    }
+
+    /// Escaped inner HTML of the page heading. Notes are untitled, so they
+    /// have none; link posts point their heading at the link target.
+    fn heading_html(&self) -> Option<String> {
+        let title = escape_text(self.title.as_deref()?);
+        match (self.kind(), self.link()) {
+            (PostKind::Note, _) => None,
+            (PostKind::Link, Some(link)) => Some(format!(
+                r#"<a href="{}">{title}</a>"#,
+                escape_attr(link)
+            )),
+            _ => Some(title),
+        }
+    }
+
+    /// How this page renders math: its `math:` field, else `MATH_OUTPUT`.
+    pub fn math_output(&self) -> MathOutput {
+        self.math
+            .as_deref()
+            .and_then(MathOutput::parse)
+            .unwrap_or(MATH_OUTPUT)
+    }
+
+    /// How this page's TOC is built: its `toc:` and `toc_depth:` fields over
+    /// the site defaults.
+    pub fn toc(&self) -> TocOptions {
+        let defaults = TocOptions::default();
+        TocOptions {
+            enabled: self.toc.unwrap_or(defaults.enabled),
+            depth: self.toc_depth.unwrap_or(defaults.depth),
+            ..defaults
+        }
+    }
+
+    /// Whether this page's paragraphs get permalinks.
+    pub fn paragraph_links(&self) -> bool {
+        self.paragraph_links.unwrap_or(PARAGRAPH_PERMALINKS)
+    }
+
+    /// Scripts this page loads for itself.
+    pub fn scripts(&self) -> &[String] {
+        self.scripts.as_deref().unwrap_or_default()
+    }
+
+    /// Stylesheets this page loads for itself.
+    pub fn styles(&self) -> &[String] {
+        self.styles.as_deref().unwrap_or_default()
+    }
+
+    /// Old locations of this page (see `crate::alias`).
+    pub fn aliases(&self) -> &[String] {
+        self.aliases.as_deref().unwrap_or_default()
+    }
+
+    /// The post's authors (see `crate::author`), `author` first.
+    pub fn authors(&self) -> Vec<String> {
+        let mut authors: Vec<String> = self.author.iter().cloned().collect();
+        for id in self.authors.iter().flatten() {
+            if !authors.contains(id) {
+                authors.push(id.clone());
+            }
+        }
+        authors
+    }
+
+    /// The post's place in a series (see `crate::series`).
+    pub fn series(&self) -> Option<SeriesEntry> {
+        let name = self.series.as_deref()?.trim();
+        (!name.is_empty()).then(|| SeriesEntry {
+            name: name.to_owned(),
+            part: self.series_part,
+        })
+    }
+
+    /// Structured fields of a recipe post, if it has any.
+    pub fn recipe(&self) -> Option<&Recipe> {
+        (self.kind() == PostKind::Recipe && !self.recipe.is_empty()).then_some(&self.recipe)
+    }
+
+    /// Outbound target of a link post.
+    pub fn link(&self) -> Option<&str> {
+        self.link.as_deref()
+    }
+
+    /// Frontmatter keys the header has no field for, e.g. `rating` or `venue`.
+    pub fn extra(&self) -> &Map<String, Value> {
+        &self.extra
+    }
+
+    /// Full `<title>` of the page: `seo_title`, else its title per the
+    /// site's title template.
+    pub fn page_title(&self, site: &SiteMeta) -> Option<String> {
+        self.seo_title
+            .clone()
+            .or_else(|| self.title.as_deref().map(|t| site.page_title(t)))
+    }
+
+    /// Use `title` unless the frontmatter already set one.
+    pub fn fill_title(&mut self, title: String) {
+        self.title.get_or_insert(title);
+    }
+
+    /// Use `mtime` unless the frontmatter already set one.
+    pub fn fill_mtime(&mut self, mtime: &IsoDate) {
+        self.mtime.get_or_insert_with(|| mtime.as_str().to_owned());
+    }
+
+    pub fn photo(&self) -> Option<&str> {
+        self.photo.as_deref().or(self.image.as_deref())
+    }
+
     pub fn tags(&self) -> Tags {
         let parsed = self
             .tags
@@ -65,19 +230,29 @@ impl Header {
         Tags::new(parsed)
     }
 
-    pub fn to_html(&self, css_href: &str, has_math: bool, katex_href: &str) -> String {
+    /// Replace the tags, e.g. with the names they are filed under.
+    pub fn set_tags(&mut self, tags: &[Tag]) {
+        self.tags = Some(tags.iter().map(|t| t.as_str().to_owned()).collect());
+    }
+
+    pub fn to_html(
+        &self,
+        site: &SiteMeta,
+        css_href: &str,
+        has_math: bool,
+        katex_href: &str,
+    ) -> String {
         let mut result = String::new();
 
         let title = self
-            .title
-            .as_ref()
+            .page_title(site)
             .map(|title| {
                 format!(
                     r#"
 <title>
 {}
 </title>"#,
-                    escape_text(title)
+                    escape_text(&title)
                 )
             })
             .unwrap_or_default();
@@ -125,9 +300,9 @@ impl Header {
     pub fn opengraph_meta(&self, page_url: &str, site: &SiteMeta) -> String {
         let title = self
             .og_title
-            .as_deref()
-            .or(self.title.as_deref())
-            .unwrap_or(site.title.as_str());
+            .clone()
+            .or_else(|| self.page_title(site))
+            .unwrap_or_else(|| site.title.clone());
         let description = self
             .og_description
             .as_deref()
@@ -144,34 +319,54 @@ impl Header {
             .as_deref()
             .or(Some(site.author.as_str()));
 
-        let image_url = self
-            .image
-            .as_deref()
-            .or(site.default_image.as_deref())
-            .map(|img| absolute_url(&site.base_url, img));
+        let image = match self.image.as_deref() {
+            Some(img) => Some(SocialImage::new(img, self.image_alt.as_deref(), site)),
+            None => SocialImage::site_default(site),
+        };
 
         render_social_meta(
-            title,
+            &title,
             description,
             url,
             og_type,
             twitter_card,
             twitter_creator,
-            image_url.as_deref(),
+            image.as_ref(),
         )
     }
 
+    /// schema.org `Recipe` JSON-LD for recipe posts.
+    pub fn recipe_json_ld(&self, page_url: &str, site: &SiteMeta) -> Option<String> {
+        let recipe = self.recipe()?;
+        let image = self
+            .image
+            .as_deref()
+            .map(|img| absolute_url(&site.base_url, img));
+        let published = self.ctime().map(|d| d.as_str());
+        let tags = self.tags();
+        let keywords: Vec<&str> = tags.0.iter().map(Tag::as_str).collect();
+
+        Some(recipe.json_ld(&RecipeInfo {
+            name: self.title.as_deref().unwrap_or(site.title.as_str()),
+            url: self.canonical.as_deref().unwrap_or(page_url),
+            author: &site.author,
+            description: self.description.as_deref(),
+            published: published.as_deref(),
+            image: image.as_deref(),
+            keywords: &keywords,
+        }))
+    }
+
     pub fn generate_body_head(&self, href_prefix: &str) -> String {
         let mut result = String::new();
 
         let title = self
-            .title
-            .as_ref()
+            .heading_html()
             .map(|title| {
                 format!(
                     r#"<h1>{}</h1>
 "#,
-                    escape_text(title)
+                    title
                 )
             })
             .unwrap_or_default();
@@ -207,9 +402,9 @@ impl Header {
     pub fn generate_feed_body_head(&self) -> String {
         let mut result = String::new();
 
-        if let Some(title) = self.title.as_ref() {
+        if let Some(title) = self.heading_html() {
             result.push_str(&format!(r#"<h1>{}</h1>
-"#, escape_text(title)));
+"#, title));
         }
 
         if let Some(sub) = self.subtitle.as_ref() {
@@ -251,7 +446,7 @@ impl Header {
                 .0
                 .iter()
                 .map(|t| {
-                    let href = format!(r#"{href_prefix}tags/{t}.html"#);
+                    let href = format!("{href_prefix}{}", tag_href(t));
                     format!(
                         r#"<a class="tag" href="{}">{}</a>"#,
                         escape_attr(&href),
@@ -262,7 +457,8 @@ impl Header {
                 .join(" ");
 
             parts.push(format!(
-                r#"<span class="meta-item">Tags: {}</span>"#,
+                r#"<span class="meta-item"><a href="{}">Tags</a>: {}</span>"#,
+                escape_attr(&format!("{href_prefix}{}", tags_index_path().display())),
                 rendered_tags
             ));
         }
@@ -286,21 +482,46 @@ pub fn generic_og_meta(
     site: &SiteMeta,
     image_override: Option<&str>,
 ) -> String {
-    let image_url = image_override
-        .or(site.default_image.as_deref())
-        .map(|img| absolute_url(&site.base_url, img));
+    let image = match image_override {
+        Some(img) => Some(SocialImage::new(img, None, site)),
+        None => SocialImage::site_default(site),
+    };
 
     render_social_meta(
-        page_title,
+        &site.page_title(page_title),
         page_description,
         page_url,
         "website",
         "summary_large_image",
         Some(site.author.as_str()),
-        image_url.as_deref(),
+        image.as_ref(),
     )
 }
 
+/// The image of a social card, with what crawlers need to lay the card out
+/// without fetching it first.
+struct SocialImage<'a> {
+    url: String,
+    /// Width and height, when the image is a local file.
+    size: Option<(u32, u32)>,
+    alt: Option<&'a str>,
+}
+
+impl<'a> SocialImage<'a> {
+    fn new(src: &str, alt: Option<&'a str>, site: &SiteMeta) -> Self {
+        Self {
+            url: absolute_url(&site.base_url, src),
+            size: local_image_size(src),
+            alt,
+        }
+    }
+
+    fn site_default(site: &'a SiteMeta) -> Option<Self> {
+        let src = site.default_image.as_deref()?;
+        Some(Self::new(src, site.default_image_alt.as_deref(), site))
+    }
+}
+
 fn absolute_url(base: &str, path: &str) -> String {
     if path.starts_with("http://") || path.starts_with("https://") {
         path.to_string()
@@ -318,7 +539,7 @@ fn render_social_meta(
     og_type: &str,
     twitter_card: &str,
     twitter_creator: Option<&str>,
-    image_url: Option<&str>,
+    image: Option<&SocialImage<'_>>,
 ) -> String {
     let mut out = String::new();
     out.push_str(&format!(
@@ -341,16 +562,31 @@ fn render_social_meta(
 <meta property="og:url" content="{}">"#,
         escape_attr(url)
     ));
-    if let Some(img) = image_url {
+    if let Some(image) = image {
         out.push_str(&format!(
             r#"
 <meta property="og:image" content="{}">"#,
-            escape_attr(img)
+            escape_attr(&image.url)
         ));
+        if let Some((width, height)) = image.size {
+            out.push_str(&format!(
+                r#"
+<meta property="og:image:width" content="{width}">
+<meta property="og:image:height" content="{height}">"#
+            ));
+        }
+        if let Some(alt) = image.alt {
+            out.push_str(&format!(
+                r#"
+<meta property="og:image:alt" content="{alt}">
+<meta name="twitter:image:alt" content="{alt}">"#,
+                alt = escape_attr(alt)
+            ));
+        }
         out.push_str(&format!(
             r#"
 <meta name="twitter:image" content="{}">"#,
-            escape_attr(img)
+            escape_attr(&image.url)
         ));
     }
     out.push_str(&format!(
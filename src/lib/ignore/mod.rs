@@ -0,0 +1,207 @@
+//! Which files source discovery skips. Patterns come from `DISCOVERY_EXCLUDES`
+//! and from `.gitignore` and `.ssgignore` files anywhere in a content root, and
+//! follow gitignore rules: `*` and `?` stay within one path segment, `**`
+//! spans any number of them, `[...]` matches a class of characters, a
+//! trailing `/` matches only directories, and `!` re-includes what an earlier
+//! pattern excluded. A pattern without an inner `/` matches an entry of that
+//! name at any depth; otherwise it matches from the directory it was written
+//! in. The last matching pattern decides.
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use walkdir::WalkDir;
+
+/// Ignore files read in each directory; later ones take precedence.
+pub const IGNORE_FILES: [&str; 2] = [".gitignore", ".ssgignore"];
+
+#[derive(Clone, Debug)]
+struct Pattern {
+    glob: String,
+    negated: bool,
+    dir_only: bool,
+    /// Matched against the whole path below `base` rather than a name.
+    anchored: bool,
+    /// Directory, relative to the root, the pattern was written in.
+    base: PathBuf,
+}
+
+/// Ignore patterns for one content root, applied to paths relative to it.
+#[derive(Clone, Debug, Default)]
+pub struct IgnoreRules {
+    patterns: Vec<Pattern>,
+}
+
+impl IgnoreRules {
+    /// Rules from `globs` alone.
+    pub fn new(globs: &[&str]) -> Self {
+        let mut rules = Self::default();
+        for glob in globs {
+            rules.add(Path::new(""), glob);
+        }
+        rules
+    }
+
+    /// Rules from `globs` plus every ignore file under `root`, except in
+    /// directories the rules gathered so far exclude, which are not entered.
+    pub fn load(root: &Path, globs: &[&str]) -> Self {
+        let mut rules = Self::new(globs);
+        // Directories come before their contents, so a directory's ignore
+        // files are read before anything below it is matched, and deeper
+        // files can override them.
+        let mut walk = WalkDir::new(root).follow_links(true).into_iter();
+        while let Some(entry) = walk.next() {
+            let Ok(entry) = entry else { continue };
+            if !entry.file_type().is_dir() {
+                continue;
+            }
+            let Ok(dir) = entry.path().strip_prefix(root) else {
+                continue;
+            };
+            if entry.depth() > 0 && rules.is_ignored(dir, true) {
+                walk.skip_current_dir();
+                continue;
+            }
+            for name in IGNORE_FILES {
+                if let Ok(contents) = fs::read_to_string(entry.path().join(name)) {
+                    rules.add_file(dir, &contents);
+                }
+            }
+        }
+        rules
+    }
+
+    /// Add the patterns of an ignore file found in `dir`.
+    pub fn add_file(&mut self, dir: &Path, contents: &str) {
+        for line in contents.lines() {
+            self.add(dir, line);
+        }
+    }
+
+    fn add(&mut self, base: &Path, line: &str) {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        let glob = line.trim_start_matches('/');
+        if glob.is_empty() {
+            return;
+        }
+        self.patterns.push(Pattern {
+            glob: glob.to_owned(),
+            negated,
+            dir_only,
+            anchored,
+            base: base.to_path_buf(),
+        });
+    }
+
+    /// Whether the entry at `rel` (relative to the root) is excluded.
+    pub fn is_ignored(&self, rel: &Path, is_dir: bool) -> bool {
+        let mut ignored = false;
+        for pattern in &self.patterns {
+            if pattern.dir_only && !is_dir {
+                continue;
+            }
+            let Ok(below) = rel.strip_prefix(&pattern.base) else {
+                continue;
+            };
+            let matched = if pattern.anchored {
+                glob_match(&pattern.glob, &slash_path(below))
+            } else {
+                below
+                    .file_name()
+                    .is_some_and(|name| glob_match(&pattern.glob, &name.to_string_lossy()))
+            };
+            if matched {
+                ignored = !pattern.negated;
+            }
+        }
+        ignored
+    }
+}
+
+fn slash_path(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Whether `path` (segments separated by `/`) matches `glob`.
+pub fn glob_match(glob: &str, path: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let path: Vec<char> = path.chars().collect();
+    matches_from(&glob, &path)
+}
+
+fn matches_from(glob: &[char], path: &[char]) -> bool {
+    match glob {
+        [] => path.is_empty(),
+        ['*', '*', '/', rest @ ..] => {
+            // Zero or more whole segments.
+            matches_from(rest, path)
+                || path
+                    .iter()
+                    .enumerate()
+                    .any(|(i, &c)| c == '/' && matches_from(rest, &path[i + 1..]))
+        }
+        ['*', '*', rest @ ..] => (0..=path.len()).any(|i| matches_from(rest, &path[i..])),
+        ['*', rest @ ..] => {
+            let segment = path.iter().position(|&c| c == '/').unwrap_or(path.len());
+            (0..=segment).any(|i| matches_from(rest, &path[i..]))
+        }
+        ['?', rest @ ..] => {
+            matches!(path.first(), Some(&c) if c != '/') && matches_from(rest, &path[1..])
+        }
+        ['[', class @ ..] => match (path.first(), class_match(class, path.first().copied())) {
+            (Some(_), Some((true, len))) => matches_from(&class[len..], &path[1..]),
+            (_, Some((false, _))) | (None, _) => false,
+            // An unclosed bracket is a literal.
+            (Some(&c), None) => c == '[' && matches_from(class, &path[1..]),
+        },
+        ['\\', c, rest @ ..] | [c, rest @ ..] => {
+            path.first() == Some(c) && matches_from(rest, &path[1..])
+        }
+    }
+}
+
+/// Whether `c` is in the class starting just after a `[`, and how much of
+/// the glob the class takes up including its `]`. `None` if unclosed.
+fn class_match(class: &[char], c: Option<char>) -> Option<(bool, usize)> {
+    let (negated, start) = match class.first() {
+        Some('!' | '^') => (true, 1),
+        _ => (false, 0),
+    };
+    let mut i = start;
+    let mut found = false;
+    loop {
+        let first = *class.get(i)?;
+        if first == ']' && i > start {
+            break;
+        }
+        if class.get(i + 1) == Some(&'-') && class.get(i + 2).is_some_and(|&e| e != ']') {
+            let last = class[i + 2];
+            found |= c.is_some_and(|c| (first..=last).contains(&c));
+            i += 3;
+        } else {
+            found |= c == Some(first);
+            i += 1;
+        }
+    }
+    let matched = c.is_some_and(|c| c != '/') && found != negated;
+    Some((matched, i + 1))
+}
+
+#[cfg(test)]
+mod tests;
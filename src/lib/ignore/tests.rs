@@ -0,0 +1,73 @@
+use std::{fs, path::Path};
+
+use tempfile::TempDir;
+
+use crate::ignore::{IgnoreRules, glob_match};
+
+#[test]
+fn globs_keep_single_stars_within_a_segment() {
+    assert!(glob_match("*.md", "post.md"));
+    assert!(!glob_match("*.md", "drafts/post.md"));
+    assert!(glob_match("drafts/*.md", "drafts/post.md"));
+    assert!(glob_match("post?.md", "post1.md"));
+    assert!(!glob_match("post?.md", "post/.md"));
+}
+
+#[test]
+fn double_stars_span_segments() {
+    assert!(glob_match("**/drafts/**", "drafts/a.md"));
+    assert!(glob_match("**/drafts/**", "2024/drafts/deep/a.md"));
+    assert!(!glob_match("**/drafts/**", "2024/drafted/a.md"));
+    assert!(glob_match("notes/**", "notes/a/b.md"));
+    assert!(glob_match("a/**/b.md", "a/b.md"));
+}
+
+#[test]
+fn classes_and_escapes() {
+    assert!(glob_match(".*.sw[op]", ".post.md.swp"));
+    assert!(!glob_match(".*.sw[op]", ".post.md.swx"));
+    assert!(glob_match("[a-c]x", "bx"));
+    assert!(glob_match("[!a-c]x", "dx"));
+    assert!(!glob_match("[!a-c]x", "ax"));
+    assert!(glob_match(r"\*.md", "*.md"));
+    assert!(!glob_match(r"\*.md", "a.md"));
+    assert!(glob_match("[oops", "[oops"));
+}
+
+#[test]
+fn unanchored_patterns_match_names_at_any_depth() {
+    let rules = IgnoreRules::new(&["*~", ".git/", "/top.md"]);
+    assert!(rules.is_ignored(Path::new("a/b/post.md~"), false));
+    assert!(rules.is_ignored(Path::new("sub/.git"), true));
+    assert!(!rules.is_ignored(Path::new("sub/.git"), false));
+    assert!(rules.is_ignored(Path::new("top.md"), false));
+    assert!(!rules.is_ignored(Path::new("sub/top.md"), false));
+}
+
+#[test]
+fn ignore_files_apply_below_their_directory_and_can_negate() {
+    let mut rules = IgnoreRules::new(&["*.md"]);
+    rules.add_file(Path::new("keep"), "# comment\n\n!*.md\nsecret.md\n");
+    assert!(rules.is_ignored(Path::new("post.md"), false));
+    assert!(!rules.is_ignored(Path::new("keep/post.md"), false));
+    assert!(rules.is_ignored(Path::new("keep/secret.md"), false));
+}
+
+#[test]
+fn excluded_directories_are_not_searched_for_ignore_files() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path();
+    fs::create_dir_all(root.join(".git/info")).unwrap();
+    fs::create_dir_all(root.join("drafts")).unwrap();
+    fs::create_dir_all(root.join("posts")).unwrap();
+    fs::write(root.join(".gitignore"), "drafts/\n").unwrap();
+    fs::write(root.join(".git/info/.gitignore"), "*.md\n").unwrap();
+    fs::write(root.join("drafts/.ssgignore"), "*.md\n").unwrap();
+    fs::write(root.join("posts/.ssgignore"), "*.tmp\n").unwrap();
+
+    let rules = IgnoreRules::load(root, &[".git/"]);
+
+    let bases: Vec<&Path> = rules.patterns.iter().map(|p| p.base.as_path()).collect();
+    assert_eq!(bases, [Path::new(""), Path::new(""), Path::new("posts")]);
+    assert!(rules.is_ignored(Path::new("posts/a.tmp"), false));
+}
@@ -0,0 +1,204 @@
+//! Include directives, which splice another file into a Markdown source
+//! before it is parsed: a line holding only `{{ include "snippets/setup.md" }}`,
+//! or a fence opened with ```` ```include path=snippets/setup.md ```` (its
+//! body is ignored). Paths are relative to the site root, and included files
+//! may include others. `table` in place of `include` reads a CSV or TSV file
+//! instead and splices it in as a Markdown table (see `crate::table`).
+//! Directives inside other code fences are left alone so they can be
+//! documented.
+//!
+//! Keep snippets outside the content roots (or ignore them there), or they
+//! are published as pages of their own.
+use std::{
+    borrow::Cow,
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::{Section, eyre::eyre};
+
+use crate::{
+    fence::{self, Line},
+    table::{delimiter_for, markdown_table, parse_delimited},
+};
+
+/// What a directive splices in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Directive {
+    /// Another Markdown file, itself expanded.
+    Include,
+    /// A CSV or TSV file as a table.
+    Table,
+}
+
+impl Directive {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "include" => Some(Self::Include),
+            "table" => Some(Self::Table),
+            _ => None,
+        }
+    }
+}
+
+/// Expand every include directive in `source`, the contents of `path`.
+/// Each file read is added to `included`. Returns the source unchanged (and
+/// borrowed) when it includes nothing.
+pub fn expand_includes<'a>(
+    source: &'a str,
+    path: &Path,
+    root: &Path,
+    included: &mut BTreeSet<PathBuf>,
+) -> color_eyre::Result<Cow<'a, str>> {
+    if !source.contains("include") && !source.contains("table") {
+        return Ok(Cow::Borrowed(source));
+    }
+    let mut stack = vec![fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())];
+    let expanded = expand(source, root, &mut stack, included)?;
+    Ok(match expanded {
+        Some(out) => Cow::Owned(out),
+        None => Cow::Borrowed(source),
+    })
+}
+
+/// `source` with its includes expanded, or `None` if it has none. `stack`
+/// holds the files being expanded, outermost first.
+fn expand(
+    source: &str,
+    root: &Path,
+    stack: &mut Vec<PathBuf>,
+    included: &mut BTreeSet<PathBuf>,
+) -> color_eyre::Result<Option<String>> {
+    let mut out = String::with_capacity(source.len());
+    let mut changed = false;
+    // Set while skipping the body of an ```include fence.
+    let mut skipping = false;
+
+    for line in fence::lines(source) {
+        let directive = match line {
+            Line::Fenced(_) if skipping => continue,
+            Line::Fenced(_) => None,
+            Line::Open { info, .. } => parse_include_fence(info),
+            Line::Text(text) => parse_include(text.trim()),
+        };
+        skipping = matches!(line, Line::Open { .. }) && directive.is_some();
+        match directive {
+            Some((directive, target)) => {
+                out.push_str(&splice(directive, target, root, stack, included)?);
+                changed = true;
+            }
+            None => out.push_str(line.raw()),
+        }
+    }
+
+    Ok(changed.then_some(out))
+}
+
+/// What `directive` puts in place of itself, ending in a newline.
+fn splice(
+    directive: Directive,
+    target: &str,
+    root: &Path,
+    stack: &mut Vec<PathBuf>,
+    included: &mut BTreeSet<PathBuf>,
+) -> color_eyre::Result<String> {
+    match directive {
+        Directive::Include => include(target, root, stack, included),
+        Directive::Table => table(target, root, included),
+    }
+}
+
+/// The expanded contents of `target`, ending in a newline.
+fn include(
+    target: &str,
+    root: &Path,
+    stack: &mut Vec<PathBuf>,
+    included: &mut BTreeSet<PathBuf>,
+) -> color_eyre::Result<String> {
+    let (path, canonical) = resolve(target, root, "include")?;
+    if stack.contains(&canonical) {
+        let chain: Vec<String> = stack
+            .iter()
+            .chain([&canonical])
+            .map(|p| p.display().to_string())
+            .collect();
+        return Err(eyre!("include cycle: {}", chain.join(" → ")));
+    }
+    let contents = fs::read_to_string(&canonical)
+        .map_err(|e| eyre!("cannot include {}: {e}", path.display()))?;
+    included.insert(path);
+
+    stack.push(canonical);
+    let expanded = expand(&contents, root, stack, included)?;
+    stack.pop();
+
+    let mut text = expanded.unwrap_or(contents);
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+    Ok(text)
+}
+
+/// The CSV or TSV file `target` as a Markdown table.
+fn table(
+    target: &str,
+    root: &Path,
+    included: &mut BTreeSet<PathBuf>,
+) -> color_eyre::Result<String> {
+    let (path, canonical) = resolve(target, root, "read table")?;
+    let contents = fs::read_to_string(&canonical)
+        .map_err(|e| eyre!("cannot read table {}: {e}", path.display()))?;
+    let rows = parse_delimited(&contents, delimiter_for(&path))
+        .with_note(|| format!("While reading table {}", path.display()))?;
+    let table = markdown_table(&rows).with_note(|| format!("In {}", path.display()))?;
+    included.insert(path);
+    Ok(table)
+}
+
+/// `target` joined to `root`, and its canonical form, which must lie inside
+/// the canonical root so a directive cannot read arbitrary files. `action`
+/// names what the directive was doing, for errors.
+fn resolve(target: &str, root: &Path, action: &str) -> color_eyre::Result<(PathBuf, PathBuf)> {
+    let path = root.join(target);
+    let canonical = fs::canonicalize(&path)
+        .map_err(|e| eyre!("cannot {action} {}: {e}", path.display()))
+        .with_note(|| "Paths are relative to the site root")?;
+    let root = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+    if !canonical.starts_with(&root) {
+        return Err(eyre!(
+            "cannot {action} {target}: it is outside the site root"
+        ))
+        .with_note(|| format!("Only files under {} may be included", root.display()));
+    }
+    Ok((path, canonical))
+}
+
+/// The directive and path of a `{{ include "..." }}` or `{{ table "..." }}`
+/// line.
+pub fn parse_include(line: &str) -> Option<(Directive, &str)> {
+    let inner = line.strip_prefix("{{")?.strip_suffix("}}")?.trim();
+    let (name, quoted) = inner.split_once(char::is_whitespace)?;
+    let directive = Directive::from_name(name)?;
+    let path = quoted
+        .trim()
+        .strip_prefix('"')?
+        .strip_suffix('"')
+        .filter(|path| !path.is_empty() && !path.contains('"'))?;
+    Some((directive, path))
+}
+
+/// The directive and path of an ```` ```include path=... ```` or
+/// ```` ```table path=... ```` fence, given its info string.
+pub fn parse_include_fence(info: &str) -> Option<(Directive, &str)> {
+    let mut words = info.split_whitespace();
+    let directive = Directive::from_name(words.next()?)?;
+    let path = words
+        .find_map(|word| word.strip_prefix("path="))
+        .map(|path| path.trim_matches('"'))
+        .filter(|path| !path.is_empty())?;
+    Some((directive, path))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,154 @@
+use std::{borrow::Cow, collections::BTreeSet, fs};
+
+use tempfile::TempDir;
+
+use crate::include::{Directive, expand_includes, parse_include, parse_include_fence};
+
+#[test]
+fn parses_include_directives() {
+    assert_eq!(
+        parse_include(r#"{{ include "snippets/setup.md" }}"#),
+        Some((Directive::Include, "snippets/setup.md"))
+    );
+    assert_eq!(
+        parse_include(r#"{{include "a.md"}}"#),
+        Some((Directive::Include, "a.md"))
+    );
+    assert_eq!(
+        parse_include(r#"{{ table "data/prices.csv" }}"#),
+        Some((Directive::Table, "data/prices.csv"))
+    );
+    assert!(parse_include(r#"{{ included "a.md" }}"#).is_none());
+    assert!(parse_include(r#"{{ include a.md }}"#).is_none());
+    assert!(parse_include(r#"See {{ include "a.md" }}"#).is_none());
+
+    assert_eq!(
+        parse_include_fence("include path=a.md"),
+        Some((Directive::Include, "a.md"))
+    );
+    assert_eq!(
+        parse_include_fence(r#"table path="a.tsv""#),
+        Some((Directive::Table, "a.tsv"))
+    );
+    assert!(parse_include_fence("rust").is_none());
+    assert!(parse_include_fence("include").is_none());
+}
+
+#[test]
+fn includes_are_expanded_recursively_and_recorded() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path();
+    fs::create_dir_all(root.join("snippets")).unwrap();
+    fs::write(
+        root.join("snippets/setup.md"),
+        "Install it.\n{{ include \"snippets/note.md\" }}\n",
+    )
+    .unwrap();
+    fs::write(root.join("snippets/note.md"), "> Note.").unwrap();
+    let page = root.join("post.md");
+    let source = "# Setup\n\n{{ include \"snippets/setup.md\" }}\n\n```include path=snippets/note.md\n```\nDone.\n";
+
+    let mut included = BTreeSet::new();
+    let out = expand_includes(source, &page, root, &mut included).unwrap();
+
+    assert_eq!(out, "# Setup\n\nInstall it.\n> Note.\n\n> Note.\nDone.\n");
+    assert_eq!(
+        included,
+        BTreeSet::from([
+            root.join("snippets/note.md"),
+            root.join("snippets/setup.md")
+        ])
+    );
+}
+
+#[test]
+fn tables_are_spliced_in_as_markdown_and_recorded() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path();
+    fs::write(root.join("prices.csv"), "Item,Price\nTea,3\n").unwrap();
+    fs::write(root.join("stock.tsv"), "Item\tCount\nTea\t12\n").unwrap();
+    let source = "Prices:\n\n{{ table \"prices.csv\" }}\n\n```table path=stock.tsv\n```\n";
+
+    let mut included = BTreeSet::new();
+    let out = expand_includes(source, &root.join("post.md"), root, &mut included).unwrap();
+
+    assert_eq!(
+        out,
+        concat!(
+            "Prices:\n\n",
+            "| Item | Price |\n| --- | --- |\n| Tea | 3 |\n\n",
+            "| Item | Count |\n| --- | --- |\n| Tea | 12 |\n",
+        )
+    );
+    assert_eq!(
+        included,
+        BTreeSet::from([root.join("prices.csv"), root.join("stock.tsv")])
+    );
+}
+
+#[test]
+fn directives_in_code_are_left_alone() {
+    let tmp = TempDir::new().unwrap();
+    let source = "```md\n{{ include \"missing.md\" }}\n```\n";
+
+    let mut included = BTreeSet::new();
+    let out = expand_includes(
+        source,
+        &tmp.path().join("post.md"),
+        tmp.path(),
+        &mut included,
+    )
+    .unwrap();
+
+    assert!(matches!(out, Cow::Borrowed(_)));
+    assert!(included.is_empty());
+}
+
+#[test]
+fn cycles_and_missing_files_are_errors() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path();
+    fs::write(root.join("a.md"), "{{ include \"b.md\" }}\n").unwrap();
+    fs::write(root.join("b.md"), "{{ include \"a.md\" }}\n").unwrap();
+    let mut included = BTreeSet::new();
+
+    let err = expand_includes(
+        "{{ include \"a.md\" }}\n",
+        &root.join("post.md"),
+        root,
+        &mut included,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("include cycle"), "{err}");
+
+    let err = expand_includes(
+        "{{ include \"gone.md\" }}\n",
+        &root.join("post.md"),
+        root,
+        &mut included,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("cannot include"), "{err}");
+}
+
+#[test]
+fn paths_outside_the_site_root_are_rejected() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path().join("site");
+    fs::create_dir_all(&root).unwrap();
+    fs::write(tmp.path().join("secret.md"), "Secret.\n").unwrap();
+    fs::write(tmp.path().join("secret.csv"), "a,b\n1,2\n").unwrap();
+    let secret = tmp.path().join("secret.md");
+
+    for source in [
+        "{{ include \"../secret.md\" }}\n".to_owned(),
+        format!("{{{{ include \"{}\" }}}}\n", secret.display()),
+        "```table path=../secret.csv\n```\n".to_owned(),
+    ] {
+        let mut included = BTreeSet::new();
+        let err =
+            expand_includes(&source, &root.join("post.md"), &root, &mut included).unwrap_err();
+        assert!(err.to_string().contains("outside the site root"), "{err}");
+        assert!(included.is_empty());
+    }
+}
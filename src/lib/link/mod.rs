@@ -0,0 +1,184 @@
+//! Link-blog posts: titles for `link:` targets, fetched once and cached.
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+use color_eyre::Section;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{FETCH_LINK_TITLES, LINK_TITLE_RETRY_DAYS};
+
+/// File under the cache directory holding fetched link titles.
+pub const LINK_TITLE_CACHE: &str = "link-titles.json";
+
+/// How long a single title fetch may take before it is abandoned.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+/// Only the start of the page is needed to find its title.
+const FETCH_LIMIT: u64 = 512 * 1024;
+
+/// Titles of outbound links keyed by URL, persisted between builds so each
+/// target is fetched at most once, and one without a title at most once
+/// every `LINK_TITLE_RETRY_DAYS`.
+#[derive(Debug, Default)]
+pub struct LinkTitles {
+    path: Option<PathBuf>,
+    titles: Mutex<BTreeMap<String, Cached>>,
+}
+
+/// What the last fetch of a target found.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Cached {
+    Title(String),
+    /// No title; `checked` is when, in seconds since the Unix epoch.
+    Missing {
+        checked: u64,
+    },
+}
+
+impl LinkTitles {
+    /// Load the cache at `path`; a missing or unreadable cache starts empty.
+    pub fn load(path: &Path) -> Self {
+        let titles = fs::read_to_string(path)
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default();
+        Self {
+            path: Some(path.to_path_buf()),
+            titles: Mutex::new(titles),
+        }
+    }
+
+    /// Title for `url`, from the cache or, failing that, from the page itself.
+    pub fn title_for(&self, url: &str) -> Option<String> {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        match self.lock().get(url) {
+            Some(Cached::Title(title)) => return Some(title.clone()),
+            Some(Cached::Missing { checked })
+                if now.saturating_sub(*checked) < LINK_TITLE_RETRY_DAYS * 24 * 60 * 60 =>
+            {
+                return None;
+            }
+            _ => {}
+        }
+        if !FETCH_LINK_TITLES || !(url.starts_with("http://") || url.starts_with("https://")) {
+            return None;
+        }
+
+        let title = fetch_title(url);
+        let cached = match &title {
+            Some(title) => Cached::Title(title.clone()),
+            None => Cached::Missing { checked: now },
+        };
+        self.lock().insert(url.to_string(), cached);
+        title
+    }
+
+    /// Write the cache back to disk.
+    pub fn save(&self) -> color_eyre::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let titles = self.lock();
+        if titles.is_empty() && !path.exists() {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(&*titles)?)
+            .with_note(|| format!("While writing {}", path.display()))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, Cached>> {
+        self.titles.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn fetch_title(url: &str) -> Option<String> {
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(FETCH_TIMEOUT))
+        .build()
+        .into();
+    let html = agent
+        .get(url)
+        .call()
+        .ok()?
+        .body_mut()
+        .with_config()
+        .limit(FETCH_LIMIT)
+        .lossy_utf8(true)
+        .read_to_string()
+        .ok()?;
+
+    page_title(&html)
+}
+
+/// Best title for a page: `og:title`, falling back to `<title>`.
+pub fn page_title(html: &str) -> Option<String> {
+    og_title(html)
+        .or_else(|| title_element(html))
+        .map(|t| unescape_html(t.trim()))
+        .filter(|t| !t.is_empty())
+}
+
+fn og_title(html: &str) -> Option<&str> {
+    let mut rest = html;
+    while let Some(start) = rest.find("<meta") {
+        let tag_start = &rest[start..];
+        let end = tag_start.find('>')?;
+        let tag = &tag_start[..end];
+        if attribute(tag, "property").or_else(|| attribute(tag, "name")) == Some("og:title") {
+            return attribute(tag, "content");
+        }
+        rest = &tag_start[end..];
+    }
+    None
+}
+
+fn title_element(html: &str) -> Option<&str> {
+    let start = html.find("<title")?;
+    let after = &html[start..];
+    let open_end = after.find('>')? + 1;
+    let close = after.find("</title>")?;
+    after.get(open_end..close)
+}
+
+/// Value of a quoted attribute inside a single tag.
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = tag;
+    loop {
+        let pos = rest.find(name)?;
+        let before = rest[..pos].chars().last();
+        let after = rest[pos + name.len()..].trim_start();
+        rest = &rest[pos + name.len()..];
+        if !before.is_some_and(char::is_whitespace) {
+            continue;
+        }
+        let Some(value) = after.strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let value = &value[1..];
+        return value.find(quote).map(|end| &value[..end]);
+    }
+}
+
+fn unescape_html(s: &str) -> String {
+    s.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,69 @@
+use tempfile::TempDir;
+
+use crate::link::{LinkTitles, page_title};
+
+#[test]
+fn og_title_is_preferred_over_title_element() {
+    let html = r#"<html><head><title>Fallback</title>
+<meta name="viewport" content="width=device-width">
+<meta property="og:title" content="Rust &amp; You"></head></html>"#;
+    assert_eq!(page_title(html).as_deref(), Some("Rust & You"));
+}
+
+#[test]
+fn title_element_is_used_without_og_title() {
+    let html = "<html><head><title>\n  Plain title\n</title></head></html>";
+    assert_eq!(page_title(html).as_deref(), Some("Plain title"));
+}
+
+#[test]
+fn pages_without_titles_yield_none() {
+    assert_eq!(page_title("<p>no head</p>"), None);
+    assert_eq!(page_title("<title>  </title>"), None);
+}
+
+#[test]
+fn cached_titles_are_reused_and_saved() {
+    let tmp = TempDir::new().expect("tempdir");
+    let path = tmp.path().join("cache").join("link-titles.json");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, r#"{"https://example.com/a":"Cached A"}"#).unwrap();
+
+    let titles = LinkTitles::load(&path);
+    assert_eq!(
+        titles.title_for("https://example.com/a").as_deref(),
+        Some("Cached A")
+    );
+    // Non-HTTP targets are never fetched.
+    assert_eq!(titles.title_for("mailto:someone@example.com"), None);
+
+    titles.save().unwrap();
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert!(saved.contains("Cached A"));
+}
+
+#[test]
+fn failed_fetches_are_cached_until_they_expire() {
+    let tmp = TempDir::new().expect("tempdir");
+    let path = tmp.path().join("link-titles.json");
+    // Nothing listens on port 1, so fetching fails without leaving the host.
+    let (recent, stale) = ("http://127.0.0.1:1/recent", "http://127.0.0.1:1/stale");
+    std::fs::write(
+        &path,
+        format!(
+            r#"{{"{recent}":{{"checked":{}}},"{stale}":{{"checked":0}}}}"#,
+            u64::MAX
+        ),
+    )
+    .unwrap();
+
+    let titles = LinkTitles::load(&path);
+    assert_eq!(titles.title_for(recent), None);
+    assert_eq!(titles.title_for(stale), None);
+    titles.save().unwrap();
+
+    let saved: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(saved[recent]["checked"], u64::MAX);
+    assert!(saved[stale]["checked"].as_u64().unwrap() > 0, "{saved}");
+}
@@ -0,0 +1,88 @@
+//! One build at a time per site. A build holds `BUILD_LOCK_FILE` in the site
+//! root while it writes, so watch mode and a manual `ssg` run cannot
+//! interleave pages in `OUTPUT_DIR`: the second waits for the first, and
+//! gives up with an error if it takes too long.
+use std::{
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    process, thread,
+    time::{Duration, Instant},
+};
+
+use color_eyre::{Section, eyre::eyre};
+
+use crate::config::BUILD_LOCK_FILE;
+
+/// How often a waiting build looks at the lock again.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The build lock of a site, released when dropped.
+#[derive(Debug)]
+pub struct BuildLock {
+    path: PathBuf,
+}
+
+impl BuildLock {
+    /// Take the lock of the site at `root`, waiting up to `wait` for a
+    /// running build to release it. A lock left behind by a process that is
+    /// no longer running is taken over.
+    pub fn acquire(root: &Path, wait: Duration) -> color_eyre::Result<Self> {
+        let path = root.join(BUILD_LOCK_FILE);
+        let deadline = Instant::now() + wait;
+        let mut waiting = false;
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    writeln!(file, "{}", process::id())?;
+                    return Ok(Self { path });
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+                Err(e) => {
+                    return Err(e)
+                        .with_note(|| format!("While creating the build lock {}", path.display()));
+                }
+            }
+
+            let holder = holder(&path);
+            if holder.is_some_and(|pid| !is_running(pid)) {
+                let _ = fs::remove_file(&path);
+                continue;
+            }
+            let by = holder
+                .map(|pid| format!(" (process {pid})"))
+                .unwrap_or_default();
+            if Instant::now() >= deadline {
+                return Err(eyre!("another build{by} holds {}", path.display())).with_note(
+                    || "Wait for it to finish, or delete the lock file if no build is running",
+                );
+            }
+            if !waiting {
+                eprintln!("Waiting for another build{by} to finish...");
+                waiting = true;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+impl Drop for BuildLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Process ID written into the lock at `path`, if it can be read yet.
+fn holder(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Whether process `pid` is alive, where the system can tell (Linux's
+/// `/proc`); elsewhere every holder is taken to be.
+fn is_running(pid: u32) -> bool {
+    let proc = Path::new("/proc");
+    !proc.join("self").exists() || proc.join(pid.to_string()).exists()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,36 @@
+use std::{fs, process, time::Duration};
+
+use tempfile::TempDir;
+
+use crate::{config::BUILD_LOCK_FILE, lock::BuildLock};
+
+#[test]
+fn a_held_lock_turns_others_away_until_dropped() {
+    let tmp = TempDir::new().expect("tempdir");
+    let lock_file = tmp.path().join(BUILD_LOCK_FILE);
+
+    let lock = BuildLock::acquire(tmp.path(), Duration::ZERO).unwrap();
+    assert_eq!(
+        fs::read_to_string(&lock_file).unwrap().trim(),
+        process::id().to_string()
+    );
+    let err = BuildLock::acquire(tmp.path(), Duration::from_millis(150)).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains(&format!("process {}", process::id())),
+        "{err}"
+    );
+
+    drop(lock);
+    assert!(!lock_file.exists());
+    BuildLock::acquire(tmp.path(), Duration::ZERO).unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn a_lock_left_by_a_dead_process_is_taken_over() {
+    let tmp = TempDir::new().expect("tempdir");
+    fs::write(tmp.path().join(BUILD_LOCK_FILE), "4294967295\n").unwrap();
+
+    BuildLock::acquire(tmp.path(), Duration::ZERO).unwrap();
+}
@@ -0,0 +1,199 @@
+//! Static maps for `{{ map lat lon zoom }}` shortcodes. The tiles around the
+//! point are fetched from `MAP_TILE_URL` at build time and stitched into one
+//! image with ImageMagick (`IMAGE_CLI`), which links to an interactive map
+//! instead of embedding one. Tiles and finished maps are kept under the cache
+//! directory, so a map is only drawn once.
+use std::{
+    f64::consts::PI,
+    fs,
+    path::Path,
+    process::Command,
+};
+
+use crate::{
+    config::{IMAGE_CLI, MAP_ATTRIBUTION, MAP_LINK_URL, MAP_SIZE, MAP_TILE_URL, MAPS_DIR},
+    proxy::download,
+    utils::{escape_attr, escape_text, partial_path},
+};
+
+/// Directory under the cache directory holding tiles and finished maps.
+pub const MAP_CACHE_DIR: &str = "maps";
+
+/// Edge of a map tile in pixels.
+const TILE_SIZE: u32 = 256;
+/// Most zoomed-in level tile providers serve.
+pub const MAX_ZOOM: u8 = 19;
+/// Latitudes beyond this are off the edge of a Web Mercator map.
+const MAX_LATITUDE: f64 = 85.051_128_78;
+
+/// A point to centre a map on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MapPoint {
+    pub lat: f64,
+    pub lon: f64,
+    pub zoom: u8,
+}
+
+impl MapPoint {
+    /// `None` unless the point is on the map and the zoom level is served.
+    pub fn new(lat: f64, lon: f64, zoom: u8) -> Option<Self> {
+        let on_map = lat.abs() <= MAX_LATITUDE && lon.abs() <= 180.0;
+        (on_map && zoom <= MAX_ZOOM).then_some(Self { lat, lon, zoom })
+    }
+
+    /// Position of the point in pixels from the top left of the world at
+    /// its zoom level.
+    fn world_pixel(self) -> (f64, f64) {
+        let world = f64::from(TILE_SIZE) * f64::from(1u32 << self.zoom);
+        let lat = self.lat.to_radians();
+        let x = (self.lon + 180.0) / 360.0 * world;
+        let y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0 * world;
+        (x, y)
+    }
+
+    /// Address of the interactive map of this point.
+    pub fn link(self) -> String {
+        MAP_LINK_URL
+            .replace("{lat}", &format!("{:.5}", self.lat))
+            .replace("{lon}", &format!("{:.5}", self.lon))
+            .replace("{zoom}", &self.zoom.to_string())
+    }
+}
+
+/// Tiles covering a `size` map centred on `point`, row by row, and the
+/// offset of the map within them.
+#[derive(Debug, PartialEq)]
+pub struct TileGrid {
+    /// Tile coordinates; each row holds the same number of tiles.
+    pub rows: Vec<Vec<(u32, u32)>>,
+    pub offset: (u32, u32),
+}
+
+pub fn tile_grid(point: MapPoint, (width, height): (u32, u32)) -> TileGrid {
+    let tiles = 1i64 << point.zoom;
+    let tile = i64::from(TILE_SIZE);
+    let (x, y) = point.world_pixel();
+    let left = x.round() as i64 - i64::from(width / 2);
+    let top = y.round() as i64 - i64::from(height / 2);
+    let (first_col, first_row) = (left.div_euclid(tile), top.div_euclid(tile));
+    let last_col = (left + i64::from(width) - 1).div_euclid(tile);
+    let last_row = (top + i64::from(height) - 1).div_euclid(tile);
+
+    // Columns wrap around the antimeridian; rows past the poles repeat the
+    // edge tiles, which are mostly sea or ice anyway.
+    let rows = (first_row..=last_row)
+        .map(|row| {
+            (first_col..=last_col)
+                .map(|col| (col.rem_euclid(tiles) as u32, row.clamp(0, tiles - 1) as u32))
+                .collect()
+        })
+        .collect();
+    let offset = (
+        (left - first_col * tile) as u32,
+        (top - first_row * tile) as u32,
+    );
+    TileGrid { rows, offset }
+}
+
+fn tile_url(zoom: u8, (x, y): (u32, u32)) -> String {
+    MAP_TILE_URL
+        .replace("{z}", &zoom.to_string())
+        .replace("{x}", &x.to_string())
+        .replace("{y}", &y.to_string())
+}
+
+/// File name of the map of `point`, which changes with the tile provider
+/// and map size as well as the point itself.
+pub fn map_name(point: MapPoint) -> String {
+    let key = format!(
+        "{MAP_TILE_URL}\0{:.5}\0{:.5}\0{}\0{}x{}",
+        point.lat, point.lon, point.zoom, MAP_SIZE.0, MAP_SIZE.1
+    );
+    let hash = blake3::hash(key.as_bytes()).to_hex();
+    format!("{}.png", &hash[..16])
+}
+
+/// Draw the map of `point` into `MAPS_DIR` under `output_dir`, returning its
+/// path relative to the site root.
+pub fn static_map(point: MapPoint, cache_dir: &Path, output_dir: &Path) -> Result<String, String> {
+    let name = map_name(point);
+    let cache_dir = cache_dir.join(MAP_CACHE_DIR);
+    let cached = cache_dir.join(&name);
+    if !cached.is_file() {
+        render_map(point, &cache_dir, &cached)?;
+    }
+
+    let out_dir = output_dir.join(MAPS_DIR);
+    fs::create_dir_all(&out_dir).map_err(|e| e.to_string())?;
+    fs::copy(&cached, out_dir.join(&name)).map_err(|e| e.to_string())?;
+    Ok(format!("{MAPS_DIR}/{name}"))
+}
+
+/// Fetch the tiles around `point` and stitch, crop, and mark them into
+/// `dest`.
+fn render_map(point: MapPoint, cache_dir: &Path, dest: &Path) -> Result<(), String> {
+    let grid = tile_grid(point, MAP_SIZE);
+    let mut args: Vec<String> = Vec::new();
+    for row in &grid.rows {
+        args.push("(".into());
+        for &tile in row {
+            let url = tile_url(point.zoom, tile);
+            let hash = blake3::hash(url.as_bytes()).to_hex();
+            let path = cache_dir.join("tiles").join(format!("{}.png", &hash[..16]));
+            if !path.is_file() {
+                download(&url, &path)?;
+            }
+            args.push(path.display().to_string());
+        }
+        args.extend(["+append".into(), ")".into()]);
+    }
+
+    let (width, height) = MAP_SIZE;
+    let (cx, cy) = (width / 2, height / 2);
+    let partial = partial_path(dest);
+    let status = Command::new(IMAGE_CLI)
+        .args(&args)
+        .arg("-append")
+        .arg("-crop")
+        .arg(format!(
+            "{width}x{height}+{}+{}",
+            grid.offset.0, grid.offset.1
+        ))
+        .arg("+repage")
+        .args(["-fill", "#c0392b", "-stroke", "white", "-strokewidth", "2"])
+        .arg("-draw")
+        .arg(format!("circle {cx},{cy} {},{cy}", cx + 6))
+        .args(["-strip"])
+        .arg(format!("png:{}", partial.display()))
+        .status()
+        .map_err(|e| format!("could not run {IMAGE_CLI}: {e}"))?;
+    if !status.success() {
+        let _ = fs::remove_file(&partial);
+        return Err(format!("{IMAGE_CLI} exited with {status}"));
+    }
+    fs::rename(&partial, dest).map_err(|e| e.to_string())
+}
+
+/// Markup for the map of `point`: the image at `image` (relative to the site
+/// root, from a page `href_prefix` away) linking to the interactive map, or
+/// just the link when there is no image.
+pub fn map_html(point: MapPoint, href_prefix: &str, image: Option<&str>) -> String {
+    let link = escape_attr(&point.link());
+    let label = format!("Map of {:.5}, {:.5}", point.lat, point.lon);
+    let Some(image) = image else {
+        return format!(
+            r#"<a class="map-link" href="{link}">{}</a>"#,
+            escape_text(&label)
+        );
+    };
+    let (width, height) = MAP_SIZE;
+    format!(
+        r#"<figure class="map"><a href="{link}"><img src="{}" alt="{}" width="{width}" height="{height}" loading="lazy" decoding="async"></a><figcaption class="map-attribution">{}</figcaption></figure>"#,
+        escape_attr(&format!("{href_prefix}{image}")),
+        escape_attr(&label),
+        escape_text(MAP_ATTRIBUTION),
+    )
+}
+
+#[cfg(test)]
+mod tests;
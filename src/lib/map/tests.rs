@@ -0,0 +1,55 @@
+use crate::map::{MapPoint, TileGrid, map_html, map_name, tile_grid};
+
+#[test]
+fn grids_cover_the_map_around_the_point() {
+    let point = MapPoint::new(0.0, 0.0, 2).unwrap();
+    let grid = tile_grid(point, (640, 360));
+    assert_eq!(
+        grid,
+        TileGrid {
+            rows: vec![
+                vec![(0, 1), (1, 1), (2, 1), (3, 1)],
+                vec![(0, 2), (1, 2), (2, 2), (3, 2)],
+            ],
+            offset: (192, 76),
+        }
+    );
+}
+
+#[test]
+fn grids_wrap_columns_and_clamp_rows() {
+    let point = MapPoint::new(0.0, 0.0, 0).unwrap();
+    let grid = tile_grid(point, (640, 360));
+    assert_eq!(
+        grid,
+        TileGrid {
+            rows: vec![vec![(0, 0); 3]; 3],
+            offset: (64, 204),
+        }
+    );
+}
+
+#[test]
+fn points_off_the_map_are_rejected() {
+    assert!(MapPoint::new(86.0, 0.0, 3).is_none());
+    assert!(MapPoint::new(0.0, 181.0, 3).is_none());
+    assert!(MapPoint::new(0.0, 0.0, 20).is_none());
+}
+
+#[test]
+fn maps_are_named_after_their_point() {
+    let a = MapPoint::new(51.5, -0.12, 13).unwrap();
+    let b = MapPoint::new(51.5, -0.12, 14).unwrap();
+    assert_eq!(map_name(a), map_name(a));
+    assert_ne!(map_name(a), map_name(b));
+    assert!(map_name(a).ends_with(".png"));
+}
+
+#[test]
+fn undrawn_maps_are_plain_links() {
+    let point = MapPoint::new(51.5, -0.12, 13).unwrap();
+    let html = map_html(point, "", None);
+    assert!(html.starts_with(r#"<a class="map-link" href="https://www.openstreetmap.org/"#));
+    assert!(html.ends_with(">Map of 51.50000, -0.12000</a>"));
+    assert!(!html.contains("<img"));
+}
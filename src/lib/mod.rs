@@ -1,10 +1,47 @@
+pub mod alias;
+pub mod anchors;
+pub mod api;
+pub mod archive;
 pub mod article;
+pub mod author;
+pub mod backlinks;
+pub mod bibliography;
+pub mod cache;
 pub mod config;
 pub mod css;
+pub mod dates;
+pub mod diagnostics;
 pub mod feed;
+pub mod fence;
+pub mod fragment;
 pub mod header;
+pub mod ignore;
+pub mod include;
+pub mod link;
+pub mod lock;
+pub mod map;
+pub mod page_assets;
+pub mod pandoc;
+pub mod photo;
 pub mod pipeline;
+pub mod postprocess;
+pub mod preload;
+pub mod progress;
+pub mod projects;
+pub mod proxy;
+pub mod recipe;
+pub mod related;
+pub mod report;
+pub mod search;
+pub mod series;
+pub mod shortcode;
+pub mod slug;
+pub mod table;
+pub mod tag_info;
 pub mod templates;
+pub mod terms;
+pub mod thumbnail;
 pub mod transformer;
 pub mod types;
 pub mod utils;
+pub mod watch;
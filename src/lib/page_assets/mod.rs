@@ -0,0 +1,191 @@
+//! Scripts and stylesheets a single page loads for itself, listed under
+//! `scripts:` and `styles:` in its frontmatter. They are held to per-page byte
+//! budgets, so an experiment cannot quietly bloat the site, and their tags
+//! are written with Subresource Integrity hashes; scripts are deferred so they
+//! never block rendering. Remote files are fetched once into the cache to be
+//! measured and hashed.
+use std::{
+    fs,
+    path::{Component, Path},
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use ring::digest::{SHA384, digest};
+
+use crate::{
+    config::{PAGE_ASSETS, PAGE_SCRIPT_BUDGET, PAGE_STYLE_BUDGET},
+    diagnostics::{DocumentSink, Severity},
+    proxy::download,
+    utils::{escape_attr, external_host},
+};
+
+/// Directory under the cache directory holding fetched remote assets.
+pub const PAGE_ASSET_CACHE_DIR: &str = "page-assets";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AssetKind {
+    Script,
+    Style,
+}
+
+impl AssetKind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Script => "script",
+            Self::Style => "style",
+        }
+    }
+}
+
+/// Where a page's assets come from and go, and what they may weigh.
+pub struct PageAssets<'p> {
+    pub enabled: bool,
+    /// Site root, which local assets are relative to.
+    pub root: &'p Path,
+    pub cache_dir: &'p Path,
+    pub output_dir: &'p Path,
+    /// Most bytes of script and of stylesheet one page may load.
+    pub script_budget: u64,
+    pub style_budget: u64,
+}
+
+impl<'p> PageAssets<'p> {
+    /// Site defaults for a build in `root`.
+    pub fn new(root: &'p Path, cache_dir: &'p Path, output_dir: &'p Path) -> Self {
+        Self {
+            enabled: PAGE_ASSETS,
+            root,
+            cache_dir,
+            output_dir,
+            script_budget: PAGE_SCRIPT_BUDGET,
+            style_budget: PAGE_STYLE_BUDGET,
+        }
+    }
+
+    /// `<head>` tags loading `scripts` and `styles` from a page `prefix`
+    /// away from the root. Local files are copied into the output. Problems
+    /// go to `sink`: over budget is an error, the rest warnings.
+    pub fn head_tags(
+        &self,
+        scripts: &[String],
+        styles: &[String],
+        prefix: &str,
+        sink: &DocumentSink,
+    ) -> String {
+        if scripts.is_empty() && styles.is_empty() {
+            return String::new();
+        }
+        if !self.enabled {
+            let first = scripts
+                .iter()
+                .chain(styles)
+                .next()
+                .map_or("", String::as_str);
+            sink.warn(
+                "assets",
+                first,
+                "page scripts and styles are disabled (PAGE_ASSETS); ignoring them".into(),
+            );
+            return String::new();
+        }
+
+        let mut html = String::new();
+        for (kind, sources, budget) in [
+            (AssetKind::Style, styles, self.style_budget),
+            (AssetKind::Script, scripts, self.script_budget),
+        ] {
+            let mut total: u64 = 0;
+            for src in sources {
+                match self.load(src) {
+                    Ok(bytes) => {
+                        total += bytes.len() as u64;
+                        html.push_str(&asset_tag(kind, src, prefix, Some(&integrity(&bytes))));
+                    }
+                    Err(error) => {
+                        sink.warn("assets", src, format!("{src}: {error}"));
+                        if external_host(src).is_some() {
+                            html.push_str(&asset_tag(kind, src, prefix, None));
+                        }
+                    }
+                }
+            }
+            if total > budget {
+                let first = sources.first().map_or("", String::as_str);
+                sink.report(
+                    Severity::Error,
+                    "assets",
+                    first,
+                    format!(
+                        "page loads {total} bytes of {}s, over the {budget}-byte budget",
+                        kind.name()
+                    ),
+                );
+            }
+        }
+        html
+    }
+
+    /// Bytes of the asset at `src`, fetching remote ones into the cache and
+    /// copying local ones into the output.
+    fn load(&self, src: &str) -> Result<Vec<u8>, String> {
+        if external_host(src).is_some() {
+            let hash = blake3::hash(src.as_bytes()).to_hex();
+            let cached = self.cache_dir.join(PAGE_ASSET_CACHE_DIR).join(&hash[..16]);
+            if !cached.is_file() {
+                download(src, &cached)?;
+            }
+            return fs::read(&cached).map_err(|e| e.to_string());
+        }
+
+        let rel = Path::new(src.trim_start_matches('/'));
+        if rel.components().any(|c| !matches!(c, Component::Normal(_))) {
+            return Err("must be a path within the site".into());
+        }
+        let bytes = fs::read(self.root.join(rel)).map_err(|e| e.to_string())?;
+        let dest = self.output_dir.join(rel);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::write(&dest, &bytes).map_err(|e| e.to_string())?;
+        Ok(bytes)
+    }
+}
+
+/// Subresource Integrity value for `bytes`.
+pub fn integrity(bytes: &[u8]) -> String {
+    format!("sha384-{}", STANDARD.encode(digest(&SHA384, bytes)))
+}
+
+fn asset_tag(kind: AssetKind, src: &str, prefix: &str, integrity: Option<&str>) -> String {
+    let remote = external_host(src).is_some();
+    let href = if remote {
+        src.to_owned()
+    } else {
+        format!("{prefix}{}", src.trim_start_matches('/'))
+    };
+    let mut attrs = String::new();
+    if let Some(integrity) = integrity {
+        attrs.push_str(&format!(r#" integrity="{integrity}""#));
+        if remote {
+            attrs.push_str(r#" crossorigin="anonymous""#);
+        }
+    }
+    match kind {
+        AssetKind::Style => format!(
+            "\n<link rel=\"stylesheet\" href=\"{}\"{attrs}>",
+            escape_attr(&href)
+        ),
+        // Module scripts are deferred already.
+        AssetKind::Script if src.ends_with(".mjs") => format!(
+            "\n<script type=\"module\" src=\"{}\"{attrs}></script>",
+            escape_attr(&href)
+        ),
+        AssetKind::Script => format!(
+            "\n<script src=\"{}\"{attrs} defer></script>",
+            escape_attr(&href)
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests;
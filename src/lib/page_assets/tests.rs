@@ -0,0 +1,103 @@
+use std::{fs, path::Path};
+
+use tempfile::TempDir;
+
+use crate::{
+    diagnostics::{Diagnostics, Severity},
+    page_assets::{PageAssets, integrity},
+};
+
+fn assets(root: &Path) -> PageAssets<'_> {
+    PageAssets {
+        enabled: true,
+        root,
+        cache_dir: root,
+        output_dir: root,
+        script_budget: 16,
+        style_budget: 16,
+    }
+}
+
+fn strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| (*s).to_owned()).collect()
+}
+
+#[test]
+fn integrity_is_a_base64_sha384() {
+    assert_eq!(
+        integrity(b"alert(1)"),
+        "sha384-HT2E9NfWiuQ/w1PRai+hTyqW16NIoCGA/m8VQDUopfAtcz6YQjtsMmQd5uRbVDpW"
+    );
+}
+
+#[test]
+fn local_assets_are_deferred_hashed_and_copied() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path().join("site");
+    let out = tmp.path().join("public");
+    fs::create_dir_all(root.join("js")).unwrap();
+    fs::write(root.join("js/a.js"), "alert(1)").unwrap();
+    fs::write(root.join("js/b.mjs"), "export {}").unwrap();
+    fs::write(root.join("a.css"), "p{}").unwrap();
+    let diagnostics = Diagnostics::default();
+    let sink = diagnostics.for_document(Path::new("post.md"), "", Vec::new());
+
+    let html = PageAssets {
+        output_dir: &out,
+        script_budget: 1024,
+        ..assets(&root)
+    }
+    .head_tags(
+        &strings(&["/js/a.js", "js/b.mjs"]),
+        &strings(&["a.css"]),
+        "../",
+        &sink,
+    );
+
+    assert!(html.contains(&format!(
+        r#"<script src="../js/a.js" integrity="{}" defer></script>"#,
+        integrity(b"alert(1)")
+    )));
+    assert!(html.contains(r#"<script type="module" src="../js/b.mjs" integrity="sha384-"#));
+    assert!(html.contains(r#"<link rel="stylesheet" href="../a.css" integrity="sha384-"#));
+    assert_eq!(fs::read_to_string(out.join("js/a.js")).unwrap(), "alert(1)");
+    assert!(diagnostics.take().is_empty());
+}
+
+#[test]
+fn pages_over_budget_fail_the_build() {
+    let tmp = TempDir::new().unwrap();
+    fs::write(tmp.path().join("big.js"), "x".repeat(17)).unwrap();
+    let diagnostics = Diagnostics::default();
+    let sink = diagnostics.for_document(Path::new("post.md"), "", Vec::new());
+
+    assets(tmp.path()).head_tags(&strings(&["big.js"]), &[], "", &sink);
+
+    let reported = diagnostics.take();
+    assert_eq!(reported.len(), 1);
+    assert_eq!(reported[0].severity, Severity::Error);
+    assert!(reported[0].message.contains("17 bytes of scripts"));
+}
+
+#[test]
+fn missing_escaping_and_disabled_assets_are_left_out() {
+    let tmp = TempDir::new().unwrap();
+    let diagnostics = Diagnostics::default();
+    let sink = diagnostics.for_document(Path::new("post.md"), "", Vec::new());
+
+    let html =
+        assets(tmp.path()).head_tags(&strings(&["missing.js", "../outside.js"]), &[], "", &sink);
+    assert!(html.is_empty());
+    assert_eq!(diagnostics.take().len(), 2);
+
+    let disabled = PageAssets {
+        enabled: false,
+        ..assets(tmp.path())
+    };
+    assert!(
+        disabled
+            .head_tags(&strings(&["a.js"]), &[], "", &sink)
+            .is_empty()
+    );
+    assert!(diagnostics.take()[0].message.contains("disabled"));
+}
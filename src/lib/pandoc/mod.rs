@@ -0,0 +1,157 @@
+//! Fallback conversion of non-Markdown sources through pandoc.
+//!
+//! Files whose extension appears in `PANDOC_FORMATS` are converted to an HTML
+//! fragment at discovery time. The result is stored as the page's content in
+//! the shape the rest of the pipeline expects: a frontmatter block (pandoc's
+//! metadata as JSON, which is valid YAML) followed by the HTML body, which
+//! `converted_body` hands to rendering verbatim instead of parsing it as
+//! Markdown.
+use std::{
+    path::Path,
+    process::{Command, Output},
+};
+
+use color_eyre::{Section, eyre::eyre};
+use serde_json::{Map, Value};
+
+use crate::config::{PANDOC_COMMAND, PANDOC_FORMATS};
+
+/// Pandoc reader for a source file, if its extension is configured.
+pub fn pandoc_format(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?;
+    PANDOC_FORMATS
+        .iter()
+        .find(|(e, _)| e.eq_ignore_ascii_case(ext))
+        .map(|(_, format)| *format)
+}
+
+/// Convert `path` with pandoc into frontmatter plus an HTML body.
+pub fn convert(path: &Path, format: &str) -> color_eyre::Result<String> {
+    let ast = run_pandoc(path, format, "json")?;
+    let ast: Value = serde_json::from_slice(&ast.stdout)?;
+    let meta = frontmatter(ast.get("meta").unwrap_or(&Value::Null));
+
+    let body = run_pandoc(path, format, "html5")?;
+    let body = String::from_utf8(body.stdout)?;
+
+    Ok(format!(
+        "---\n{}\n---\n{body}",
+        serde_json::to_string(&meta)?
+    ))
+}
+
+/// HTML body of content produced by `convert`.
+pub fn converted_body(content: &str) -> &str {
+    content
+        .strip_prefix("---\n")
+        .and_then(|rest| rest.split_once("\n---\n"))
+        .map_or(content, |(_, body)| body)
+}
+
+fn run_pandoc(path: &Path, from: &str, to: &str) -> color_eyre::Result<Output> {
+    let output = Command::new(PANDOC_COMMAND)
+        .args(["--from", from, "--to", to])
+        .arg(path)
+        .output()
+        .with_note(|| format!("While running {PANDOC_COMMAND} on {}", path.display()))?;
+    if !output.status.success() {
+        return Err(eyre!(
+            "{PANDOC_COMMAND} failed on {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output)
+}
+
+/// Flatten pandoc's metadata AST into frontmatter fields, mapping pandoc's
+/// conventional names (`date`, `keywords`, `abstract`) onto ours.
+pub fn frontmatter(meta: &Value) -> Map<String, Value> {
+    let mut fields: Map<String, Value> = meta
+        .as_object()
+        .map(|m| m.iter().map(|(k, v)| (k.clone(), meta_value(v))).collect())
+        .unwrap_or_default();
+
+    for (theirs, ours) in [
+        ("date", "ctime"),
+        ("keywords", "tags"),
+        ("abstract", "description"),
+    ] {
+        if !fields.contains_key(ours)
+            && let Some(value) = fields.remove(theirs)
+        {
+            fields.insert(ours.to_string(), value);
+        }
+    }
+    // Tags written inline (`#+FILETAGS: a b`) arrive as one string.
+    if let Some(Value::String(tags)) = fields.get("tags") {
+        let list = tags
+            .split(|c: char| c.is_whitespace() || c == ',' || c == ':')
+            .filter(|t| !t.is_empty())
+            .map(|t| Value::String(t.to_string()))
+            .collect();
+        fields.insert("tags".to_string(), Value::Array(list));
+    }
+    fields
+}
+
+fn meta_value(value: &Value) -> Value {
+    let content = value.get("c").unwrap_or(&Value::Null);
+    match value.get("t").and_then(Value::as_str) {
+        Some("MetaMap") => Value::Object(
+            content
+                .as_object()
+                .map(|m| m.iter().map(|(k, v)| (k.clone(), meta_value(v))).collect())
+                .unwrap_or_default(),
+        ),
+        Some("MetaList") => Value::Array(
+            content
+                .as_array()
+                .map(|items| items.iter().map(meta_value).collect())
+                .unwrap_or_default(),
+        ),
+        Some("MetaBool") => content.clone(),
+        Some("MetaString") => content.clone(),
+        _ => {
+            let mut text = String::new();
+            plain_text(content, &mut text);
+            Value::String(text.split_whitespace().collect::<Vec<_>>().join(" "))
+        }
+    }
+}
+
+/// Collect the text of inline/block AST nodes. Bare strings are attributes or
+/// link targets, not text, so only `Str`-like nodes contribute.
+fn plain_text(node: &Value, out: &mut String) {
+    match node {
+        Value::Array(items) => items.iter().for_each(|i| plain_text(i, out)),
+        Value::Object(obj) => match obj.get("t").and_then(Value::as_str) {
+            Some("Str") => out.push_str(obj.get("c").and_then(Value::as_str).unwrap_or("")),
+            Some("Space" | "SoftBreak" | "LineBreak") => out.push(' '),
+            Some("Code" | "Math" | "RawInline") => {
+                let text = obj
+                    .get("c")
+                    .and_then(Value::as_array)
+                    .and_then(|c| c.last())
+                    .and_then(Value::as_str)
+                    .unwrap_or("");
+                out.push_str(text);
+            }
+            Some("Para" | "Plain") => {
+                if let Some(c) = obj.get("c") {
+                    plain_text(c, out);
+                }
+                out.push(' ');
+            }
+            _ => {
+                if let Some(c) = obj.get("c") {
+                    plain_text(c, out);
+                }
+            }
+        },
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests;
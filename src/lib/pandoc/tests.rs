@@ -0,0 +1,47 @@
+use std::path::Path;
+
+use serde_json::json;
+
+use crate::{
+    header::Header,
+    pandoc::{converted_body, frontmatter, pandoc_format},
+};
+
+#[test]
+fn configured_extensions_use_pandoc() {
+    assert_eq!(pandoc_format(Path::new("post.org")), Some("org"));
+    assert_eq!(pandoc_format(Path::new("post.md")), None);
+}
+
+#[test]
+fn metadata_is_flattened_into_frontmatter() {
+    let meta = json!({
+        "title": {"t": "MetaInlines", "c": [
+            {"t": "Str", "c": "Hello"},
+            {"t": "Space"},
+            {"t": "Emph", "c": [{"t": "Str", "c": "world"}]},
+            {"t": "Link", "c": [["", [], []], [{"t": "Str", "c": "!"}], ["https://x", ""]]}
+        ]},
+        "date": {"t": "MetaInlines", "c": [{"t": "Str", "c": "2024-02-03"}]},
+        "keywords": {"t": "MetaInlines", "c": [{"t": "Str", "c": "rust"}, {"t": "Space"}, {"t": "Str", "c": "web"}]},
+        "draft": {"t": "MetaBool", "c": false}
+    });
+
+    let fields = frontmatter(&meta);
+
+    assert_eq!(fields["title"], "Hello world!");
+    assert_eq!(fields["ctime"], "2024-02-03");
+    assert_eq!(fields["tags"], json!(["rust", "web"]));
+    assert_eq!(fields["draft"], false);
+    assert!(!fields.contains_key("date"));
+}
+
+#[test]
+fn converted_content_splits_into_header_and_body() {
+    let content = "---\n{\"title\":\"Org post\",\"tags\":[\"emacs\"]}\n---\n<p>Body</p>\n";
+
+    let header = Header::try_from(content).unwrap();
+
+    assert_eq!(header.title(), Some("Org post"));
+    assert_eq!(converted_body(content), "<p>Body</p>\n");
+}
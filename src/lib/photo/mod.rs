@@ -0,0 +1,202 @@
+//! Photo posts: the primary image named in frontmatter, its EXIF capture
+//! metadata, and the gallery page that aggregates every photo.
+use std::{fs::File, io::BufReader, path::Path};
+
+use exif::{Exif, Field, In, Reader, Value};
+
+use crate::{
+    article::Article,
+    templates::{ListingContext, Templates},
+    transformer::image::placeholder_style,
+    types::IsoDate,
+    utils::{escape_attr, escape_text},
+};
+
+/// Capture metadata read from a photo's EXIF block.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PhotoMeta {
+    pub camera: Option<String>,
+    pub lens: Option<String>,
+    pub taken: Option<IsoDate>,
+    pub exposure: Option<String>,
+    pub aperture: Option<String>,
+    pub iso: Option<u32>,
+    pub focal_length: Option<String>,
+}
+
+impl PhotoMeta {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Read EXIF metadata from a local image. Missing files, remote URLs, and
+/// images without EXIF all yield `None`.
+pub fn read_exif(path: &Path) -> Option<PhotoMeta> {
+    let file = File::open(path).ok()?;
+    let exif = Reader::new()
+        .read_from_container(&mut BufReader::new(file))
+        .ok()?;
+
+    let field = |tag| exif.get_field(tag, In::PRIMARY);
+
+    let make = field(exif::Tag::Make).and_then(ascii);
+    let model = field(exif::Tag::Model).and_then(ascii);
+    let camera = match (make, model) {
+        // Many models already start with the maker's name ("Canon EOS R6").
+        (Some(make), Some(model)) if model.starts_with(&make) => Some(model),
+        (Some(make), Some(model)) => Some(format!("{make} {model}")),
+        (make, model) => make.or(model),
+    };
+
+    let meta = PhotoMeta {
+        camera,
+        lens: field(exif::Tag::LensModel).and_then(ascii),
+        taken: field(exif::Tag::DateTimeOriginal)
+            .or_else(|| field(exif::Tag::DateTime))
+            .and_then(ascii)
+            .and_then(|s| exif_date(&s)),
+        exposure: field(exif::Tag::ExposureTime).map(|f| with_unit(f, &exif)),
+        aperture: field(exif::Tag::FNumber).map(|f| format!("f/{}", f.display_value())),
+        iso: field(exif::Tag::PhotographicSensitivity).and_then(|f| f.value.get_uint(0)),
+        focal_length: field(exif::Tag::FocalLength).map(|f| with_unit(f, &exif)),
+    };
+
+    (!meta.is_empty()).then_some(meta)
+}
+
+fn ascii(field: &Field) -> Option<String> {
+    match &field.value {
+        Value::Ascii(parts) => {
+            let raw = parts.first()?;
+            let s = String::from_utf8_lossy(raw)
+                .trim_matches(|c: char| c == '\0' || c.is_whitespace())
+                .to_string();
+            (!s.is_empty()).then_some(s)
+        }
+        _ => None,
+    }
+}
+
+fn with_unit(field: &Field, exif: &Exif) -> String {
+    field.display_value().with_unit(exif).to_string()
+}
+
+/// Convert an EXIF timestamp (`2024:05:06 12:34:56`) to its date.
+pub fn exif_date(raw: &str) -> Option<IsoDate> {
+    let date = raw.split_whitespace().next()?;
+    IsoDate::parse(&date.replace(':', "-"))
+}
+
+/// Where a page `href_prefix` away from the root finds the `photo:` image,
+/// which names a file relative to the site root (with or without a leading
+/// `/`) unless it is a remote URL.
+pub fn photo_src(photo: &str, href_prefix: &str) -> String {
+    if photo.contains("://") || photo.starts_with("//") || photo.starts_with("data:") {
+        return photo.to_string();
+    }
+    format!("{href_prefix}{}", photo.trim_start_matches('/'))
+}
+
+/// Full-bleed figure for a photo post's primary image, with its capture
+/// metadata as a caption. `color` tints the frame while the image loads.
+pub fn render_photo(
+    src: &str,
+    href_prefix: &str,
+    alt: &str,
+    meta: Option<&PhotoMeta>,
+    color: Option<&str>,
+) -> String {
+    let mut html = format!(
+        r#"<figure class="photo full-bleed"{}><img src="{}" alt="{}" decoding="async" fetchpriority="high">"#,
+        color.map(placeholder_style).unwrap_or_default(),
+        escape_attr(&photo_src(src, href_prefix)),
+        escape_attr(alt)
+    );
+
+    if let Some(meta) = meta {
+        let mut items: Vec<(&str, String)> = Vec::new();
+        if let Some(camera) = &meta.camera {
+            items.push(("Camera", escape_text(camera)));
+        }
+        if let Some(lens) = &meta.lens {
+            items.push(("Lens", escape_text(lens)));
+        }
+        let settings: Vec<String> = [
+            meta.focal_length.clone(),
+            meta.aperture.clone(),
+            meta.exposure.clone(),
+            meta.iso.map(|iso| format!("ISO {iso}")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if !settings.is_empty() {
+            items.push(("Settings", escape_text(&settings.join(" · "))));
+        }
+        if let Some(taken) = &meta.taken {
+            let taken = taken.as_str();
+            items.push((
+                "Taken",
+                format!(
+                    r#"<time datetime="{}">{}</time>"#,
+                    escape_attr(&taken),
+                    escape_text(&taken)
+                ),
+            ));
+        }
+
+        if !items.is_empty() {
+            html.push_str(r#"<figcaption><dl class="exif">"#);
+            for (label, value) in items {
+                html.push_str(&format!("<dt>{label}</dt><dd>{value}</dd>"));
+            }
+            html.push_str("</dl></figcaption>");
+        }
+    }
+
+    html.push_str("</figure>\n");
+    html
+}
+
+/// Render the gallery index: a grid of every photo post, newest first.
+pub fn render_gallery(
+    templates: &Templates,
+    page_title: &str,
+    heading: &str,
+    photos: &[Article],
+    head_includes: &str,
+    href_prefix: &str,
+) -> color_eyre::Result<String> {
+    let mut body = String::from(r#"<ul class="gallery">"#);
+    body.push('\n');
+
+    for p in photos {
+        let Some(photo) = &p.photo else { continue };
+        let src = match &p.thumbnail {
+            Some(thumb) => format!("{href_prefix}{thumb}"),
+            None => photo_src(photo, href_prefix),
+        };
+        let full_href = format!("{href_prefix}{}", p.href.as_str());
+        body.push_str(&format!(
+            r#"<li><a href="{}"><img src="{}" alt="{}" loading="lazy" decoding="async"></a></li>"#,
+            escape_attr(&full_href),
+            escape_attr(&src),
+            escape_attr(&p.title)
+        ));
+        body.push('\n');
+    }
+    body.push_str("</ul>\n");
+
+    templates.listing(&ListingContext {
+        title: page_title,
+        heading,
+        articles: photos,
+        href_prefix,
+        head: head_includes,
+        body: &body,
+    })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,62 @@
+use crate::{
+    photo::{PhotoMeta, exif_date, photo_src, read_exif, render_photo},
+    types::IsoDate,
+};
+
+#[test]
+fn exif_timestamps_become_dates() {
+    assert_eq!(
+        exif_date("2024:05:06 12:34:56"),
+        IsoDate::parse("2024-05-06")
+    );
+    assert_eq!(exif_date("not a date"), None);
+}
+
+#[test]
+fn photo_figure_lists_capture_metadata() {
+    let meta = PhotoMeta {
+        camera: Some("Fujifilm X100V".to_string()),
+        taken: IsoDate::parse("2024-05-06"),
+        aperture: Some("f/2".to_string()),
+        iso: Some(160),
+        ..PhotoMeta::default()
+    };
+
+    let html = render_photo("/assets/a.jpg", "", "A \"quoted\" alt", Some(&meta), None);
+
+    assert!(html.starts_with(
+        r#"<figure class="photo full-bleed"><img src="assets/a.jpg" alt="A &quot;quoted&quot; alt""#
+    ));
+    assert!(html.contains("<dt>Camera</dt><dd>Fujifilm X100V</dd>"));
+    assert!(html.contains("<dd>f/2 · ISO 160</dd>"));
+    assert!(html.contains(r#"<time datetime="2024-05-06">2024-05-06</time>"#));
+}
+
+#[test]
+fn photo_without_metadata_has_no_caption() {
+    let html = render_photo("/assets/a.jpg", "", "", None, Some("#a0b0c0"));
+    assert!(!html.contains("figcaption"));
+    assert!(
+        html.starts_with(
+            r#"<figure class="photo full-bleed" style="background-color: #a0b0c0"><img"#
+        )
+    );
+}
+
+#[test]
+fn photos_are_found_from_pages_below_the_root() {
+    assert_eq!(photo_src("images/x.jpg", "../"), "../images/x.jpg");
+    assert_eq!(photo_src("/images/x.jpg", "../"), "../images/x.jpg");
+    assert_eq!(
+        photo_src("https://example.com/x.jpg", "../"),
+        "https://example.com/x.jpg"
+    );
+
+    let html = render_photo("images/x.jpg", "../", "", None, None);
+    assert!(html.contains(r#"<img src="../images/x.jpg""#));
+}
+
+#[test]
+fn missing_images_have_no_exif() {
+    assert_eq!(read_exif(std::path::Path::new("does/not/exist.jpg")), None);
+}
@@ -1,77 +1,352 @@
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fs,
     io::{self, Write},
     path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
 };
 
 use brotli::CompressorWriter;
 use color_eyre::{Section, eyre::eyre};
 use flate2::{Compression, write::GzEncoder};
 use minify_html::{Cfg, minify};
-use pulldown_cmark::{Event, Options, Parser};
+use pulldown_cmark::{CowStr, Event, Options};
 use rayon::prelude::*;
+use time::OffsetDateTime;
 use walkdir::WalkDir;
 
 use crate::{
-    article::{render_listing_page, Article},
-    config::{site_meta, SiteMeta, INPUT_DIR, OUTPUT_DIR, POSTS_DIR, TAGS_DIR},
-    css::build_css,
-    feed::write_feeds,
-    header::{generic_og_meta, Header},
-    templates::page_shell,
+    alias::{alias_out_path, redirect_html},
+    anchors::{AnchorRegistry, FragmentLink, collect_ids, fragment_links},
+    api::{API_DIR, build_article_pages, build_tag_files},
+    archive::{archive_page_path, archive_years, month_label, render_month_nav},
+    article::{
+        Article, note_title, render_listing_body, render_listing_page, render_note_stream,
+        render_tag_index,
+    },
+    author::{Authors, byline},
+    backlinks::{
+        PageIndex, backlink_graph, link_passages, outbound_links, render_backlinks,
+        resolve_post_refs, resolve_wikilinks,
+    },
+    bibliography::Bibliography,
+    cache::{self, CacheLimits, remote::RemoteCache},
+    config::{
+        API_PAGE_SIZE, ARCHIVE_DIR, AUTHORS_DIR, AUTHORS_FILE, BIBLIOGRAPHY, BUILD_LOCK_WAIT,
+        CACHE_DIR, CITATION_STYLE, CONTENT_RELATED_LIMIT, CONTENT_ROOTS, CSS_SAFELIST,
+        DATE_FALLBACKS, DISCOVERY_EXCLUDES, EXPIRED_POSTS, ExpiredPosts, FEEDS_DIR, GALLERY_DIR,
+        GIT_MTIME, HEADERS_FILE, INPUT_DIR, KEYWORDS_PER_POST, MATH_OUTPUT, MAX_TAGS_PER_POST,
+        MathOutput, NOT_FOUND_PAGE, NOTES_DIR, OUTPUT_DIR, PAGES_DIR, POSTS_DIR, PRELOAD_HINTS,
+        PRETTY_URLS, PROJECTS_DIR, PROJECTS_FILE, PROXY_ALLOWED_HOSTS, PROXY_EXTERNAL_RESOURCES,
+        PRUNE_UNUSED_CSS, RELATED_POSTS_LIMIT, SERIES_DIR, SYNTAXES_DIR, SiteMeta, TAG_CLOUD,
+        TAG_ORDER, TAG_SLUG_COLLISION, TAGS_DIR, TAGS_FILE, TEMPLATES_DIR, VOCABULARY_DIR,
+        VOCABULARY_TERMS, site_meta,
+    },
+    css::{UsedSelectors, build_css, lint_css, prune_css},
+    dates::{fallback_date, git_modified},
+    diagnostics::{
+        Diagnostic, Diagnostics, DocumentSink, Severity, format::ReportFormat, parse_with_spans,
+    },
+    feed::{
+        ATOM_FILE, FeedCandidate, JSON_FEED_FILE, RSS_FILE, feed_content_hrefs, tag_feed_path,
+        write_author_feeds, write_feeds, write_notes_feeds, write_tag_feeds,
+    },
+    fragment::Fragments,
+    header::{Header, generic_og_meta},
+    ignore::IgnoreRules,
+    include::expand_includes,
+    link::{LINK_TITLE_CACHE, LinkTitles},
+    lock::BuildLock,
+    page_assets::PageAssets,
+    pandoc::{convert, converted_body, pandoc_format},
+    photo::{read_exif, render_gallery, render_photo},
+    postprocess::{Injections, PageInfo, PostProcessors},
+    preload::{headers_manifest, page_preloads},
+    progress::Progress,
+    projects::{load_projects, render_project_detail, render_projects_grid},
+    proxy::ResourceProxy,
+    related::{related_posts, render_related, render_similar},
+    report::{PageStats, RenderMeta, Sizes},
+    search::{SEARCH_DIR, build_search_index, plain_text, search_page_body},
+    series::{
+        collect_series, render_series_nav, render_series_parts, series_href, series_page_path,
+    },
+    shortcode::{ShortcodePage, expand_shortcodes},
+    slug::{Claim, TagNames},
+    tag_info::TagInfos,
+    templates::{FeedLink, FeedsContext, ListingContext, PageContext, Templates},
+    terms::{Corpus, render_vocabulary},
+    thumbnail::thumbnail,
     transformer::{
-        code_block::{CodeHighlightTransformer, FeedCodeLabelTransformer}, epigraph::EpigraphTransformer,
+        WithTransformer,
+        blockquote::BlockquoteCitationTransformer,
+        citation::CitationTransformer,
+        code_block::{
+            CodeHighlightTransformer, FeedCodeLabelTransformer, load_syntaxes, load_theme,
+        },
+        definition_list::DefinitionListTransformer,
+        epigraph::EpigraphTransformer,
+        external_link::ExternalLinkTransformer,
         footnote::{FootnoteTransformer, PlainFootnoteTransformer},
-        heading::HeadingDemoterTransformer, image::ImageCaptionTransformer, math::MathTransformer,
-        toc::{FeedTocTransformer, TocTransformer}, WithTransformer,
+        heading::HeadingDemoterTransformer,
+        image::{
+            IMAGE_COLOR_CACHE_FILE, ImageCaptionTransformer, dominant_color,
+            load_image_color_cache, save_image_color_cache,
+        },
+        margin_note::{FeedMarginNoteTransformer, MarginNoteTransformer},
+        math::{MATH_CACHE_FILE, MathTransformer, load_math_cache, save_math_cache},
+        mermaid::{MermaidTransformer, is_mermaid, mermaid_script, needs_mermaid_script},
+        paragraph::ParagraphLinkTransformer,
+        punctuation::SmartPunctuationTransformer,
+        text_macro::TextMacroTransformer,
+        toc::{FeedTocTransformer, TocOptions, TocTransformer},
+        todo::TodoTransformer,
+    },
+    types::{Href, IsoDate, PostKind, RelPath, Tag},
+    utils::{
+        escape_attr, escape_text, page_out_path, prefix_to_root, tag_href, tag_page_path,
+        tags_index_path, write_atomic,
     },
-    types::{Href, RelPath, Tag},
-    utils::{escape_attr, prefix_to_root},
 };
 
 type ParsedDoc = (PathBuf, String);
+/// An article page whose shell is filled in once site-wide data, such as
+/// related posts, is known.
+struct DraftPage {
+    out_path: PathBuf,
+    prefix: String,
+    head: String,
+    /// Head markup spliced in while post-processing rather than templated.
+    head_meta: String,
+    header: String,
+    body: String,
+    /// Internal pages this one links to, for backlinks.
+    links: Vec<Href>,
+    /// Text fragments locating the passage linking to each of `links`.
+    passages: HashMap<Href, String>,
+    /// Links into specific anchors, checked once every page is rendered.
+    fragments: Vec<FragmentLink>,
+    /// Anchor ids this page defines.
+    anchor_ids: HashSet<String>,
+    /// Redirect pages at the page's `aliases:`, with the alias each is for.
+    redirects: Vec<(String, RenderedPage)>,
+    /// `Link` header values of the page's preload hints.
+    link_headers: Vec<String>,
+    meta: RenderMeta,
+}
+
 struct RenderedPage {
     out_path: PathBuf,
     minified: Vec<u8>,
+    link_headers: Vec<String>,
+    meta: RenderMeta,
 }
 
 type RenderOutcome = (Vec<RenderedPage>, Vec<Article>);
 
+/// Knobs for a single build.
+#[derive(Clone, Default)]
+pub struct BuildOptions {
+    /// Run over every page after templating.
+    pub post_processors: PostProcessors,
+    /// Fail on warnings (such as formulas KaTeX rejects), not just errors.
+    pub strict: bool,
+    /// Fail on `TODO:`/`FIXME:` markers left in sources.
+    pub strict_todos: bool,
+    /// Publish posts whose `ctime` is after the build's date, which are
+    /// otherwise held back until then.
+    pub future: bool,
+    /// Show progress bars on a terminal while building.
+    pub progress: bool,
+    /// How diagnostics are reported once the build is done.
+    pub report_format: ReportFormat,
+    /// File to write the diagnostics to instead of the terminal.
+    pub report_file: Option<PathBuf>,
+}
+
+/// What a finished build read besides its content roots, and what it wrote.
+#[derive(Clone, Debug, Default)]
+pub struct BuildReport {
+    /// Files spliced into pages by include directives, which watch mode has
+    /// to track as well.
+    pub included: BTreeSet<PathBuf>,
+    /// For each source embedding fragments defined elsewhere, the sources
+    /// defining them, which it has to be rebuilt along with.
+    pub fragment_sources: BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+    /// Every HTML page written, ordered by path.
+    pub pages: Vec<PageStats>,
+}
+
 /// Build once into OUTPUT_DIR using current working directory.
-pub fn build_once() -> color_eyre::Result<()> {
+pub fn build_once() -> color_eyre::Result<BuildReport> {
+    build_once_with(BuildOptions::default())
+}
+
+/// As [`build_once`], with explicit [`BuildOptions`].
+pub fn build_once_with(options: BuildOptions) -> color_eyre::Result<BuildReport> {
     let root =
         std::env::current_dir().with_note(|| "While getting the current working directory")?;
-    build_at(&root)
+    build_with(&root, options)
 }
 
-pub fn build_at(root: &Path) -> color_eyre::Result<()> {
-    let ctx = BuildCtx::load_at(root)?;
+pub fn build_at(root: &Path) -> color_eyre::Result<BuildReport> {
+    build_with(root, BuildOptions::default())
+}
+
+/// Build the site at `root` according to `options`.
+pub fn build_with(root: &Path, options: BuildOptions) -> color_eyre::Result<BuildReport> {
+    let _lock = BuildLock::acquire(root, BUILD_LOCK_WAIT)?;
+    let cache_dir = root.join(CACHE_DIR);
+    let remote = RemoteCache::from_env();
+    if let Some(remote) = &remote {
+        remote.warm(&cache_dir, LINK_TITLE_CACHE);
+        remote.warm(&cache_dir, MATH_CACHE_FILE);
+        remote.warm(&cache_dir, IMAGE_COLOR_CACHE_FILE);
+    }
+
+    let mut ctx = BuildCtx::load_at(root)?;
+    ctx.post_processors = options.post_processors;
+    ctx.future = options.future;
+    ctx.progress = Progress::new(options.progress);
+    if PROXY_EXTERNAL_RESOURCES {
+        let proxy = ResourceProxy::new(
+            &cache_dir,
+            &ctx.output_dir,
+            PROXY_ALLOWED_HOSTS,
+            ctx.diagnostics.clone(),
+        );
+        ctx.post_processors = ctx.post_processors.with(proxy);
+    }
     fs::create_dir_all(&ctx.output_dir)?;
+    let diagnostics = ctx.diagnostics.clone();
+    let source_dirs: Vec<(PathBuf, PathBuf)> = ctx
+        .content_roots
+        .iter()
+        .map(|source| (source.prefix.clone(), source.dir.clone()))
+        .collect();
 
-    Pipeline::new(ctx)
+    let report = Pipeline::new(ctx)
         .discover()?
         .parse()?
         .transform()?
         .render()?
-        .emit()
+        .emit()?;
+
+    let reported = diagnostics.take();
+    let rendered = match options.report_format {
+        ReportFormat::Text => ReportFormat::Text.render(&reported),
+        format => {
+            // Annotations go on the files as checked in, not as published.
+            let checked_in: Vec<Diagnostic> = reported
+                .iter()
+                .cloned()
+                .map(|mut diagnostic| {
+                    diagnostic.document =
+                        Arc::from(checked_in_path(root, &source_dirs, &diagnostic.document));
+                    diagnostic
+                })
+                .collect();
+            format.render(&checked_in)
+        }
+    };
+    match (&options.report_file, options.report_format) {
+        (Some(path), _) => fs::write(path, rendered)
+            .with_note(|| format!("While writing the report to {}", path.display()))?,
+        (None, ReportFormat::Text) => eprint!("{rendered}"),
+        (None, _) => print!("{rendered}"),
+    }
+    let errors = reported
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    if errors > 0 {
+        return Err(eyre!("build reported {errors} error(s)"));
+    }
+    let warnings = reported
+        .iter()
+        .filter(|d| d.severity == Severity::Warning)
+        .count();
+    if options.strict && warnings > 0 {
+        return Err(eyre!("build reported {warnings} warning(s)"))
+            .with_note(|| "Warnings are fatal under --strict");
+    }
+    let todos = reported.len() - errors - warnings;
+    if options.strict_todos && todos > 0 {
+        return Err(eyre!("sources still have {todos} TODO marker(s)"))
+            .with_note(|| "TODO and FIXME markers are fatal under --strict-todos");
+    }
+
+    cache::gc(&cache_dir, CacheLimits::default(), SystemTime::now())?;
+    if let Some(remote) = &remote {
+        remote.publish(&cache_dir, LINK_TITLE_CACHE)?;
+        remote.publish(&cache_dir, MATH_CACHE_FILE)?;
+        remote.publish(&cache_dir, IMAGE_COLOR_CACHE_FILE)?;
+    }
+    Ok(report)
+}
+
+/// Where the document a diagnostic names, such as `posts/a.md` as published
+/// from content root `(prefix, dir)`, is relative to the site `root`.
+/// Documents outside every content root, like `style.css`, already are.
+fn checked_in_path(root: &Path, source_dirs: &[(PathBuf, PathBuf)], document: &Path) -> PathBuf {
+    source_dirs
+        .iter()
+        .filter_map(|(prefix, dir)| Some(dir.join(document.strip_prefix(prefix).ok()?)))
+        .find(|path| path.is_file())
+        .and_then(|path| Some(path.strip_prefix(root).ok()?.to_path_buf()))
+        .unwrap_or_else(|| document.to_path_buf())
+}
+
+/// A content root resolved against the site root.
+struct SourceRoot {
+    dir: PathBuf,
+    prefix: PathBuf,
+    ignore: IgnoreRules,
 }
 
 struct BuildCtx {
     current_dir: PathBuf,
-    input_dir: PathBuf,
+    content_roots: Vec<SourceRoot>,
     output_dir: PathBuf,
     head_html: String,
     footer_html: String,
     site_meta: SiteMeta,
+    templates: Templates,
     parser_options: Options,
     min_cfg: Cfg,
+    link_titles: LinkTitles,
+    bibliography: Bibliography,
+    authors: Authors,
+    tag_infos: TagInfos,
+    post_processors: PostProcessors,
+    /// Problems reported while rendering, printed once the build finishes.
+    diagnostics: Diagnostics,
+    report: BuildReport,
+    /// Stats of the pages written so far, which may be from several threads.
+    page_stats: Mutex<Vec<PageStats>>,
+    /// The build's date, against which `expires:` dates are checked.
+    today: IsoDate,
+    /// Whether posts dated after `today` are built.
+    future: bool,
+    /// Where each stage's progress is shown, if anywhere.
+    progress: Progress,
 }
 
 impl BuildCtx {
     fn load_at(root: &Path) -> color_eyre::Result<Self> {
         let current_dir = root.to_path_buf();
-        let input_dir = current_dir.join(INPUT_DIR);
+        let content_roots = CONTENT_ROOTS
+            .iter()
+            .map(|root| {
+                let dir = current_dir.join(root.dir);
+                SourceRoot {
+                    ignore: IgnoreRules::load(&dir, DISCOVERY_EXCLUDES),
+                    dir,
+                    prefix: PathBuf::from(root.prefix),
+                }
+            })
+            .collect();
         let output_dir = current_dir.join(OUTPUT_DIR);
 
         let head_html = fs::read_to_string(current_dir.join("header").with_extension("html"))
@@ -79,16 +354,26 @@ impl BuildCtx {
         let footer_html = fs::read_to_string(current_dir.join("footer").with_extension("html"))
             .unwrap_or_default();
         let site_meta = site_meta();
+        let templates = Templates::load(&current_dir.join(TEMPLATES_DIR), &site_meta);
+        load_syntaxes(&current_dir.join(SYNTAXES_DIR))?;
+        load_theme(&current_dir)?;
+        let link_titles = LinkTitles::load(&current_dir.join(CACHE_DIR).join(LINK_TITLE_CACHE));
+        let bibliography = Bibliography::load(&current_dir.join(BIBLIOGRAPHY))?;
+        let authors = Authors::load(&current_dir.join(AUTHORS_FILE))?;
+        let tag_infos = TagInfos::load(&current_dir.join(TAGS_FILE))?;
+        load_math_cache(&current_dir.join(CACHE_DIR).join(MATH_CACHE_FILE));
+        load_image_color_cache(&current_dir.join(CACHE_DIR).join(IMAGE_COLOR_CACHE_FILE));
 
         let mut options = Options::empty();
         options.insert(Options::ENABLE_GFM);
         options.insert(Options::ENABLE_MATH);
         options.insert(Options::ENABLE_YAML_STYLE_METADATA_BLOCKS);
+        options.insert(Options::ENABLE_WIKILINKS);
         options.insert(Options::ENABLE_FOOTNOTES);
         options.insert(Options::ENABLE_TABLES);
         options.insert(Options::ENABLE_SUPERSCRIPT);
         options.insert(Options::ENABLE_SUBSCRIPT);
-        options.insert(Options::ENABLE_SMART_PUNCTUATION);
+        options.insert(Options::ENABLE_DEFINITION_LIST);
 
         let mut min_cfg = Cfg::new();
         // Keep HTML minification aggressive, but leave CSS minification to
@@ -107,37 +392,147 @@ impl BuildCtx {
 
         Ok(Self {
             current_dir,
-            input_dir,
+            content_roots,
             output_dir,
             head_html,
             footer_html,
             site_meta,
+            templates,
             parser_options: options,
             min_cfg,
+            link_titles,
+            bibliography,
+            authors,
+            tag_infos,
+            post_processors: PostProcessors::default(),
+            diagnostics: Diagnostics::default(),
+            report: BuildReport::default(),
+            page_stats: Mutex::default(),
+            today: OffsetDateTime::now_utc().date().into(),
+            future: false,
+            progress: Progress::hidden(),
         })
     }
+
+    /// Post-process and minify a finished page bound for `out_path`.
+    fn finish_html(&self, html: &str, out_path: &Path) -> color_eyre::Result<Vec<u8>> {
+        self.finish_html_with(html, out_path, &Injections::default())
+    }
+
+    /// As [`finish_html`](Self::finish_html), splicing `injections` into the
+    /// page during the same rewrite pass.
+    fn finish_html_with(
+        &self,
+        html: &str,
+        out_path: &Path,
+        injections: &Injections,
+    ) -> color_eyre::Result<Vec<u8>> {
+        let rel_path = out_path.strip_prefix(&self.output_dir).unwrap_or(out_path);
+        let prefix = prefix_to_root(rel_path);
+        let html = self.post_processors.apply_with(
+            html,
+            PageInfo {
+                rel_path,
+                prefix: &prefix,
+            },
+            &[injections],
+        )?;
+        Ok(minify(html.as_bytes(), &self.min_cfg))
+    }
+
+    /// Post-process, minify, and write a page (plus compressed variants).
+    fn write_html(&self, out_path: &Path, html: &str) -> color_eyre::Result<()> {
+        let bytes = self.finish_html(html, out_path)?;
+        self.write_page(out_path, &bytes, RenderMeta::default())
+    }
+
+    /// Write a finished page (plus compressed variants) and record its stats.
+    fn write_page(&self, out_path: &Path, html: &[u8], meta: RenderMeta) -> color_eyre::Result<()> {
+        let sizes = write_with_compression(out_path, html)?;
+        let rel = out_path.strip_prefix(&self.output_dir).unwrap_or(out_path);
+        let stats = PageStats::new(&self.output_dir, rel, html, sizes, meta);
+        if let Ok(mut pages) = self.page_stats.lock() {
+            pages.push(stats);
+        }
+        Ok(())
+    }
 }
 
-fn discover_sources(ctx: &BuildCtx) -> color_eyre::Result<Vec<(PathBuf, String)>> {
-    let md_paths: Vec<PathBuf> = WalkDir::new(&ctx.input_dir)
-        .into_iter()
-        .filter_map(Result::ok)
-        .filter(|entry| {
-            entry.file_type().is_file()
-                && entry
-                    .path()
-                    .extension()
-                    .is_some_and(|ext| ext == "md")
+impl BuildCtx {
+    /// The content root a source belongs to: the innermost containing it,
+    /// so a root nested in another keeps its own files.
+    fn root_of(&self, path: &Path) -> Option<&SourceRoot> {
+        self.content_roots
+            .iter()
+            .filter(|root| path.starts_with(&root.dir))
+            .max_by_key(|root| root.dir.components().count())
+    }
+
+    /// Where the source published as `rel_src` lives.
+    fn source_path(&self, rel_src: &Path) -> Option<PathBuf> {
+        self.content_roots.iter().find_map(|root| {
+            let path = root.dir.join(rel_src.strip_prefix(&root.prefix).ok()?);
+            (path.is_file()
+                && self
+                    .root_of(&path)
+                    .is_some_and(|owner| owner.dir == root.dir))
+            .then_some(path)
         })
-        .map(|entry| entry.path().to_path_buf())
-        .collect();
+    }
+}
 
+fn discover_sources(ctx: &BuildCtx) -> color_eyre::Result<Vec<(PathBuf, String)>> {
+    let mut md_paths: Vec<PathBuf> = Vec::new();
+    // Rendered on its own by `build_not_found_page`.
+    let not_found_source = ctx.current_dir.join(INPUT_DIR).join(NOT_FOUND_PAGE);
+    for root in &ctx.content_roots {
+        md_paths.extend(
+            WalkDir::new(&root.dir)
+                .follow_links(true)
+                .into_iter()
+                .filter_entry(|entry| {
+                    let rel = entry.path().strip_prefix(&root.dir).unwrap_or(entry.path());
+                    entry.depth() == 0 || !root.ignore.is_ignored(rel, entry.file_type().is_dir())
+                })
+                .filter_map(|entry| match entry {
+                    Ok(entry) => Some(entry),
+                    Err(e) if e.loop_ancestor().is_some() => {
+                        let path = e.path().unwrap_or(&root.dir);
+                        let rel = path.strip_prefix(&root.dir).unwrap_or(path);
+                        ctx.diagnostics
+                            .for_document(&root.prefix.join(rel), "", Vec::new())
+                            .warn("sources", "", "symlink loop skipped".to_owned());
+                        None
+                    }
+                    Err(_) => None,
+                })
+                .filter(|entry| {
+                    entry.file_type().is_file()
+                        && entry.path() != not_found_source
+                        && (entry.path().extension().is_some_and(|ext| ext == "md")
+                            || pandoc_format(entry.path()).is_some())
+                        && ctx
+                            .root_of(entry.path())
+                            .is_some_and(|owner| owner.dir == root.dir)
+                })
+                .map(|entry| entry.path().to_path_buf()),
+        );
+    }
+
+    let stage = ctx.progress.parallel_stage("discover", md_paths.len());
     let docs_res: Vec<_> = md_paths
         .par_iter()
         .map(|path| {
-            fs::read_to_string(path)
-                .map(|content| (path.clone(), content))
-                .map_err(|e| eyre!("Failed to read {}: {e}", path.display()))
+            stage.working_on(path.strip_prefix(&ctx.current_dir).unwrap_or(path));
+            let read = if let Some(format) = pandoc_format(path) {
+                convert(path, format).map(|content| (path.clone(), content))
+            } else {
+                fs::read_to_string(path)
+                    .map(|content| (path.clone(), content))
+                    .map_err(|e| eyre!("Failed to read {}: {e}", path.display()))
+            };
+            stage.step();
+            read
         })
         .collect();
 
@@ -146,153 +541,823 @@ fn discover_sources(ctx: &BuildCtx) -> color_eyre::Result<Vec<(PathBuf, String)>
 
     Ok(docs)
 }
+/// Map sources to their place in the site and expand their includes, adding
+/// every included file to `included`.
 fn parse_sources(
     ctx: &BuildCtx,
     sources: Vec<(PathBuf, String)>,
+    included: &mut BTreeSet<PathBuf>,
 ) -> color_eyre::Result<Vec<ParsedDoc>> {
     let mut parsed = Vec::with_capacity(sources.len());
+    // Which source claimed each output page, to catch two landing on one.
+    let mut claimed: HashMap<PathBuf, PathBuf> = HashMap::new();
+    let stage = ctx.progress.stage("parse", sources.len());
     for (full_path, content) in sources {
-        let rel_src = full_path
-            .strip_prefix(&ctx.input_dir)
-            .map(|p| p.to_owned())
-            .map_err(|_| eyre!("Path outside input_dir"))?;
+        let root = ctx
+            .root_of(&full_path)
+            .ok_or_else(|| eyre!("{} is outside every content root", full_path.display()))?;
+        let rel_src = root.prefix.join(full_path.strip_prefix(&root.dir)?);
+
+        let header = Header::try_from(content.as_str()).unwrap_or_default();
+        let kind = source_kind(&rel_src, &header);
+        let rel_out = article_rel_out(&rel_src, kind)?.as_path().to_path_buf();
+        if kind == PostKind::Page && generated_at(&rel_out) {
+            return Err(eyre!(
+                "{} would be published as {}, which the build generates",
+                full_path.display(),
+                rel_out.display()
+            ))
+            .with_note(|| "Rename the page");
+        }
+        if let Some(other) = claimed.insert(rel_out.clone(), full_path.clone()) {
+            return Err(eyre!(
+                "{} and {} would both be published as {}",
+                other.display(),
+                full_path.display(),
+                rel_out.display()
+            ))
+            .with_note(|| "Rename one, or give their content roots different prefixes");
+        }
+        // Pandoc output is already HTML.
+        let content = if pandoc_format(&full_path).is_some() {
+            content
+        } else {
+            expand_includes(&content, &full_path, &ctx.current_dir, included)
+                .with_note(|| format!("While expanding includes in {}", full_path.display()))?
+                .into_owned()
+        };
         parsed.push((rel_src, content));
+        stage.step();
     }
     Ok(parsed)
 }
 
-fn transform_docs(parsed: Vec<ParsedDoc>) -> color_eyre::Result<Vec<ParsedDoc>> {
-    Ok(parsed)
+/// Expand fragment embeds once every source's definitions are known,
+/// recording which sources each one draws on in `uses`.
+fn transform_docs(
+    parsed: Vec<ParsedDoc>,
+    uses: &mut BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+) -> color_eyre::Result<Vec<ParsedDoc>> {
+    let mut fragments = Fragments::default();
+    let mut collected = Vec::with_capacity(parsed.len());
+    for (rel_src, content) in parsed {
+        // Pandoc output is already HTML.
+        let content = if pandoc_format(&rel_src).is_some() {
+            content
+        } else {
+            fragments
+                .collect(&rel_src, &content)
+                .with_note(|| format!("While reading fragments in {}", rel_src.display()))?
+                .into_owned()
+        };
+        collected.push((rel_src, content));
+    }
+
+    let mut transformed = Vec::with_capacity(collected.len());
+    for (rel_src, content) in collected {
+        let mut sources = BTreeSet::new();
+        let content = fragments
+            .expand(&content, &mut sources)
+            .with_note(|| format!("While embedding fragments in {}", rel_src.display()))?
+            .into_owned();
+        sources.remove(&rel_src);
+        if !sources.is_empty() {
+            uses.insert(rel_src.clone(), sources);
+        }
+        transformed.push((rel_src, content));
+    }
+    Ok(transformed)
 }
 
 fn render_docs(ctx: &BuildCtx, items: Vec<ParsedDoc>) -> color_eyre::Result<RenderOutcome> {
+    // Scheduled posts wait for their date, and expired ones may be gone.
+    let items: Vec<ParsedDoc> = items
+        .into_iter()
+        .filter(|(_, content)| {
+            let header = Header::try_from(content.as_str()).unwrap_or_default();
+            let scheduled = !ctx.future && header.ctime().is_some_and(|ctime| ctime > ctx.today);
+            let unpublished = EXPIRED_POSTS == ExpiredPosts::Unpublished
+                && header.expires().is_some_and(|expires| expires < ctx.today);
+            !scheduled && !unpublished
+        })
+        .collect();
+    // Wikilinks may name any page, so every page is indexed before rendering.
+    // Tags are likewise filed site-wide, in source order, so the first
+    // spelling of a tag decides its page.
+    let mut pages = PageIndex::default();
+    let mut tag_names = TagNames::new(TAG_SLUG_COLLISION);
+    for (rel_src, content) in &items {
+        let header = Header::try_from(content.as_str()).unwrap_or_default();
+        let kind = source_kind(rel_src, &header);
+        let href = Href::from_rel(&article_rel_out(rel_src, kind)?);
+        pages.insert(rel_src, header.title(), &href);
+        // Pages are filed under no tag.
+        if kind == PostKind::Page {
+            continue;
+        }
+
+        for tag in &header.tags() {
+            let Some(claim) = tag_names.insert(tag) else {
+                continue;
+            };
+            if let Some(message) = claim.describe("tag page") {
+                let severity = match claim {
+                    Claim::Collided(_) => Severity::Error,
+                    _ => Severity::Warning,
+                };
+                ctx.diagnostics
+                    .for_document(rel_src, content, Vec::new())
+                    .report(severity, "slug", tag.as_str(), message);
+            }
+        }
+        let mut filed: Vec<Tag> = Vec::new();
+        for tag in header.tags().0.iter().map(|t| tag_names.resolve(t)) {
+            if !filed.contains(&tag) {
+                filed.push(tag);
+            }
+        }
+        tag_names.count_uses(&filed);
+    }
+
+    // Full content is only needed by feeds and the notes stream, which show
+    // the newest posts; dating every post first means only those are given a
+    // feed render at all.
+    let candidates: Vec<Candidate> = items
+        .par_iter()
+        .map(|(rel_src, content)| {
+            let header = Header::try_from(content.as_str()).unwrap_or_default();
+            let kind = source_kind(rel_src, &header);
+            let mut tags: Vec<Tag> = Vec::new();
+            if kind != PostKind::Page {
+                for tag in header.tags().0.iter().map(|t| tag_names.resolve(t)) {
+                    if !tags.contains(&tag) {
+                        tags.push(tag);
+                    }
+                }
+            }
+            Ok(Candidate {
+                href: Href::from_rel(&article_rel_out(rel_src, kind)?),
+                kind,
+                ctime: post_date(ctx, rel_src, &header, kind),
+                expires: header.expires(),
+                tags,
+                authors: header.authors(),
+            })
+        })
+        .collect::<color_eyre::Result<_>>()?;
+    let mut newest_first: Vec<&Candidate> = candidates.iter().collect();
+    newest_first.sort_by(|a, b| b.ctime.cmp(&a.ctime));
+    let full_content = feed_content_hrefs(
+        newest_first.into_iter().map(|c| FeedCandidate {
+            href: &c.href,
+            kind: c.kind,
+            ctime: c.ctime.as_ref(),
+            expires: c.expires.as_ref(),
+            tags: &c.tags,
+            authors: &c.authors,
+        }),
+        &ctx.today,
+    );
+
+    let stage = ctx.progress.parallel_stage("render", items.len());
     let results: Vec<_> = items
         .par_iter()
-        .map(|(rel_src, content)| render_single(ctx, rel_src, content))
+        .zip(&candidates)
+        .map(|((rel_src, content), candidate)| {
+            stage.working_on(rel_src);
+            let dated = Dated {
+                ctime: candidate.ctime.clone(),
+                full_content: full_content.contains(&candidate.href),
+            };
+            let rendered = render_single(ctx, &pages, &tag_names, rel_src, content, dated);
+            stage.step();
+            rendered
+        })
         .collect();
 
-    let mut rendered_pages = Vec::with_capacity(results.len());
-    let mut articles = Vec::with_capacity(results.len());
-
-    for res in results {
-        let (page, article) = res?;
-        rendered_pages.push(page);
-        articles.push(article);
-    }
+    let mut drafts = results
+        .into_iter()
+        .collect::<color_eyre::Result<Vec<_>>>()?;
+    ctx.link_titles.save()?;
+    save_math_cache(&ctx.current_dir.join(CACHE_DIR).join(MATH_CACHE_FILE))?;
+    save_image_color_cache(&ctx.current_dir.join(CACHE_DIR).join(IMAGE_COLOR_CACHE_FILE))?;
 
+    let mut articles: Vec<Article> = drafts.iter().map(|(_, a)| a.clone()).collect();
     // Sort by time first, then title
     articles.sort_by(|a, b| b.ctime.cmp(&a.ctime).then_with(|| a.title.cmp(&b.title)));
 
+    let outbound: Vec<(Href, Vec<Href>)> = drafts
+        .iter()
+        .map(|(draft, article)| (article.href.clone(), draft.links.clone()))
+        .collect();
+    let graph = backlink_graph(&outbound);
+    let passages: HashMap<Href, HashMap<Href, String>> = drafts
+        .iter_mut()
+        .map(|(draft, article)| (article.href.clone(), std::mem::take(&mut draft.passages)))
+        .collect();
+
+    let mut anchors = AnchorRegistry::default();
+    for (draft, article) in &drafts {
+        anchors.insert(&article.href, draft.anchor_ids.iter().cloned());
+    }
+    // `drafts` are in the order of `items`, one each.
+    for ((rel_src, content), (draft, _)) in items.iter().zip(&drafts) {
+        let sink = ctx.diagnostics.for_document(rel_src, content, Vec::new());
+        for link in anchors.broken(&draft.fragments) {
+            sink.warn("anchors", &format!("#{}", link.fragment), link.to_string());
+        }
+    }
+
+    // A redirect never replaces a page, nor another post's redirect.
+    let mut claimed: HashSet<PathBuf> = drafts.iter().map(|(d, _)| d.out_path.clone()).collect();
+    let mut redirects = Vec::new();
+    for ((rel_src, content), (draft, _)) in items.iter().zip(&mut drafts) {
+        for (alias, redirect) in std::mem::take(&mut draft.redirects) {
+            if claimed.insert(redirect.out_path.clone()) {
+                redirects.push(redirect);
+            } else {
+                let rel = redirect
+                    .out_path
+                    .strip_prefix(&ctx.output_dir)
+                    .unwrap_or(&redirect.out_path);
+                ctx.diagnostics
+                    .for_document(rel_src, content, Vec::new())
+                    .warn(
+                        "aliases",
+                        &alias,
+                        format!(
+                            "alias `{alias}` ({}) is already taken; skipped",
+                            rel.display()
+                        ),
+                    );
+            }
+        }
+    }
+
+    // Standalone pages and expired posts are never suggested as related.
+    let posts: Vec<Article> = articles
+        .iter()
+        .filter(|a| a.kind != PostKind::Page && !a.is_expired(&ctx.today))
+        .cloned()
+        .collect();
+    let series = collect_series(&posts);
+    let corpus = Corpus::new(posts.iter().map(|a| (&a.href, a.text.as_str())));
+    stage.add_steps(drafts.len());
+    stage.note("finishing pages");
+    let mut rendered_pages = drafts
+        .into_par_iter()
+        .map(|(draft, article)| {
+            stage.working_on(
+                draft
+                    .out_path
+                    .strip_prefix(&ctx.output_dir)
+                    .unwrap_or(&draft.out_path),
+            );
+            let sources = graph.get(&article.href).map(Vec::as_slice).unwrap_or(&[]);
+            let backlinks: Vec<(&Article, Option<&str>)> = articles
+                .iter()
+                .filter(|a| sources.contains(&a.href))
+                .map(|a| {
+                    let passage = passages
+                        .get(&a.href)
+                        .and_then(|p| p.get(&article.href))
+                        .map(String::as_str);
+                    (a, passage)
+                })
+                .collect();
+            let parts = article
+                .series
+                .as_ref()
+                .and_then(|entry| series.get(entry.name.as_str()))
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let nearby = Nearby {
+                articles: &posts,
+                series_parts: parts,
+                corpus: &corpus,
+            };
+            let finished = finish_page(ctx, draft, &article, &nearby, &backlinks);
+            stage.step();
+            finished
+        })
+        .collect::<color_eyre::Result<Vec<_>>>()?;
+    rendered_pages.extend(redirects);
+
     Ok((rendered_pages, articles))
 }
 
+/// What a page's closing sections draw on besides the page itself.
+struct Nearby<'a> {
+    /// Every post, for related suggestions.
+    articles: &'a [Article],
+    /// The parts of the page's series, in order.
+    series_parts: &'a [&'a Article],
+    corpus: &'a Corpus,
+}
+
+/// Append the site-wide sections to a draft and render it into its shell.
+fn finish_page(
+    ctx: &BuildCtx,
+    draft: DraftPage,
+    article: &Article,
+    nearby: &Nearby<'_>,
+    backlinks: &[(&Article, Option<&str>)],
+) -> color_eyre::Result<RenderedPage> {
+    let DraftPage {
+        out_path,
+        prefix,
+        head,
+        mut head_meta,
+        header,
+        body,
+        links: _,
+        passages: _,
+        fragments: _,
+        anchor_ids: _,
+        redirects: _,
+        link_headers,
+        meta,
+    } = draft;
+
+    let related = related_posts(article, nearby.articles, RELATED_POSTS_LIMIT);
+    let already: Vec<&Href> = related.iter().map(|a| &a.href).collect();
+    let similar: Vec<&Article> = nearby
+        .corpus
+        .similar(&article.href, CONTENT_RELATED_LIMIT, &already)
+        .into_iter()
+        .filter_map(|href| nearby.articles.iter().find(|a| &a.href == href))
+        .collect();
+    let keywords = nearby.corpus.keywords(&article.href, KEYWORDS_PER_POST);
+    if !keywords.is_empty() {
+        head_meta.push_str(&format!(
+            r#"
+<meta name="keywords" content="{}">"#,
+            escape_attr(&keywords.join(", "))
+        ));
+    }
+    let injections = Injections {
+        head: head_meta,
+        body_end: format!(
+            r#"{}{}{}{}
+<p class="meta"><a href="{4}index.html">Index</a></p>
+"#,
+            render_series_nav(article, nearby.series_parts, &prefix),
+            render_related(&related, &prefix),
+            render_similar(&similar, &prefix),
+            render_backlinks(backlinks, &prefix),
+            escape_attr(&prefix)
+        ),
+    };
+
+    let page_html = ctx.templates.page(&PageContext {
+        article,
+        href_prefix: &prefix,
+        head_common: &ctx.head_html,
+        head: &head,
+        header: &header,
+        body: &body,
+        footer: &ctx.footer_html,
+    })?;
+    let minified = ctx.finish_html_with(&page_html, &out_path, &injections)?;
+
+    Ok(RenderedPage {
+        out_path,
+        minified,
+        link_headers,
+        meta,
+    })
+}
+
+/// The kind of the source published as `rel_src`: a page if it is under
+/// `PAGES_DIR`, otherwise whatever its frontmatter says.
+fn source_kind(rel_src: &Path, header: &Header) -> PostKind {
+    if rel_src.starts_with(PAGES_DIR) {
+        PostKind::Page
+    } else {
+        header.kind()
+    }
+}
+
+/// Output location of the page built from a source file of `kind`.
+fn article_rel_out(rel_src: &Path, kind: PostKind) -> color_eyre::Result<RelPath> {
+    let rel_html = match kind {
+        PostKind::Page => rel_src
+            .strip_prefix(PAGES_DIR)
+            .unwrap_or(rel_src)
+            .with_extension("html"),
+        _ => PathBuf::from(POSTS_DIR)
+            .join(rel_src)
+            .with_extension("html"),
+    };
+    let rel_out = page_out_path(&rel_html, PRETTY_URLS);
+    RelPath::new(rel_out).ok_or_else(|| eyre!("Output path must be relative"))
+}
+
+/// Whether `rel_out` is, or is inside, something the build writes itself.
+fn generated_at(rel_out: &Path) -> bool {
+    let generated = [
+        "index.html",
+        "404.html",
+        POSTS_DIR,
+        TAGS_DIR,
+        NOTES_DIR,
+        GALLERY_DIR,
+        PROJECTS_DIR,
+        AUTHORS_DIR,
+        SERIES_DIR,
+        ARCHIVE_DIR,
+        VOCABULARY_DIR,
+        FEEDS_DIR,
+        SEARCH_DIR,
+        API_DIR,
+    ];
+    rel_out
+        .components()
+        .next()
+        .is_some_and(|first| generated.iter().any(|name| first.as_os_str() == *name))
+}
+
+/// What decides whether a source's full content goes to feeds.
+struct Candidate {
+    href: Href,
+    kind: PostKind,
+    ctime: Option<IsoDate>,
+    expires: Option<IsoDate>,
+    tags: Vec<Tag>,
+    authors: Vec<String>,
+}
+
+/// What is settled about a source before it is rendered.
+struct Dated {
+    ctime: Option<IsoDate>,
+    /// Whether feeds or the notes stream show the full post, which is only
+    /// rendered for them if so.
+    full_content: bool,
+}
+
+/// The date a post is listed under: its `ctime`, else when a photo post's
+/// photo was taken, else per `DATE_FALLBACKS`.
+fn post_date(ctx: &BuildCtx, rel_src: &Path, header: &Header, kind: PostKind) -> Option<IsoDate> {
+    header
+        .ctime()
+        .or_else(|| {
+            let photo = header.photo().filter(|_| kind == PostKind::Photo)?;
+            read_exif(&ctx.current_dir.join(photo.trim_start_matches('/')))?.taken
+        })
+        .or_else(|| {
+            let path = ctx.source_path(rel_src)?;
+            fallback_date(&path, DATE_FALLBACKS)
+        })
+}
+
 fn render_single(
     ctx: &BuildCtx,
-    rel_src: &PathBuf,
+    pages: &PageIndex,
+    tag_names: &TagNames,
+    rel_src: &Path,
     content: &str,
-) -> color_eyre::Result<(RenderedPage, Article)> {
-    let rel_out = PathBuf::from(POSTS_DIR)
-        .join(rel_src)
-        .with_extension("html");
-    let rel_out = RelPath::new(rel_out).ok_or_else(|| eyre!("Output path must be relative"))?;
+    dated: Dated,
+) -> color_eyre::Result<(DraftPage, Article)> {
+    let mut header = Header::try_from(content).unwrap_or_default();
+    let kind = source_kind(rel_src, &header);
+    let rel_out = article_rel_out(rel_src, kind)?;
     let out_path = ctx.output_dir.join(rel_out.as_path());
+    let prefix = prefix_to_root(rel_out.as_path());
+    let shortcode_sink = ctx.diagnostics.for_document(rel_src, content, Vec::new());
+    let shortcode_page = ShortcodePage {
+        root: &ctx.current_dir,
+        output_dir: &ctx.output_dir,
+        prefix: &prefix,
+        extra: header.extra(),
+        sink: &shortcode_sink,
+    };
+    let expanded = expand_shortcodes(content, &shortcode_page)
+        .with_note(|| format!("While expanding shortcodes in {}", rel_src.display()))?;
+    let content: &str = &expanded;
 
     let href = Href::from_rel(&rel_out);
-    let prefix = prefix_to_root(rel_out.as_path());
     let css_href = format!("{prefix}style.css");
     let page_url = format!("{}/{}", ctx.site_meta.base_url, href.as_str());
 
-    let header = Header::try_from(content).unwrap_or_default();
-    let body_header = header.generate_body_head(&prefix);
+    let mut tags: Vec<Tag> = Vec::new();
+    let filed = if kind == PostKind::Page {
+        Vec::new()
+    } else {
+        header.tags().0
+    };
+    for tag in filed.iter().map(|t| tag_names.resolve(t)) {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tag_names.sort(&mut tags, TAG_ORDER);
+    header.set_tags(&tags);
+    if header.kind() == PostKind::Link
+        && header.title().is_none()
+        && let Some(title) = header.link().and_then(|url| ctx.link_titles.title_for(url))
+    {
+        header.fill_title(title);
+    }
+    if GIT_MTIME
+        && header.mtime().is_none()
+        && let Some(modified) = ctx.source_path(rel_src).as_deref().and_then(git_modified)
+        && header.ctime().is_none_or(|ctime| modified > ctime)
+    {
+        header.fill_mtime(&modified);
+    }
+    let mut body_header = header.generate_body_head(&prefix);
     let feed_body_header = header.generate_feed_body_head();
 
-    let parser = Parser::new_ext(content, ctx.parser_options);
-    let events: Vec<Event<'_>> = parser.collect();
+    // Pandoc output is already HTML and passes through untouched.
+    let (events, source_spans) = if pandoc_format(rel_src).is_some() {
+        let html = Event::Html(CowStr::Borrowed(converted_body(content)));
+        (vec![html], Vec::new())
+    } else {
+        parse_with_spans(content, ctx.parser_options)
+    };
+    // Only the page render reports problems; the feed render would repeat them.
+    let sink = ctx.diagnostics.for_document(rel_src, content, source_spans);
+    let events: Vec<Event<'_>> = events
+        .into_iter()
+        .with_transformer_reporting::<TodoTransformer<'_>>(&sink)
+        .with_transformer_reporting::<TextMacroTransformer<'_>>(&sink)
+        .collect();
+    let authors = header.authors();
+    if !ctx.authors.is_empty() {
+        for id in authors.iter().filter(|id| ctx.authors.get(id).is_none()) {
+            sink.warn(
+                "authors",
+                id,
+                format!("author `{id}` is not listed in {AUTHORS_FILE}"),
+            );
+        }
+    }
+    let credited: Vec<_> = authors.iter().map(|id| ctx.authors.resolve(id)).collect();
+    body_header.push_str(&byline(&credited, &prefix));
+    let expires = header.expires();
+    if let Some(expired) = expires.as_ref().filter(|expires| **expires < ctx.today) {
+        body_header.push_str(&format!(
+            r#"<p class="expired" role="note">Archived: this post expired on <time datetime="{expired}">{expired}</time> and may be out of date.</p>
+"#
+        ));
+    }
+    let events = resolve_wikilinks(events, pages, &prefix);
+    let events = resolve_post_refs(events, pages, &prefix, &sink);
+    let links = outbound_links(&events, &href);
+    let passages = link_passages(&events, &href);
+    let fragments = fragment_links(&events, &href);
+    if let Some(max) = MAX_TAGS_PER_POST
+        && tags.len() > max
+    {
+        sink.warn(
+            "tags",
+            "tags:",
+            format!(
+                "{} tags, more than the {max} a post should have",
+                tags.len()
+            ),
+        );
+    }
 
     let has_math = events
         .iter()
         .any(|e| matches!(e, Event::InlineMath(_) | Event::DisplayMath(_)));
 
     let katex_href = format!("{prefix}assets/katex/katex.min.css");
-    let mut head_fragment = header.to_html(&css_href, has_math, &katex_href);
-    head_fragment.push_str(&header.opengraph_meta(&page_url, &ctx.site_meta));
-
-    let page_body = render_page_body(events.clone());
-    let feed_body = render_feed_body(events);
+    // MathML needs no stylesheet, so only KaTeX HTML pulls in the KaTeX assets.
+    let math_output = header.math_output();
+    let needs_katex = has_math && math_output == MathOutput::Html;
+    // Every other code block goes through syntect, mermaid ones excepted.
+    let highlighted = events.iter().any(
+        |e| matches!(e, Event::Start(pulldown_cmark::Tag::CodeBlock(kind)) if !is_mermaid(kind)),
+    );
+    let mut head_fragment = header.to_html(&ctx.site_meta, &css_href, needs_katex, &katex_href);
+    if needs_mermaid_script(&events) {
+        head_fragment.push_str(&mermaid_script());
+    }
+    let cache_dir = ctx.current_dir.join(CACHE_DIR);
+    let page_assets = PageAssets::new(&ctx.current_dir, &cache_dir, &ctx.output_dir);
+    head_fragment.push_str(&page_assets.head_tags(
+        header.scripts(),
+        header.styles(),
+        &prefix,
+        &sink,
+    ));
+    let mut head_meta = header.opengraph_meta(&page_url, &ctx.site_meta);
+    if let Some(json_ld) = header.recipe_json_ld(&page_url, &ctx.site_meta) {
+        head_meta.push_str(&json_ld);
+    }
 
+    let photo = match kind {
+        PostKind::Photo => header.photo().map(ToOwned::to_owned),
+        PostKind::Article | PostKind::Note | PostKind::Link | PostKind::Recipe | PostKind::Page => {
+            None
+        }
+    };
+    let photo_path = photo
+        .as_deref()
+        .map(|src| ctx.current_dir.join(src.trim_start_matches('/')));
+    let photo_meta = photo_path.as_deref().and_then(read_exif);
+    let photo_color = photo_path.as_deref().and_then(dominant_color);
+    let thumbnail = header
+        .photo()
+        .filter(|src| !src.starts_with("http://") && !src.starts_with("https://"))
+        .and_then(|src| {
+            thumbnail(
+                &ctx.current_dir.join(src.trim_start_matches('/')),
+                &cache_dir,
+                &ctx.output_dir,
+            )
+        });
+    let photo_html = photo
+        .as_deref()
+        .map(|src| {
+            let alt = header.description().or(header.title()).unwrap_or_default();
+            render_photo(
+                src,
+                &prefix,
+                alt,
+                photo_meta.as_ref(),
+                photo_color.as_deref(),
+            )
+        })
+        .unwrap_or_default();
+
+    let text = plain_text(&events);
+    // Cited before the page and feed renders split, so sidenote citations
+    // become footnotes in both and unknown keys are reported once.
+    let events: Vec<Event<'_>> = CitationTransformer::with_bibliography(
+        events.into_iter(),
+        &sink,
+        &ctx.bibliography,
+        CITATION_STYLE,
+    )
+    .collect();
+    let toc = header.toc();
+    let feed_events = dated.full_content.then(|| events.clone());
+    let page_body = render_page_body(events, &sink, math_output, toc, header.paragraph_links());
     // Capture the rendered article body (including header) for full-text feeds before adding
     // any extra navigation links that are only relevant on-page.
-    let feed_content_html = format!("{feed_body_header}{feed_body}");
-
-    let mut page_body_with_nav = page_body;
-    page_body_with_nav.push_str(&format!(
-        r#"
-<p class="meta"><a href="{0}index.html">Index</a></p>
-"#,
-        escape_attr(&prefix)
-    ));
-
-    let title = header
-        .title()
-        .map(ToOwned::to_owned)
-        .unwrap_or_else(|| rel_out.as_path().to_string_lossy().to_string());
+    let recipe_html = header.recipe().map(|r| r.to_html()).unwrap_or_default();
+    let feed_content_html = feed_events.map(|events| {
+        let feed_body = render_feed_body(events, math_output, toc);
+        Arc::from(format!(
+            "{feed_body_header}{photo_html}{feed_body}{recipe_html}"
+        ))
+    });
+
+    let page_body = format!("{photo_html}{page_body}{recipe_html}");
+    let link_headers = if PRELOAD_HINTS {
+        let katex_fonts = needs_katex.then(|| format!("{prefix}assets/katex/fonts/"));
+        let preloads = page_preloads(&css_href, &page_body, katex_fonts.as_deref());
+        for preload in &preloads {
+            head_fragment.push('\n');
+            head_fragment.push_str(&preload.to_html());
+        }
+        preloads
+            .iter()
+            .filter_map(|preload| preload.link_header(&href))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let mut anchor_ids = collect_ids(&body_header);
+    anchor_ids.extend(collect_ids(&page_body));
+
+    let fallback_title = || rel_out.as_path().to_string_lossy().to_string();
+    let title = match (header.title(), kind) {
+        (Some(title), _) => title.to_owned(),
+        (None, PostKind::Note) => note_title(&text),
+        (None, PostKind::Link) => header
+            .link()
+            .map(ToOwned::to_owned)
+            .unwrap_or_else(fallback_title),
+        (None, PostKind::Article | PostKind::Photo | PostKind::Recipe | PostKind::Page) => {
+            fallback_title()
+        }
+    };
+    if kind == PostKind::Note && header.page_title(&ctx.site_meta).is_none() {
+        head_fragment.push_str(&format!(
+            r#"
+<title>{}</title>"#,
+            escape_text(&ctx.site_meta.page_title(&title))
+        ));
+    }
 
     let summary = header.description().map(ToOwned::to_owned);
 
     let article = Article {
         title,
-        ctime: header.ctime(),
+        ctime: dated.ctime,
         updated: header.mtime(),
+        expires,
         summary,
         content_html: feed_content_html,
+        text,
         href,
         tags: header.tags().0,
+        authors,
+        series: header.series().filter(|_| kind != PostKind::Page),
+        kind,
+        photo,
+        thumbnail,
+        link: match kind {
+            PostKind::Link => header.link().map(ToOwned::to_owned),
+            _ => None,
+        },
+        extra: header.extra().clone(),
     };
 
-    let page_html = page_shell(
-        &ctx.head_html,
-        &head_fragment,
-        &body_header,
-        &page_body_with_nav,
-        &ctx.footer_html,
-    );
-    let minified = minify(page_html.as_bytes(), &ctx.min_cfg);
+    let redirects = header
+        .aliases()
+        .iter()
+        .filter_map(|alias| {
+            let Some(rel) = alias_out_path(alias) else {
+                sink.warn(
+                    "aliases",
+                    alias,
+                    format!("alias `{alias}` is not a path inside the site"),
+                );
+                return None;
+            };
+            Some((
+                alias.clone(),
+                RenderedPage {
+                    out_path: ctx.output_dir.join(rel),
+                    minified: redirect_html(&page_url, &article.title).into_bytes(),
+                    link_headers: Vec::new(),
+                    meta: RenderMeta::default(),
+                },
+            ))
+        })
+        .collect();
 
-    Ok((
-        RenderedPage {
-            out_path,
-            minified,
+    let draft = DraftPage {
+        out_path,
+        prefix,
+        head: head_fragment,
+        head_meta,
+        header: body_header,
+        body: page_body,
+        links,
+        passages,
+        fragments,
+        anchor_ids,
+        redirects,
+        link_headers,
+        meta: RenderMeta {
+            katex: needs_katex,
+            highlighted,
         },
-        article,
-    ))
+    };
+
+    Ok((draft, article))
 }
 
-fn render_page_body<'a>(events: Vec<Event<'a>>) -> String {
-    let transformed = events
+fn render_page_body<'a>(
+    events: Vec<Event<'a>>,
+    sink: &DocumentSink,
+    math: MathOutput,
+    toc: TocOptions,
+    paragraph_links: bool,
+) -> String {
+    let highlighted = events
         .into_iter()
         .with_transformer::<EpigraphTransformer<'_>>()
-        .with_transformer::<CodeHighlightTransformer<'_, _>>()
-        .with_transformer::<MathTransformer<'_, _>>()
-        .with_transformer::<FootnoteTransformer<'_>>()
-        .with_transformer::<HeadingDemoterTransformer<'_, _>>()
-        .with_transformer::<TocTransformer<'_>>()
-        .with_transformer::<ImageCaptionTransformer<_>>();
+        .with_transformer::<BlockquoteCitationTransformer<'_>>()
+        .with_transformer::<SmartPunctuationTransformer<'_, _>>()
+        .with_transformer_reporting::<MermaidTransformer<'_, _>>(sink)
+        .with_transformer_reporting::<CodeHighlightTransformer<'_, _>>(sink);
+    let footnoted = MathTransformer::with_output(highlighted, sink, math)
+        .with_transformer::<ExternalLinkTransformer<'_>>()
+        .with_transformer::<MarginNoteTransformer<'_>>()
+        .with_transformer::<FootnoteTransformer<'_>>();
+    let demoted = ParagraphLinkTransformer::with_enabled(footnoted, paragraph_links)
+        .with_transformer::<HeadingDemoterTransformer<'_, _>>();
+    let transformed = TocTransformer::with_options(demoted, sink, toc)
+        .with_transformer::<ImageCaptionTransformer<_>>()
+        .with_transformer::<DefinitionListTransformer<'_>>();
 
     let mut rendered = String::new();
     pulldown_cmark::html::push_html(&mut rendered, transformed);
     rendered
 }
 
-fn render_feed_body<'a>(events: Vec<Event<'a>>) -> String {
-    let transformed = events
+fn render_feed_body<'a>(events: Vec<Event<'a>>, math: MathOutput, toc: TocOptions) -> String {
+    let labelled = events
         .into_iter()
         .with_transformer::<EpigraphTransformer<'_>>()
-        .with_transformer::<FeedCodeLabelTransformer<'_, _>>()
-        .with_transformer::<MathTransformer<'_, _>>()
+        .with_transformer::<BlockquoteCitationTransformer<'_>>()
+        .with_transformer::<SmartPunctuationTransformer<'_, _>>()
+        .with_transformer::<MermaidTransformer<'_, _>>()
+        .with_transformer::<FeedCodeLabelTransformer<'_, _>>();
+    let demoted = MathTransformer::with_output(labelled, &DocumentSink::default(), math)
+        .with_transformer::<FeedMarginNoteTransformer<'_>>()
         .with_transformer::<PlainFootnoteTransformer<'_>>()
-        .with_transformer::<HeadingDemoterTransformer<'_, _>>()
-        .with_transformer::<FeedTocTransformer<'_>>()
-        .with_transformer::<ImageCaptionTransformer<_>>();
+        .with_transformer::<HeadingDemoterTransformer<'_, _>>();
+    let transformed = FeedTocTransformer::with_options(demoted, toc)
+        .with_transformer::<ImageCaptionTransformer<_>>()
+        .with_transformer::<DefinitionListTransformer<'_>>();
 
     let mut rendered = String::new();
     pulldown_cmark::html::push_html(&mut rendered, transformed);
@@ -304,31 +1369,95 @@ fn emit_docs(
     rendered: Vec<RenderedPage>,
     articles: &[Article],
 ) -> color_eyre::Result<()> {
-    for RenderedPage { out_path, minified } in rendered {
-        write_with_compression(&out_path, &minified)?;
+    let mut link_headers: Vec<(Href, Vec<String>)> = Vec::new();
+    let stage = ctx.progress.stage("emit", rendered.len());
+    for RenderedPage {
+        out_path,
+        minified,
+        link_headers: links,
+        meta,
+    } in rendered
+    {
+        ctx.write_page(&out_path, &minified, meta)?;
+        let rel = out_path.strip_prefix(&ctx.output_dir).unwrap_or(&out_path);
+        if let Some(rel) = RelPath::new(rel.to_path_buf()) {
+            link_headers.push((Href::from_rel(&rel), links));
+        }
+        stage.step();
     }
+    if PRELOAD_HINTS {
+        link_headers.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+        let manifest = headers_manifest(
+            link_headers
+                .iter()
+                .map(|(href, links)| (href, links.as_slice())),
+        );
+        write_atomic(&ctx.output_dir.join(HEADERS_FILE), manifest.as_bytes())?;
+    }
+
+    // Standalone pages and expired posts are searchable but otherwise listed
+    // nowhere.
+    let posts: Vec<Article> = articles
+        .iter()
+        .filter(|a| a.kind != PostKind::Page && !a.is_expired(&ctx.today))
+        .cloned()
+        .collect();
+    // Notes get their own stream and feeds; the main index and feeds are
+    // essays only.
+    let (notes, essays): (Vec<Article>, Vec<Article>) = posts
+        .iter()
+        .cloned()
+        .partition(|a| a.kind == PostKind::Note);
 
     // Index and tag pages
-    build_index(ctx, articles)?;
-    build_tag_indices(ctx, articles)?;
+    stage.note("listings");
+    build_index(ctx, &essays)?;
+    build_archive(ctx, &essays)?;
+    build_tag_indices(ctx, &posts)?;
+    build_author_pages(ctx, &posts)?;
+    build_series_pages(ctx, &posts)?;
+    build_vocabulary(ctx, &posts)?;
+    build_search(ctx, articles)?;
+    build_notes(ctx, &notes)?;
+    build_gallery(ctx, &posts)?;
+    build_projects(ctx)?;
+    build_api(ctx, &posts)?;
 
     // Feeds; compress after writing
-    write_feeds(&ctx.output_dir, articles)?;
-    compress_existing(&ctx.output_dir.join("rss.xml"))?;
-    compress_existing(&ctx.output_dir.join("atom.xml"))?;
+    stage.note("feeds");
+    write_feeds(&ctx.output_dir, &essays)?;
+    compress_existing(&ctx.output_dir.join(RSS_FILE))?;
+    compress_existing(&ctx.output_dir.join(ATOM_FILE))?;
+    compress_existing(&ctx.output_dir.join(JSON_FEED_FILE))?;
+    build_feeds_page(ctx, !notes.is_empty())?;
+    build_not_found_page(ctx)?;
 
     // Minify and copy over style.css, then compress
+    stage.note("stylesheet");
     let stylesheet_in_path = ctx.current_dir.join("style").with_extension("css");
     if stylesheet_in_path.exists() {
         let stylesheet_out_path = ctx.output_dir.join("style").with_extension("css");
-        let stylesheet = build_css(stylesheet_in_path.as_path())?;
+        let source = fs::read_to_string(&stylesheet_in_path)?;
+        let sink = ctx
+            .diagnostics
+            .for_document(Path::new("style.css"), &source, Vec::new());
+        lint_css(&source, &sink);
+        let mut stylesheet = build_css(stylesheet_in_path.as_path())?;
+        if PRUNE_UNUSED_CSS {
+            // Every page has been written by now.
+            let used = UsedSelectors::scan_dir(&ctx.output_dir);
+            match prune_css(&stylesheet, &used, CSS_SAFELIST) {
+                Ok(pruned) => stylesheet = pruned,
+                Err(e) => sink.warn("css", "", format!("left unpruned: {e}")),
+            }
+        }
         write_with_compression(&stylesheet_out_path, stylesheet.as_bytes())?;
     }
 
     Ok(())
 }
 
-fn write_gzip_variant(path: &Path, data: &[u8]) -> io::Result<()> {
+fn write_gzip_variant(path: &Path, data: &[u8]) -> io::Result<u64> {
     let out_path = path.with_file_name(format!(
         "{}.gz",
         path.file_name()
@@ -339,10 +1468,11 @@ fn write_gzip_variant(path: &Path, data: &[u8]) -> io::Result<()> {
     let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
     encoder.write_all(data)?;
     let compressed = encoder.finish()?;
-    fs::write(out_path, compressed)
+    write_atomic(&out_path, &compressed)?;
+    Ok(compressed.len() as u64)
 }
 
-fn write_brotli_variant(path: &Path, data: &[u8]) -> io::Result<()> {
+fn write_brotli_variant(path: &Path, data: &[u8]) -> io::Result<u64> {
     let out_path = path.with_file_name(format!(
         "{}.br",
         path.file_name()
@@ -354,17 +1484,21 @@ fn write_brotli_variant(path: &Path, data: &[u8]) -> io::Result<()> {
     let mut writer = CompressorWriter::new(Vec::new(), 4096, 6, 22);
     writer.write_all(data)?;
     let compressed = writer.into_inner();
-    fs::write(out_path, compressed)
+    write_atomic(&out_path, &compressed)?;
+    Ok(compressed.len() as u64)
 }
 
-fn write_with_compression(path: &Path, data: &[u8]) -> io::Result<()> {
+/// Write `data` to `path` with `.gz` and `.br` variants beside it.
+fn write_with_compression(path: &Path, data: &[u8]) -> io::Result<Sizes> {
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
     }
-    fs::write(path, data)?;
-    write_gzip_variant(path, data)?;
-    write_brotli_variant(path, data)?;
-    Ok(())
+    write_atomic(path, data)?;
+    Ok(Sizes {
+        raw: data.len() as u64,
+        gzip: write_gzip_variant(path, data)?,
+        brotli: write_brotli_variant(path, data)?,
+    })
 }
 
 fn compress_existing(path: &Path) -> io::Result<()> {
@@ -374,7 +1508,7 @@ fn compress_existing(path: &Path) -> io::Result<()> {
     Ok(())
 }
 
-fn build_index(ctx: &BuildCtx, articles: &[Article]) -> io::Result<()> {
+fn build_index(ctx: &BuildCtx, articles: &[Article]) -> color_eyre::Result<()> {
     let index_rel = std::path::Path::new("index.html");
     let index_prefix = prefix_to_root(index_rel);
     let page_url = format!("{}/index.html", ctx.site_meta.base_url);
@@ -386,18 +1520,453 @@ fn build_index(ctx: &BuildCtx, articles: &[Article]) -> io::Result<()> {
 <meta name="description" content="{}">"#,
         escape_attr(&ctx.site_meta.description)
     ));
+    head_includes.push_str(&site_feed_alternates(&index_prefix, &ctx.site_meta.title));
     head_includes.push_str(&generic_og_meta(
-        "Index",
+        &ctx.site_meta.title,
         &ctx.site_meta.description,
         &page_url,
         &ctx.site_meta,
         None,
     ));
 
-    let index_html = render_listing_page("Index", "Index", articles, &head_includes, &index_prefix);
+    // Year headings lead to that year's archive page.
+    let body = render_listing_body("", articles, &index_prefix, |label| {
+        let year = label.parse().ok()?;
+        Some(format!(
+            "{index_prefix}{}",
+            archive_page_path(year, None).display()
+        ))
+    });
+    let index_html = ctx.templates.listing(&ListingContext {
+        title: &ctx.site_meta.title,
+        heading: "Index",
+        articles,
+        href_prefix: &index_prefix,
+        head: &head_includes,
+        body: &body,
+    })?;
+
+    ctx.write_html(&ctx.output_dir.join("index.html"), &index_html)?;
+    Ok(())
+}
 
-    let bytes = minify(index_html.as_bytes(), &ctx.min_cfg);
-    write_with_compression(&ctx.output_dir.join("index.html"), &bytes)
+/// The archive pages of every year and month `articles` were written in.
+fn build_archive(ctx: &BuildCtx, articles: &[Article]) -> color_eyre::Result<()> {
+    for (year, archive) in archive_years(articles) {
+        let months = archive.months.iter().map(|(&month, articles)| {
+            let label = month_label(year, month);
+            (Some(month), label, String::new(), articles)
+        });
+        let year_rel = archive_page_path(year, None);
+        let year_nav = render_month_nav(year, &archive, &prefix_to_root(&year_rel));
+        let pages = std::iter::once((None, year.to_string(), year_nav, &archive.articles));
+
+        for (month, label, intro, listed) in pages.chain(months) {
+            let page_rel = archive_page_path(year, month);
+            let prefix = prefix_to_root(&page_rel);
+            let page_url = format!("{}/{}", ctx.site_meta.base_url, page_rel.display());
+            let page_description = format!("Posts from {label}");
+
+            let mut head_includes = String::new();
+            head_includes.push_str(&ctx.head_html);
+            head_includes.push_str(&format!(
+                r#"
+<meta name="description" content="{}">"#,
+                escape_attr(&page_description)
+            ));
+            head_includes.push_str(&generic_og_meta(
+                &label,
+                &page_description,
+                &page_url,
+                &ctx.site_meta,
+                None,
+            ));
+
+            let html = render_listing_page(
+                &ctx.templates,
+                &format!("Archive: {label}"),
+                &label,
+                &intro,
+                listed,
+                &head_includes,
+                &prefix,
+            )?;
+            let out_path = ctx.output_dir.join(&page_rel);
+            if let Some(parent) = out_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            ctx.write_html(&out_path, &html)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// `<link rel="alternate">` tags for the site-wide feeds, from a page
+/// `prefix` away from the root.
+fn site_feed_alternates(prefix: &str, title: &str) -> String {
+    format!(
+        r#"
+<link rel="alternate" type="application/rss+xml" title="{title}" href="{prefix}{RSS_FILE}">
+<link rel="alternate" type="application/atom+xml" title="{title}" href="{prefix}{ATOM_FILE}">
+<link rel="alternate" type="application/feed+json" title="{title}" href="{prefix}{JSON_FEED_FILE}">"#,
+        title = escape_attr(title),
+        prefix = escape_attr(prefix),
+    )
+}
+
+/// A page at `FEEDS_DIR` for visitors who open a feed link in a browser,
+/// explaining feeds and listing the site-wide and notes feeds.
+fn build_feeds_page(ctx: &BuildCtx, has_notes: bool) -> color_eyre::Result<()> {
+    let page_rel = Path::new(FEEDS_DIR).join("index.html");
+    let prefix = prefix_to_root(&page_rel);
+    let base_url = &ctx.site_meta.base_url;
+    let page_url = format!("{base_url}/{FEEDS_DIR}/index.html");
+    let page_description = format!("Follow {} in a feed reader", ctx.site_meta.title);
+
+    let mut files = vec![
+        ("Posts", "RSS", RSS_FILE.to_string()),
+        ("Posts", "Atom", ATOM_FILE.to_string()),
+        ("Posts", "JSON Feed", JSON_FEED_FILE.to_string()),
+    ];
+    if has_notes {
+        files.push(("Notes", "RSS", format!("{NOTES_DIR}/{RSS_FILE}")));
+        files.push(("Notes", "Atom", format!("{NOTES_DIR}/{ATOM_FILE}")));
+    }
+    let feeds: Vec<FeedLink> = files
+        .into_iter()
+        .map(|(title, format, rel)| FeedLink {
+            title: title.to_string(),
+            format,
+            href: format!("{prefix}{rel}"),
+            url: format!("{base_url}/{rel}"),
+        })
+        .collect();
+
+    let mut head_includes = String::new();
+    head_includes.push_str(&ctx.head_html);
+    head_includes.push_str(&format!(
+        r#"
+<meta name="description" content="{}">"#,
+        escape_attr(&page_description)
+    ));
+    head_includes.push_str(&site_feed_alternates(&prefix, &ctx.site_meta.title));
+    head_includes.push_str(&generic_og_meta(
+        "Feeds",
+        &page_description,
+        &page_url,
+        &ctx.site_meta,
+        None,
+    ));
+
+    let html = ctx.templates.feeds(&FeedsContext {
+        title: "Feeds",
+        feeds: &feeds,
+        href_prefix: &prefix,
+        head: &head_includes,
+    })?;
+    ctx.write_html(&ctx.output_dir.join(page_rel), &html)?;
+
+    Ok(())
+}
+
+/// `404.html`, from `NOT_FOUND_PAGE` if the site has one. Hosts serve it for
+/// any missing path, so its links are root-relative rather than relative to
+/// where it sits.
+fn build_not_found_page(ctx: &BuildCtx) -> color_eyre::Result<()> {
+    let prefix = site_root_path(&ctx.site_meta.base_url);
+    let source_path = ctx.current_dir.join(INPUT_DIR).join(NOT_FOUND_PAGE);
+    let source = fs::read_to_string(&source_path).ok();
+
+    let (title, description, body) = match &source {
+        Some(source) => {
+            let header = Header::try_from(source.as_str())
+                .with_note(|| format!("While reading {}", source_path.display()))?;
+            let sink = ctx
+                .diagnostics
+                .for_document(Path::new(NOT_FOUND_PAGE), source, Vec::new());
+            let (events, _) = parse_with_spans(source, ctx.parser_options);
+            let body = render_page_body(events, &sink, header.math_output(), header.toc(), false);
+            let title = header.title().unwrap_or("Page not found").to_owned();
+            let description = header.description().map(ToOwned::to_owned);
+            (title, description, body)
+        }
+        None => (
+            "Page not found".to_owned(),
+            None,
+            format!(
+                r#"<p>There is nothing at this address. It may have moved; try the <a href="{}index.html">index</a>.</p>
+"#,
+                escape_attr(&prefix)
+            ),
+        ),
+    };
+    let description =
+        description.unwrap_or_else(|| format!("No such page on {}", ctx.site_meta.title));
+    let page_url = format!("{}/404.html", ctx.site_meta.base_url);
+
+    let mut head = String::new();
+    head.push_str(&ctx.head_html);
+    head.push_str(&format!(
+        r#"
+<meta name="description" content="{}">
+<meta name="robots" content="noindex">"#,
+        escape_attr(&description)
+    ));
+    head.push_str(&generic_og_meta(
+        &title,
+        &description,
+        &page_url,
+        &ctx.site_meta,
+        None,
+    ));
+
+    let html = ctx.templates.listing(&ListingContext {
+        title: &title,
+        heading: &title,
+        articles: &[],
+        href_prefix: &prefix,
+        head: &head,
+        body: &body,
+    })?;
+    ctx.write_html(&ctx.output_dir.join("404.html"), &html)
+}
+
+/// Path of the site root on its host, e.g. `/` or `/blog/`.
+fn site_root_path(base_url: &str) -> String {
+    let after_scheme = base_url
+        .split_once("://")
+        .map_or(base_url, |(_, rest)| rest);
+    let path = after_scheme
+        .split_once('/')
+        .map_or("", |(_, path)| path)
+        .trim_matches('/');
+    if path.is_empty() {
+        "/".to_owned()
+    } else {
+        format!("/{path}/")
+    }
+}
+
+fn build_notes(ctx: &BuildCtx, notes: &[Article]) -> color_eyre::Result<()> {
+    if notes.is_empty() {
+        return Ok(());
+    }
+
+    write_notes_feeds(&ctx.output_dir, notes)?;
+    let notes_dir = ctx.output_dir.join(NOTES_DIR);
+    compress_existing(&notes_dir.join(RSS_FILE))?;
+    compress_existing(&notes_dir.join(ATOM_FILE))?;
+
+    let page_rel = Path::new(NOTES_DIR).join("index.html");
+    let prefix = prefix_to_root(&page_rel);
+    let page_url = format!("{}/{NOTES_DIR}/index.html", ctx.site_meta.base_url);
+    let page_description = format!("Notes from {}", ctx.site_meta.title);
+
+    let mut head_includes = String::new();
+    head_includes.push_str(&ctx.head_html);
+    head_includes.push_str(&format!(
+        r#"
+<meta name="description" content="{}">"#,
+        escape_attr(&page_description)
+    ));
+    head_includes.push_str(&format!(
+        r#"
+<link rel="alternate" type="application/rss+xml" title="{title}" href="{RSS_FILE}">
+<link rel="alternate" type="application/atom+xml" title="{title}" href="{ATOM_FILE}">"#,
+        title = escape_attr(&page_description),
+    ));
+    head_includes.push_str(&generic_og_meta(
+        "Notes",
+        &page_description,
+        &page_url,
+        &ctx.site_meta,
+        None,
+    ));
+
+    let intro = format!(
+        r#"<p class="feeds">Subscribe: <a href="{RSS_FILE}">RSS</a> · <a href="{ATOM_FILE}">Atom</a></p>
+"#
+    );
+    let html = render_note_stream(
+        &ctx.templates,
+        "Notes",
+        "Notes",
+        &intro,
+        notes,
+        &head_includes,
+        &prefix,
+    )?;
+    ctx.write_html(&notes_dir.join("index.html"), &html)?;
+
+    Ok(())
+}
+
+fn build_gallery(ctx: &BuildCtx, articles: &[Article]) -> color_eyre::Result<()> {
+    let photos: Vec<Article> = articles
+        .iter()
+        .filter(|a| a.kind == PostKind::Photo && a.photo.is_some())
+        .cloned()
+        .collect();
+    if photos.is_empty() {
+        return Ok(());
+    }
+
+    let page_rel = Path::new(GALLERY_DIR).join("index.html");
+    let prefix = prefix_to_root(&page_rel);
+    let page_url = format!("{}/{GALLERY_DIR}/index.html", ctx.site_meta.base_url);
+    let page_description = format!("Photos from {}", ctx.site_meta.title);
+
+    let mut head_includes = String::new();
+    head_includes.push_str(&ctx.head_html);
+    head_includes.push_str(&format!(
+        r#"
+<meta name="description" content="{}">"#,
+        escape_attr(&page_description)
+    ));
+    head_includes.push_str(&generic_og_meta(
+        "Gallery",
+        &page_description,
+        &page_url,
+        &ctx.site_meta,
+        photos[0].photo.as_deref(),
+    ));
+
+    let html = render_gallery(
+        &ctx.templates,
+        "Gallery",
+        "Gallery",
+        &photos,
+        &head_includes,
+        &prefix,
+    )?;
+    ctx.write_html(&ctx.output_dir.join(page_rel), &html)?;
+
+    Ok(())
+}
+
+fn build_projects(ctx: &BuildCtx) -> color_eyre::Result<()> {
+    let projects = load_projects(&ctx.current_dir.join(PROJECTS_FILE))?;
+    if projects.is_empty() {
+        return Ok(());
+    }
+
+    let projects_dir = ctx.output_dir.join(PROJECTS_DIR);
+    let page_rel = Path::new(PROJECTS_DIR).join("index.html");
+    let prefix = prefix_to_root(&page_rel);
+    let page_description = format!("Projects by {}", ctx.site_meta.author);
+
+    let listing_head = |title: &str, description: &str, url: &str| {
+        let mut head = String::new();
+        head.push_str(&ctx.head_html);
+        head.push_str(&format!(
+            r#"
+<meta name="description" content="{}">"#,
+            escape_attr(description)
+        ));
+        head.push_str(&generic_og_meta(
+            title,
+            description,
+            url,
+            &ctx.site_meta,
+            None,
+        ));
+        head
+    };
+
+    let mut with_detail = Vec::new();
+    for project in &projects {
+        let Some(source) = project.page_source(&ctx.current_dir) else {
+            continue;
+        };
+        let content = fs::read_to_string(&source)
+            .with_note(|| format!("While reading {}", source.display()))?;
+        let (events, spans) = parse_with_spans(&content, ctx.parser_options);
+        let rel_source = source.strip_prefix(&ctx.current_dir).unwrap_or(&source);
+        let sink = ctx.diagnostics.for_document(rel_source, &content, spans);
+        let body = render_project_detail(
+            project,
+            &render_page_body(events, &sink, MATH_OUTPUT, TocOptions::default(), false),
+        );
+
+        let detail_rel = Path::new(PROJECTS_DIR).join(project.detail_href());
+        let url = format!("{}/{}", ctx.site_meta.base_url, detail_rel.display());
+        let description = project.description.as_deref().unwrap_or(&page_description);
+        let html = ctx.templates.listing(&ListingContext {
+            title: &project.name,
+            heading: &project.name,
+            articles: &[],
+            href_prefix: &prefix,
+            head: &listing_head(&project.name, description, &url),
+            body: &body,
+        })?;
+        ctx.write_html(&ctx.output_dir.join(detail_rel), &html)?;
+        with_detail.push(project.slug());
+    }
+
+    let url = format!("{}/{PROJECTS_DIR}/index.html", ctx.site_meta.base_url);
+    let body = render_projects_grid(&projects, |p| with_detail.contains(&p.slug()));
+    let html = ctx.templates.listing(&ListingContext {
+        title: "Projects",
+        heading: "Projects",
+        articles: &[],
+        href_prefix: &prefix,
+        head: &listing_head("Projects", &page_description, &url),
+        body: &body,
+    })?;
+    ctx.write_html(&projects_dir.join("index.html"), &html)?;
+
+    Ok(())
+}
+
+fn build_api(ctx: &BuildCtx, articles: &[Article]) -> color_eyre::Result<()> {
+    let base_url = &ctx.site_meta.base_url;
+    let mut files = build_article_pages(articles, API_PAGE_SIZE, base_url)?;
+    files.extend(build_tag_files(articles, base_url)?);
+
+    for (rel, json) in files {
+        write_with_compression(&ctx.output_dir.join(rel), json.as_bytes())?;
+    }
+    Ok(())
+}
+
+fn build_search(ctx: &BuildCtx, articles: &[Article]) -> color_eyre::Result<()> {
+    let search_dir = ctx.output_dir.join(SEARCH_DIR);
+
+    let index_json = build_search_index(articles)?;
+    write_with_compression(&search_dir.join("index.json"), index_json.as_bytes())?;
+
+    let page_rel = Path::new(SEARCH_DIR).join("index.html");
+    let prefix = prefix_to_root(&page_rel);
+    let page_url = format!("{}/{SEARCH_DIR}/index.html", ctx.site_meta.base_url);
+    let page_description = format!("Search {}", ctx.site_meta.title);
+
+    let mut head_includes = String::new();
+    head_includes.push_str(&ctx.head_html);
+    head_includes.push_str(&format!(
+        r#"
+<meta name="description" content="{}">"#,
+        escape_attr(&page_description)
+    ));
+    head_includes.push_str(&generic_og_meta(
+        "Search",
+        &page_description,
+        &page_url,
+        &ctx.site_meta,
+        None,
+    ));
+
+    let html = ctx.templates.listing(&ListingContext {
+        title: "Search",
+        heading: "Search",
+        articles: &[],
+        href_prefix: &prefix,
+        head: &head_includes,
+        body: &search_page_body(&prefix),
+    })?;
+    ctx.write_html(&search_dir.join("index.html"), &html)?;
+
+    Ok(())
 }
 
 trait PipelineStage {}
@@ -436,8 +2005,10 @@ impl PipelineStage for Rendered {}
 impl PipelineStage for () {}
 
 impl Pipeline<Discovered> {
-    fn parse(self) -> color_eyre::Result<Pipeline<Parsed>> {
-        let parsed = parse_sources(&self.ctx, self.state.0)?;
+    fn parse(mut self) -> color_eyre::Result<Pipeline<Parsed>> {
+        let mut included = BTreeSet::new();
+        let parsed = parse_sources(&self.ctx, self.state.0, &mut included)?;
+        self.ctx.report.included = included;
         Ok(Pipeline {
             ctx: self.ctx,
             state: Parsed(parsed),
@@ -446,8 +2017,8 @@ impl Pipeline<Discovered> {
 }
 
 impl Pipeline<Parsed> {
-    fn transform(self) -> color_eyre::Result<Pipeline<Transformed>> {
-        let transformed = transform_docs(self.state.0)?;
+    fn transform(mut self) -> color_eyre::Result<Pipeline<Transformed>> {
+        let transformed = transform_docs(self.state.0, &mut self.ctx.report.fragment_sources)?;
         Ok(Pipeline {
             ctx: self.ctx,
             state: Transformed(transformed),
@@ -466,12 +2037,16 @@ impl Pipeline<Transformed> {
 }
 
 impl Pipeline<Rendered> {
-    fn emit(self) -> color_eyre::Result<()> {
-        emit_docs(&self.ctx, self.state.pages, &self.state.articles)
+    fn emit(self) -> color_eyre::Result<BuildReport> {
+        emit_docs(&self.ctx, self.state.pages, &self.state.articles)?;
+        let mut report = self.ctx.report;
+        report.pages = self.ctx.page_stats.into_inner().unwrap_or_default();
+        report.pages.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(report)
     }
 }
 
-fn build_tag_indices(ctx: &BuildCtx, articles: &[Article]) -> io::Result<()> {
+fn build_tag_indices(ctx: &BuildCtx, articles: &[Article]) -> color_eyre::Result<()> {
     let mut by_tag: BTreeMap<Tag, Vec<Article>> = BTreeMap::new();
     for a in articles {
         for t in &a.tags {
@@ -479,13 +2054,35 @@ fn build_tag_indices(ctx: &BuildCtx, articles: &[Article]) -> io::Result<()> {
         }
     }
 
+    if let Some(tag) = by_tag
+        .keys()
+        .find(|tag| tag_page_path(tag) == tags_index_path())
+    {
+        return Err(eyre!("tag `{tag}` would replace the tags index"))
+            .with_note(|| "Rename the tag in the posts that use it");
+    }
+    let counts: Vec<(Tag, usize)> = by_tag
+        .iter()
+        .map(|(tag, tagged)| (tag.clone(), tagged.len()))
+        .collect();
+
     let tags_dir = ctx.output_dir.join(TAGS_DIR);
     fs::create_dir_all(&tags_dir)?;
+    build_tags_index(ctx, &counts)?;
     for (tag, tagged) in by_tag {
-        let tag_rel = std::path::PathBuf::from(TAGS_DIR).join(format!("{tag}.html"));
+        let tag_rel = tag_page_path(&tag);
         let tag_prefix = prefix_to_root(&tag_rel);
-        let page_url = format!("{}/tags/{tag}.html", ctx.site_meta.base_url);
-        let page_description = format!("Posts tagged {tag}");
+        let page_url = format!("{}/{}", ctx.site_meta.base_url, tag_href(&tag));
+        let page_title = ctx.tag_infos.title(&tag);
+        let page_description = ctx.tag_infos.description(&tag);
+
+        write_tag_feeds(&ctx.output_dir, &tag, &ctx.tag_infos, &tagged)?;
+        let rss_rel = tag_feed_path(&tag, RSS_FILE);
+        let atom_rel = tag_feed_path(&tag, ATOM_FILE);
+        compress_existing(&ctx.output_dir.join(&rss_rel))?;
+        compress_existing(&ctx.output_dir.join(&atom_rel))?;
+        let rss_href = format!("{tag_prefix}{}", rss_rel.display());
+        let atom_href = format!("{tag_prefix}{}", atom_rel.display());
 
         let mut head_includes = String::new();
         head_includes.push_str(&ctx.head_html);
@@ -494,8 +2091,33 @@ fn build_tag_indices(ctx: &BuildCtx, articles: &[Article]) -> io::Result<()> {
 <meta name="description" content="{}">"#,
             escape_attr(&page_description)
         ));
+        head_includes.push_str(&format!(
+            r#"
+<link rel="alternate" type="application/rss+xml" title="{title}" href="{rss}">
+<link rel="alternate" type="application/atom+xml" title="{title}" href="{atom}">"#,
+            title = escape_attr(&page_description),
+            rss = escape_attr(&rss_href),
+            atom = escape_attr(&atom_href),
+        ));
+        let mut intro = String::new();
+        if let Some(description) = ctx
+            .tag_infos
+            .get(&tag)
+            .and_then(|info| info.description.as_deref())
+        {
+            intro.push_str(&format!(
+                "<p class=\"tag-description\">{}</p>\n",
+                escape_text(description)
+            ));
+        }
+        intro.push_str(&format!(
+            r#"<p class="feeds">Subscribe: <a href="{}">RSS</a> · <a href="{}">Atom</a></p>
+"#,
+            escape_attr(&rss_href),
+            escape_attr(&atom_href),
+        ));
         head_includes.push_str(&generic_og_meta(
-            &format!("Tag: {tag}"),
+            &page_title,
             &page_description,
             &page_url,
             &ctx.site_meta,
@@ -503,15 +2125,215 @@ fn build_tag_indices(ctx: &BuildCtx, articles: &[Article]) -> io::Result<()> {
         ));
 
         let html = render_listing_page(
-            &format!("Tag: {tag}"),
-            &format!("Tag: {tag}"),
+            &ctx.templates,
+            &page_title,
+            &page_title,
+            &intro,
             &tagged,
             &head_includes,
             &tag_prefix,
+        )?;
+
+        ctx.write_html(&ctx.output_dir.join(tag_rel), &html)?;
+    }
+
+    Ok(())
+}
+
+/// The vocabulary page at `VOCABULARY_DIR`: the site's most used terms and
+/// the posts using each.
+fn build_vocabulary(ctx: &BuildCtx, articles: &[Article]) -> color_eyre::Result<()> {
+    if VOCABULARY_TERMS == 0 || articles.is_empty() {
+        return Ok(());
+    }
+
+    let page_rel = Path::new(VOCABULARY_DIR).join("index.html");
+    let prefix = prefix_to_root(&page_rel);
+    let page_url = format!("{}/{}", ctx.site_meta.base_url, page_rel.display());
+    let page_description = format!("The words most used on {}", ctx.site_meta.title);
+
+    let mut head_includes = String::new();
+    head_includes.push_str(&ctx.head_html);
+    head_includes.push_str(&format!(
+        r#"
+<meta name="description" content="{}">"#,
+        escape_attr(&page_description)
+    ));
+    head_includes.push_str(&generic_og_meta(
+        "Vocabulary",
+        &page_description,
+        &page_url,
+        &ctx.site_meta,
+        None,
+    ));
+
+    let corpus = Corpus::new(articles.iter().map(|a| (&a.href, a.text.as_str())));
+    let html = ctx.templates.listing(&ListingContext {
+        title: "Vocabulary",
+        heading: "Vocabulary",
+        articles: &[],
+        href_prefix: &prefix,
+        head: &head_includes,
+        body: &render_vocabulary(&corpus, articles, VOCABULARY_TERMS, &prefix),
+    })?;
+    fs::create_dir_all(ctx.output_dir.join(VOCABULARY_DIR))?;
+    ctx.write_html(&ctx.output_dir.join(page_rel), &html)?;
+    Ok(())
+}
+
+/// A landing page for each series: its parts in order.
+fn build_series_pages(ctx: &BuildCtx, articles: &[Article]) -> color_eyre::Result<()> {
+    let series = collect_series(articles);
+    if series.is_empty() {
+        return Ok(());
+    }
+
+    fs::create_dir_all(ctx.output_dir.join(SERIES_DIR))?;
+    for (name, parts) in series {
+        let series_rel = series_page_path(name);
+        let series_prefix = prefix_to_root(&series_rel);
+        let page_url = format!("{}/{}", ctx.site_meta.base_url, series_href(name));
+        let page_description = format!("A series of {} posts", parts.len());
+
+        let mut head_includes = String::new();
+        head_includes.push_str(&ctx.head_html);
+        head_includes.push_str(&format!(
+            r#"
+<meta name="description" content="{}">"#,
+            escape_attr(&page_description)
+        ));
+        head_includes.push_str(&generic_og_meta(
+            name,
+            &page_description,
+            &page_url,
+            &ctx.site_meta,
+            None,
+        ));
+
+        let listed: Vec<Article> = parts.iter().map(|a| (*a).clone()).collect();
+        let html = ctx.templates.listing(&ListingContext {
+            title: name,
+            heading: name,
+            articles: &listed,
+            href_prefix: &series_prefix,
+            head: &head_includes,
+            body: &render_series_parts(&parts, &series_prefix),
+        })?;
+        ctx.write_html(&ctx.output_dir.join(series_rel), &html)?;
+    }
+
+    Ok(())
+}
+
+/// The page at `tags_index_path()` listing every tag with its post count.
+fn build_tags_index(ctx: &BuildCtx, counts: &[(Tag, usize)]) -> color_eyre::Result<()> {
+    if counts.is_empty() {
+        return Ok(());
+    }
+    let index_rel = tags_index_path();
+    let prefix = prefix_to_root(&index_rel);
+    let page_url = format!("{}/{}", ctx.site_meta.base_url, index_rel.display());
+    let page_description = format!("Every tag on {}", ctx.site_meta.title);
+
+    let mut head_includes = String::new();
+    head_includes.push_str(&ctx.head_html);
+    head_includes.push_str(&format!(
+        r#"
+<meta name="description" content="{}">"#,
+        escape_attr(&page_description)
+    ));
+    head_includes.push_str(&generic_og_meta(
+        "Tags",
+        &page_description,
+        &page_url,
+        &ctx.site_meta,
+        None,
+    ));
+
+    let html = ctx.templates.listing(&ListingContext {
+        title: "Tags",
+        heading: "Tags",
+        articles: &[],
+        href_prefix: &prefix,
+        head: &head_includes,
+        body: &render_tag_index(counts, TAG_CLOUD, &prefix),
+    })?;
+    ctx.write_html(&ctx.output_dir.join(index_rel), &html)
+}
+
+/// A page and feeds for each author credited on a post: their h-card, then
+/// their posts.
+fn build_author_pages(ctx: &BuildCtx, articles: &[Article]) -> color_eyre::Result<()> {
+    let mut by_author: BTreeMap<&str, Vec<Article>> = BTreeMap::new();
+    for a in articles {
+        for id in &a.authors {
+            by_author.entry(id).or_default().push(a.clone());
+        }
+    }
+    if by_author.is_empty() {
+        return Ok(());
+    }
+
+    fs::create_dir_all(ctx.output_dir.join(AUTHORS_DIR))?;
+    for (id, written) in by_author {
+        let author = ctx.authors.resolve(id);
+        let author_rel = author.page_path();
+        let author_prefix = prefix_to_root(&author_rel);
+        let page_url = format!("{}/{}", ctx.site_meta.base_url, author.href());
+        let page_description = author
+            .bio
+            .clone()
+            .unwrap_or_else(|| format!("Posts by {}", author.name));
+
+        write_author_feeds(&ctx.output_dir, &author, &written)?;
+        let rss_rel = author.feed_path(RSS_FILE);
+        let atom_rel = author.feed_path(ATOM_FILE);
+        compress_existing(&ctx.output_dir.join(&rss_rel))?;
+        compress_existing(&ctx.output_dir.join(&atom_rel))?;
+        let rss_href = format!("{author_prefix}{}", rss_rel.display());
+        let atom_href = format!("{author_prefix}{}", atom_rel.display());
+
+        let mut head_includes = String::new();
+        head_includes.push_str(&ctx.head_html);
+        head_includes.push_str(&format!(
+            r#"
+<meta name="description" content="{}">"#,
+            escape_attr(&page_description)
+        ));
+        head_includes.push_str(&format!(
+            r#"
+<link rel="alternate" type="application/rss+xml" title="{title}" href="{rss}">
+<link rel="alternate" type="application/atom+xml" title="{title}" href="{atom}">"#,
+            title = escape_attr(&format!("Posts by {}", author.name)),
+            rss = escape_attr(&rss_href),
+            atom = escape_attr(&atom_href),
+        ));
+        head_includes.push_str(&generic_og_meta(
+            &author.name,
+            &page_description,
+            &page_url,
+            &ctx.site_meta,
+            None,
+        ));
+        let intro = format!(
+            r#"{}<p class="feeds">Subscribe: <a href="{}">RSS</a> · <a href="{}">Atom</a></p>
+"#,
+            author.h_card(&author_prefix),
+            escape_attr(&rss_href),
+            escape_attr(&atom_href),
         );
 
-        let bytes = minify(html.as_bytes(), &ctx.min_cfg);
-        write_with_compression(&ctx.output_dir.join(tag_rel), &bytes)?;
+        let html = render_listing_page(
+            &ctx.templates,
+            &author.name,
+            &author.name,
+            &intro,
+            &written,
+            &head_includes,
+            &author_prefix,
+        )?;
+
+        ctx.write_html(&ctx.output_dir.join(author_rel), &html)?;
     }
 
     Ok(())
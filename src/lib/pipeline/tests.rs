@@ -1,4 +1,5 @@
 use std::{
+    collections::{BTreeMap, BTreeSet},
     fs,
     path::{Path, PathBuf},
 };
@@ -13,8 +14,18 @@ use tempfile::TempDir;
 use walkdir::WalkDir;
 
 use crate::{
-    config::{INPUT_DIR, OUTPUT_DIR, POSTS_DIR, SITE_BASE_URL, SITE_DEFAULT_OG_IMAGE, TAGS_DIR},
-    pipeline::build_at,
+    config::{
+        ARCHIVE_DIR, AUTHORS_DIR, AUTHORS_FILE, BIBLIOGRAPHY, INPUT_DIR, NOT_FOUND_PAGE,
+        OUTPUT_DIR, PAGES_DIR, POSTS_DIR, SERIES_DIR, SITE_BASE_URL, SITE_DEFAULT_OG_IMAGE,
+        SITE_TITLE, TAGS_DIR, TAGS_FILE, TEMPLATES_DIR, VOCABULARY_DIR, site_meta,
+    },
+    feed::RSS_FILE,
+    ignore::IgnoreRules,
+    pipeline::{
+        BuildCtx, BuildOptions, SourceRoot, build_at, build_with, checked_in_path,
+        discover_sources, parse_sources,
+    },
+    templates::PAGE_TEMPLATE,
 };
 
 // Simple guard to restore cwd even on panic.
@@ -168,6 +179,36 @@ fn math_pages_toggle_katex_link() {
     assert!(!plain_html.contains("katex.min.css"));
 }
 
+#[test]
+fn strict_builds_fail_on_broken_math() {
+    let tmp = TempDir::new().expect("tempdir");
+
+    let broken = "---\ntitle: Broken\nctime: 2024-03-01\n---\nInline $x^$.\n";
+    write_md(tmp.path(), Path::new("broken.md"), broken).unwrap();
+
+    build_at(tmp.path()).unwrap();
+    let strict = BuildOptions {
+        strict: true,
+        ..BuildOptions::default()
+    };
+    let err = build_with(tmp.path(), strict).unwrap_err();
+    assert!(err.to_string().contains("1 warning(s)"), "{err}");
+}
+
+#[test]
+fn mathml_pages_skip_katex_assets() {
+    let tmp = TempDir::new().expect("tempdir");
+
+    let math = "---\ntitle: Native\nctime: 2024-03-01\nmath: mathml\n---\nInline $x^2$.\n";
+    write_md(tmp.path(), Path::new("native.md"), math).unwrap();
+
+    build_at(tmp.path()).unwrap();
+
+    let html = read_public(&tmp, Path::new(POSTS_DIR).join("native.html"));
+    assert!(html.contains("<math"));
+    assert!(!html.contains("katex.min.css"));
+}
+
 #[test]
 fn tag_pages_are_filtered_and_sorted() {
     let tmp = TempDir::new().expect("tempdir");
@@ -210,6 +251,451 @@ fn tag_pages_are_filtered_and_sorted() {
     assert!(!bad_tag_path.exists(), "invalid tags should be discarded");
 }
 
+#[test]
+fn tags_differing_in_case_share_a_page() {
+    let tmp = TempDir::new().expect("tempdir");
+
+    let first = "---\ntitle: First\nctime: 2025-01-01\ntags: [rust]\n---\nBody\n";
+    let second = "---\ntitle: Second\nctime: 2025-01-02\ntags: [Rust]\n---\nBody\n";
+    write_md(tmp.path(), Path::new("a.md"), first).unwrap();
+    write_md(tmp.path(), Path::new("b.md"), second).unwrap();
+
+    build_at(tmp.path()).unwrap();
+
+    let rust_html = read_public(&tmp, Path::new(TAGS_DIR).join("rust.html"));
+    assert!(rust_html.contains("First") && rust_html.contains("Second"));
+    assert!(!public_path(&tmp, Path::new(TAGS_DIR).join("Rust.html")).exists());
+    let second_html = read_public(&tmp, Path::new(POSTS_DIR).join("b.html"));
+    assert!(second_html.contains("tags/rust.html"));
+}
+
+#[test]
+fn post_tags_are_listed_in_order() {
+    let tmp = TempDir::new().expect("tempdir");
+
+    let md = "---\ntitle: Ordered\nctime: 2025-01-01\ntags: [zig, C, rust]\n---\nBody\n";
+    write_md(tmp.path(), Path::new("ordered.md"), md).unwrap();
+
+    build_at(tmp.path()).unwrap();
+
+    let html = read_public(&tmp, Path::new(POSTS_DIR).join("ordered.html"));
+    let at = |tag: &str| html.find(&format!("tags/{tag}.html")).unwrap();
+    assert!(at("C") < at("rust") && at("rust") < at("zig"));
+}
+
+#[test]
+fn tag_feeds_are_emitted_and_linked() {
+    let tmp = TempDir::new().expect("tempdir");
+
+    fs::create_dir_all(INPUT_DIR).unwrap();
+    fs::write("style.css", "body { color: black; }").unwrap();
+
+    let rust = "---\ntitle: Rusty\nctime: 2025-01-01\ntags: [rust]\n---\nBody\n";
+    write_md(tmp.path(), Path::new("rusty.md"), rust).unwrap();
+    let life = "---\ntitle: Lively\nctime: 2025-02-01\ntags: [life]\n---\nBody\n";
+    write_md(tmp.path(), Path::new("lively.md"), life).unwrap();
+
+    build_at(tmp.path()).unwrap();
+
+    let rss_bytes = read_public_bytes(&tmp, Path::new(TAGS_DIR).join("rust.rss.xml"));
+    let channel = rss::Channel::read_from(&rss_bytes[..]).expect("parse tag rss");
+    assert_eq!(channel.items().len(), 1);
+    assert_eq!(channel.items()[0].title(), Some("Rusty"));
+    assert!(channel.link().ends_with("tags/rust.html"));
+
+    let atom_bytes = read_public_bytes(&tmp, Path::new(TAGS_DIR).join("rust.atom.xml"));
+    let feed = atom_syndication::Feed::read_from(&atom_bytes[..]).expect("parse tag atom");
+    assert_eq!(feed.entries().len(), 1);
+    assert_eq!(feed.entries()[0].title().to_string(), "Rusty");
+
+    let rust_html = read_public(&tmp, Path::new(TAGS_DIR).join("rust.html"));
+    assert!(rust_html.contains("rust.rss.xml"));
+    assert!(rust_html.contains("rust.atom.xml"));
+    assert!(rust_html.contains("application/atom+xml"));
+}
+
+#[test]
+fn notes_have_their_own_stream_and_feeds() {
+    let tmp = TempDir::new().expect("tempdir");
+
+    fs::create_dir_all(INPUT_DIR).unwrap();
+    fs::write("style.css", "body { color: black; }").unwrap();
+
+    let essay = "---\ntitle: Essay\nctime: 2025-01-01\n---\nLong form.\n";
+    write_md(tmp.path(), Path::new("essay.md"), essay).unwrap();
+    let note = "---\ntype: note\nctime: 2025-02-01\n---\nJust a *quick* thought.\n";
+    write_md(tmp.path(), Path::new("quick.md"), note).unwrap();
+
+    build_at(tmp.path()).unwrap();
+
+    let index_html = read_public(&tmp, "index.html");
+    assert!(index_html.contains("Essay"));
+    assert!(!index_html.contains("quick.html"));
+
+    let note_html = read_public(&tmp, Path::new(POSTS_DIR).join("quick.html"));
+    assert!(!note_html.contains("<h1>"));
+    let title = site_meta().page_title("Just a quick thought.");
+    assert!(note_html.contains(&format!("<title>{title}</title>")));
+    assert!(note_html.contains("<em>quick</em>"));
+
+    let stream = read_public(&tmp, Path::new("notes").join("index.html"));
+    assert!(stream.contains("<em>quick</em>"));
+    assert!(stream.contains("2025-02-01"));
+    assert!(!stream.contains("Long form."));
+
+    let rss_bytes = read_public_bytes(&tmp, Path::new("notes").join("rss.xml"));
+    let channel = rss::Channel::read_from(&rss_bytes[..]).expect("parse notes rss");
+    assert_eq!(channel.items().len(), 1);
+    assert_eq!(channel.items()[0].title(), Some("Just a quick thought."));
+    assert!(
+        channel.items()[0]
+            .content()
+            .unwrap()
+            .contains("<em>quick</em>")
+    );
+
+    let main_rss = read_public_bytes(&tmp, "rss.xml");
+    let main = rss::Channel::read_from(&main_rss[..]).expect("parse rss");
+    assert_eq!(main.items().len(), 1);
+    assert_eq!(main.items()[0].title(), Some("Essay"));
+}
+
+#[test]
+fn feeds_page_lists_feeds_for_browser_visitors() {
+    let tmp = TempDir::new().expect("tempdir");
+
+    let essay = "---\ntitle: Essay\nctime: 2025-01-01\n---\nLong form.\n";
+    write_md(tmp.path(), Path::new("essay.md"), essay).unwrap();
+    let note = "---\ntype: note\nctime: 2025-02-01\n---\nA thought.\n";
+    write_md(tmp.path(), Path::new("thought.md"), note).unwrap();
+
+    build_at(tmp.path()).unwrap();
+
+    let page = read_public(&tmp, Path::new("feed").join("index.html"));
+    assert!(page.contains("feed reader"));
+    for rel in [
+        "../rss.xml",
+        "../atom.xml",
+        "../feed.json",
+        "../notes/rss.xml",
+    ] {
+        assert!(page.contains(rel), "missing {rel}");
+    }
+    let base = SITE_BASE_URL.trim_end_matches('/');
+    assert!(page.contains(&format!("{base}/notes/atom.xml")));
+    assert!(page.contains("application/rss+xml"));
+
+    let index = read_public(&tmp, "index.html");
+    assert!(index.contains("application/feed+json"));
+}
+
+#[test]
+fn photo_posts_render_full_bleed_and_join_the_gallery() {
+    let tmp = TempDir::new().expect("tempdir");
+
+    fs::create_dir_all(INPUT_DIR).unwrap();
+    fs::write("style.css", "body { color: black; }").unwrap();
+
+    let photo = "---\ntitle: Harbour\ntype: photo\nctime: 2025-03-03\nphoto: /assets/harbour.jpg\n---\nMorning light.\n";
+    write_md(tmp.path(), Path::new("harbour.md"), photo).unwrap();
+    let relative =
+        "---\ntitle: Pier\ntype: photo\nctime: 2025-03-02\nphoto: images/pier.jpg\n---\nDusk.\n";
+    write_md(tmp.path(), Path::new("pier.md"), relative).unwrap();
+    let essay = "---\ntitle: Essay\nctime: 2025-01-01\nimage: /assets/cover.jpg\n---\nWords.\n";
+    write_md(tmp.path(), Path::new("essay.md"), essay).unwrap();
+
+    build_at(tmp.path()).unwrap();
+
+    let page = read_public(&tmp, Path::new(POSTS_DIR).join("harbour.html"));
+    assert!(page.contains("photo full-bleed"));
+    assert!(page.contains("src=../assets/harbour.jpg"));
+    let page = read_public(&tmp, Path::new(POSTS_DIR).join("pier.html"));
+    assert!(page.contains("src=../images/pier.jpg"));
+
+    let gallery = read_public(&tmp, Path::new("gallery").join("index.html"));
+    assert!(gallery.contains("harbour.html"));
+    assert!(gallery.contains("src=../assets/harbour.jpg"));
+    assert!(gallery.contains("src=../images/pier.jpg"));
+    assert!(!gallery.contains("essay.html"));
+}
+
+#[test]
+fn link_posts_point_listings_at_their_target() {
+    let tmp = TempDir::new().expect("tempdir");
+
+    fs::create_dir_all(INPUT_DIR).unwrap();
+    fs::write("style.css", "body { color: black; }").unwrap();
+    // Seed the title cache so the build never reaches the network.
+    let cache_dir = tmp.path().join(".cache");
+    fs::create_dir_all(&cache_dir).unwrap();
+    fs::write(
+        cache_dir.join("link-titles.json"),
+        r#"{"https://example.com/article":"Fetched Title"}"#,
+    )
+    .unwrap();
+
+    let link = "---\ntype: link\nlink: https://example.com/article\nctime: 2025-04-04\n---\nWorth reading.\n";
+    write_md(tmp.path(), Path::new("worth.md"), link).unwrap();
+
+    build_at(tmp.path()).unwrap();
+
+    let index_html = read_public(&tmp, "index.html");
+    assert!(index_html.contains("https://example.com/article"));
+    assert!(index_html.contains("Fetched Title"));
+    assert!(index_html.contains("class=permalink"));
+    assert!(index_html.contains("posts/worth.html"));
+
+    let page = read_public(&tmp, Path::new(POSTS_DIR).join("worth.html"));
+    assert!(page.contains("Fetched Title</a></h1>"));
+}
+
+#[test]
+fn custom_frontmatter_reaches_templates_and_shortcodes() {
+    let tmp = TempDir::new().expect("tempdir");
+    fs::create_dir_all(tmp.path().join(INPUT_DIR)).unwrap();
+    fs::write(tmp.path().join("style.css"), "body { color: black; }").unwrap();
+    let templates = tmp.path().join(TEMPLATES_DIR);
+    fs::create_dir_all(&templates).unwrap();
+    fs::write(
+        templates.join(PAGE_TEMPLATE),
+        "rating={{ article.extra.rating }}|{{ body }}",
+    )
+    .unwrap();
+
+    let md = "---\ntitle: Gig\nctime: 2025-01-01\nrating: 4\nvenue: Town Hall\n---\nSeen at {{ meta venue }}.\n";
+    write_md(tmp.path(), Path::new("gig.md"), md).unwrap();
+
+    build_at(tmp.path()).unwrap();
+
+    let page = read_public(&tmp, Path::new(POSTS_DIR).join("gig.html"));
+    assert!(page.starts_with("rating=4|"), "{page}");
+    assert!(page.contains("Seen at Town Hall."));
+}
+
+#[test]
+fn citations_resolve_against_the_site_bibliography() {
+    let tmp = TempDir::new().expect("tempdir");
+    fs::write(
+        tmp.path().join(BIBLIOGRAPHY),
+        "@book{knuth1984, author = {Knuth, Donald}, title = {Literate Programming}, year = 1984}\n",
+    )
+    .unwrap();
+    let md = "---\ntitle: Prose\nctime: 2025-01-01\n---\nCode is literature[@knuth1984, p. 97].\n";
+    write_md(tmp.path(), Path::new("prose.md"), md).unwrap();
+
+    build_at(tmp.path()).unwrap();
+
+    let page = read_public(&tmp, Path::new(POSTS_DIR).join("prose.html"));
+    assert!(page.contains("Knuth 1984</a>, p. 97."), "{page}");
+    assert!(page.contains("Literate Programming"));
+    assert!(!page.contains("[@knuth1984"));
+}
+
+#[test]
+fn aliases_redirect_to_the_post() {
+    let tmp = TempDir::new().expect("tempdir");
+    let md = "---\ntitle: Moved\nctime: 2025-01-01\naliases: [old/moved.html, 2019/moved/, ../escape.html]\n---\nHere now.\n";
+    write_md(tmp.path(), Path::new("moved.md"), md).unwrap();
+
+    build_at(tmp.path()).unwrap();
+
+    let url = format!("{}/{POSTS_DIR}/moved.html", site_meta().base_url);
+    for alias in ["old/moved.html", "2019/moved/index.html"] {
+        let page = read_public(&tmp, Path::new(alias));
+        assert!(
+            page.contains(&format!(r#"<link rel="canonical" href="{url}">"#)),
+            "{page}"
+        );
+        assert!(page.contains("http-equiv=\"refresh\""));
+    }
+    assert!(!tmp.path().join("escape.html").exists());
+}
+
+#[test]
+fn colliding_aliases_are_reported() {
+    let tmp = TempDir::new().expect("tempdir");
+    for name in ["first", "second"] {
+        let md =
+            format!("---\ntitle: {name}\nctime: 2025-01-01\naliases: [old.html]\n---\nText.\n");
+        write_md(tmp.path(), Path::new(&format!("{name}.md")), &md).unwrap();
+    }
+
+    build_at(tmp.path()).unwrap();
+    assert!(read_public(&tmp, Path::new("old.html")).contains("http-equiv=\"refresh\""));
+    let strict = BuildOptions {
+        strict: true,
+        ..BuildOptions::default()
+    };
+    let err = build_with(tmp.path(), strict).unwrap_err();
+    assert!(err.to_string().contains("1 warning(s)"), "{err}");
+}
+
+#[test]
+fn includes_are_spliced_in_and_reported() {
+    let tmp = TempDir::new().expect("tempdir");
+    let snippets = tmp.path().join("snippets");
+    fs::create_dir_all(&snippets).unwrap();
+    fs::write(snippets.join("setup.md"), "Run `make` first.\n").unwrap();
+
+    let md = "---\ntitle: Guide\nctime: 2025-01-01\n---\n{{ include \"snippets/setup.md\" }}\n";
+    write_md(tmp.path(), Path::new("guide.md"), md).unwrap();
+
+    let report = build_at(tmp.path()).unwrap();
+
+    let page = read_public(&tmp, Path::new(POSTS_DIR).join("guide.html"));
+    assert!(page.contains("<code>make</code>"), "{page}");
+    assert_eq!(report.included, BTreeSet::from([snippets.join("setup.md")]));
+}
+
+#[test]
+fn stylesheet_drops_rules_no_page_uses() {
+    let tmp = TempDir::new().expect("tempdir");
+    fs::write(
+        tmp.path().join("style.css"),
+        "body { color: black }\n.definition-term { font-weight: bold }\n.never-used { color: red }\n",
+    )
+    .unwrap();
+    let md = "---\ntitle: Terms\nctime: 2025-01-01\n---\nTerm\n: Meaning.\n";
+    write_md(tmp.path(), Path::new("terms.md"), md).unwrap();
+
+    build_at(tmp.path()).unwrap();
+
+    let css = read_public(&tmp, "style.css");
+    assert!(css.contains("body"));
+    assert!(css.contains(".definition-term"));
+    assert!(!css.contains(".never-used"), "{css}");
+}
+
+#[test]
+fn posts_list_related_posts_by_shared_tags() {
+    let tmp = TempDir::new().expect("tempdir");
+
+    fs::create_dir_all(INPUT_DIR).unwrap();
+    fs::write("style.css", "body { color: black; }").unwrap();
+
+    let docs = [
+        ("first", "2025-01-01", "[rust, web]"),
+        ("second", "2025-02-02", "[rust]"),
+        ("third", "2025-03-03", "[life]"),
+    ];
+    for (title, date, tags) in docs {
+        let md = format!("---\ntitle: {title}\nctime: {date}\ntags: {tags}\n---\nBody\n");
+        write_md(tmp.path(), Path::new(&format!("{title}.md")), &md).unwrap();
+    }
+
+    build_at(tmp.path()).unwrap();
+
+    let first = read_public(&tmp, Path::new(POSTS_DIR).join("first.html"));
+    assert!(first.contains("Related"));
+    assert!(first.contains("second.html"));
+    assert!(!first.contains("third.html"));
+
+    let third = read_public(&tmp, Path::new(POSTS_DIR).join("third.html"));
+    assert!(!third.contains("Related"));
+}
+
+#[test]
+fn recipe_posts_render_a_card_and_json_ld() {
+    let tmp = TempDir::new().expect("tempdir");
+
+    fs::create_dir_all(INPUT_DIR).unwrap();
+    fs::write("style.css", "body { color: black; }").unwrap();
+
+    let md = r#"---
+title: Pancakes
+type: recipe
+ctime: 2025-05-05
+prep_time: 10
+cook_time: PT20M
+servings: 4
+ingredients:
+  - 200 g flour
+  - 2 eggs
+steps:
+  - Mix everything.
+  - Fry in batches.
+---
+Sunday morning staple.
+"#;
+    write_md(tmp.path(), Path::new("pancakes.md"), md).unwrap();
+
+    build_at(tmp.path()).unwrap();
+
+    let page = read_public(&tmp, Path::new(POSTS_DIR).join("pancakes.html"));
+    assert!(page.contains("application/ld+json"));
+    assert!(page.contains(r#""@type":"Recipe""#));
+    assert!(page.contains(r#""prepTime":"PT10M""#));
+    assert!(page.contains("200 g flour"));
+    assert!(page.contains("Fry in batches."));
+    assert!(page.contains("Sunday morning staple."));
+}
+
+#[test]
+fn pages_list_backlinks_from_links_and_wikilinks() {
+    let tmp = TempDir::new().expect("tempdir");
+
+    fs::create_dir_all(INPUT_DIR).unwrap();
+    fs::write("style.css", "body { color: black; }").unwrap();
+
+    let target = "---\ntitle: Target Page\nctime: 2025-01-01\n---\nHello.\n";
+    write_md(tmp.path(), Path::new("target.md"), target).unwrap();
+    let markdown_link = "---\ntitle: Linker\nctime: 2025-02-02\n---\nSee [this](target.md).\n";
+    write_md(tmp.path(), Path::new("linker.md"), markdown_link).unwrap();
+    let wikilink =
+        "---\ntitle: Wiki\nctime: 2025-03-03\n---\nSee [[Target Page]] and [[Missing]].\n";
+    write_md(tmp.path(), Path::new("wiki.md"), wikilink).unwrap();
+
+    build_at(tmp.path()).unwrap();
+
+    let target_html = read_public(&tmp, Path::new(POSTS_DIR).join("target.html"));
+    assert!(target_html.contains("Linked from"));
+    assert!(target_html.contains("linker.html"));
+    assert!(target_html.contains("wiki.html"));
+
+    let wiki_html = read_public(&tmp, Path::new(POSTS_DIR).join("wiki.html"));
+    assert!(wiki_html.contains("target.html"));
+    assert!(wiki_html.contains("Missing"));
+    assert!(!wiki_html.contains("Linked from"));
+}
+
+#[test]
+fn projects_page_is_built_from_yaml_and_markdown() {
+    let tmp = TempDir::new().expect("tempdir");
+
+    fs::create_dir_all(INPUT_DIR).unwrap();
+    fs::write("style.css", "body { color: black; }").unwrap();
+    write_md(
+        tmp.path(),
+        Path::new("post.md"),
+        "---\ntitle: Post\n---\nBody\n",
+    )
+    .unwrap();
+
+    fs::write(
+        tmp.path().join("projects.yml"),
+        "- name: Big Thing\n  description: Does big things.\n  status: active\n- name: Small Thing\n",
+    )
+    .unwrap();
+    fs::create_dir_all(tmp.path().join("projects")).unwrap();
+    fs::write(
+        tmp.path().join("projects").join("big-thing.md"),
+        "The *whole* story.\n",
+    )
+    .unwrap();
+
+    build_at(tmp.path()).unwrap();
+
+    let grid = read_public(&tmp, Path::new("projects").join("index.html"));
+    assert!(grid.contains("big-thing.html"));
+    assert!(grid.contains("Does big things."));
+    assert!(grid.contains("Small Thing"));
+    assert!(!grid.contains("small-thing.html"));
+
+    let detail = read_public(&tmp, Path::new("projects").join("big-thing.html"));
+    assert!(detail.contains("<em>whole</em>"));
+    assert!(detail.contains("Big Thing"));
+}
+
 #[test]
 fn asset_prefixes_match_depth() {
     let mut runner = TestRunner::new(Config {
@@ -333,6 +819,28 @@ fn feeds_are_emitted_and_sorted_with_absolute_links() {
         .map(|c| c.term())
         .collect();
     assert!(atom_cats.contains(&"rust"));
+
+    // JSON Feed assertions
+    let json = read_public(&tmp, "feed.json");
+    let parsed: serde_json::Value = serde_json::from_str(&json).expect("parse json feed");
+    assert_eq!(parsed["version"], "https://jsonfeed.org/version/1.1");
+    let items = parsed["items"].as_array().expect("json feed items");
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0]["title"], "Newer");
+    assert_eq!(items[0]["summary"], "Summary here");
+    assert_eq!(items[0]["tags"][0], "rust");
+    assert!(
+        items[0]["date_published"]
+            .as_str()
+            .unwrap()
+            .starts_with("2025-01-01")
+    );
+    assert!(
+        items[0]["content_html"]
+            .as_str()
+            .unwrap()
+            .contains("<p>Body</p>")
+    );
 }
 
 #[test]
@@ -436,6 +944,67 @@ Body
     assert!(html.contains("rel=canonical"));
 }
 
+#[test]
+fn titles_follow_the_template_unless_overridden() {
+    let tmp = TempDir::new().expect("tempdir");
+
+    fs::create_dir_all(INPUT_DIR).unwrap();
+    fs::write("style.css", "body { color: black; }").unwrap();
+    let plain = "---\ntitle: Plain\nctime: 2025-01-01\n---\nBody\n";
+    write_md(tmp.path(), Path::new("plain.md"), plain).unwrap();
+    let custom = "---\ntitle: Custom\nseo_title: A Custom Title\nctime: 2025-01-02\n---\nBody\n";
+    write_md(tmp.path(), Path::new("custom.md"), custom).unwrap();
+
+    build_at(tmp.path()).unwrap();
+
+    let site = site_meta();
+    let plain = read_public(&tmp, Path::new(POSTS_DIR).join("plain.html"));
+    let title = site.page_title("Plain");
+    assert_ne!(title, "Plain");
+    assert!(plain.contains(&format!("<title>\n{title}\n</title>")));
+    assert!(plain.contains("<h1>Plain</h1>"));
+
+    let custom = read_public(&tmp, Path::new(POSTS_DIR).join("custom.html"));
+    assert!(custom.contains("<title>\nA Custom Title\n</title>"));
+    assert!(custom.contains(r#"content="A Custom Title"property=og:title"#));
+
+    let index = read_public(&tmp, "index.html");
+    assert!(index.contains(&format!("<title>{}</title>", site.title)));
+}
+
+#[test]
+fn local_social_images_carry_their_dimensions_and_alt() {
+    let tmp = TempDir::new().expect("tempdir");
+
+    fs::create_dir_all(INPUT_DIR).unwrap();
+    fs::write("style.css", "body { color: black; }").unwrap();
+    // PNG signature and IHDR chunk: enough for the size probe.
+    let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+    png.extend_from_slice(&1200u32.to_be_bytes());
+    png.extend_from_slice(&630u32.to_be_bytes());
+    png.extend_from_slice(&[8, 6, 0, 0, 0]);
+    fs::create_dir_all("images").unwrap();
+    fs::write("images/card.png", png).unwrap();
+
+    let md = r#"---
+title: Sized Card
+ctime: 2025-01-01
+image: images/card.png
+image_alt: A "sized" card
+---
+Body
+"#;
+    write_md(tmp.path(), Path::new("sized.md"), md).unwrap();
+
+    build_at(tmp.path()).unwrap();
+
+    let html = read_public(&tmp, Path::new(POSTS_DIR).join("sized.html"));
+    assert!(html.contains("content=1200 property=og:image:width"));
+    assert!(html.contains("content=630 property=og:image:height"));
+    assert!(html.contains(r#"content='A "sized" card'property=og:image:alt"#));
+    assert!(html.contains(r#"content='A "sized" card'name=twitter:image:alt"#));
+}
+
 #[test]
 fn default_social_image_is_used_when_frontmatter_is_absent() {
     let tmp = TempDir::new().expect("tempdir");
@@ -491,3 +1060,673 @@ Body
     assert!(html.contains(&format!("{base}/index.html")));
     assert!(html.contains("Index"));
 }
+
+#[test]
+fn search_index_and_page_are_emitted() {
+    let tmp = TempDir::new().expect("tempdir");
+
+    let md = r#"---
+title: Findable
+ctime: 2025-03-03
+---
+Needle in a haystack.
+"#;
+    write_md(tmp.path(), Path::new("findable.md"), md).unwrap();
+
+    build_at(tmp.path()).unwrap();
+
+    let index = read_public(&tmp, Path::new("search").join("index.json"));
+    assert!(index.contains("Findable"));
+    assert!(index.contains("Needle in a haystack."));
+    assert!(index.contains("posts/findable.html"));
+
+    let page = read_public(&tmp, Path::new("search").join("index.html"));
+    assert!(page.contains("search-input"));
+    assert!(page.contains("index.json"));
+}
+
+#[test]
+fn content_roots_merge_under_their_prefixes() {
+    let tmp = TempDir::new().expect("tempdir");
+    write_md(tmp.path(), Path::new("essay.md"), "# Essay\n").unwrap();
+    let notes = tmp.path().join("elsewhere").join("notes");
+    fs::create_dir_all(notes.join("daily")).unwrap();
+    fs::write(notes.join("daily").join("today.md"), "# Today\n").unwrap();
+
+    let mut ctx = BuildCtx::load_at(tmp.path()).unwrap();
+    ctx.content_roots.push(SourceRoot {
+        dir: notes.clone(),
+        prefix: PathBuf::from("garden"),
+        ignore: IgnoreRules::default(),
+    });
+    let sources = discover_sources(&ctx).unwrap();
+    let parsed = parse_sources(&ctx, sources, &mut BTreeSet::new()).unwrap();
+    let mut rel: Vec<PathBuf> = parsed.into_iter().map(|(rel, _)| rel).collect();
+    rel.sort();
+
+    assert_eq!(
+        rel,
+        [
+            PathBuf::from("essay.md"),
+            Path::new("garden").join("daily").join("today.md"),
+        ]
+    );
+
+    // A second root publishing over the first is refused.
+    fs::create_dir_all(notes.join("garden")).unwrap();
+    write_md(tmp.path(), &Path::new("garden").join("clash.md"), "x").unwrap();
+    fs::write(notes.join("clash.md"), "y").unwrap();
+    let sources = discover_sources(&ctx).unwrap();
+    let err = parse_sources(&ctx, sources, &mut BTreeSet::new()).unwrap_err();
+    assert!(
+        err.to_string().contains("would both be published as"),
+        "{err}"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn discovery_skips_ignored_files_and_symlink_loops() {
+    let tmp = TempDir::new().expect("tempdir");
+    write_md(tmp.path(), Path::new("kept.md"), "# Kept\n").unwrap();
+    write_md(tmp.path(), Path::new(".#kept.md"), "# Lock file\n").unwrap();
+    write_md(tmp.path(), &Path::new("drafts").join("wip.md"), "# WIP\n").unwrap();
+    write_md(
+        tmp.path(),
+        &Path::new("shared").join("linked.md"),
+        "# Linked\n",
+    )
+    .unwrap();
+    let contents = tmp.path().join(INPUT_DIR);
+    fs::write(contents.join(".ssgignore"), "drafts/\n").unwrap();
+    std::os::unix::fs::symlink(contents.join("shared"), contents.join("alias")).unwrap();
+    std::os::unix::fs::symlink(&contents, contents.join("shared").join("loop")).unwrap();
+
+    let ctx = BuildCtx::load_at(tmp.path()).unwrap();
+    let sources = discover_sources(&ctx).unwrap();
+    let parsed = parse_sources(&ctx, sources, &mut BTreeSet::new()).unwrap();
+    let mut rel: Vec<PathBuf> = parsed.into_iter().map(|(rel, _)| rel).collect();
+    rel.sort();
+
+    assert_eq!(
+        rel,
+        [
+            Path::new("alias").join("linked.md"),
+            PathBuf::from("kept.md"),
+            Path::new("shared").join("linked.md"),
+        ]
+    );
+    let loops: Vec<PathBuf> = ctx
+        .diagnostics
+        .take()
+        .into_iter()
+        .filter(|d| d.message == "symlink loop skipped")
+        .map(|d| d.document.to_path_buf())
+        .collect();
+    assert!(
+        loops.contains(&Path::new("shared").join("loop")),
+        "{loops:?}"
+    );
+}
+
+#[test]
+fn not_found_page_is_built_from_its_source_or_a_default() {
+    let tmp = TempDir::new().expect("tempdir");
+    write_md(
+        tmp.path(),
+        Path::new("post.md"),
+        "---\ntitle: Post\nctime: 2025-01-01\n---\nHi.\n",
+    )
+    .unwrap();
+
+    build_at(tmp.path()).unwrap();
+    let page = read_public(&tmp, "404.html");
+    assert!(page.contains("Page not found"), "{page}");
+    assert!(
+        page.contains(r#"href=/style.css"#) || page.contains(r#"href="/style.css""#),
+        "{page}"
+    );
+
+    write_md(
+        tmp.path(),
+        Path::new(NOT_FOUND_PAGE),
+        "---\ntitle: Lost?\ndescription: Nothing here\n---\nTry the *archive*.\n",
+    )
+    .unwrap();
+    build_at(tmp.path()).unwrap();
+
+    let page = read_public(&tmp, "404.html");
+    assert!(page.contains("<em>archive</em>"), "{page}");
+    assert!(page.contains("Nothing here"));
+    assert!(!public_path(&tmp, Path::new(POSTS_DIR).join("404.html")).exists());
+}
+
+#[test]
+fn report_lists_every_page_with_its_sizes_and_needs() {
+    let tmp = TempDir::new().expect("tempdir");
+    write_md(
+        tmp.path(),
+        Path::new("math.md"),
+        "---\ntitle: Math\nctime: 2025-01-01\n---\nEuler: $e^{i\\pi} = -1$.\n\n```rust\nfn main() {}\n```\n",
+    )
+    .unwrap();
+    write_md(
+        tmp.path(),
+        Path::new("plain.md"),
+        "---\ntitle: Plain\nctime: 2025-01-02\n---\nNo formulas.\n",
+    )
+    .unwrap();
+
+    let report = build_at(tmp.path()).unwrap();
+
+    let paths: Vec<&Path> = report.pages.iter().map(|p| p.path.as_path()).collect();
+    assert!(paths.contains(&Path::new("index.html")), "{paths:?}");
+    assert!(paths.contains(&Path::new("404.html")), "{paths:?}");
+    assert!(paths.is_sorted());
+    let post = |name: &str| {
+        report
+            .pages
+            .iter()
+            .find(|p| p.path.starts_with(POSTS_DIR) && p.path.to_string_lossy().contains(name))
+            .unwrap_or_else(|| panic!("no {name} in {paths:?}"))
+    };
+    let math = post("math");
+    assert!(math.meta.katex && math.meta.highlighted, "{math:?}");
+    assert!(!post("plain").meta.katex && !post("plain").meta.highlighted);
+
+    let written = fs::metadata(public_path(&tmp, &math.path)).unwrap().len();
+    assert_eq!(math.sizes.raw, written);
+    assert!(math.sizes.gzip > 0 && math.sizes.brotli > 0);
+    assert!(math.sizes.brotli < math.sizes.raw);
+}
+
+#[test]
+fn standalone_pages_are_published_at_the_root_and_listed_nowhere() {
+    let tmp = TempDir::new().expect("tempdir");
+    write_md(
+        tmp.path(),
+        &Path::new(PAGES_DIR).join("about.md"),
+        "---\ntitle: About me\ntags: [meta]\n---\nHello.\n",
+    )
+    .unwrap();
+    write_md(
+        tmp.path(),
+        Path::new("colophon.md"),
+        "---\ntitle: Colophon\ntype: page\n---\nBuilt with ssg.\n",
+    )
+    .unwrap();
+    write_md(
+        tmp.path(),
+        Path::new("post.md"),
+        "---\ntitle: A post\nctime: 2025-01-01\n---\nSee [about](@/pages/about.md).\n",
+    )
+    .unwrap();
+
+    build_at(tmp.path()).unwrap();
+
+    assert!(read_public(&tmp, "about.html").contains("Hello."));
+    assert!(read_public(&tmp, "colophon.html").contains("Built with ssg."));
+    assert!(!public_path(&tmp, Path::new(POSTS_DIR).join("colophon.html")).exists());
+    assert!(!public_path(&tmp, Path::new(TAGS_DIR).join("meta.html")).exists());
+    let post = read_public(&tmp, Path::new(POSTS_DIR).join("post.html"));
+    assert!(post.contains("../about.html"), "{post}");
+
+    let index = read_public(&tmp, "index.html");
+    assert!(index.contains("A post"));
+    assert!(
+        !index.contains("About me") && !index.contains("Colophon"),
+        "{index}"
+    );
+    let rss = read_public(&tmp, RSS_FILE);
+    assert!(
+        !rss.contains("About me") && !rss.contains("Colophon"),
+        "{rss}"
+    );
+
+    write_md(
+        tmp.path(),
+        &Path::new(PAGES_DIR).join("index.md"),
+        "# Home\n",
+    )
+    .unwrap();
+    let err = build_at(tmp.path()).unwrap_err();
+    assert!(
+        err.to_string().contains("which the build generates"),
+        "{err}"
+    );
+}
+
+#[test]
+fn todos_are_cut_from_pages_and_fatal_under_strict_todos() {
+    let tmp = TempDir::new().expect("tempdir");
+    write_md(
+        tmp.path(),
+        Path::new("draft.md"),
+        "---\ntitle: Draft\nctime: 2025-01-01\n---\nAlmost there.\n\nTODO: write the conclusion\n",
+    )
+    .unwrap();
+
+    build_at(tmp.path()).unwrap();
+    let page = read_public(&tmp, Path::new(POSTS_DIR).join("draft.html"));
+    assert!(page.contains("Almost there."));
+    assert!(!page.contains("conclusion"), "{page}");
+
+    let strict = BuildOptions {
+        strict_todos: true,
+        ..BuildOptions::default()
+    };
+    let err = build_with(tmp.path(), strict).unwrap_err();
+    assert!(err.to_string().contains("1 TODO marker"), "{err}");
+}
+
+#[test]
+fn fragments_are_embedded_across_posts_and_their_sources_reported() {
+    let tmp = TempDir::new().expect("tempdir");
+    write_md(
+        tmp.path(),
+        Path::new("about.md"),
+        "---\ntitle: About\nctime: 2025-01-01\n---\n::: fragment bio\nI write about *compilers*.\n:::\n",
+    )
+    .unwrap();
+    write_md(
+        tmp.path(),
+        Path::new("post.md"),
+        "---\ntitle: Post\nctime: 2025-01-02\n---\nBody.\n\n{{ fragment \"bio\" }}\n",
+    )
+    .unwrap();
+
+    let report = build_at(tmp.path()).unwrap();
+
+    for page in ["about.html", "post.html"] {
+        let html = read_public(&tmp, Path::new(POSTS_DIR).join(page));
+        assert!(html.contains("I write about <em>compilers</em>."), "{html}");
+        assert!(!html.contains(":::") && !html.contains("{{"), "{html}");
+    }
+    assert_eq!(
+        report.fragment_sources,
+        BTreeMap::from([(
+            PathBuf::from("post.md"),
+            BTreeSet::from([PathBuf::from("about.md")])
+        )])
+    );
+}
+
+#[test]
+fn authors_get_a_byline_a_page_and_feeds() {
+    let tmp = TempDir::new().expect("tempdir");
+    fs::write(
+        tmp.path().join(AUTHORS_FILE),
+        "alice:\n  name: Alice Liddell\n  bio: Writes about compilers.\n  url: https://alice.example\n",
+    )
+    .unwrap();
+    write_md(
+        tmp.path(),
+        Path::new("guest.md"),
+        "---\ntitle: Guest post\nctime: 2025-01-01\nauthors: [alice, bob]\n---\nHi.\n",
+    )
+    .unwrap();
+    write_md(
+        tmp.path(),
+        Path::new("solo.md"),
+        "---\ntitle: Solo post\nctime: 2025-01-02\n---\nHi.\n",
+    )
+    .unwrap();
+
+    build_at(tmp.path()).unwrap();
+
+    let post = read_public(&tmp, Path::new(POSTS_DIR).join("guest.html"));
+    assert!(post.contains("class=byline"), "{post}");
+    assert!(post.contains("../authors/alice.html"), "{post}");
+    assert!(
+        post.contains("Alice Liddell") && post.contains("bob"),
+        "{post}"
+    );
+    let solo = read_public(&tmp, Path::new(POSTS_DIR).join("solo.html"));
+    assert!(!solo.contains("class=byline"), "{solo}");
+
+    let page = read_public(&tmp, Path::new(AUTHORS_DIR).join("alice.html"));
+    assert!(page.contains("h-card"), "{page}");
+    assert!(page.contains("Writes about compilers."), "{page}");
+    assert!(
+        page.contains("Guest post") && !page.contains("Solo post"),
+        "{page}"
+    );
+    let rss = read_public(&tmp, Path::new(AUTHORS_DIR).join("alice.rss.xml"));
+    assert!(
+        rss.contains("Guest post") && !rss.contains("Solo post"),
+        "{rss}"
+    );
+    // Unlisted authors are still credited, under their key.
+    assert!(public_path(&tmp, Path::new(AUTHORS_DIR).join("bob.html")).exists());
+}
+
+#[test]
+fn series_get_a_landing_page_and_part_navigation() {
+    let tmp = TempDir::new().expect("tempdir");
+    for (name, part, date) in [("intro", 1, "2025-01-05"), ("next", 2, "2025-01-01")] {
+        write_md(
+            tmp.path(),
+            Path::new(&format!("{name}.md")),
+            &format!(
+                "---\ntitle: Parsers {part}\nctime: {date}\nseries: Writing a parser\nseries_part: {part}\n---\nPart body.\n"
+            ),
+        )
+        .unwrap();
+    }
+
+    build_at(tmp.path()).unwrap();
+
+    let first = read_public(&tmp, Path::new(POSTS_DIR).join("intro.html"));
+    assert!(first.contains("Part 1 of 2"), "{first}");
+    assert!(first.contains("../series/writing-a-parser.html"), "{first}");
+    assert!(first.contains("Parsers 2 →"), "{first}");
+    let second = read_public(&tmp, Path::new(POSTS_DIR).join("next.html"));
+    assert!(
+        second.contains("Part 2 of 2") && second.contains("← Parsers 1"),
+        "{second}"
+    );
+
+    let landing = read_public(&tmp, Path::new(SERIES_DIR).join("writing-a-parser.html"));
+    let (one, two) = (
+        landing.find("Parsers 1").expect("part 1 listed"),
+        landing.find("Parsers 2").expect("part 2 listed"),
+    );
+    assert!(one < two, "{landing}");
+}
+
+#[test]
+fn archive_pages_list_each_year_and_month() {
+    let tmp = TempDir::new().expect("tempdir");
+    for (name, date) in [
+        ("spring", "2024-03-10"),
+        ("winter", "2024-01-05"),
+        ("older", "2023-06-01"),
+    ] {
+        write_md(
+            tmp.path(),
+            Path::new(&format!("{name}.md")),
+            &format!("---\ntitle: Post {name}\nctime: {date}\n---\nBody.\n"),
+        )
+        .unwrap();
+    }
+
+    build_at(tmp.path()).unwrap();
+
+    let year = read_public(&tmp, Path::new(ARCHIVE_DIR).join("2024").join("index.html"));
+    assert!(
+        year.contains("Post spring") && year.contains("Post winter"),
+        "{year}"
+    );
+    assert!(!year.contains("Post older"), "{year}");
+    assert!(year.contains("03/index.html"), "{year}");
+    let month = read_public(
+        &tmp,
+        Path::new(ARCHIVE_DIR)
+            .join("2024")
+            .join("03")
+            .join("index.html"),
+    );
+    assert!(
+        month.contains("Post spring") && !month.contains("Post winter"),
+        "{month}"
+    );
+
+    let index = read_public(&tmp, "index.html");
+    assert!(index.contains("archive/2023/index.html"), "{index}");
+}
+
+#[test]
+fn posts_get_keywords_and_content_suggestions_and_the_site_a_vocabulary() {
+    let tmp = TempDir::new().expect("tempdir");
+    for (name, body) in [
+        ("parsers", "Recursive descent parsers handle grammars well."),
+        ("descent", "More on recursive descent parsers and grammars."),
+        ("garden", "Tomatoes grow slowly in a shaded garden."),
+    ] {
+        write_md(
+            tmp.path(),
+            Path::new(&format!("{name}.md")),
+            &format!("---\ntitle: Post {name}\nctime: 2025-01-01\n---\n{body}\n"),
+        )
+        .unwrap();
+    }
+
+    build_at(tmp.path()).unwrap();
+
+    let post = read_public(&tmp, Path::new(POSTS_DIR).join("parsers.html"));
+    assert!(post.contains("name=keywords"), "{post}");
+    assert!(post.contains("<h2>Similar</h2>"), "{post}");
+    assert!(
+        post.contains("Post descent") && !post.contains("Post garden"),
+        "{post}"
+    );
+
+    let vocabulary = read_public(&tmp, Path::new(VOCABULARY_DIR).join("index.html"));
+    assert!(vocabulary.contains("parsers"), "{vocabulary}");
+    assert!(vocabulary.contains("Post garden"), "{vocabulary}");
+}
+
+#[test]
+fn expired_posts_are_archived_and_listed_nowhere() {
+    let tmp = TempDir::new().expect("tempdir");
+    write_md(
+        tmp.path(),
+        Path::new("sale.md"),
+        "---\ntitle: Spring sale\nctime: 1999-12-01\nexpires: 2000-01-01\ntags: [news]\n---\nHalf off.\n",
+    )
+    .unwrap();
+    write_md(
+        tmp.path(),
+        Path::new("talk.md"),
+        "---\ntitle: Upcoming talk\nctime: 2025-01-01\nexpires: 2999-12-31\ntags: [news]\n---\nCome along.\n",
+    )
+    .unwrap();
+
+    build_at(tmp.path()).unwrap();
+
+    let sale = read_public(&tmp, Path::new(POSTS_DIR).join("sale.html"));
+    assert!(
+        sale.contains("Half off.") && sale.contains("class=expired"),
+        "{sale}"
+    );
+    let talk = read_public(&tmp, Path::new(POSTS_DIR).join("talk.html"));
+    assert!(!talk.contains("class=expired"), "{talk}");
+
+    for listing in [
+        read_public(&tmp, "index.html"),
+        read_public(&tmp, RSS_FILE),
+        read_public(&tmp, Path::new(TAGS_DIR).join("news.html")),
+    ] {
+        assert!(listing.contains("Upcoming talk"), "{listing}");
+        assert!(!listing.contains("Spring sale"), "{listing}");
+    }
+}
+
+#[test]
+fn tags_index_counts_posts_and_is_linked_from_posts() {
+    let tmp = TempDir::new().expect("tempdir");
+    for (name, tags) in [("a", "[rust, web]"), ("b", "[rust]")] {
+        write_md(
+            tmp.path(),
+            Path::new(&format!("{name}.md")),
+            &format!("---\ntitle: {name}\nctime: 2025-01-01\ntags: {tags}\n---\nBody.\n"),
+        )
+        .unwrap();
+    }
+
+    build_at(tmp.path()).unwrap();
+
+    let index = read_public(&tmp, Path::new(TAGS_DIR).join("index.html"));
+    assert!(
+        index.contains("rust.html") && index.contains("web.html"),
+        "{index}"
+    );
+    assert!(index.contains("<span class=count>2</span>"), "{index}");
+    let post = read_public(&tmp, Path::new(POSTS_DIR).join("a.html"));
+    assert!(post.contains("../tags/index.html"), "{post}");
+
+    write_md(
+        tmp.path(),
+        Path::new("c.md"),
+        "---\ntitle: c\ntags: [index]\n---\nBody.\n",
+    )
+    .unwrap();
+    let err = build_at(tmp.path()).unwrap_err();
+    assert!(
+        err.to_string().contains("would replace the tags index"),
+        "{err}"
+    );
+}
+
+#[test]
+fn text_macros_expand_in_posts() {
+    let tmp = TempDir::new().expect("tempdir");
+    write_md(
+        tmp.path(),
+        Path::new("hello.md"),
+        "---\ntitle: Hello\n---\nWelcome to @@site@@, says @@nobody@@.\n\n`@@site@@`\n",
+    )
+    .unwrap();
+
+    build_at(tmp.path()).unwrap();
+
+    let html = read_public(&tmp, Path::new(POSTS_DIR).join("hello.html"));
+    assert!(
+        html.contains(&format!("Welcome to {SITE_TITLE}, says @@nobody@@.")),
+        "{html}"
+    );
+    assert!(html.contains("<code>@@site@@</code>"), "{html}");
+}
+
+#[test]
+fn tags_file_titles_and_describes_tag_pages() {
+    let tmp = TempDir::new().expect("tempdir");
+    fs::write(
+        tmp.path().join(TAGS_FILE),
+        "rust:\n  title: The Rust language\n  description: Notes on writing Rust.\n",
+    )
+    .unwrap();
+    write_md(
+        tmp.path(),
+        Path::new("a.md"),
+        "---\ntitle: a\nctime: 2025-01-01\ntags: [rust, web]\n---\nBody.\n",
+    )
+    .unwrap();
+
+    build_at(tmp.path()).unwrap();
+
+    let rust = read_public(&tmp, Path::new(TAGS_DIR).join("rust.html"));
+    assert!(rust.contains("<h1>The Rust language</h1>"), "{rust}");
+    assert!(
+        rust.contains(r#"<meta content="Notes on writing Rust."name=description>"#),
+        "{rust}"
+    );
+    assert!(
+        rust.contains("<p class=tag-description>Notes on writing Rust."),
+        "{rust}"
+    );
+    let feed = read_public(&tmp, Path::new(TAGS_DIR).join("rust.rss.xml"));
+    assert!(feed.contains("Notes on writing Rust."), "{feed}");
+    let web = read_public(&tmp, Path::new(TAGS_DIR).join("web.html"));
+    assert!(web.contains("Posts tagged web"), "{web}");
+}
+
+#[test]
+fn future_posts_wait_for_their_date_unless_asked_for() {
+    let tmp = TempDir::new().expect("tempdir");
+    write_md(
+        tmp.path(),
+        Path::new("out.md"),
+        "---\ntitle: Already out\nctime: 2025-01-01\n---\nHello.\n",
+    )
+    .unwrap();
+    write_md(
+        tmp.path(),
+        Path::new("queued.md"),
+        "---\ntitle: Queued post\nctime: 2999-01-01\n---\nLater.\n",
+    )
+    .unwrap();
+    let queued = tmp
+        .path()
+        .join(OUTPUT_DIR)
+        .join(POSTS_DIR)
+        .join("queued.html");
+
+    build_at(tmp.path()).unwrap();
+
+    assert!(!queued.exists());
+    let index = read_public(&tmp, "index.html");
+    assert!(
+        index.contains("Already out") && !index.contains("Queued post"),
+        "{index}"
+    );
+
+    let future = BuildOptions {
+        future: true,
+        ..BuildOptions::default()
+    };
+    build_with(tmp.path(), future).unwrap();
+
+    assert!(queued.exists());
+    assert!(read_public(&tmp, "index.html").contains("Queued post"));
+}
+
+#[test]
+fn raw_html_links_and_images_are_treated_like_markdown_ones() {
+    let tmp = TempDir::new().expect("tempdir");
+    write_md(
+        tmp.path(),
+        Path::new("target.md"),
+        "---\ntitle: Target Page\nctime: 2025-01-01\n---\nHello.\n",
+    )
+    .unwrap();
+    write_md(
+        tmp.path(),
+        Path::new("raw.md"),
+        "---\ntitle: Raw\nctime: 2025-02-02\n---\n<figure><img src=\"photo.jpg\" alt=\"\"><figcaption><a href=\"@/target\">Target</a></figcaption></figure>\n",
+    )
+    .unwrap();
+
+    build_at(tmp.path()).unwrap();
+
+    let raw = read_public(&tmp, Path::new(POSTS_DIR).join("raw.html"));
+    assert!(raw.contains("href=../posts/target.html"), "{raw}");
+    assert!(
+        raw.contains("fetchpriority=high") && raw.contains("decoding=async"),
+        "{raw}"
+    );
+    let target = read_public(&tmp, Path::new(POSTS_DIR).join("target.html"));
+    assert!(
+        target.contains("Linked from") && target.contains("raw.html"),
+        "{target}"
+    );
+}
+
+#[test]
+fn diagnostics_name_sources_as_checked_in() {
+    let tmp = TempDir::new().expect("tempdir");
+    let root = tmp.path();
+    let notes = root.join("notes");
+    fs::create_dir_all(root.join(INPUT_DIR).join(POSTS_DIR)).unwrap();
+    fs::create_dir_all(&notes).unwrap();
+    fs::write(root.join(INPUT_DIR).join(POSTS_DIR).join("a.md"), "# A").unwrap();
+    fs::write(notes.join("b.md"), "# B").unwrap();
+    let source_dirs = [
+        (PathBuf::new(), root.join(INPUT_DIR)),
+        (PathBuf::from("garden"), notes),
+    ];
+
+    let posts_a = Path::new(POSTS_DIR).join("a.md");
+    assert_eq!(
+        checked_in_path(root, &source_dirs, &posts_a),
+        Path::new(INPUT_DIR).join(&posts_a)
+    );
+    assert_eq!(
+        checked_in_path(root, &source_dirs, Path::new("garden/b.md")),
+        Path::new("notes/b.md")
+    );
+    assert_eq!(
+        checked_in_path(root, &source_dirs, Path::new("style.css")),
+        Path::new("style.css")
+    );
+}
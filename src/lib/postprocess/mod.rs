@@ -0,0 +1,191 @@
+//! HTML-level post-processing.
+//!
+//! Some rewrites (head dedupe, link rewriting, sanitisation) are easier on the
+//! finished HTML than on Markdown events. Every page, after its template is
+//! applied and before it is minified, is streamed once through `lol_html`
+//! with the handlers of all registered `PostProcessor`s.
+use std::{borrow::Cow, cell::Cell, path::Path, rc::Rc, sync::Arc};
+
+use color_eyre::eyre::eyre;
+use lol_html::{
+    DocumentContentHandlers, ElementContentHandlers, HandlerResult, RewriteStrSettings, Selector,
+    html_content::{ContentType, DocumentEnd, Element, TextChunk},
+    rewrite_str,
+};
+
+/// The page being rewritten.
+#[derive(Clone, Copy, Debug)]
+pub struct PageInfo<'a> {
+    /// Output path relative to the site root, e.g. `posts/foo.html`.
+    pub rel_path: &'a Path,
+    /// Relative prefix from the page back to the site root, e.g. `../`.
+    pub prefix: &'a str,
+}
+
+/// A streaming rewrite applied to every emitted HTML page.
+pub trait PostProcessor: Send + Sync {
+    /// Register this processor's handlers for one page. Handlers run in
+    /// document order, interleaved with those of other processors.
+    fn register<'h>(
+        &'h self,
+        page: PageInfo<'h>,
+        handlers: &mut Handlers<'h>,
+    ) -> color_eyre::Result<()>;
+}
+
+/// Handlers collected from the processors for a single rewrite pass.
+#[derive(Default)]
+pub struct Handlers<'h> {
+    element: Vec<(Cow<'static, Selector>, ElementContentHandlers<'h>)>,
+    document: Vec<DocumentContentHandlers<'h>>,
+}
+
+impl<'h> Handlers<'h> {
+    /// Call `handler` on every element matching the CSS `selector`.
+    pub fn element(
+        &mut self,
+        selector: &str,
+        handler: impl FnMut(&mut Element<'_, '_>) -> HandlerResult + 'h,
+    ) -> color_eyre::Result<()> {
+        let handlers = ElementContentHandlers::default().element(handler);
+        self.element.push((parse_selector(selector)?, handlers));
+        Ok(())
+    }
+
+    /// Call `handler` on text chunks inside elements matching `selector`.
+    pub fn text(
+        &mut self,
+        selector: &str,
+        handler: impl FnMut(&mut TextChunk<'_>) -> HandlerResult + 'h,
+    ) -> color_eyre::Result<()> {
+        let handlers = ElementContentHandlers::default().text(handler);
+        self.element.push((parse_selector(selector)?, handlers));
+        Ok(())
+    }
+
+    /// Call `handler` once the whole document has been seen, e.g. to append.
+    pub fn end(&mut self, handler: impl FnOnce(&mut DocumentEnd<'_>) -> HandlerResult + 'h) {
+        self.document
+            .push(DocumentContentHandlers::default().end(handler));
+    }
+
+    fn is_empty(&self) -> bool {
+        self.element.is_empty() && self.document.is_empty()
+    }
+}
+
+fn parse_selector(selector: &str) -> color_eyre::Result<Cow<'static, Selector>> {
+    selector
+        .parse()
+        .map(Cow::Owned)
+        .map_err(|e| eyre!("invalid selector `{selector}`: {e}"))
+}
+
+/// The post-processors registered for a build.
+#[derive(Clone, Default)]
+pub struct PostProcessors {
+    processors: Vec<Arc<dyn PostProcessor>>,
+}
+
+impl PostProcessors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a processor; processors register their handlers in this order.
+    pub fn with(mut self, processor: impl PostProcessor + 'static) -> Self {
+        self.processors.push(Arc::new(processor));
+        self
+    }
+
+    /// Rewrite `html` with every processor in one streaming pass.
+    pub fn apply<'a>(&self, html: &'a str, page: PageInfo<'_>) -> color_eyre::Result<Cow<'a, str>> {
+        self.apply_with(html, page, &[])
+    }
+
+    /// Like [`apply`](Self::apply), but also runs page-specific processors
+    /// (e.g. [`Injections`]) in the same pass, after the registered ones.
+    pub fn apply_with<'a>(
+        &self,
+        html: &'a str,
+        page: PageInfo<'_>,
+        local: &[&dyn PostProcessor],
+    ) -> color_eyre::Result<Cow<'a, str>> {
+        let mut handlers = Handlers::default();
+        for processor in &self.processors {
+            processor.register(page, &mut handlers)?;
+        }
+        for processor in local {
+            processor.register(page, &mut handlers)?;
+        }
+        if handlers.is_empty() {
+            return Ok(Cow::Borrowed(html));
+        }
+
+        let settings = RewriteStrSettings {
+            element_content_handlers: handlers.element,
+            document_content_handlers: handlers.document,
+            ..RewriteStrSettings::new()
+        };
+        rewrite_str(html, settings)
+            .map(Cow::Owned)
+            .map_err(|e| eyre!("while post-processing {}: {e}", page.rel_path.display()))
+    }
+}
+
+/// Page-specific fragments spliced into a templated page, so templates stay
+/// oblivious to them and the page is not re-assembled with string formatting.
+#[derive(Clone, Debug, Default)]
+pub struct Injections {
+    /// Appended at the end of `<head>`, e.g. OpenGraph meta.
+    pub head: String,
+    /// Appended to the first `article > section`, or before `</body>` when the
+    /// template has none, e.g. related posts and the index link.
+    pub body_end: String,
+}
+
+impl PostProcessor for Injections {
+    fn register<'h>(
+        &'h self,
+        _page: PageInfo<'h>,
+        handlers: &mut Handlers<'h>,
+    ) -> color_eyre::Result<()> {
+        if !self.head.is_empty() {
+            let mut done = false;
+            handlers.element("head", move |el| {
+                if !std::mem::replace(&mut done, true) {
+                    el.append(&self.head, ContentType::Html);
+                }
+                Ok(())
+            })?;
+        }
+        if !self.body_end.is_empty() {
+            let done = Rc::new(Cell::new(false));
+            let in_section = Rc::clone(&done);
+            handlers.element("article > section", move |el| {
+                if !in_section.replace(true) {
+                    el.append(&self.body_end, ContentType::Html);
+                }
+                Ok(())
+            })?;
+            // End-tag handlers must be `'static`, so they get their own copy.
+            let fallback: Rc<str> = Rc::from(self.body_end.as_str());
+            handlers.element("body", move |el| {
+                let (done, fallback) = (Rc::clone(&done), Rc::clone(&fallback));
+                if let Some(handlers) = el.end_tag_handlers() {
+                    handlers.push(Box::new(move |end| {
+                        if !done.replace(true) {
+                            end.before(&fallback, ContentType::Html);
+                        }
+                        Ok(())
+                    }));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
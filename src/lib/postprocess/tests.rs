@@ -0,0 +1,126 @@
+use std::path::Path;
+
+use lol_html::html_content::ContentType;
+
+use crate::postprocess::{Handlers, Injections, PageInfo, PostProcessor, PostProcessors};
+
+struct MarkExternal;
+
+impl PostProcessor for MarkExternal {
+    fn register<'h>(
+        &'h self,
+        _page: PageInfo<'h>,
+        handlers: &mut Handlers<'h>,
+    ) -> color_eyre::Result<()> {
+        handlers.element(r#"a[href^="http"]"#, |el| {
+            el.set_attribute("rel", "external")?;
+            Ok(())
+        })
+    }
+}
+
+struct Footer;
+
+impl PostProcessor for Footer {
+    fn register<'h>(
+        &'h self,
+        page: PageInfo<'h>,
+        handlers: &mut Handlers<'h>,
+    ) -> color_eyre::Result<()> {
+        let note = format!("<!-- {} -->", page.rel_path.display());
+        handlers.end(move |end| {
+            end.append(&note, ContentType::Html);
+            Ok(())
+        });
+        Ok(())
+    }
+}
+
+fn page() -> PageInfo<'static> {
+    PageInfo {
+        rel_path: Path::new("posts/a.html"),
+        prefix: "../",
+    }
+}
+
+#[test]
+fn processors_share_one_pass() {
+    let processors = PostProcessors::new().with(MarkExternal).with(Footer);
+    let html = r#"<p><a href="https://x.org">x</a> <a href="b.html">b</a></p>"#;
+
+    let out = processors.apply(html, page()).unwrap();
+
+    assert_eq!(
+        out,
+        r#"<p><a href="https://x.org" rel="external">x</a> <a href="b.html">b</a></p><!-- posts/a.html -->"#
+    );
+}
+
+#[test]
+fn no_processors_leaves_html_untouched() {
+    let html = "<p>unchanged</p>";
+
+    let out = PostProcessors::default().apply(html, page()).unwrap();
+
+    assert!(matches!(out, std::borrow::Cow::Borrowed(_)));
+}
+
+#[test]
+fn invalid_selectors_are_reported() {
+    struct Broken;
+    impl PostProcessor for Broken {
+        fn register<'h>(
+            &'h self,
+            _page: PageInfo<'h>,
+            handlers: &mut Handlers<'h>,
+        ) -> color_eyre::Result<()> {
+            handlers.element("a[", |_| Ok(()))
+        }
+    }
+
+    let err = PostProcessors::new()
+        .with(Broken)
+        .apply("<a></a>", page())
+        .unwrap_err();
+
+    assert!(err.to_string().contains("invalid selector"));
+}
+
+#[test]
+fn injections_land_in_head_and_first_section() {
+    let injections = Injections {
+        head: r#"<meta property="og:title" content="A">"#.into(),
+        body_end: "<nav>Index</nav>".into(),
+    };
+    let html = "<html><head><title>A</title></head><body><article><section><p>x</p>\
+                <section>inner</section></section></article></body></html>";
+
+    let out = PostProcessors::new()
+        .apply_with(html, page(), &[&injections])
+        .unwrap();
+
+    assert_eq!(
+        out,
+        "<html><head><title>A</title><meta property=\"og:title\" content=\"A\"></head>\
+         <body><article><section><p>x</p><section>inner</section><nav>Index</nav></section>\
+         </article></body></html>"
+    );
+}
+
+#[test]
+fn injections_fall_back_to_body_end() {
+    let injections = Injections {
+        body_end: "<nav>Index</nav>".into(),
+        ..Injections::default()
+    };
+    let html = "<html><head></head><body><main>x</main></body></html>";
+
+    let out = PostProcessors::new()
+        .apply_with(html, page(), &[&injections])
+        .unwrap();
+
+    assert_eq!(
+        out,
+        "<html><head></head><body><main>x</main><nav>Index</nav></body></html>"
+    );
+}
@@ -0,0 +1,161 @@
+//! `<link rel="preload">` hints for what a page needs before it can paint:
+//! its stylesheet, its first image, and the KaTeX fonts its formulas use.
+//! The same hints are collected into a `_headers` file (the format Netlify
+//! and Cloudflare Pages read) so hosts can send them as `Link` headers
+//! before the page itself arrives.
+use std::{cell::RefCell, collections::BTreeSet};
+
+use lol_html::{HtmlRewriter, Settings, element};
+
+use crate::{backlinks::internal_href, types::Href, utils::escape_attr};
+
+/// KaTeX classes and the font each pulls in, after `katex.css`.
+const KATEX_FONTS: &[(&str, &str)] = &[
+    ("katex", "KaTeX_Main-Regular"),
+    ("mathnormal", "KaTeX_Math-Italic"),
+    ("mathit", "KaTeX_Main-Italic"),
+    ("mathbf", "KaTeX_Main-Bold"),
+    ("boldsymbol", "KaTeX_Math-BoldItalic"),
+    ("amsrm", "KaTeX_AMS-Regular"),
+    ("mathbb", "KaTeX_AMS-Regular"),
+    ("mathcal", "KaTeX_Caligraphic-Regular"),
+    ("mathfrak", "KaTeX_Fraktur-Regular"),
+    ("mathscr", "KaTeX_Script-Regular"),
+    ("mathsf", "KaTeX_SansSerif-Regular"),
+    ("mathtt", "KaTeX_Typewriter-Regular"),
+    ("small-op", "KaTeX_Size1-Regular"),
+    ("large-op", "KaTeX_Size2-Regular"),
+    ("size1", "KaTeX_Size1-Regular"),
+    ("size2", "KaTeX_Size2-Regular"),
+    ("size3", "KaTeX_Size3-Regular"),
+    ("size4", "KaTeX_Size4-Regular"),
+];
+
+/// Something a page should start fetching early. Hrefs are as written in
+/// the page, i.e. relative to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Preload {
+    Style(String),
+    Image {
+        src: String,
+        srcset: Option<String>,
+        sizes: Option<String>,
+    },
+    Font(String),
+}
+
+impl Preload {
+    fn href(&self) -> &str {
+        match self {
+            Self::Style(href) | Self::Font(href) => href,
+            Self::Image { src, .. } => src,
+        }
+    }
+
+    pub fn to_html(&self) -> String {
+        let href = escape_attr(self.href());
+        match self {
+            Self::Style(_) => format!(r#"<link rel="preload" href="{href}" as="style">"#),
+            Self::Image { srcset, sizes, .. } => {
+                let mut link = format!(r#"<link rel="preload" href="{href}" as="image""#);
+                if let Some(srcset) = srcset {
+                    link.push_str(&format!(r#" imagesrcset="{}""#, escape_attr(srcset)));
+                }
+                if let Some(sizes) = sizes {
+                    link.push_str(&format!(r#" imagesizes="{}""#, escape_attr(sizes)));
+                }
+                link.push('>');
+                link
+            }
+            Self::Font(_) => format!(
+                r#"<link rel="preload" href="{href}" as="font" type="font/woff2" crossorigin>"#
+            ),
+        }
+    }
+
+    /// The hint as a `Link` header value for the page at `page`, or `None`
+    /// for resources on other hosts.
+    pub fn link_header(&self, page: &Href) -> Option<String> {
+        let target = internal_href(self.href(), page)?;
+        let kind = match self {
+            Self::Style(_) => "as=style",
+            Self::Image { .. } => "as=image",
+            Self::Font(_) => r#"as=font; type="font/woff2"; crossorigin"#,
+        };
+        Some(format!("</{target}>; rel=preload; {kind}"))
+    }
+}
+
+/// What the page whose body is `body_html` should preload: the stylesheet
+/// at `css_href`, the first image, and, when `katex_fonts` (the directory
+/// holding KaTeX's fonts, ending in `/`) is given, the fonts its formulas
+/// use.
+pub fn page_preloads(css_href: &str, body_html: &str, katex_fonts: Option<&str>) -> Vec<Preload> {
+    let image: RefCell<Option<Preload>> = RefCell::new(None);
+    let fonts: RefCell<BTreeSet<&'static str>> = RefCell::new(BTreeSet::new());
+    let mut handlers = vec![element!("img[src]", |el| {
+        let mut image = image.borrow_mut();
+        if image.is_none()
+            && let Some(src) = el.get_attribute("src")
+        {
+            *image = Some(Preload::Image {
+                src,
+                srcset: el.get_attribute("srcset"),
+                sizes: el.get_attribute("sizes"),
+            });
+        }
+        Ok(())
+    })];
+    if katex_fonts.is_some() {
+        handlers.push(element!(".katex [class], .katex", |el| {
+            let classes = el.get_attribute("class").unwrap_or_default();
+            let mut fonts = fonts.borrow_mut();
+            for class in classes.split_whitespace() {
+                let used = KATEX_FONTS.iter().filter(|(name, _)| *name == class);
+                fonts.extend(used.map(|(_, font)| *font));
+            }
+            Ok(())
+        }));
+    }
+    let mut rewriter = HtmlRewriter::new(
+        Settings {
+            element_content_handlers: handlers,
+            ..Settings::new()
+        },
+        |_: &[u8]| {},
+    );
+    // Only the handlers matter; malformed markup just yields fewer hints.
+    let _ = rewriter
+        .write(body_html.as_bytes())
+        .and_then(|()| rewriter.end());
+
+    let mut preloads = vec![Preload::Style(css_href.to_owned())];
+    preloads.extend(image.into_inner());
+    if let Some(dir) = katex_fonts {
+        preloads.extend(
+            fonts
+                .into_inner()
+                .into_iter()
+                .map(|font| Preload::Font(format!("{dir}{font}.woff2"))),
+        );
+    }
+    preloads
+}
+
+/// A `_headers` file giving each page in `pages` its `Link` headers.
+pub fn headers_manifest<'p>(pages: impl IntoIterator<Item = (&'p Href, &'p [String])>) -> String {
+    let mut manifest = String::new();
+    for (page, links) in pages {
+        if links.is_empty() {
+            continue;
+        }
+        manifest.push_str(&format!("/{page}\n"));
+        for link in links {
+            manifest.push_str(&format!("  Link: {link}\n"));
+        }
+    }
+    manifest
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+
+use crate::{
+    preload::{Preload, headers_manifest, page_preloads},
+    types::{Href, RelPath},
+};
+
+fn href(s: &str) -> Href {
+    Href::from_rel(&RelPath::new(PathBuf::from(s)).unwrap())
+}
+
+#[test]
+fn preloads_cover_the_stylesheet_first_image_and_used_fonts() {
+    let body = concat!(
+        r#"<p><img src="../img/a.jpg" srcset="../img/a-480.jpg 480w" sizes="50vw"><img src="../img/b.jpg"></p>"#,
+        r#"<span class="katex"><span class="mord mathnormal">x</span><span class="mord mathbb">R</span></span>"#,
+    );
+
+    let preloads = page_preloads("../style.css", body, Some("../assets/katex/fonts/"));
+
+    assert_eq!(
+        preloads,
+        [
+            Preload::Style("../style.css".into()),
+            Preload::Image {
+                src: "../img/a.jpg".into(),
+                srcset: Some("../img/a-480.jpg 480w".into()),
+                sizes: Some("50vw".into()),
+            },
+            Preload::Font("../assets/katex/fonts/KaTeX_AMS-Regular.woff2".into()),
+            Preload::Font("../assets/katex/fonts/KaTeX_Main-Regular.woff2".into()),
+            Preload::Font("../assets/katex/fonts/KaTeX_Math-Italic.woff2".into()),
+        ]
+    );
+    assert_eq!(
+        preloads[1].to_html(),
+        r#"<link rel="preload" href="../img/a.jpg" as="image" imagesrcset="../img/a-480.jpg 480w" imagesizes="50vw">"#
+    );
+
+    // Without KaTeX output, classes that happen to match are not fonts.
+    assert_eq!(page_preloads("style.css", body, None).len(), 2);
+}
+
+#[test]
+fn link_headers_are_root_relative_and_skip_other_hosts() {
+    let page = href("posts/a.html");
+    let font = Preload::Font("../assets/katex/fonts/KaTeX_Main-Regular.woff2".into());
+    assert_eq!(
+        font.link_header(&page).unwrap(),
+        r#"</assets/katex/fonts/KaTeX_Main-Regular.woff2>; rel=preload; as=font; type="font/woff2"; crossorigin"#
+    );
+    let remote = Preload::Image {
+        src: "https://cdn.example.com/a.jpg".into(),
+        srcset: None,
+        sizes: None,
+    };
+    assert_eq!(remote.link_header(&page), None);
+
+    let links = vec!["</style.css>; rel=preload; as=style".to_owned()];
+    let manifest = headers_manifest([(&page, links.as_slice()), (&href("empty.html"), &[][..])]);
+    assert_eq!(
+        manifest,
+        "/posts/a.html\n  Link: </style.css>; rel=preload; as=style\n"
+    );
+}
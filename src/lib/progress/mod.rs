@@ -0,0 +1,128 @@
+//! Progress display for `--progress`. A large site can spend several seconds
+//! in one stage with nothing on screen; this draws a bar per pipeline stage
+//! on stderr and, for the stages run on the worker pool, a line per worker
+//! naming the source it is on. It draws nothing unless asked for, on CI, or
+//! when stderr is not a terminal.
+use std::{env, ffi::OsStr, path::Path, time::Duration};
+
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+use crate::config::PROGRESS_WORKER_LINES;
+
+const STAGE_TEMPLATE: &str = "{prefix:>9.bold} [{bar:30}] {pos}/{len} {msg}";
+const WORKER_TEMPLATE: &str = "{prefix:>9.dim} {spinner} {wide_msg}";
+const TICK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Whether to draw progress: when asked for, and not on CI, where nobody
+/// watches and redraws only clutter the log.
+pub fn enabled(requested: bool, ci: Option<&OsStr>) -> bool {
+    requested && ci.is_none_or(|value| value.is_empty() || value == "false")
+}
+
+/// Where a build's stage bars are drawn.
+#[derive(Clone)]
+pub struct Progress {
+    bars: MultiProgress,
+}
+
+impl Progress {
+    /// Progress on stderr if `requested` and [`enabled`] in this environment,
+    /// otherwise a display that is never drawn.
+    pub fn new(requested: bool) -> Self {
+        let target = match enabled(requested, env::var_os("CI").as_deref()) {
+            true => ProgressDrawTarget::stderr(),
+            false => ProgressDrawTarget::hidden(),
+        };
+        Self {
+            bars: MultiProgress::with_draw_target(target),
+        }
+    }
+
+    /// A display that is never drawn.
+    pub fn hidden() -> Self {
+        Self {
+            bars: MultiProgress::with_draw_target(ProgressDrawTarget::hidden()),
+        }
+    }
+
+    /// Start a stage of `len` steps run on the current thread.
+    pub fn stage(&self, name: &str, len: usize) -> Stage {
+        let bar = self.bars.add(ProgressBar::new(len as u64));
+        bar.set_style(style(STAGE_TEMPLATE).progress_chars("=> "));
+        bar.set_prefix(name.to_string());
+        Stage {
+            bar,
+            workers: Vec::new(),
+        }
+    }
+
+    /// Start a stage of `len` steps spread over the worker pool, with a line
+    /// for each worker (up to `PROGRESS_WORKER_LINES`) showing what it is on.
+    pub fn parallel_stage(&self, name: &str, len: usize) -> Stage {
+        let mut stage = self.stage(name, len);
+        let lines = rayon::current_num_threads().clamp(1, PROGRESS_WORKER_LINES.max(1));
+        stage.workers = (0..lines)
+            .map(|worker| {
+                let line = self.bars.add(ProgressBar::new_spinner());
+                line.set_style(style(WORKER_TEMPLATE));
+                line.set_prefix(format!("#{worker}"));
+                line.enable_steady_tick(TICK_INTERVAL);
+                line
+            })
+            .collect();
+        stage
+    }
+}
+
+fn style(template: &str) -> ProgressStyle {
+    ProgressStyle::with_template(template).expect("progress templates are valid")
+}
+
+/// One stage of a build on the display. Finished when dropped.
+pub struct Stage {
+    bar: ProgressBar,
+    workers: Vec<ProgressBar>,
+}
+
+impl Stage {
+    /// Note that the calling worker has started on `source`.
+    pub fn working_on(&self, source: &Path) {
+        if self.workers.is_empty() {
+            return;
+        }
+        let worker = rayon::current_thread_index().unwrap_or(0) % self.workers.len();
+        self.workers[worker].set_message(source.display().to_string());
+    }
+
+    /// Count one step done.
+    pub fn step(&self) {
+        self.bar.inc(1);
+    }
+
+    /// Count `more` steps still to come.
+    pub fn add_steps(&self, more: usize) {
+        self.bar.inc_length(more as u64);
+    }
+
+    /// Say what the stage is doing besides its steps.
+    pub fn note(&self, message: &'static str) {
+        self.bar.set_message(message);
+    }
+
+    /// Steps done so far.
+    pub fn done(&self) -> u64 {
+        self.bar.position()
+    }
+}
+
+impl Drop for Stage {
+    fn drop(&mut self) {
+        for line in &self.workers {
+            line.finish_and_clear();
+        }
+        self.bar.finish_with_message("done");
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,24 @@
+use std::{ffi::OsStr, path::Path};
+
+use crate::progress::{Progress, enabled};
+
+#[test]
+fn progress_is_only_drawn_when_asked_for_and_off_ci() {
+    assert!(enabled(true, None));
+    assert!(enabled(true, Some(OsStr::new("false"))));
+    assert!(!enabled(true, Some(OsStr::new("true"))));
+    assert!(!enabled(true, Some(OsStr::new("1"))));
+    assert!(!enabled(false, None));
+}
+
+#[test]
+fn a_hidden_stage_still_counts_its_steps() {
+    let progress = Progress::hidden();
+    let stage = progress.parallel_stage("render", 2);
+    stage.working_on(Path::new("posts/a.md"));
+    stage.step();
+    stage.add_steps(1);
+    stage.step();
+    stage.note("finishing");
+    assert_eq!(stage.done(), 2);
+}
@@ -0,0 +1,151 @@
+//! Data-driven projects page: `projects.yml` rendered into a portfolio grid,
+//! with optional Markdown detail pages.
+use std::path::{Path, PathBuf};
+
+use color_eyre::Section;
+use gray_matter::{
+    Pod,
+    engine::{Engine, YAML},
+};
+use serde::Deserialize;
+
+use crate::{
+    config::PROJECTS_DIR,
+    utils::{escape_attr, escape_text, slugify},
+};
+
+/// One entry of `projects.yml`.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct Project {
+    pub name: String,
+    pub description: Option<String>,
+    pub repo: Option<String>,
+    /// Homepage or live demo.
+    pub url: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub status: Option<String>,
+    /// Markdown detail page, relative to the site root. Defaults to
+    /// `projects/<slug>.md` when that file exists.
+    pub page: Option<PathBuf>,
+}
+
+impl Project {
+    pub fn slug(&self) -> String {
+        slugify(&self.name)
+    }
+
+    /// Markdown source of the detail page, if there is one.
+    pub fn page_source(&self, root: &Path) -> Option<PathBuf> {
+        let path = match &self.page {
+            Some(page) => root.join(page),
+            None => root
+                .join(PROJECTS_DIR)
+                .join(self.slug())
+                .with_extension("md"),
+        };
+        path.is_file().then_some(path)
+    }
+
+    /// Link to the detail page, relative to the projects directory.
+    pub fn detail_href(&self) -> String {
+        format!("{}.html", self.slug())
+    }
+}
+
+/// Read the projects list; a missing file means no projects page.
+pub fn load_projects(path: &Path) -> color_eyre::Result<Vec<Project>> {
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let raw = std::fs::read_to_string(path)?;
+    parse_projects(&raw).with_note(|| format!("While parsing {}", path.display()))
+}
+
+pub fn parse_projects(raw: &str) -> color_eyre::Result<Vec<Project>> {
+    let pod = YAML::parse(raw)?;
+    if matches!(pod, Pod::Null) {
+        return Ok(Vec::new());
+    }
+    Ok(pod.deserialize()?)
+}
+
+/// Name, status, description, tags, and outbound links shared by the grid
+/// card and the detail page.
+fn project_meta(project: &Project) -> String {
+    let mut html = String::new();
+
+    if let Some(status) = &project.status {
+        html.push_str(&format!(
+            r#"<p class="status status-{}">{}</p>"#,
+            escape_attr(&slugify(status)),
+            escape_text(status)
+        ));
+    }
+    if let Some(desc) = &project.description {
+        html.push_str(&format!("<p>{}</p>", escape_text(desc)));
+    }
+    if !project.tags.is_empty() {
+        let tags = project
+            .tags
+            .iter()
+            .map(|t| format!(r#"<span class="tag">{}</span>"#, escape_text(t)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        html.push_str(&format!(r#"<p class="meta">{tags}</p>"#));
+    }
+
+    let links: Vec<String> = [("Source", &project.repo), ("Website", &project.url)]
+        .into_iter()
+        .filter_map(|(label, href)| {
+            href.as_ref()
+                .map(|h| format!(r#"<a href="{}">{label}</a>"#, escape_attr(h)))
+        })
+        .collect();
+    if !links.is_empty() {
+        html.push_str(&format!(
+            r#"<p class="links">{}</p>"#,
+            links.join(r#"<span class="meta-sep">·</span>"#)
+        ));
+    }
+
+    html
+}
+
+/// Portfolio grid. `has_detail` tells which projects have a detail page.
+pub fn render_projects_grid(projects: &[Project], has_detail: impl Fn(&Project) -> bool) -> String {
+    let mut html = String::from(r#"<ul class="projects">"#);
+    html.push('\n');
+
+    for p in projects {
+        let name = escape_text(&p.name);
+        let heading = if has_detail(p) {
+            format!(r#"<a href="{}">{name}</a>"#, escape_attr(&p.detail_href()))
+        } else {
+            name
+        };
+        html.push_str(&format!(
+            r#"<li class="project" id="{}"><h2>{heading}</h2>{}</li>"#,
+            escape_attr(&p.slug()),
+            project_meta(p)
+        ));
+        html.push('\n');
+    }
+
+    html.push_str("</ul>\n");
+    html
+}
+
+/// Body of a project's detail page: its metadata followed by the rendered
+/// Markdown.
+pub fn render_project_detail(project: &Project, body_html: &str) -> String {
+    format!(
+        r#"<div class="project-meta">{}</div>
+{body_html}<p class="meta"><a href="index.html">All projects</a></p>
+"#,
+        project_meta(project)
+    )
+}
+
+#[cfg(test)]
+mod tests;
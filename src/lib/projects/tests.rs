@@ -0,0 +1,44 @@
+use crate::projects::{parse_projects, render_project_detail, render_projects_grid};
+
+const YAML: &str = r#"
+- name: Static Site
+  description: Builds <this> site.
+  repo: https://example.com/ssg
+  tags: [rust, web]
+  status: Active
+- name: Toy
+"#;
+
+#[test]
+fn projects_yaml_is_parsed() {
+    let projects = parse_projects(YAML).unwrap();
+
+    assert_eq!(projects.len(), 2);
+    assert_eq!(projects[0].slug(), "static-site");
+    assert_eq!(projects[0].tags, vec!["rust", "web"]);
+    assert_eq!(projects[1].description, None);
+    assert!(parse_projects("").unwrap().is_empty());
+}
+
+#[test]
+fn grid_links_only_projects_with_detail_pages() {
+    let projects = parse_projects(YAML).unwrap();
+
+    let html = render_projects_grid(&projects, |p| p.name == "Static Site");
+
+    assert!(html.contains(r#"<h2><a href="static-site.html">Static Site</a></h2>"#));
+    assert!(html.contains("<h2>Toy</h2>"));
+    assert!(html.contains(r#"<p class="status status-active">Active</p>"#));
+    assert!(html.contains("Builds &lt;this&gt; site."));
+    assert!(html.contains(r#"<a href="https://example.com/ssg">Source</a>"#));
+}
+
+#[test]
+fn detail_page_wraps_rendered_markdown() {
+    let projects = parse_projects(YAML).unwrap();
+    let html = render_project_detail(&projects[0], "<p>Long story.</p>\n");
+
+    assert!(html.contains(r#"<div class="project-meta">"#));
+    assert!(html.contains("<p>Long story.</p>"));
+    assert!(html.contains(r#"<a href="index.html">All projects</a>"#));
+}
@@ -0,0 +1,203 @@
+//! Self-hosting of external resources. When `PROXY_EXTERNAL_RESOURCES` is on,
+//! images and scripts on hosts in `PROXY_ALLOWED_HOSTS` are downloaded at
+//! build time and served from `PROXIED_DIR`, so published pages make no
+//! third-party requests for them. Downloads are kept under the cache
+//! directory, named after their URL, so each is fetched once.
+use std::{
+    collections::HashMap,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use crate::{
+    config::PROXIED_DIR,
+    diagnostics::{Diagnostic, Diagnostics, Severity},
+    postprocess::{Handlers, PageInfo, PostProcessor},
+    utils::{external_host, write_atomic},
+};
+
+/// Directory under the cache directory holding downloaded resources.
+pub const PROXY_CACHE_DIR: &str = "external";
+
+/// How long a single download may take before it is abandoned.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+/// Largest resource worth self-hosting.
+const FETCH_LIMIT: u64 = 20 * 1024 * 1024;
+
+/// Elements whose external sources are self-hosted, with the attribute
+/// naming the source and the extension to assume when the URL has none.
+const PROXIED_ELEMENTS: [(&str, &str, Option<&str>); 2] = [
+    ("img[src]", "src", None),
+    ("script[src]", "src", Some("js")),
+];
+
+/// Post-processor rewriting allowlisted external sources to local copies.
+/// Sources that cannot be fetched are left pointing off-site and reported.
+pub struct ResourceProxy {
+    cache_dir: PathBuf,
+    output_dir: PathBuf,
+    allowed_hosts: Vec<String>,
+    diagnostics: Diagnostics,
+    /// Local copy (relative to the site root) of each URL seen this build,
+    /// or `None` if it could not be fetched.
+    local: Mutex<HashMap<String, Option<String>>>,
+}
+
+impl ResourceProxy {
+    pub fn new(
+        cache_dir: &Path,
+        output_dir: &Path,
+        allowed_hosts: &[&str],
+        diagnostics: Diagnostics,
+    ) -> Self {
+        Self {
+            cache_dir: cache_dir.join(PROXY_CACHE_DIR),
+            output_dir: output_dir.to_path_buf(),
+            allowed_hosts: allowed_hosts.iter().map(|h| h.to_lowercase()).collect(),
+            diagnostics,
+            local: Mutex::default(),
+        }
+    }
+
+    /// Path of the local copy of `url` relative to the site root, fetching
+    /// it if needed. `None` for URLs that are not proxied.
+    fn local_copy(&self, url: &str, default_ext: Option<&str>) -> Option<Result<String, String>> {
+        let host = external_host(url)?;
+        if !self.is_allowed(&host) {
+            return None;
+        }
+        if let Some(known) = self.lock().get(url) {
+            return Some(
+                known
+                    .clone()
+                    .ok_or_else(|| format!("could not fetch {url}")),
+            );
+        }
+
+        let result = self.copy_in(url, default_ext);
+        self.lock()
+            .insert(url.to_owned(), result.as_ref().ok().cloned());
+        Some(result)
+    }
+
+    fn is_allowed(&self, host: &str) -> bool {
+        self.allowed_hosts.iter().any(|allowed| {
+            host == allowed
+                || host
+                    .strip_suffix(allowed.as_str())
+                    .is_some_and(|sub| sub.ends_with('.'))
+        })
+    }
+
+    /// Fetch `url` into the cache unless it is there, and copy it into the
+    /// output directory.
+    fn copy_in(&self, url: &str, default_ext: Option<&str>) -> Result<String, String> {
+        let name = resource_name(url, default_ext);
+        let cached = self.cache_dir.join(&name);
+        if !cached.is_file() {
+            download(url, &cached)?;
+        }
+
+        let out_dir = self.output_dir.join(PROXIED_DIR);
+        fs::create_dir_all(&out_dir).map_err(|e| e.to_string())?;
+        fs::copy(&cached, out_dir.join(&name)).map_err(|e| e.to_string())?;
+        Ok(format!("{PROXIED_DIR}/{name}"))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Option<String>>> {
+        self.local.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn report(&self, page: &Path, url: &str, error: &str) {
+        self.diagnostics.push(Diagnostic {
+            severity: Severity::Warning,
+            origin: "proxy",
+            document: Arc::from(page),
+            offset: None,
+            position: None,
+            message: format!("{url} is still loaded from its host: {error}"),
+        });
+    }
+}
+
+impl PostProcessor for ResourceProxy {
+    fn register<'h>(
+        &'h self,
+        page: PageInfo<'h>,
+        handlers: &mut Handlers<'h>,
+    ) -> color_eyre::Result<()> {
+        for (selector, attribute, default_ext) in PROXIED_ELEMENTS {
+            handlers.element(selector, move |el| {
+                let Some(url) = el.get_attribute(attribute) else {
+                    return Ok(());
+                };
+                match self.local_copy(&url, default_ext) {
+                    Some(Ok(rel)) => {
+                        el.set_attribute(attribute, &format!("{}{rel}", page.prefix))?;
+                        // Same-origin now; CORS mode would only get in the way.
+                        el.remove_attribute("crossorigin");
+                    }
+                    Some(Err(error)) => self.report(page.rel_path, &url, &error),
+                    None => {}
+                }
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// File name of the local copy of `url`: a hash of the URL, keeping the
+/// extension of its path so the copy is served with the right type.
+fn resource_name(url: &str, default_ext: Option<&str>) -> String {
+    let hash = blake3::hash(url.as_bytes()).to_hex();
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let file = path.rsplit('/').next().unwrap_or_default();
+    let ext = file
+        .rsplit_once('.')
+        .map(|(_, ext)| ext)
+        .filter(|ext| {
+            (1..=5).contains(&ext.len()) && ext.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+        .map(str::to_lowercase)
+        .or_else(|| default_ext.map(str::to_owned));
+    match ext {
+        Some(ext) => format!("{}.{ext}", &hash[..16]),
+        None => hash[..16].to_owned(),
+    }
+}
+
+/// Fetch `url` to `dest`, refusing anything over `FETCH_LIMIT`.
+pub(crate) fn download(url: &str, dest: &Path) -> Result<(), String> {
+    let url = match url.strip_prefix("//") {
+        Some(rest) => format!("https://{rest}"),
+        None => url.to_owned(),
+    };
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(FETCH_TIMEOUT))
+        .build()
+        .into();
+    let mut response = agent.get(&url).call().map_err(|e| e.to_string())?;
+    let mut bytes = Vec::new();
+    response
+        .body_mut()
+        .as_reader()
+        .take(FETCH_LIMIT + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| e.to_string())?;
+    if bytes.len() as u64 > FETCH_LIMIT {
+        return Err(format!("larger than {FETCH_LIMIT} bytes"));
+    }
+
+    // Pages are post-processed in parallel: write a private file and move it
+    // into place so no one copies a half-written download.
+    let dir = dest.parent().ok_or("no cache directory")?;
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    write_atomic(dest, &bytes).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,78 @@
+use std::{fs, path::Path};
+
+use tempfile::TempDir;
+
+use crate::{
+    config::PROXIED_DIR,
+    diagnostics::Diagnostics,
+    postprocess::{PageInfo, PostProcessors},
+    proxy::{PROXY_CACHE_DIR, ResourceProxy, resource_name},
+    utils::external_host,
+};
+
+fn page() -> PageInfo<'static> {
+    PageInfo {
+        rel_path: Path::new("posts/a.html"),
+        prefix: "../",
+    }
+}
+
+#[test]
+fn allowlisted_resources_are_served_from_local_copies() {
+    let tmp = TempDir::new().expect("tempdir");
+    let (cache, out) = (tmp.path().join(".cache"), tmp.path().join("public"));
+    let url = "https://img.example.com/pics/cat.PNG?w=600";
+    let name = resource_name(url, None);
+    fs::create_dir_all(cache.join(PROXY_CACHE_DIR)).unwrap();
+    fs::write(cache.join(PROXY_CACHE_DIR).join(&name), b"png").unwrap();
+
+    let proxy = ResourceProxy::new(&cache, &out, &["example.com"], Diagnostics::default());
+    let processors = PostProcessors::new().with(proxy);
+    let html = format!(
+        r#"<img src="{url}" crossorigin="anonymous"><img src="https://elsewhere.org/x.png"><img src="local.png">"#
+    );
+    let rewritten = processors.apply(&html, page()).unwrap();
+
+    assert_eq!(
+        rewritten,
+        format!(
+            r#"<img src="../{PROXIED_DIR}/{name}"><img src="https://elsewhere.org/x.png"><img src="local.png">"#
+        )
+    );
+    assert_eq!(fs::read(out.join(PROXIED_DIR).join(&name)).unwrap(), b"png");
+}
+
+#[test]
+fn hosts_match_exactly_or_as_subdomains() {
+    assert_eq!(
+        external_host("https://User@CDN.Example.com:8443/a.js").as_deref(),
+        Some("cdn.example.com")
+    );
+    assert_eq!(
+        external_host("//example.com/a.js").as_deref(),
+        Some("example.com")
+    );
+    assert_eq!(external_host("/local.js"), None);
+
+    let proxy = ResourceProxy::new(
+        Path::new("c"),
+        Path::new("o"),
+        &["example.com"],
+        Diagnostics::default(),
+    );
+    assert!(proxy.is_allowed("example.com"));
+    assert!(proxy.is_allowed("cdn.example.com"));
+    assert!(!proxy.is_allowed("badexample.com"));
+}
+
+#[test]
+fn resource_names_keep_sensible_extensions() {
+    assert!(resource_name("https://a.org/x.min.JS?v=2", None).ends_with(".js"));
+    assert!(resource_name("https://a.org/script", Some("js")).ends_with(".js"));
+    assert!(!resource_name("https://a.org/image", None).contains('.'));
+    assert!(!resource_name("https://a.org/x.not-an-ext", None).contains('.'));
+    assert_ne!(
+        resource_name("https://a.org/x.js", None),
+        resource_name("https://b.org/x.js", None)
+    );
+}
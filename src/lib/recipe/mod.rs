@@ -0,0 +1,239 @@
+//! Recipe posts: structured frontmatter rendered as a recipe card plus
+//! schema.org `Recipe` JSON-LD.
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::utils::{escape_attr, escape_text};
+
+/// Recipe fields read from a post's frontmatter.
+#[derive(Deserialize, Default, Debug, Clone)]
+pub struct Recipe {
+    #[serde(default)]
+    pub ingredients: Vec<String>,
+    #[serde(default)]
+    pub steps: Vec<String>,
+    pub prep_time: Option<RecipeDuration>,
+    pub cook_time: Option<RecipeDuration>,
+    pub total_time: Option<RecipeDuration>,
+    #[serde(alias = "yield", default, deserialize_with = "string_or_number")]
+    pub servings: Option<String>,
+}
+
+/// Accept `servings: 4` as well as `servings: "4 slices"`.
+fn string_or_number<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Text(String),
+        Int(i64),
+        Float(f64),
+    }
+
+    Ok(Option::<Raw>::deserialize(deserializer)?.map(|raw| match raw {
+        Raw::Text(s) => s,
+        Raw::Int(n) => n.to_string(),
+        Raw::Float(n) => n.to_string(),
+    }))
+}
+
+/// A duration given either as minutes (`20`) or as ISO 8601 (`PT1H15M`).
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum RecipeDuration {
+    Minutes(u32),
+    Text(String),
+}
+
+impl RecipeDuration {
+    fn minutes(&self) -> Option<u32> {
+        match self {
+            Self::Minutes(m) => Some(*m),
+            Self::Text(t) => parse_iso_minutes(t),
+        }
+    }
+
+    /// ISO 8601 form for JSON-LD and `<time datetime>`.
+    pub fn iso(&self) -> Option<String> {
+        let minutes = self.minutes()?;
+        let (h, m) = (minutes / 60, minutes % 60);
+        Some(match (h, m) {
+            (0, m) => format!("PT{m}M"),
+            (h, 0) => format!("PT{h}H"),
+            (h, m) => format!("PT{h}H{m}M"),
+        })
+    }
+
+    /// Short human-readable form, e.g. `1 h 15 min`.
+    pub fn human(&self) -> String {
+        let Some(minutes) = self.minutes() else {
+            return match self {
+                Self::Text(t) => t.clone(),
+                Self::Minutes(m) => format!("{m} min"),
+            };
+        };
+        match (minutes / 60, minutes % 60) {
+            (0, m) => format!("{m} min"),
+            (h, 0) => format!("{h} h"),
+            (h, m) => format!("{h} h {m} min"),
+        }
+    }
+}
+
+/// Minutes in an ISO 8601 time duration such as `PT1H15M`.
+fn parse_iso_minutes(raw: &str) -> Option<u32> {
+    let rest = raw.trim().strip_prefix("PT")?;
+    let mut minutes = 0;
+    let mut number = String::new();
+    for ch in rest.chars() {
+        match ch {
+            '0'..='9' => number.push(ch),
+            'H' => minutes += number.parse::<u32>().ok()? * 60,
+            'M' => minutes += number.parse::<u32>().ok()?,
+            _ => return None,
+        }
+        if !ch.is_ascii_digit() {
+            number.clear();
+        }
+    }
+    number.is_empty().then_some(minutes)
+}
+
+impl Recipe {
+    pub fn is_empty(&self) -> bool {
+        self.ingredients.is_empty() && self.steps.is_empty()
+    }
+
+    fn times(&self) -> impl Iterator<Item = (&'static str, &RecipeDuration)> {
+        [
+            ("Prep", self.prep_time.as_ref()),
+            ("Cook", self.cook_time.as_ref()),
+            ("Total", self.total_time.as_ref()),
+        ]
+        .into_iter()
+        .filter_map(|(label, d)| d.map(|d| (label, d)))
+    }
+
+    /// Recipe card with times, ingredients, and numbered steps.
+    pub fn to_html(&self) -> String {
+        let mut html = String::from(r#"<section class="recipe">"#);
+        html.push('\n');
+
+        let mut facts: Vec<String> = self
+            .times()
+            .map(|(label, d)| {
+                let value = match d.iso() {
+                    Some(iso) => format!(
+                        r#"<time datetime="{}">{}</time>"#,
+                        escape_attr(&iso),
+                        escape_text(&d.human())
+                    ),
+                    None => escape_text(&d.human()),
+                };
+                format!("<dt>{label}</dt><dd>{value}</dd>")
+            })
+            .collect();
+        if let Some(servings) = &self.servings {
+            facts.push(format!("<dt>Serves</dt><dd>{}</dd>", escape_text(servings)));
+        }
+        if !facts.is_empty() {
+            html.push_str(r#"<dl class="recipe-facts">"#);
+            html.push_str(&facts.concat());
+            html.push_str("</dl>\n");
+        }
+
+        if !self.ingredients.is_empty() {
+            html.push_str("<h2>Ingredients</h2>\n<ul class=\"ingredients\">\n");
+            for i in &self.ingredients {
+                html.push_str(&format!("<li>{}</li>\n", escape_text(i)));
+            }
+            html.push_str("</ul>\n");
+        }
+
+        if !self.steps.is_empty() {
+            html.push_str("<h2>Method</h2>\n<ol class=\"steps\">\n");
+            for s in &self.steps {
+                html.push_str(&format!("<li>{}</li>\n", escape_text(s)));
+            }
+            html.push_str("</ol>\n");
+        }
+
+        html.push_str("</section>\n");
+        html
+    }
+
+    /// schema.org `Recipe` JSON-LD for the page head.
+    pub fn json_ld(&self, info: &RecipeInfo<'_>) -> String {
+        let mut ld = json!({
+            "@context": "https://schema.org",
+            "@type": "Recipe",
+            "name": info.name,
+            "url": info.url,
+            "author": { "@type": "Person", "name": info.author },
+            "recipeIngredient": self.ingredients,
+            "recipeInstructions": self
+                .steps
+                .iter()
+                .map(|s| json!({ "@type": "HowToStep", "text": s }))
+                .collect::<Vec<_>>(),
+        });
+
+        let obj = ld.as_object_mut().expect("json object");
+        let mut set = |key: &str, value: Option<Value>| {
+            if let Some(value) = value {
+                obj.insert(key.to_string(), value);
+            }
+        };
+        set("description", info.description.map(Value::from));
+        set("datePublished", info.published.map(Value::from));
+        set("image", info.image.map(Value::from));
+        set(
+            "prepTime",
+            self.prep_time
+                .as_ref()
+                .and_then(|d| d.iso())
+                .map(Value::from),
+        );
+        set(
+            "cookTime",
+            self.cook_time
+                .as_ref()
+                .and_then(|d| d.iso())
+                .map(Value::from),
+        );
+        set(
+            "totalTime",
+            self.total_time
+                .as_ref()
+                .and_then(|d| d.iso())
+                .map(Value::from),
+        );
+        set("recipeYield", self.servings.clone().map(Value::from));
+        if !info.keywords.is_empty() {
+            set("keywords", Some(Value::from(info.keywords.join(", "))));
+        }
+
+        // `</` cannot appear inside the script element.
+        let json = ld.to_string().replace("</", "<\\/");
+        format!(
+            r#"
+<script type="application/ld+json">{json}</script>"#
+        )
+    }
+}
+
+/// Page details folded into the recipe's JSON-LD.
+pub struct RecipeInfo<'a> {
+    pub name: &'a str,
+    pub url: &'a str,
+    pub author: &'a str,
+    pub description: Option<&'a str>,
+    pub published: Option<&'a str>,
+    pub image: Option<&'a str>,
+    pub keywords: &'a [&'a str],
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,68 @@
+use crate::recipe::{Recipe, RecipeDuration, RecipeInfo};
+
+fn recipe() -> Recipe {
+    Recipe {
+        ingredients: vec!["2 eggs".to_string(), "Salt & pepper".to_string()],
+        steps: vec!["Whisk.".to_string(), "Cook gently.".to_string()],
+        prep_time: Some(RecipeDuration::Minutes(5)),
+        cook_time: Some(RecipeDuration::Text("PT1H15M".to_string())),
+        total_time: Some(RecipeDuration::Text("overnight".to_string())),
+        servings: Some("2".to_string()),
+    }
+}
+
+#[test]
+fn durations_normalise_to_iso_and_human_forms() {
+    assert_eq!(
+        RecipeDuration::Minutes(90).iso().as_deref(),
+        Some("PT1H30M")
+    );
+    assert_eq!(RecipeDuration::Minutes(90).human(), "1 h 30 min");
+    let text = RecipeDuration::Text("PT2H".to_string());
+    assert_eq!(text.iso().as_deref(), Some("PT2H"));
+    assert_eq!(text.human(), "2 h");
+    let free = RecipeDuration::Text("overnight".to_string());
+    assert_eq!(free.iso(), None);
+    assert_eq!(free.human(), "overnight");
+}
+
+#[test]
+fn recipe_card_lists_ingredients_and_steps() {
+    let html = recipe().to_html();
+
+    assert!(html.contains(r#"<dt>Prep</dt><dd><time datetime="PT5M">5 min</time></dd>"#));
+    assert!(html.contains("<dt>Total</dt><dd>overnight</dd>"));
+    assert!(html.contains("<li>Salt &amp; pepper</li>"));
+    assert!(html.contains("<ol class=\"steps\">\n<li>Whisk.</li>"));
+}
+
+#[test]
+fn json_ld_describes_the_recipe() {
+    let keywords = ["breakfast"];
+    let script = recipe().json_ld(&RecipeInfo {
+        name: "Eggs </script>",
+        url: "https://example.com/posts/eggs.html",
+        author: "Me",
+        description: Some("Quick eggs"),
+        published: Some("2025-01-01"),
+        image: None,
+        keywords: &keywords,
+    });
+
+    let json = script
+        .trim()
+        .strip_prefix(r#"<script type="application/ld+json">"#)
+        .and_then(|s| s.strip_suffix("</script>"))
+        .unwrap();
+    assert!(!json.contains("</script"));
+    let parsed: serde_json::Value = serde_json::from_str(json).unwrap();
+
+    assert_eq!(parsed["@type"], "Recipe");
+    assert_eq!(parsed["name"], "Eggs </script>");
+    assert_eq!(parsed["recipeIngredient"][0], "2 eggs");
+    assert_eq!(parsed["recipeInstructions"][1]["text"], "Cook gently.");
+    assert_eq!(parsed["cookTime"], "PT1H15M");
+    assert_eq!(parsed["recipeYield"], "2");
+    assert_eq!(parsed["keywords"], "breakfast");
+    assert!(parsed.get("image").is_none());
+}
@@ -0,0 +1,85 @@
+//! "Related" section: other posts ranked by how many tags they share.
+use crate::{
+    article::Article,
+    utils::{escape_attr, escape_text},
+};
+
+/// Up to `limit` posts sharing at least one tag with `article`, ordered by
+/// the number of shared tags, then newest first, then title.
+pub fn related_posts<'a>(
+    article: &Article,
+    articles: &'a [Article],
+    limit: usize,
+) -> Vec<&'a Article> {
+    if limit == 0 || article.tags.is_empty() {
+        return Vec::new();
+    }
+
+    let mut scored: Vec<(usize, &Article)> = articles
+        .iter()
+        .filter(|other| other.href != article.href)
+        .map(|other| {
+            let shared = other
+                .tags
+                .iter()
+                .filter(|t| article.tags.contains(t))
+                .count();
+            (shared, other)
+        })
+        .filter(|(shared, _)| *shared > 0)
+        .collect();
+
+    scored.sort_by(|(sa, a), (sb, b)| {
+        sb.cmp(sa)
+            .then_with(|| b.ctime.cmp(&a.ctime))
+            .then_with(|| a.title.cmp(&b.title))
+    });
+
+    scored.into_iter().take(limit).map(|(_, a)| a).collect()
+}
+
+/// Render the related posts as a section, or nothing when there are none.
+pub fn render_related(related: &[&Article], href_prefix: &str) -> String {
+    render_section("related", "Related", related, href_prefix)
+}
+
+/// Render posts similar in content (see [`crate::terms`]) as a section, or
+/// nothing when there are none.
+pub fn render_similar(similar: &[&Article], href_prefix: &str) -> String {
+    render_section("related similar", "Similar", similar, href_prefix)
+}
+
+fn render_section(class: &str, heading: &str, related: &[&Article], href_prefix: &str) -> String {
+    if related.is_empty() {
+        return String::new();
+    }
+
+    let mut html = format!(
+        r#"<section class="{class}">
+<h2>{heading}</h2>
+<ul>
+"#
+    );
+    for a in related {
+        let full_href = format!("{href_prefix}{}", a.href.as_str());
+        html.push_str(&format!(
+            r#"<li><a href="{}">{}</a>"#,
+            escape_attr(&full_href),
+            escape_text(&a.title)
+        ));
+        if let Some(ctime) = &a.ctime {
+            let ctime = ctime.as_str();
+            html.push_str(&format!(
+                r#" <time datetime="{}">{}</time>"#,
+                escape_attr(&ctime),
+                escape_text(&ctime)
+            ));
+        }
+        html.push_str("</li>\n");
+    }
+    html.push_str("</ul>\n</section>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,46 @@
+use crate::{
+    article::Article,
+    related::{related_posts, render_related},
+};
+
+fn article(title: &str, date: &str, tags: &[&str]) -> Article {
+    Article::test(title).dated(date).tagged(tags)
+}
+
+#[test]
+fn related_posts_rank_by_overlap_then_date() {
+    let articles = vec![
+        article("current", "2025-01-01", &["rust", "web", "cli"]),
+        article("one-tag-new", "2025-06-01", &["rust"]),
+        article("two-tags", "2020-01-01", &["rust", "web"]),
+        article("one-tag-old", "2021-01-01", &["cli"]),
+        article("unrelated", "2025-07-01", &["life"]),
+    ];
+
+    let related = related_posts(&articles[0], &articles, 5);
+    let titles: Vec<_> = related.iter().map(|a| a.title.as_str()).collect();
+
+    assert_eq!(titles, vec!["two-tags", "one-tag-new", "one-tag-old"]);
+}
+
+#[test]
+fn related_posts_respect_the_limit() {
+    let articles = vec![
+        article("current", "2025-01-01", &["rust"]),
+        article("a", "2025-02-01", &["rust"]),
+        article("b", "2025-03-01", &["rust"]),
+    ];
+
+    assert_eq!(related_posts(&articles[0], &articles, 1).len(), 1);
+    assert!(related_posts(&articles[0], &articles, 0).is_empty());
+}
+
+#[test]
+fn empty_related_renders_nothing() {
+    assert_eq!(render_related(&[], "../"), "");
+
+    let a = article("a", "2025-02-01", &["rust"]);
+    let html = render_related(&[&a], "../");
+    assert!(html.contains(r#"<a href="../posts/a.html">a</a>"#));
+    assert!(html.contains("<h2>Related</h2>"));
+}
@@ -0,0 +1,169 @@
+//! Per-page weight, for deciding what to optimize: each emitted page's size
+//! as written and precompressed, the images it loads, and whether it pulls
+//! in the KaTeX stylesheet or carries highlighted code. `ssg report` builds
+//! the site and prints [`render_report`].
+use std::{
+    cell::RefCell,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use lol_html::{HtmlRewriter, Settings, element};
+
+use crate::{
+    backlinks::internal_href,
+    cache::format_bytes,
+    types::{Href, RelPath},
+};
+
+/// Bytes written for a file and its precompressed variants.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Sizes {
+    pub raw: u64,
+    pub gzip: u64,
+    pub brotli: u64,
+}
+
+/// What the render knew about a page beyond its markup.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RenderMeta {
+    /// The page links the KaTeX stylesheet.
+    pub katex: bool,
+    /// The page has syntax-highlighted code blocks, whose styles are inline.
+    pub highlighted: bool,
+}
+
+/// One emitted page's weight.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PageStats {
+    /// Location relative to the output directory.
+    pub path: PathBuf,
+    pub sizes: Sizes,
+    /// `<img>` elements on the page.
+    pub images: usize,
+    /// Combined size of those images served from the site; images on other
+    /// hosts are counted but not weighed.
+    pub image_bytes: u64,
+    pub meta: RenderMeta,
+}
+
+impl PageStats {
+    /// Stats for the page written to `rel` under `output_dir` as `html`.
+    pub fn new(output_dir: &Path, rel: &Path, html: &[u8], sizes: Sizes, meta: RenderMeta) -> Self {
+        let sources = image_sources(html);
+        let page = RelPath::new(rel.to_path_buf()).map(|rel| Href::from_rel(&rel));
+        let image_bytes = page
+            .map(|page| {
+                sources
+                    .iter()
+                    .filter_map(|src| internal_href(src, &page))
+                    .filter_map(|href| fs::metadata(output_dir.join(href.as_str())).ok())
+                    .map(|meta| meta.len())
+                    .sum()
+            })
+            .unwrap_or_default();
+        Self {
+            path: rel.to_path_buf(),
+            sizes,
+            images: sources.len(),
+            image_bytes,
+            meta,
+        }
+    }
+}
+
+/// The `src` of every `<img>` in `html`.
+pub fn image_sources(html: &[u8]) -> Vec<String> {
+    let sources = RefCell::new(Vec::new());
+    let mut rewriter = HtmlRewriter::new(
+        Settings {
+            element_content_handlers: vec![element!("img[src]", |el| {
+                sources.borrow_mut().extend(el.get_attribute("src"));
+                Ok(())
+            })],
+            ..Settings::new()
+        },
+        |_: &[u8]| {},
+    );
+    // Only the handler matters; malformed markup just yields fewer images.
+    let _ = rewriter.write(html).and_then(|()| rewriter.end());
+    sources.into_inner()
+}
+
+/// `pages` as a table, heaviest (compressed) first, with a total row.
+pub fn render_report(pages: &[PageStats]) -> String {
+    let mut pages: Vec<&PageStats> = pages.iter().collect();
+    pages.sort_by(|a, b| {
+        b.sizes
+            .brotli
+            .cmp(&a.sizes.brotli)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    let flag = |set: bool| if set { "yes" } else { "" }.to_owned();
+    let mut rows: Vec<[String; 8]> = vec![[
+        "page".into(),
+        "html".into(),
+        "gzip".into(),
+        "br".into(),
+        "images".into(),
+        "image bytes".into(),
+        "katex".into(),
+        "code".into(),
+    ]];
+    for page in &pages {
+        rows.push([
+            page.path.display().to_string(),
+            format_bytes(page.sizes.raw),
+            format_bytes(page.sizes.gzip),
+            format_bytes(page.sizes.brotli),
+            page.images.to_string(),
+            format_bytes(page.image_bytes),
+            flag(page.meta.katex),
+            flag(page.meta.highlighted),
+        ]);
+    }
+    let sum = |f: fn(&PageStats) -> u64| pages.iter().map(|p| f(p)).sum::<u64>();
+    rows.push([
+        format!("total ({} pages)", pages.len()),
+        format_bytes(sum(|p| p.sizes.raw)),
+        format_bytes(sum(|p| p.sizes.gzip)),
+        format_bytes(sum(|p| p.sizes.brotli)),
+        sum(|p| p.images as u64).to_string(),
+        format_bytes(sum(|p| p.image_bytes)),
+        pages.iter().filter(|p| p.meta.katex).count().to_string(),
+        pages
+            .iter()
+            .filter(|p| p.meta.highlighted)
+            .count()
+            .to_string(),
+    ]);
+
+    let mut widths = [0; 8];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let mut table = String::new();
+    for row in &rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .enumerate()
+            .map(|(i, (cell, width))| {
+                // The path reads left to right; numbers line up on the right.
+                if i == 0 {
+                    format!("{cell:<width$}")
+                } else {
+                    format!("{cell:>width$}")
+                }
+            })
+            .collect();
+        table.push_str(cells.join("  ").trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests;
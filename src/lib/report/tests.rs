@@ -0,0 +1,71 @@
+use std::{fs, path::Path};
+
+use tempfile::TempDir;
+
+use crate::report::{PageStats, RenderMeta, Sizes, image_sources, render_report};
+
+#[test]
+fn images_are_counted_and_local_ones_weighed() {
+    let tmp = TempDir::new().expect("tempdir");
+    fs::create_dir_all(tmp.path().join("img")).unwrap();
+    fs::write(tmp.path().join("img").join("a.jpg"), [0u8; 300]).unwrap();
+    fs::write(tmp.path().join("img").join("b.png"), [0u8; 20]).unwrap();
+    let html = br#"<p><img src="../img/a.jpg"><img src="/img/b.png"><img src="https://example.com/c.jpg"><img alt="no source"></p>"#;
+
+    assert_eq!(
+        image_sources(html),
+        ["../img/a.jpg", "/img/b.png", "https://example.com/c.jpg"]
+    );
+
+    let stats = PageStats::new(
+        tmp.path(),
+        Path::new("posts/a.html"),
+        html,
+        Sizes::default(),
+        RenderMeta::default(),
+    );
+    assert_eq!(stats.images, 3);
+    assert_eq!(stats.image_bytes, 320);
+}
+
+#[test]
+fn report_puts_the_heaviest_pages_first_and_totals_them() {
+    let page = |path: &str, brotli: u64, meta: RenderMeta| PageStats {
+        path: path.into(),
+        sizes: Sizes {
+            raw: brotli * 4,
+            gzip: brotli + 100,
+            brotli,
+        },
+        images: 1,
+        image_bytes: 2048,
+        meta,
+    };
+    let pages = [
+        page("index.html", 900, RenderMeta::default()),
+        page(
+            "posts/math.html",
+            3000,
+            RenderMeta {
+                katex: true,
+                highlighted: false,
+            },
+        ),
+    ];
+
+    let report = render_report(&pages);
+    let lines: Vec<&str> = report.lines().collect();
+
+    assert_eq!(lines.len(), 4, "{report}");
+    assert!(lines[0].starts_with("page"), "{report}");
+    assert!(lines[1].starts_with("posts/math.html"), "{report}");
+    assert!(
+        lines[1].contains("11.7 KiB") && lines[1].contains("yes"),
+        "{report}"
+    );
+    assert!(lines[2].starts_with("index.html"), "{report}");
+    assert!(lines[3].starts_with("total (2 pages)"), "{report}");
+    assert!(lines[3].contains("4.0 KiB"), "{report}");
+    // Columns line up.
+    assert_eq!(lines[1].find("KiB"), lines[3].find("KiB"), "{report}");
+}
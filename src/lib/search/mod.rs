@@ -0,0 +1,137 @@
+//! Client-side search. During rendering each article's body is flattened to
+//! plain text; at emit time the texts are serialised into a JSON index that a
+//! small script on the search page queries in the browser.
+use pulldown_cmark::{Event, Tag, TagEnd};
+use serde::Serialize;
+
+use crate::article::Article;
+
+/// Directory (relative to the output root) holding the index and search page.
+pub const SEARCH_DIR: &str = "search";
+
+/// A single document in the emitted search index.
+#[derive(Serialize, Debug)]
+struct SearchDocument<'a> {
+    title: &'a str,
+    href: &'a str,
+    date: Option<String>,
+    tags: Vec<&'a str>,
+    summary: Option<&'a str>,
+    text: &'a str,
+}
+
+/// Flatten Markdown events into whitespace-normalised plain text, skipping the
+/// frontmatter block and raw HTML.
+pub fn plain_text(events: &[Event<'_>]) -> String {
+    let mut out = String::new();
+    let mut in_metadata = false;
+
+    for event in events {
+        match event {
+            Event::Start(Tag::MetadataBlock(_)) => in_metadata = true,
+            Event::End(TagEnd::MetadataBlock(_)) => in_metadata = false,
+            _ if in_metadata => {}
+            Event::Text(t) | Event::Code(t) | Event::InlineMath(t) | Event::DisplayMath(t) => {
+                out.push_str(t);
+            }
+            Event::SoftBreak | Event::HardBreak => out.push(' '),
+            Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Heading(_)
+                | TagEnd::Item
+                | TagEnd::CodeBlock
+                | TagEnd::TableCell
+                | TagEnd::BlockQuote(_)
+                | TagEnd::FootnoteDefinition,
+            ) => out.push(' '),
+            _ => {}
+        }
+    }
+
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Serialise the articles into the JSON index consumed by the search page.
+pub fn build_search_index(articles: &[Article]) -> color_eyre::Result<String> {
+    let docs: Vec<SearchDocument<'_>> = articles
+        .iter()
+        .map(|a| SearchDocument {
+            title: &a.title,
+            href: a.href.as_str(),
+            date: a.ctime.as_ref().map(|d| d.as_str()),
+            tags: a.tags.iter().map(|t| t.as_str()).collect(),
+            summary: a.summary.as_deref(),
+            text: &a.text,
+        })
+        .collect();
+
+    Ok(serde_json::to_string(&docs)?)
+}
+
+/// Body of the search page: a query box, a results list, and the script that
+/// fetches `index.json` and filters it as the reader types.
+pub fn search_page_body(href_prefix: &str) -> String {
+    format!(
+        r#"<form role="search" class="search-form" onsubmit="return false">
+<input type="search" id="search-input" name="q" placeholder="Search posts" aria-label="Search posts" autocomplete="off">
+</form>
+<p class="meta" id="search-status"></p>
+<ol class="search-results" id="search-results"></ol>
+<script>
+(function () {{
+  var prefix = "{href_prefix}";
+  var input = document.getElementById("search-input");
+  var list = document.getElementById("search-results");
+  var status = document.getElementById("search-status");
+  var docs = [];
+
+  function run() {{
+    var terms = input.value.toLowerCase().split(/\s+/).filter(Boolean);
+    list.textContent = "";
+    if (!terms.length) {{ status.textContent = ""; return; }}
+    var hits = [];
+    docs.forEach(function (d) {{
+      var title = d.title.toLowerCase();
+      var hay = title + " " + d.tags.join(" ").toLowerCase() + " " + d.text.toLowerCase();
+      var score = 0;
+      for (var i = 0; i < terms.length; i++) {{
+        if (hay.indexOf(terms[i]) < 0) return;
+        score += title.indexOf(terms[i]) < 0 ? 1 : 10;
+      }}
+      hits.push({{ doc: d, score: score }});
+    }});
+    hits.sort(function (a, b) {{ return b.score - a.score; }});
+    status.textContent = hits.length + (hits.length === 1 ? " result" : " results");
+    hits.slice(0, 50).forEach(function (h) {{
+      var li = document.createElement("li");
+      var a = document.createElement("a");
+      a.href = prefix + h.doc.href;
+      a.textContent = h.doc.title;
+      li.appendChild(a);
+      if (h.doc.summary) {{
+        var p = document.createElement("p");
+        p.className = "meta";
+        p.textContent = h.doc.summary;
+        li.appendChild(p);
+      }}
+      list.appendChild(li);
+    }});
+  }}
+
+  fetch("index.json")
+    .then(function (r) {{ return r.json(); }})
+    .then(function (data) {{
+      docs = data;
+      var q = new URLSearchParams(location.search).get("q");
+      if (q) input.value = q;
+      run();
+    }});
+  input.addEventListener("input", run);
+}})();
+</script>
+"#
+    )
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,39 @@
+use pulldown_cmark::{Options, Parser};
+
+use crate::{
+    article::Article,
+    search::{build_search_index, plain_text},
+};
+
+#[test]
+fn plain_text_skips_frontmatter_and_html() {
+    let md = "---\ntitle: Hidden\n---\n# Heading\n\nSome *emphasis* and `code`.\n\n<div>raw</div>\n\n- one\n- two\n";
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_YAML_STYLE_METADATA_BLOCKS);
+    let events: Vec<_> = Parser::new_ext(md, options).collect();
+
+    let text = plain_text(&events);
+
+    assert_eq!(text, "Heading Some emphasis and code. one two");
+}
+
+#[test]
+fn search_index_contains_article_fields() {
+    let article = Article::test("Searchable")
+        .at("posts/searchable.html")
+        .dated("2024-05-06")
+        .summarised("A summary")
+        .with_text("body words")
+        .tagged(&["rust"]);
+
+    let json = build_search_index(&[article]).unwrap();
+    let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+    let doc = &parsed[0];
+    assert_eq!(doc["title"], "Searchable");
+    assert_eq!(doc["href"], "posts/searchable.html");
+    assert_eq!(doc["date"], "2024-05-06");
+    assert_eq!(doc["tags"][0], "rust");
+    assert_eq!(doc["summary"], "A summary");
+    assert_eq!(doc["text"], "body words");
+}
@@ -0,0 +1,139 @@
+//! Posts grouped into ordered series with `series:` in their frontmatter. The
+//! parts are ordered by `series_part:` where given, then by date. Each series
+//! gets a landing page under `SERIES_DIR`, and each part a "Part N of M"
+//! navigation linking its neighbours.
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    article::Article,
+    config::{PRETTY_URLS, SERIES_DIR},
+    types::{Href, RelPath},
+    utils::{escape_attr, escape_text, page_out_path, slugify},
+};
+
+/// A post's place in a series.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SeriesEntry {
+    pub name: String,
+    /// Explicit position; parts without one follow those with one, by date.
+    pub part: Option<u32>,
+}
+
+/// Output location of a series' landing page, relative to the site root.
+pub fn series_page_path(name: &str) -> PathBuf {
+    page_out_path(
+        &Path::new(SERIES_DIR).join(format!("{}.html", slugify(name))),
+        PRETTY_URLS,
+    )
+}
+
+/// Root-relative link to a series' landing page.
+pub fn series_href(name: &str) -> Href {
+    let rel = RelPath::new(series_page_path(name)).expect("series page path is relative");
+    Href::from_rel(&rel)
+}
+
+/// Every series among `articles`, by name, each with its parts in order.
+pub fn collect_series(articles: &[Article]) -> BTreeMap<&str, Vec<&Article>> {
+    let mut by_name: BTreeMap<&str, Vec<&Article>> = BTreeMap::new();
+    for a in articles {
+        if let Some(entry) = &a.series {
+            by_name.entry(&entry.name).or_default().push(a);
+        }
+    }
+    // Numbered parts first, in number order.
+    let position = |a: &Article| {
+        let part = a.series.as_ref().and_then(|entry| entry.part);
+        (part.is_none(), part)
+    };
+    for parts in by_name.values_mut() {
+        parts.sort_by(|a, b| {
+            position(a)
+                .cmp(&position(b))
+                .then_with(|| a.ctime.cmp(&b.ctime))
+                .then_with(|| a.title.cmp(&b.title))
+        });
+    }
+    by_name
+}
+
+/// "Part N of M" with links to the series and the neighbouring parts, for
+/// `article` among `parts`, or nothing if it is not one of them.
+pub fn render_series_nav(article: &Article, parts: &[&Article], href_prefix: &str) -> String {
+    let (Some(entry), Some(at)) = (
+        &article.series,
+        parts.iter().position(|a| a.href == article.href),
+    ) else {
+        return String::new();
+    };
+    let link = |a: &Article, rel: &str, text: String| {
+        format!(
+            r#"<a rel="{rel}" href="{}">{text}</a>"#,
+            escape_attr(&format!("{href_prefix}{}", a.href.as_str()))
+        )
+    };
+
+    let mut html = format!(
+        r#"<nav class="series-nav" aria-label="Series">
+<p>Part {} of {} in <a href="{}">{}</a></p>
+"#,
+        at + 1,
+        parts.len(),
+        escape_attr(&format!("{href_prefix}{}", series_href(&entry.name))),
+        escape_text(&entry.name)
+    );
+    let previous = at.checked_sub(1).and_then(|i| parts.get(i));
+    let next = parts.get(at + 1);
+    if previous.is_some() || next.is_some() {
+        html.push_str("<p>");
+        if let Some(previous) = previous {
+            html.push_str(&link(
+                previous,
+                "prev",
+                format!("← {}", escape_text(&previous.title)),
+            ));
+        }
+        if let Some(next) = next {
+            if previous.is_some() {
+                html.push_str(r#"<span class="meta-sep">·</span>"#);
+            }
+            html.push_str(&link(
+                next,
+                "next",
+                format!("{} →", escape_text(&next.title)),
+            ));
+        }
+        html.push_str("</p>\n");
+    }
+    html.push_str("</nav>\n");
+    html
+}
+
+/// The parts of a series as an ordered list, for its landing page.
+pub fn render_series_parts(parts: &[&Article], href_prefix: &str) -> String {
+    let mut html = String::from("<ol class=\"series-parts\">\n");
+    for a in parts {
+        html.push_str(&format!(
+            r#"<li><a href="{}">{}</a>"#,
+            escape_attr(&format!("{href_prefix}{}", a.href.as_str())),
+            escape_text(&a.title)
+        ));
+        if let Some(ctime) = &a.ctime {
+            let ctime = ctime.as_str();
+            html.push_str(&format!(
+                r#" <time datetime="{}">{}</time>"#,
+                escape_attr(&ctime),
+                escape_text(&ctime)
+            ));
+        }
+        html.push_str("</li>\n");
+    }
+    html.push_str("</ol>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,75 @@
+use std::path::PathBuf;
+
+use crate::{
+    article::Article,
+    series::{collect_series, render_series_nav, series_page_path},
+};
+
+fn article(title: &str, date: &str, series: Option<(&str, Option<u32>)>) -> Article {
+    let article = Article::test(title).dated(date);
+    match series {
+        Some((name, part)) => article.in_series(name, part),
+        None => article,
+    }
+}
+
+#[test]
+fn parts_are_ordered_by_number_then_date() {
+    let articles = vec![
+        article("late", "2025-03-01", Some(("Parsing", None))),
+        article("two", "2025-01-01", Some(("Parsing", Some(2)))),
+        article("early", "2025-02-01", Some(("Parsing", None))),
+        article("one", "2025-04-01", Some(("Parsing", Some(1)))),
+        article("other", "2025-01-01", Some(("Lexing", None))),
+        article("loose", "2025-01-01", None),
+    ];
+
+    let series = collect_series(&articles);
+    let titles =
+        |name: &str| -> Vec<&str> { series[name].iter().map(|a| a.title.as_str()).collect() };
+
+    assert_eq!(series.len(), 2);
+    assert_eq!(titles("Parsing"), ["one", "two", "early", "late"]);
+    assert_eq!(titles("Lexing"), ["other"]);
+    assert_eq!(
+        series_page_path("Parsing Things"),
+        PathBuf::from("series/parsing-things.html")
+    );
+}
+
+#[test]
+fn nav_counts_the_part_and_links_its_neighbours() {
+    let articles = vec![
+        article("one", "2025-01-01", Some(("Parsing", Some(1)))),
+        article("two", "2025-01-02", Some(("Parsing", Some(2)))),
+        article("three", "2025-01-03", Some(("Parsing", Some(3)))),
+    ];
+    let series = collect_series(&articles);
+    let parts = &series["Parsing"];
+
+    let middle = render_series_nav(&articles[1], parts, "../");
+    assert!(middle.contains("Part 2 of 3"), "{middle}");
+    assert!(
+        middle.contains(r#"href="../series/parsing.html""#),
+        "{middle}"
+    );
+    assert!(
+        middle.contains(r#"<a rel="prev" href="../posts/one.html">← one</a>"#),
+        "{middle}"
+    );
+    assert!(
+        middle.contains(r#"<a rel="next" href="../posts/three.html">three →</a>"#),
+        "{middle}"
+    );
+
+    let first = render_series_nav(&articles[0], parts, "../");
+    assert!(
+        first.contains("Part 1 of 3") && !first.contains("prev"),
+        "{first}"
+    );
+    let last = render_series_nav(&articles[2], parts, "../");
+    assert!(!last.contains("next"), "{last}");
+
+    let loose = article("loose", "2025-01-01", None);
+    assert_eq!(render_series_nav(&loose, parts, "../"), "");
+}
@@ -0,0 +1,374 @@
+//! `{{ exec "cmd args" }}` shortcodes: run an allow-listed command at build
+//! time and splice its stdout into the Markdown source, e.g. a `figlet`
+//! banner or a generated table. `{{ map lat lon zoom }}` shortcodes become a
+//! static map image linking to an interactive one (see `crate::map`), and
+//! `{{ meta key }}` the value of a custom frontmatter field.
+//!
+//! Commands run without a shell, from the site root, and are killed after
+//! `EXEC_TIMEOUT`. Output is cached under `CACHE_DIR/exec`, keyed by a hash
+//! of the command line and the contents of any argument naming a file, so an
+//! unchanged command is not rerun on every build. Shortcodes inside code
+//! fences and code spans are left alone so they can be documented.
+use std::{
+    borrow::Cow,
+    fs,
+    io::Read,
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use color_eyre::{
+    Section,
+    eyre::{bail, eyre},
+};
+use serde_json::{Map, Value};
+
+use crate::{
+    config::{CACHE_DIR, EXEC_ALLOWED_COMMANDS, EXEC_SHORTCODES, EXEC_TIMEOUT},
+    diagnostics::DocumentSink,
+    fence::{self, Line},
+    map::{MAX_ZOOM, MapPoint, map_html, static_map},
+};
+
+/// Subdirectory of `CACHE_DIR` holding command output.
+pub const EXEC_CACHE: &str = "exec";
+
+/// The page whose source is being expanded.
+pub struct ShortcodePage<'p> {
+    /// Site root, where commands run and the cache lives.
+    pub root: &'p Path,
+    /// Output directory, for shortcodes that produce files.
+    pub output_dir: &'p Path,
+    /// Path from the page back to the site root.
+    pub prefix: &'p str,
+    /// Custom frontmatter fields, for `meta` shortcodes.
+    pub extra: &'p Map<String, Value>,
+    /// Where problems with the page's shortcodes are reported.
+    pub sink: &'p DocumentSink,
+}
+
+/// Expand every shortcode in `source`. Returns the source unchanged (and
+/// borrowed) when there is nothing to expand.
+pub fn expand_shortcodes<'a>(
+    source: &'a str,
+    page: &ShortcodePage<'_>,
+) -> color_eyre::Result<Cow<'a, str>> {
+    if !source.contains("{{") {
+        return Ok(Cow::Borrowed(source));
+    }
+
+    let mut out = String::with_capacity(source.len());
+    let mut changed = false;
+
+    for line in fence::lines(source) {
+        match line {
+            Line::Text(line) => changed |= expand_line(line, page, &mut out)?,
+            fenced => out.push_str(fenced.raw()),
+        }
+    }
+
+    Ok(if changed {
+        Cow::Owned(out)
+    } else {
+        Cow::Borrowed(source)
+    })
+}
+
+/// Expand the shortcodes on one line outside code spans into `out`,
+/// returning whether any were found.
+fn expand_line(line: &str, page: &ShortcodePage<'_>, out: &mut String) -> color_eyre::Result<bool> {
+    let mut changed = false;
+    let mut rest = line;
+    let mut in_code = false;
+
+    while !rest.is_empty() {
+        let next_tick = rest.find('`');
+        let next_open = if in_code { None } else { rest.find("{{") };
+        match (next_tick, next_open) {
+            (Some(tick), open) if open.is_none_or(|o| tick < o) => {
+                out.push_str(&rest[..=tick]);
+                rest = &rest[tick + 1..];
+                in_code = !in_code;
+            }
+            (_, Some(open)) => {
+                out.push_str(&rest[..open]);
+                if let Some((point, len)) = parse_map_shortcode(&rest[open..]) {
+                    let point = point?;
+                    out.push_str(&render_map(point, &rest[open..open + len], page));
+                    changed = true;
+                    rest = &rest[open + len..];
+                    continue;
+                }
+                if let Some((key, len)) = parse_meta_shortcode(&rest[open..]) {
+                    match page.extra.get(key).map(meta_text) {
+                        Some(text) => out.push_str(&text),
+                        None => {
+                            let shortcode = &rest[open..open + len];
+                            page.sink.warn(
+                                "shortcode",
+                                shortcode,
+                                format!("no frontmatter field `{key}`; meta shortcode left as-is"),
+                            );
+                            out.push_str(shortcode);
+                        }
+                    }
+                    changed = true;
+                    rest = &rest[open + len..];
+                    continue;
+                }
+                match parse_shortcode(&rest[open..]) {
+                    Some((command, len)) if EXEC_SHORTCODES => {
+                        out.push_str(&run_cached(&command, page.root)?);
+                        changed = true;
+                        rest = &rest[open + len..];
+                    }
+                    Some((command, len)) => {
+                        page.sink.warn(
+                            "shortcode",
+                            &rest[open..open + len],
+                            format!("exec shortcodes are disabled; `{command}` left as-is"),
+                        );
+                        out.push_str("{{");
+                        rest = &rest[open + 2..];
+                    }
+                    None => {
+                        out.push_str("{{");
+                        rest = &rest[open + 2..];
+                    }
+                }
+            }
+            _ => {
+                out.push_str(rest);
+                rest = "";
+            }
+        }
+    }
+
+    Ok(changed)
+}
+
+/// Parse `{{ exec "..." }}` at the start of `s`, returning the command line
+/// and the length of the shortcode.
+pub fn parse_shortcode(s: &str) -> Option<(String, usize)> {
+    let inner = s.strip_prefix("{{")?.trim_start();
+    let inner = inner.strip_prefix("exec")?;
+    if !inner.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let quoted = inner.trim_start().strip_prefix('"')?;
+
+    let mut command = String::new();
+    let mut chars = quoted.char_indices();
+    let close = loop {
+        match chars.next()? {
+            (_, '\\') => command.push(chars.next()?.1),
+            (i, '"') => break i,
+            (_, c) => command.push(c),
+        }
+    };
+    let tail = &quoted[close + 1..];
+    let after = tail.trim_start().strip_prefix("}}")?;
+
+    Some((command, s.len() - after.len()))
+}
+
+/// Parse `{{ map lat lon zoom }}` at the start of `s`, returning the point
+/// (or why it is not one) and the length of the shortcode.
+pub fn parse_map_shortcode(s: &str) -> Option<(color_eyre::Result<MapPoint>, usize)> {
+    let inner = s.strip_prefix("{{")?.trim_start();
+    let inner = inner.strip_prefix("map")?;
+    if !inner.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let close = inner.find("}}")?;
+    let args: Vec<&str> = inner[..close].split_whitespace().collect();
+    let len = s.len() - (inner.len() - close - 2);
+
+    let point = match args[..] {
+        [lat, lon, zoom] => match (lat.parse(), lon.parse(), zoom.parse()) {
+            (Ok(lat), Ok(lon), Ok(zoom)) => MapPoint::new(lat, lon, zoom)
+                .ok_or_else(|| eyre!("map at {lat}, {lon} (zoom {zoom}) is off the map"))
+                .with_note(|| {
+                    format!("Latitude must be within ±85.05, longitude ±180, zoom 0-{MAX_ZOOM}")
+                }),
+            _ => Err(eyre!(
+                "map shortcode arguments must be numbers: `{}`",
+                &s[..len]
+            )),
+        },
+        _ => Err(eyre!(
+            "map shortcode takes a latitude, longitude, and zoom: `{}`",
+            &s[..len]
+        ))
+        .with_note(|| "For example: {{ map 51.5074 -0.1278 13 }}"),
+    };
+    Some((point, len))
+}
+
+/// Parse `{{ meta key }}` at the start of `s`, returning the key and the
+/// length of the shortcode.
+pub fn parse_meta_shortcode(s: &str) -> Option<(&str, usize)> {
+    let inner = s.strip_prefix("{{")?.trim_start();
+    let inner = inner.strip_prefix("meta")?;
+    if !inner.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let close = inner.find("}}")?;
+    let key = inner[..close].trim();
+    if key.is_empty() || key.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((key, s.len() - (inner.len() - close - 2)))
+}
+
+/// A frontmatter value as text: strings as they are, anything else as JSON.
+fn meta_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// The map of `point`, drawn for `shortcode`, falling back to a bare link
+/// (with a warning) when it cannot be drawn, e.g. offline or without
+/// `IMAGE_CLI`.
+fn render_map(point: MapPoint, shortcode: &str, page: &ShortcodePage<'_>) -> String {
+    let cache_dir = page.root.join(CACHE_DIR);
+    match static_map(point, &cache_dir, page.output_dir) {
+        Ok(image) => map_html(point, page.prefix, Some(&image)),
+        Err(error) => {
+            page.sink.warn(
+                "shortcode",
+                shortcode,
+                format!(
+                    "map of {}, {} left as a link: {error}",
+                    point.lat, point.lon
+                ),
+            );
+            map_html(point, page.prefix, None)
+        }
+    }
+}
+
+/// Split a command line on whitespace, keeping single-quoted runs together.
+pub fn split_command(command: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_arg = false;
+
+    for c in command.chars() {
+        match c {
+            '\'' => {
+                in_quotes = !in_quotes;
+                has_arg = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_arg {
+                    args.push(std::mem::take(&mut current));
+                    has_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_arg = true;
+            }
+        }
+    }
+    if has_arg {
+        args.push(current);
+    }
+    args
+}
+
+/// Cache key for a command: its arguments plus the contents of any that
+/// name a file under `root`.
+fn cache_key(args: &[String], root: &Path) -> String {
+    let mut hasher = blake3::Hasher::new();
+    for arg in args {
+        hasher.update(arg.as_bytes());
+        hasher.update(&[0]);
+        if let Ok(contents) = fs::read(root.join(arg)) {
+            hasher.update(&contents);
+        }
+        hasher.update(&[0]);
+    }
+    hasher.finalize().to_hex().to_string()
+}
+
+fn run_cached(command: &str, root: &Path) -> color_eyre::Result<String> {
+    let args = split_command(command);
+    let Some(program) = args.first() else {
+        bail!("empty exec shortcode");
+    };
+    if !EXEC_ALLOWED_COMMANDS.contains(&program.as_str()) {
+        return Err(eyre!("`{program}` is not an allowed exec command"))
+            .with_note(|| format!("Allowed commands: {}", EXEC_ALLOWED_COMMANDS.join(", ")));
+    }
+
+    let cache_path = root
+        .join(CACHE_DIR)
+        .join(EXEC_CACHE)
+        .join(cache_key(&args, root));
+    if let Ok(cached) = fs::read_to_string(&cache_path) {
+        return Ok(cached);
+    }
+
+    let output = run(&args, root, EXEC_TIMEOUT)
+        .with_note(|| format!("While running exec shortcode `{command}`"))?;
+    if let Some(parent) = cache_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&cache_path, &output)?;
+    Ok(output)
+}
+
+/// Run `args` in `root`, killing it if it outlives `timeout`.
+fn run(args: &[String], root: &Path, timeout: Duration) -> color_eyre::Result<String> {
+    let mut child = Command::new(&args[0])
+        .args(&args[1..])
+        .current_dir(root)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Drain the pipes on other threads so a chatty command cannot block on a
+    // full pipe while we wait for it.
+    let stdout = drain(child.stdout.take().ok_or_else(|| eyre!("no stdout"))?);
+    let stderr = drain(child.stderr.take().ok_or_else(|| eyre!("no stderr"))?);
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("timed out after {}s", timeout.as_secs());
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    let stdout = stdout
+        .join()
+        .map_err(|_| eyre!("stdout reader panicked"))??;
+    if !status.success() {
+        let stderr = stderr.join().ok().and_then(Result::ok).unwrap_or_default();
+        bail!("exited with {status}: {}", stderr.trim());
+    }
+    Ok(stdout)
+}
+
+fn drain(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<std::io::Result<String>> {
+    thread::spawn(move || {
+        let mut buf = String::new();
+        pipe.read_to_string(&mut buf).map(|_| buf)
+    })
+}
+
+#[cfg(test)]
+mod tests;
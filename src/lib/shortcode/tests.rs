@@ -0,0 +1,204 @@
+use std::{borrow::Cow, fs, sync::LazyLock, time::Duration};
+
+use serde_json::{Map, Value, json};
+use tempfile::TempDir;
+
+use crate::{
+    config::{CACHE_DIR, MAPS_DIR},
+    diagnostics::{Diagnostics, DocumentSink},
+    map::{MAP_CACHE_DIR, MapPoint, map_name},
+    shortcode::{
+        ShortcodePage, expand_shortcodes, parse_map_shortcode, parse_meta_shortcode,
+        parse_shortcode, run, split_command,
+    },
+};
+
+static EMPTY: LazyLock<Map<String, Value>> = LazyLock::new(Map::new);
+static SINK: LazyLock<DocumentSink> = LazyLock::new(DocumentSink::default);
+
+fn page(root: &std::path::Path) -> ShortcodePage<'_> {
+    ShortcodePage {
+        root,
+        output_dir: root,
+        prefix: "../",
+        extra: &EMPTY,
+        sink: &SINK,
+    }
+}
+
+#[test]
+fn parses_exec_shortcodes() {
+    let (command, len) = parse_shortcode(r#"{{ exec "figlet -f 'big font' Hi" }} tail"#).unwrap();
+    assert_eq!(command, "figlet -f 'big font' Hi");
+    assert_eq!(len, r#"{{ exec "figlet -f 'big font' Hi" }}"#.len());
+
+    let (command, _) = parse_shortcode(r#"{{exec "echo \"q\""}}"#).unwrap();
+    assert_eq!(command, r#"echo "q""#);
+
+    assert!(parse_shortcode("{{ execute \"x\" }}").is_none());
+    assert!(parse_shortcode("{{ exec \"x\"").is_none());
+}
+
+#[test]
+fn commands_split_on_whitespace_outside_single_quotes() {
+    assert_eq!(
+        split_command("figlet  -f 'big font' ''"),
+        vec!["figlet", "-f", "big font", ""]
+    );
+}
+
+#[test]
+fn code_is_left_alone() {
+    let tmp = TempDir::new().unwrap();
+    let md = "Use `{{ exec \"figlet hi\" }}` like so:\n\n```\n{{ exec \"figlet hi\" }}\n```\n";
+
+    let out = expand_shortcodes(md, &page(tmp.path())).unwrap();
+
+    assert!(matches!(out, Cow::Borrowed(_)));
+}
+
+#[test]
+fn disabled_exec_shortcodes_are_reported() {
+    let tmp = TempDir::new().unwrap();
+    let source = "Banner:\n{{ exec \"figlet hi\" }}\n";
+    let diagnostics = Diagnostics::default();
+    let sink = diagnostics.for_document(std::path::Path::new("post.md"), source, Vec::new());
+    let page = ShortcodePage {
+        sink: &sink,
+        ..page(tmp.path())
+    };
+
+    let out = expand_shortcodes(source, &page).unwrap();
+
+    assert_eq!(out, source);
+    let reported = diagnostics.take();
+    assert_eq!(reported.len(), 1);
+    assert_eq!(reported[0].position, Some((2, 1)));
+    assert!(reported[0].message.contains("`figlet hi`"));
+}
+
+#[test]
+fn commands_are_run_and_timed_out() {
+    let tmp = TempDir::new().unwrap();
+    let args = |s: &str| split_command(s);
+
+    let out = run(
+        &args("echo 'hello there'"),
+        tmp.path(),
+        Duration::from_secs(5),
+    )
+    .unwrap();
+    assert_eq!(out, "hello there\n");
+
+    let err = run(&args("sleep 5"), tmp.path(), Duration::from_millis(100)).unwrap_err();
+    assert!(err.to_string().contains("timed out"));
+
+    assert!(run(&args("false"), tmp.path(), Duration::from_secs(5)).is_err());
+}
+
+#[test]
+fn parses_map_shortcodes() {
+    let (point, len) = parse_map_shortcode("{{ map 51.5 -0.12 13 }} tail").unwrap();
+    assert_eq!(point.unwrap(), MapPoint::new(51.5, -0.12, 13).unwrap());
+    assert_eq!(len, "{{ map 51.5 -0.12 13 }}".len());
+
+    assert!(parse_map_shortcode("{{ mapping 1 2 3 }}").is_none());
+    assert!(parse_map_shortcode("{{ map 1 2 3").is_none());
+    assert!(parse_map_shortcode("{{ map 1 2 }}").unwrap().0.is_err());
+    assert!(
+        parse_map_shortcode("{{ map north 2 3 }}")
+            .unwrap()
+            .0
+            .is_err()
+    );
+    assert!(parse_map_shortcode("{{ map 89 0 3 }}").unwrap().0.is_err());
+    assert!(parse_map_shortcode("{{ map 0 0 20 }}").unwrap().0.is_err());
+}
+
+#[test]
+fn cached_maps_are_linked_to_the_interactive_map() {
+    let tmp = TempDir::new().unwrap();
+    let point = MapPoint::new(51.5, -0.12, 13).unwrap();
+    let cache = tmp.path().join(CACHE_DIR).join(MAP_CACHE_DIR);
+    fs::create_dir_all(&cache).unwrap();
+    fs::write(cache.join(map_name(point)), b"png").unwrap();
+
+    let out = expand_shortcodes("{{ map 51.5 -0.12 13 }}\n", &page(tmp.path())).unwrap();
+
+    let image = format!("{MAPS_DIR}/{}", map_name(point));
+    assert!(out.starts_with(r#"<figure class="map"><a href="https://www.openstreetmap.org/?mlat=51.50000&amp;mlon=-0.12000#map=13/51.50000/-0.12000">"#), "{out}");
+    assert!(out.contains(&format!(r#"<img src="../{image}""#)));
+    assert_eq!(fs::read(tmp.path().join(image)).unwrap(), b"png");
+}
+
+#[test]
+fn maps_that_cannot_be_drawn_are_reported() {
+    let tmp = TempDir::new().unwrap();
+    let point = MapPoint::new(51.5, -0.12, 13).unwrap();
+    let cache = tmp.path().join(CACHE_DIR).join(MAP_CACHE_DIR);
+    fs::create_dir_all(&cache).unwrap();
+    fs::write(cache.join(map_name(point)), b"png").unwrap();
+    // An output "directory" that is a file, so the map cannot be copied out.
+    let output_dir = tmp.path().join("public");
+    fs::write(&output_dir, b"").unwrap();
+    let source = "See {{ map 51.5 -0.12 13 }}\n";
+    let diagnostics = Diagnostics::default();
+    let sink = diagnostics.for_document(std::path::Path::new("post.md"), source, Vec::new());
+    let page = ShortcodePage {
+        output_dir: &output_dir,
+        sink: &sink,
+        ..page(tmp.path())
+    };
+
+    let out = expand_shortcodes(source, &page).unwrap();
+
+    assert!(!out.contains("<img"), "{out}");
+    let reported = diagnostics.take();
+    assert_eq!(reported.len(), 1);
+    assert_eq!(reported[0].position, Some((1, 5)));
+    assert!(
+        reported[0]
+            .message
+            .starts_with("map of 51.5, -0.12 left as a link")
+    );
+}
+
+#[test]
+fn parses_meta_shortcodes() {
+    assert_eq!(
+        parse_meta_shortcode("{{ meta venue }} tail"),
+        Some(("venue", "{{ meta venue }}".len()))
+    );
+    assert!(parse_meta_shortcode("{{ metadata venue }}").is_none());
+    assert!(parse_meta_shortcode("{{ meta }}").is_none());
+    assert!(parse_meta_shortcode("{{ meta a b }}").is_none());
+}
+
+#[test]
+fn meta_shortcodes_insert_frontmatter_fields() {
+    let tmp = TempDir::new().unwrap();
+    let Value::Object(extra) = json!({ "venue": "Town Hall", "rating": 4, "cast": ["A", "B"] })
+    else {
+        unreachable!()
+    };
+    let source =
+        "At {{ meta venue }}, rated {{ meta rating }} with {{ meta cast }}; {{ meta missing }}.\n";
+    let diagnostics = Diagnostics::default();
+    let sink = diagnostics.for_document(std::path::Path::new("post.md"), source, Vec::new());
+    let page = ShortcodePage {
+        extra: &extra,
+        sink: &sink,
+        ..page(tmp.path())
+    };
+
+    let out = expand_shortcodes(source, &page).unwrap();
+
+    assert_eq!(
+        out,
+        "At Town Hall, rated 4 with [\"A\",\"B\"]; {{ meta missing }}.\n"
+    );
+    let reported = diagnostics.take();
+    assert_eq!(reported.len(), 1);
+    assert_eq!(reported[0].position, Some((1, 68)));
+    assert!(reported[0].message.contains("`missing`"));
+}
@@ -0,0 +1,167 @@
+//! Resolving clashes between generated names: heading ids within a page and
+//! tag page names across the site. Both follow a configured
+//! [`SlugCollision`] policy so they behave alike.
+use std::{cmp::Reverse, collections::HashMap};
+
+use crate::{
+    config::{SlugCollision, TagOrder},
+    types::Tag,
+};
+
+/// How a claimed slug was resolved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Claim {
+    /// Nothing else had the slug.
+    Fresh(String),
+    /// The slug was taken, so a numbered variant was used instead.
+    Suffixed { taken: String, slug: String },
+    /// The slug was taken and is shared with its first claimant.
+    Merged(String),
+    /// The slug was taken and the policy is to fail.
+    Collided(String),
+}
+
+impl Claim {
+    /// The slug to use, whatever the outcome.
+    pub fn slug(&self) -> &str {
+        match self {
+            Self::Fresh(slug)
+            | Self::Suffixed { slug, .. }
+            | Self::Merged(slug)
+            | Self::Collided(slug) => slug,
+        }
+    }
+
+    /// A description of the decision, or `None` when there was no clash.
+    pub fn describe(&self, what: &str) -> Option<String> {
+        match self {
+            Self::Fresh(_) => None,
+            Self::Suffixed { taken, slug } => Some(format!(
+                "{what} `{taken}` is already used; using `{slug}` instead"
+            )),
+            Self::Merged(slug) => Some(format!("{what} `{slug}` is shared with an earlier one")),
+            Self::Collided(slug) => Some(format!("{what} `{slug}` is already used")),
+        }
+    }
+}
+
+/// Slugs handed out so far within one namespace.
+#[derive(Debug)]
+pub struct Slugs {
+    policy: SlugCollision,
+    counts: HashMap<String, usize>,
+}
+
+impl Slugs {
+    pub fn new(policy: SlugCollision) -> Self {
+        Self {
+            policy,
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Claim `base`, resolving a clash according to the policy. Suffixed
+    /// slugs are themselves claimed, so `a-2` is never handed out twice.
+    pub fn claim(&mut self, base: String) -> Claim {
+        let count = self.counts.entry(base.clone()).or_insert(0);
+        *count += 1;
+        if *count == 1 {
+            return Claim::Fresh(base);
+        }
+
+        match self.policy {
+            SlugCollision::Merge => Claim::Merged(base),
+            SlugCollision::Error => Claim::Collided(base),
+            SlugCollision::Suffix => {
+                let mut n = *count;
+                let slug = loop {
+                    let candidate = format!("{base}-{n}");
+                    if !self.counts.contains_key(&candidate) {
+                        break candidate;
+                    }
+                    n += 1;
+                };
+                self.counts.insert(slug.clone(), 1);
+                Claim::Suffixed { taken: base, slug }
+            }
+        }
+    }
+}
+
+/// Site-wide mapping from each tag as written to the tag it is filed under.
+/// Tags clash when they differ only in case, since their pages would too on
+/// case-insensitive filesystems and hosts.
+#[derive(Debug)]
+pub struct TagNames {
+    slugs: Slugs,
+    /// First spelling to claim each case-folded name.
+    firsts: HashMap<String, Tag>,
+    names: HashMap<Tag, Tag>,
+    /// Number of posts filed under each tag.
+    uses: HashMap<Tag, usize>,
+}
+
+impl TagNames {
+    pub fn new(policy: SlugCollision) -> Self {
+        Self {
+            slugs: Slugs::new(policy),
+            firsts: HashMap::new(),
+            names: HashMap::new(),
+            uses: HashMap::new(),
+        }
+    }
+
+    /// File `tag`, returning how its name was claimed the first time this
+    /// spelling is seen.
+    pub fn insert(&mut self, tag: &Tag) -> Option<Claim> {
+        if self.names.contains_key(tag) {
+            return None;
+        }
+        let key = tag.as_str().to_lowercase();
+        let claim = self.slugs.claim(key.clone());
+        let filed = match &claim {
+            Claim::Fresh(_) => {
+                self.firsts.insert(key, tag.clone());
+                tag.clone()
+            }
+            Claim::Suffixed { slug, .. } => {
+                let suffix = &slug[key.len()..];
+                Tag::parse(&format!("{tag}{suffix}")).unwrap_or_else(|| tag.clone())
+            }
+            Claim::Merged(_) | Claim::Collided(_) => self
+                .firsts
+                .get(&key)
+                .cloned()
+                .unwrap_or_else(|| tag.clone()),
+        };
+        self.names.insert(tag.clone(), filed);
+        Some(claim)
+    }
+
+    /// The tag `tag` is filed under.
+    pub fn resolve(&self, tag: &Tag) -> Tag {
+        self.names.get(tag).cloned().unwrap_or_else(|| tag.clone())
+    }
+
+    /// Count a post filed under each of `tags`, for [`TagOrder::Frequency`].
+    pub fn count_uses(&mut self, tags: &[Tag]) {
+        for tag in tags {
+            *self.uses.entry(tag.clone()).or_default() += 1;
+        }
+    }
+
+    /// Put a post's (filed) tags in `order`.
+    pub fn sort(&self, tags: &mut [Tag], order: TagOrder) {
+        let name = |tag: &Tag| (tag.as_str().to_lowercase(), tag.clone());
+        match order {
+            TagOrder::Name => tags.sort_by_cached_key(name),
+            TagOrder::Frequency => tags.sort_by_cached_key(|tag| {
+                let uses = self.uses.get(tag).copied().unwrap_or_default();
+                (Reverse(uses), name(tag))
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
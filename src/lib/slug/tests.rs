@@ -0,0 +1,65 @@
+use crate::{
+    config::{SlugCollision, TagOrder},
+    slug::{Claim, Slugs, TagNames},
+    types::Tag,
+};
+
+#[test]
+fn suffixes_skip_slugs_already_taken() {
+    let mut slugs = Slugs::new(SlugCollision::Suffix);
+
+    assert_eq!(
+        slugs.claim("intro-2".into()),
+        Claim::Fresh("intro-2".into())
+    );
+    assert_eq!(slugs.claim("intro".into()), Claim::Fresh("intro".into()));
+    let again = slugs.claim("intro".into());
+
+    assert_eq!(again.slug(), "intro-3");
+    assert_eq!(
+        again.describe("heading id").as_deref(),
+        Some("heading id `intro` is already used; using `intro-3` instead")
+    );
+}
+
+#[test]
+fn merge_and_error_keep_the_slug() {
+    let mut merge = Slugs::new(SlugCollision::Merge);
+    let mut error = Slugs::new(SlugCollision::Error);
+    merge.claim("a".into());
+    error.claim("a".into());
+
+    assert_eq!(merge.claim("a".into()), Claim::Merged("a".into()));
+    assert_eq!(error.claim("a".into()), Claim::Collided("a".into()));
+}
+
+#[test]
+fn tags_differing_in_case_merge_or_suffix() {
+    let (lower, upper) = (Tag::parse("rust").unwrap(), Tag::parse("Rust").unwrap());
+    let mut merged = TagNames::new(SlugCollision::Merge);
+    let mut suffixed = TagNames::new(SlugCollision::Suffix);
+    for names in [&mut merged, &mut suffixed] {
+        assert_eq!(names.insert(&lower), Some(Claim::Fresh("rust".into())));
+        assert!(names.insert(&upper).is_some());
+        assert_eq!(names.insert(&upper), None);
+    }
+
+    assert_eq!(merged.resolve(&upper), lower);
+    assert_eq!(suffixed.resolve(&upper).as_str(), "Rust-2");
+    assert_eq!(suffixed.resolve(&lower), lower);
+}
+
+#[test]
+fn tags_sort_by_name_or_frequency() {
+    let tag = |t: &str| Tag::parse(t).unwrap();
+    let mut names = TagNames::new(SlugCollision::Merge);
+    names.count_uses(&[tag("zig"), tag("Rust")]);
+    names.count_uses(&[tag("zig")]);
+
+    let mut tags = vec![tag("zig"), tag("c"), tag("Rust")];
+    names.sort(&mut tags, TagOrder::Name);
+    assert_eq!(tags, [tag("c"), tag("Rust"), tag("zig")]);
+
+    names.sort(&mut tags, TagOrder::Frequency);
+    assert_eq!(tags, [tag("zig"), tag("Rust"), tag("c")]);
+}
@@ -0,0 +1,91 @@
+//! CSV and TSV files turned into Markdown tables, so `{{ table "..." }}`
+//! directives (see `crate::include`) go through the same table rendering as
+//! hand-written ones. The first row is the header; cells may hold inline
+//! Markdown.
+use std::path::Path;
+
+use color_eyre::eyre::{bail, eyre};
+
+/// Field separator for the file at `path`: tabs for `.tsv` and `.tab`,
+/// commas otherwise.
+pub fn delimiter_for(path: &Path) -> char {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("tsv" | "tab") => '\t',
+        _ => ',',
+    }
+}
+
+/// Split `text` into rows of fields. Fields may be quoted with `"`, inside
+/// which delimiters and line breaks are literal and `""` is a quote.
+pub fn parse_delimited(text: &str, delimiter: char) -> color_eyre::Result<Vec<Vec<String>>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            c if quoted => field.push(c),
+            c if c == delimiter => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        bail!("unterminated quoted field in row {}", rows.len() + 1);
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows.retain(|row| row.iter().any(|field| !field.is_empty()));
+    Ok(rows)
+}
+
+/// `rows` as a GFM table, the first row as its header. Short rows are
+/// padded with empty cells.
+pub fn markdown_table(rows: &[Vec<String>]) -> color_eyre::Result<String> {
+    let (header, body) = rows
+        .split_first()
+        .ok_or_else(|| eyre!("table has no rows"))?;
+    let columns = rows.iter().map(Vec::len).max().unwrap_or_default();
+
+    let line = |row: &[String]| {
+        let cells: Vec<String> = (0..columns)
+            .map(|i| {
+                row.get(i)
+                    .map_or_else(String::new, |cell| escape_cell(cell))
+            })
+            .collect();
+        format!("| {} |\n", cells.join(" | "))
+    };
+
+    let mut table = line(header);
+    table.push_str(&format!("|{}\n", " --- |".repeat(columns)));
+    for row in body {
+        table.push_str(&line(row));
+    }
+    Ok(table)
+}
+
+/// A field as a table cell: pipes escaped, line breaks as `<br>`.
+fn escape_cell(cell: &str) -> String {
+    cell.trim()
+        .replace('|', r"\|")
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,42 @@
+use std::path::Path;
+
+use crate::table::{delimiter_for, markdown_table, parse_delimited};
+
+#[test]
+fn quoted_fields_keep_delimiters_quotes_and_breaks() {
+    let csv = "name,notes\r\n\"Smith, J\",\"said \"\"hi\"\"\nthen left\"\n\nplain,\n";
+    let rows = parse_delimited(csv, ',').unwrap();
+    assert_eq!(
+        rows,
+        [
+            vec!["name", "notes"],
+            vec!["Smith, J", "said \"hi\"\nthen left"],
+            vec!["plain", ""],
+        ]
+    );
+
+    assert!(parse_delimited("a,\"open\n", ',').is_err());
+}
+
+#[test]
+fn tabs_separate_tsv_files() {
+    assert_eq!(delimiter_for(Path::new("data/x.tsv")), '\t');
+    assert_eq!(delimiter_for(Path::new("data/x.csv")), ',');
+    let rows = parse_delimited("a\tb\n1\t2", '\t').unwrap();
+    assert_eq!(rows, [vec!["a", "b"], vec!["1", "2"]]);
+}
+
+#[test]
+fn rows_become_a_gfm_table() {
+    let rows = parse_delimited("Item,Price,Note\nTea,3\nPipe,1,\"a|b\nc\"\n", ',').unwrap();
+    assert_eq!(
+        markdown_table(&rows).unwrap(),
+        concat!(
+            "| Item | Price | Note |\n",
+            "| --- | --- | --- |\n",
+            "| Tea | 3 |  |\n",
+            "| Pipe | 1 | a\\|b<br>c |\n",
+        )
+    );
+    assert!(markdown_table(&[]).is_err());
+}
@@ -0,0 +1,80 @@
+//! Human titles and descriptions for tags. `TAGS_FILE` maps tag names to
+//! what their listing pages and feeds should say in place of the generic
+//! "Tag: …" and "Posts tagged …":
+//!
+//! ```yaml
+//! rust:
+//!   title: Rust
+//!   description: Notes on writing and shipping Rust.
+//! ```
+//!
+//! Names match whatever their case, as tags do.
+use std::{collections::BTreeMap, path::Path};
+
+use color_eyre::Section;
+use gray_matter::{
+    Pod,
+    engine::{Engine, YAML},
+};
+use serde::Deserialize;
+
+use crate::types::Tag;
+
+/// One entry of `TAGS_FILE`.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagInfo {
+    pub title: Option<String>,
+    pub description: Option<String>,
+}
+
+/// The tags described in `TAGS_FILE`, by case-folded name.
+#[derive(Debug, Default)]
+pub struct TagInfos {
+    listed: BTreeMap<String, TagInfo>,
+}
+
+impl TagInfos {
+    /// Read the file; a missing file describes no tags.
+    pub fn load(path: &Path) -> color_eyre::Result<Self> {
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let raw = std::fs::read_to_string(path)?;
+        Self::parse(&raw).with_note(|| format!("While parsing {}", path.display()))
+    }
+
+    pub fn parse(raw: &str) -> color_eyre::Result<Self> {
+        let pod = YAML::parse(raw)?;
+        if matches!(pod, Pod::Null) {
+            return Ok(Self::default());
+        }
+        let listed: BTreeMap<String, Option<TagInfo>> = pod.deserialize()?;
+        Ok(Self {
+            listed: listed
+                .into_iter()
+                .map(|(name, info)| (name.to_lowercase(), info.unwrap_or_default()))
+                .collect(),
+        })
+    }
+
+    pub fn get(&self, tag: &Tag) -> Option<&TagInfo> {
+        self.listed.get(&tag.as_str().to_lowercase())
+    }
+
+    /// Title of `tag`'s listing page.
+    pub fn title(&self, tag: &Tag) -> String {
+        self.get(tag)
+            .and_then(|info| info.title.clone())
+            .unwrap_or_else(|| format!("Tag: {tag}"))
+    }
+
+    /// One-line description of `tag`'s posts, for meta tags and feeds.
+    pub fn description(&self, tag: &Tag) -> String {
+        self.get(tag)
+            .and_then(|info| info.description.clone())
+            .unwrap_or_else(|| format!("Posts tagged {tag}"))
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,21 @@
+use crate::{tag_info::TagInfos, types::Tag};
+
+#[test]
+fn described_tags_replace_the_generic_wording() {
+    let infos = TagInfos::parse(
+        "Rust:\n  title: The Rust language\n  description: Notes on Rust.\nweb:\n  description: Building for browsers.\nlife:\n",
+    )
+    .unwrap();
+    let rust = Tag::parse("rust").unwrap();
+    let web = Tag::parse("web").unwrap();
+    let cli = Tag::parse("cli").unwrap();
+
+    assert_eq!(infos.title(&rust), "The Rust language");
+    assert_eq!(infos.description(&rust), "Notes on Rust.");
+    assert_eq!(infos.title(&web), "Tag: web");
+    assert_eq!(infos.description(&web), "Building for browsers.");
+    assert!(infos.get(&Tag::parse("life").unwrap()).is_some());
+    assert!(infos.get(&cli).is_none());
+    assert_eq!(infos.description(&cli), "Posts tagged cli");
+    assert!(TagInfos::parse("").unwrap().get(&rust).is_none());
+}
@@ -1,62 +1,322 @@
-/// Basic HTML shell shared by pages.
-pub fn page_shell(
-    head_common: &str,
-    head_fragment: &str,
-    body_header: &str,
-    body: &str,
-    footer: &str,
-) -> String {
-    format!(
-        r#"<!doctype html>
-<html lang="en">
+//! Page shells rendered through minijinja.
+//!
+//! Each shell has a built-in default; dropping a file with the same name into
+//! `templates/` overrides it. Templates may also `include`/`extend` any other
+//! file placed in that directory. Pre-rendered HTML fragments (head, body,
+//! footer) are passed as safe strings, so they need no `|safe` filter. So is
+//! `nav`, the site menu from `NAV` with links relative to the page.
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::Section;
+use minijinja::{Environment, Error, ErrorKind, Value, context};
+use serde::Serialize;
+use serde_json::{Map, Value as JsonValue};
+
+use crate::{
+    article::Article,
+    config::SiteMeta,
+    utils::{escape_attr, escape_text},
+};
+
+/// Template used for individual article pages.
+pub const PAGE_TEMPLATE: &str = "page.html";
+/// Template used for index, tag, and other listing pages.
+pub const LISTING_TEMPLATE: &str = "listing.html";
+/// Template for the human-readable page listing the site's feeds.
+pub const FEEDS_TEMPLATE: &str = "feeds.html";
+
+const DEFAULT_PAGE: &str = r#"<!doctype html>
+<html lang="{{ site.language }}">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+{{ head_common }}
+{{ head }}
+</head>
+<body>
+{{ nav }}
+<article>
+<section>
+{{ header }}
+{{ body }}
+</section>
+</article>
+</body>
+{{ footer }}
+</html>
+"#;
+
+const DEFAULT_LISTING: &str = r#"<!doctype html>
+<html lang="{{ site.language }}">
 <head>
 <meta charset="utf-8">
 <meta name="viewport" content="width=device-width, initial-scale=1">
-{head_common}
-{head_fragment}
+{{ head }}
+<title>{{ title }}</title>
+<link rel="stylesheet" href="{{ root }}style.css">
 </head>
 <body>
+{{ nav }}
 <article>
 <section>
-{body_header}
-{body}
+<h1>{{ heading }}</h1>
+{{ body }}
 </section>
 </article>
 </body>
-{footer}
 </html>
-"#
-    )
-}
-
-/// Render a listing page given shared head and href prefix.
-pub fn listing_page(
-    page_title: &str,
-    heading: &str,
-    body: &str,
-    head_includes: &str,
-    href_prefix: &str,
-) -> String {
-    format!(
-        r#"<!doctype html>
-<html lang="en">
+"#;
+
+const DEFAULT_FEEDS: &str = r#"<!doctype html>
+<html lang="{{ site.language }}">
 <head>
 <meta charset="utf-8">
 <meta name="viewport" content="width=device-width, initial-scale=1">
-{head_includes}
-<title>{}</title>
-<link rel="stylesheet" href="{}style.css">
+{{ head }}
+<title>{{ title }}</title>
+<link rel="stylesheet" href="{{ root }}style.css">
 </head>
 <body>
+{{ nav }}
 <article>
 <section>
-<h1>{}</h1>
-{}
+<h1>{{ heading }}</h1>
+<p>A feed lets you follow {{ site.title }} from a feed reader, which checks for
+new posts so you don't have to. Paste one of these addresses into your reader;
+they carry the same posts in different formats.</p>
+<ul class="feed-list">
+{%- for feed in feeds %}
+<li>{{ feed.title }} ({{ feed.format }}): <a href="{{ feed.href }}"><code>{{ feed.url }}</code></a></li>
+{%- endfor %}
+</ul>
+<p>Every tag page also links feeds of just that tag's posts.</p>
 </section>
 </article>
 </body>
 </html>
-"#,
-        page_title, href_prefix, heading, body
-    )
+"#;
+
+fn builtin(name: &str) -> Option<&'static str> {
+    match name {
+        PAGE_TEMPLATE => Some(DEFAULT_PAGE),
+        LISTING_TEMPLATE => Some(DEFAULT_LISTING),
+        FEEDS_TEMPLATE => Some(DEFAULT_FEEDS),
+        _ => None,
+    }
+}
+
+/// Article metadata exposed to templates as `article` (pages) or as the
+/// entries of `articles` (listings).
+#[derive(Serialize, Debug)]
+pub struct ArticleView {
+    pub title: String,
+    /// Link to the article relative to the page being rendered.
+    pub url: String,
+    pub ctime: Option<String>,
+    pub updated: Option<String>,
+    pub summary: Option<String>,
+    pub tags: Vec<String>,
+    /// Link to the post's thumbnail relative to the page being rendered.
+    pub thumbnail: Option<String>,
+    /// Custom frontmatter fields, e.g. `article.extra.rating`.
+    pub extra: Map<String, JsonValue>,
+}
+
+impl ArticleView {
+    pub fn new(article: &Article, href_prefix: &str) -> Self {
+        Self {
+            title: article.title.clone(),
+            url: format!("{href_prefix}{}", article.href),
+            ctime: article.ctime.as_ref().map(|d| d.as_str()),
+            updated: article.updated.as_ref().map(|d| d.as_str()),
+            summary: article.summary.clone(),
+            tags: article.tags.iter().map(|t| t.to_string()).collect(),
+            thumbnail: article
+                .thumbnail
+                .as_ref()
+                .map(|thumb| format!("{href_prefix}{thumb}")),
+            extra: article.extra.clone(),
+        }
+    }
 }
+
+/// Pieces of an article page handed to `page.html`.
+pub struct PageContext<'a> {
+    pub article: &'a Article,
+    pub href_prefix: &'a str,
+    pub head_common: &'a str,
+    pub head: &'a str,
+    pub header: &'a str,
+    pub body: &'a str,
+    pub footer: &'a str,
+}
+
+/// Pieces of a listing page handed to `listing.html`.
+pub struct ListingContext<'a> {
+    /// The page's own title; templates get it wrapped per the title template.
+    pub title: &'a str,
+    pub heading: &'a str,
+    pub articles: &'a [Article],
+    pub href_prefix: &'a str,
+    pub head: &'a str,
+    /// Default rendering of the article list, for templates that only want to
+    /// change the surrounding markup.
+    pub body: &'a str,
+}
+
+/// One feed offered on the feeds page.
+#[derive(Serialize, Debug)]
+pub struct FeedLink {
+    /// What the feed carries, e.g. `Posts` or `Notes`.
+    pub title: String,
+    /// `RSS`, `Atom`, or `JSON Feed`.
+    pub format: &'static str,
+    /// Link to the feed relative to the feeds page.
+    pub href: String,
+    /// Absolute URL, for pasting into a reader.
+    pub url: String,
+}
+
+/// Pieces of the feeds page handed to `feeds.html`.
+pub struct FeedsContext<'a> {
+    /// The page's own title, which templates get as `heading`.
+    pub title: &'a str,
+    pub feeds: &'a [FeedLink],
+    pub href_prefix: &'a str,
+    pub head: &'a str,
+}
+
+/// The template environment used for a build.
+pub struct Templates {
+    env: Environment<'static>,
+    site: SiteMeta,
+}
+
+impl Templates {
+    /// Use only the built-in templates.
+    pub fn builtin(site: &SiteMeta) -> Self {
+        Self::new(None, site)
+    }
+
+    /// Prefer templates found in `dir`, falling back to the built-ins.
+    pub fn load(dir: &Path, site: &SiteMeta) -> Self {
+        Self::new(Some(dir.to_path_buf()), site)
+    }
+
+    fn new(dir: Option<PathBuf>, site: &SiteMeta) -> Self {
+        let mut env = Environment::new();
+        env.set_keep_trailing_newline(true);
+        env.add_global("site", Value::from_serialize(site));
+        env.set_loader(move |name| {
+            if let Some(path) = dir.as_ref().map(|d| d.join(name))
+                && path.is_file()
+            {
+                return fs::read_to_string(&path).map(Some).map_err(|e| {
+                    Error::new(
+                        ErrorKind::InvalidOperation,
+                        format!("could not read template {}", path.display()),
+                    )
+                    .with_source(e)
+                });
+            }
+            Ok(builtin(name).map(str::to_owned))
+        });
+        Self {
+            env,
+            site: site.clone(),
+        }
+    }
+
+    /// Render an article page.
+    pub fn page(&self, ctx: &PageContext<'_>) -> color_eyre::Result<String> {
+        self.render(
+            PAGE_TEMPLATE,
+            context! {
+                article => ArticleView::new(ctx.article, ctx.href_prefix),
+                root => ctx.href_prefix,
+                nav => self.nav(ctx.href_prefix),
+                head_common => Value::from_safe_string(ctx.head_common.to_owned()),
+                head => Value::from_safe_string(ctx.head.to_owned()),
+                header => Value::from_safe_string(ctx.header.to_owned()),
+                body => Value::from_safe_string(ctx.body.to_owned()),
+                footer => Value::from_safe_string(ctx.footer.to_owned()),
+            },
+        )
+    }
+
+    /// Render a listing page.
+    pub fn listing(&self, ctx: &ListingContext<'_>) -> color_eyre::Result<String> {
+        let articles: Vec<ArticleView> = ctx
+            .articles
+            .iter()
+            .map(|a| ArticleView::new(a, ctx.href_prefix))
+            .collect();
+
+        self.render(
+            LISTING_TEMPLATE,
+            context! {
+                title => self.site.page_title(ctx.title),
+                heading => ctx.heading,
+                articles => articles,
+                root => ctx.href_prefix,
+                nav => self.nav(ctx.href_prefix),
+                head => Value::from_safe_string(ctx.head.to_owned()),
+                body => Value::from_safe_string(ctx.body.to_owned()),
+            },
+        )
+    }
+
+    /// Render the page listing the site's feeds.
+    pub fn feeds(&self, ctx: &FeedsContext<'_>) -> color_eyre::Result<String> {
+        self.render(
+            FEEDS_TEMPLATE,
+            context! {
+                title => self.site.page_title(ctx.title),
+                heading => ctx.title,
+                feeds => ctx.feeds,
+                root => ctx.href_prefix,
+                nav => self.nav(ctx.href_prefix),
+                head => Value::from_safe_string(ctx.head.to_owned()),
+            },
+        )
+    }
+
+    /// The site menu for a page `href_prefix` away from the root, or nothing
+    /// without one.
+    fn nav(&self, href_prefix: &str) -> Value {
+        if self.site.nav.is_empty() {
+            return Value::from_safe_string(String::new());
+        }
+        let items: String = self
+            .site
+            .nav
+            .iter()
+            .map(|link| {
+                let href = if link.href.starts_with('/') || link.href.contains(':') {
+                    link.href.to_owned()
+                } else {
+                    format!("{href_prefix}{}", link.href)
+                };
+                format!(
+                    r#"<li><a href="{}">{}</a></li>"#,
+                    escape_attr(&href),
+                    escape_text(link.label)
+                )
+            })
+            .collect();
+        Value::from_safe_string(format!(r#"<nav class="site-nav"><ul>{items}</ul></nav>"#))
+    }
+
+    fn render(&self, name: &str, ctx: Value) -> color_eyre::Result<String> {
+        self.env
+            .get_template(name)
+            .and_then(|t| t.render(ctx))
+            .with_note(|| format!("While rendering template {name}"))
+    }
+}
+
+#[cfg(test)]
+mod tests;
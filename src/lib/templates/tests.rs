@@ -0,0 +1,175 @@
+use std::fs;
+
+use tempfile::TempDir;
+
+use crate::{
+    article::Article,
+    config::{NavLink, SITE_LANGUAGE, site_meta},
+    templates::{
+        FeedLink, FeedsContext, LISTING_TEMPLATE, ListingContext, PAGE_TEMPLATE, PageContext,
+        Templates,
+    },
+};
+
+fn article() -> Article {
+    Article::test("Hello <World>")
+        .at("posts/hello.html")
+        .dated("2024-01-02")
+        .tagged(&["rust"])
+}
+
+fn page_ctx<'a>(article: &'a Article) -> PageContext<'a> {
+    PageContext {
+        article,
+        href_prefix: "../",
+        head_common: "<meta name=common>",
+        head: "<title>Hello</title>",
+        header: "<h1>Hello</h1>",
+        body: "<p>Body</p>",
+        footer: "<footer>Foot</footer>",
+    }
+}
+
+#[test]
+fn builtin_page_inserts_fragments_unescaped() {
+    let templates = Templates::builtin(&site_meta());
+    let article = article();
+    let html = templates.page(&page_ctx(&article)).unwrap();
+
+    assert!(html.starts_with("<!doctype html>"));
+    assert!(html.contains(&format!(r#"<html lang="{SITE_LANGUAGE}">"#)));
+    for piece in [
+        "<meta name=common>",
+        "<title>Hello</title>",
+        "<h1>Hello</h1>",
+        "<p>Body</p>",
+        "<footer>Foot</footer>",
+    ] {
+        assert!(html.contains(piece), "missing {piece}");
+    }
+}
+
+#[test]
+fn user_templates_override_builtins_and_see_context() {
+    let tmp = TempDir::new().unwrap();
+    fs::write(
+        tmp.path().join(PAGE_TEMPLATE),
+        "{{ site.title }}|{{ article.title }}|{{ article.url }}|{{ article.tags|join(',') }}|{% include 'partial.html' %}",
+    )
+    .unwrap();
+    fs::write(tmp.path().join("partial.html"), "{{ body }}").unwrap();
+
+    let site = site_meta();
+    let templates = Templates::load(tmp.path(), &site);
+    let article = article();
+    let html = templates.page(&page_ctx(&article)).unwrap();
+
+    assert_eq!(
+        html,
+        format!(
+            "{}|Hello &lt;World&gt;|..&#x2f;posts&#x2f;hello.html|rust|<p>Body</p>",
+            site.title
+        )
+    );
+}
+
+#[test]
+fn listing_exposes_articles() {
+    let tmp = TempDir::new().unwrap();
+    fs::write(
+        tmp.path().join(LISTING_TEMPLATE),
+        "{{ heading }}{% for a in articles %}[{{ a.title }} {{ a.ctime }}]{% endfor %}",
+    )
+    .unwrap();
+
+    let templates = Templates::load(tmp.path(), &site_meta());
+    let articles = vec![article()];
+    let html = templates
+        .listing(&ListingContext {
+            title: "T",
+            heading: "Heading",
+            articles: &articles,
+            href_prefix: "",
+            head: "",
+            body: "",
+        })
+        .unwrap();
+
+    assert_eq!(html, "Heading[Hello &lt;World&gt; 2024-01-02]");
+}
+
+#[test]
+fn missing_template_dir_falls_back_to_builtins() {
+    let tmp = TempDir::new().unwrap();
+    let templates = Templates::load(&tmp.path().join("absent"), &site_meta());
+    let article = article();
+    assert!(templates.page(&page_ctx(&article)).is_ok());
+}
+
+#[test]
+fn builtin_feeds_page_lists_feeds_escaped() {
+    let templates = Templates::builtin(&site_meta());
+    let feeds = [FeedLink {
+        title: "Posts & notes".to_string(),
+        format: "RSS",
+        href: "../rss.xml".to_string(),
+        url: "https://example.com/rss.xml".to_string(),
+    }];
+    let html = templates
+        .feeds(&FeedsContext {
+            title: "Feeds",
+            feeds: &feeds,
+            href_prefix: "../",
+            head: "<link rel=alternate>",
+        })
+        .unwrap();
+
+    assert!(html.contains(&format!(r#"<html lang="{SITE_LANGUAGE}">"#)));
+    assert!(html.contains("<link rel=alternate>"));
+    assert!(html.contains("Posts &amp; notes (RSS)"));
+    assert!(html.contains(
+        r#"<a href="..&#x2f;rss.xml"><code>https:&#x2f;&#x2f;example.com&#x2f;rss.xml</code></a>"#
+    ));
+}
+
+#[test]
+fn nav_links_are_relative_to_each_page() {
+    let mut site = site_meta();
+    site.nav = vec![
+        NavLink {
+            label: "Home",
+            href: "index.html",
+        },
+        NavLink {
+            label: "Code & such",
+            href: "https://example.com/code",
+        },
+    ];
+    let templates = Templates::builtin(&site);
+    let article = article();
+
+    let page = templates.page(&page_ctx(&article)).unwrap();
+    assert!(
+        page.contains(r#"<nav class="site-nav"><ul><li><a href="../index.html">Home</a></li><li><a href="https://example.com/code">Code &amp; such</a></li></ul></nav>"#),
+        "{page}"
+    );
+
+    let listing = templates
+        .listing(&ListingContext {
+            title: "Index",
+            heading: "Index",
+            articles: &[],
+            href_prefix: "",
+            head: "",
+            body: "",
+        })
+        .unwrap();
+    assert!(
+        listing.contains(r#"<a href="index.html">Home</a>"#),
+        "{listing}"
+    );
+
+    site.nav.clear();
+    let bare = Templates::builtin(&site).page(&page_ctx(&article)).unwrap();
+    assert!(!bare.contains("<nav"), "{bare}");
+}
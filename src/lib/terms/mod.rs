@@ -0,0 +1,258 @@
+//! Term statistics over the plain text of every post: TF-IDF keywords per
+//! post, posts related by what they say rather than by their tags, and a
+//! vocabulary page of the site's most used terms with the posts using them.
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    article::Article,
+    types::Href,
+    utils::{escape_attr, escape_text},
+};
+
+/// Words too common to say anything about a post.
+const STOPWORDS: &[&str] = &[
+    "about", "above", "after", "again", "against", "all", "also", "and", "any", "are", "because",
+    "been", "before", "being", "below", "between", "both", "but", "can", "could", "did", "does",
+    "doing", "down", "during", "each", "even", "few", "for", "from", "further", "get", "had",
+    "has", "have", "having", "her", "here", "hers", "him", "his", "how", "into", "its", "itself",
+    "just", "like", "more", "most", "much", "must", "not", "now", "off", "once", "one", "only",
+    "other", "our", "ours", "out", "over", "own", "same", "she", "should", "some", "such", "than",
+    "that", "the", "their", "theirs", "them", "then", "there", "these", "they", "this", "those",
+    "through", "too", "under", "until", "use", "used", "using", "very", "was", "way", "well",
+    "were", "what", "when", "where", "which", "while", "who", "whom", "why", "will", "with",
+    "would", "yet", "you", "your", "yours",
+];
+/// Shortest word counted as a term.
+const MIN_TERM_CHARS: usize = 3;
+/// Cosine similarity below which two posts are not suggested together.
+const MIN_SIMILARITY: f64 = 0.1;
+/// Terms two posts must share to be suggested together; one word in common
+/// is a coincidence.
+const MIN_SHARED_TERMS: usize = 2;
+
+/// The terms of a set of posts, weighed against each other.
+#[derive(Debug, Default)]
+pub struct Corpus {
+    documents: Vec<Document>,
+    /// Number of documents each term appears in.
+    document_frequency: HashMap<String, usize>,
+}
+
+#[derive(Debug)]
+struct Document {
+    href: Href,
+    counts: HashMap<String, usize>,
+    /// TF-IDF weight of each term.
+    weights: HashMap<String, f64>,
+    /// Euclidean length of `weights`.
+    norm: f64,
+}
+
+/// How often a term is used across the site, and where.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TermUse<'a> {
+    pub term: &'a str,
+    pub count: usize,
+    /// The posts using it, most uses first.
+    pub hrefs: Vec<&'a Href>,
+}
+
+impl Corpus {
+    /// Count the terms of each `(href, text)`.
+    pub fn new<'a>(texts: impl IntoIterator<Item = (&'a Href, &'a str)>) -> Self {
+        let mut document_frequency: HashMap<String, usize> = HashMap::new();
+        let mut counted = Vec::new();
+        for (href, text) in texts {
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            for term in terms(text) {
+                *counts.entry(term).or_default() += 1;
+            }
+            for term in counts.keys() {
+                *document_frequency.entry(term.clone()).or_default() += 1;
+            }
+            counted.push((href.clone(), counts));
+        }
+
+        // Smoothed IDF, so a term every post uses still weighs something.
+        let total = counted.len() as f64;
+        let idf = |term: &str| {
+            let df = document_frequency.get(term).copied().unwrap_or_default() as f64;
+            ((1.0 + total) / (1.0 + df)).ln() + 1.0
+        };
+        let documents = counted
+            .into_iter()
+            .map(|(href, counts)| {
+                let len = counts.values().sum::<usize>().max(1) as f64;
+                let weights: HashMap<String, f64> = counts
+                    .iter()
+                    .map(|(term, &count)| (term.clone(), count as f64 / len * idf(term)))
+                    .collect();
+                let norm = weights.values().map(|w| w * w).sum::<f64>().sqrt();
+                Document {
+                    href,
+                    counts,
+                    weights,
+                    norm,
+                }
+            })
+            .collect();
+        Self {
+            documents,
+            document_frequency,
+        }
+    }
+
+    /// Number of term uses across every post.
+    pub fn term_count(&self) -> usize {
+        self.documents
+            .iter()
+            .map(|doc| doc.counts.values().sum::<usize>())
+            .sum()
+    }
+
+    /// Number of distinct terms.
+    pub fn vocabulary_size(&self) -> usize {
+        self.document_frequency.len()
+    }
+
+    /// Up to `limit` terms characterising the post at `href`, strongest
+    /// first.
+    pub fn keywords(&self, href: &Href, limit: usize) -> Vec<&str> {
+        let Some(doc) = self.document(href) else {
+            return Vec::new();
+        };
+        let mut weighed: Vec<(&str, f64)> = doc
+            .weights
+            .iter()
+            .map(|(term, &weight)| (term.as_str(), weight))
+            .collect();
+        weighed.sort_by(|(ta, wa), (tb, wb)| wb.total_cmp(wa).then_with(|| ta.cmp(tb)));
+        weighed
+            .into_iter()
+            .take(limit)
+            .map(|(term, _)| term)
+            .collect()
+    }
+
+    /// Up to `limit` other posts whose terms are most like those of the post
+    /// at `href`, most similar first, leaving out `exclude`.
+    pub fn similar(&self, href: &Href, limit: usize, exclude: &[&Href]) -> Vec<&Href> {
+        let Some(doc) = self.document(href) else {
+            return Vec::new();
+        };
+        let mut scored: Vec<(f64, &Href)> = self
+            .documents
+            .iter()
+            .filter(|other| other.href != doc.href && !exclude.contains(&&other.href))
+            .map(|other| (cosine(doc, other), &other.href))
+            .filter(|(score, _)| *score >= MIN_SIMILARITY)
+            .collect();
+        scored.sort_by(|(sa, ha), (sb, hb)| {
+            sb.total_cmp(sa).then_with(|| ha.as_str().cmp(hb.as_str()))
+        });
+        scored
+            .into_iter()
+            .take(limit)
+            .map(|(_, href)| href)
+            .collect()
+    }
+
+    /// The `limit` most used terms, most used first.
+    pub fn top_terms(&self, limit: usize) -> Vec<TermUse<'_>> {
+        let mut uses: BTreeMap<&str, Vec<(usize, &Href)>> = BTreeMap::new();
+        for doc in &self.documents {
+            for (term, &count) in &doc.counts {
+                uses.entry(term).or_default().push((count, &doc.href));
+            }
+        }
+        let mut top: Vec<TermUse<'_>> = uses
+            .into_iter()
+            .map(|(term, mut users)| {
+                users.sort_by(|(ca, ha), (cb, hb)| {
+                    cb.cmp(ca).then_with(|| ha.as_str().cmp(hb.as_str()))
+                });
+                TermUse {
+                    term,
+                    count: users.iter().map(|(count, _)| count).sum(),
+                    hrefs: users.into_iter().map(|(_, href)| href).collect(),
+                }
+            })
+            .collect();
+        top.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.term.cmp(b.term)));
+        top.truncate(limit);
+        top
+    }
+
+    fn document(&self, href: &Href) -> Option<&Document> {
+        self.documents.iter().find(|doc| &doc.href == href)
+    }
+}
+
+/// Cosine similarity of the weights of `a` and `b`, or 0 if they share too
+/// few terms.
+fn cosine(a: &Document, b: &Document) -> f64 {
+    if a.norm == 0.0 || b.norm == 0.0 {
+        return 0.0;
+    }
+    let products: Vec<f64> = a
+        .weights
+        .iter()
+        .filter_map(|(term, wa)| b.weights.get(term).map(|wb| wa * wb))
+        .collect();
+    if products.len() < MIN_SHARED_TERMS {
+        return 0.0;
+    }
+    products.iter().sum::<f64>() / (a.norm * b.norm)
+}
+
+/// The terms of `text`: its words, lowercased, without numbers, short
+/// words, and stopwords.
+pub fn terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= MIN_TERM_CHARS)
+        .filter(|word| !word.chars().any(|c| c.is_numeric()))
+        .map(str::to_lowercase)
+        .filter(|word| !STOPWORDS.contains(&word.as_str()))
+}
+
+/// The vocabulary page body: totals, then each top term with its count and
+/// links to the posts using it.
+pub fn render_vocabulary(
+    corpus: &Corpus,
+    articles: &[Article],
+    limit: usize,
+    href_prefix: &str,
+) -> String {
+    let mut html = format!(
+        "<p class=\"meta\">{} posts, {} terms, {} distinct.</p>\n<dl class=\"vocabulary\">\n",
+        articles.len(),
+        corpus.term_count(),
+        corpus.vocabulary_size()
+    );
+    for used in corpus.top_terms(limit) {
+        html.push_str(&format!(
+            "<dt>{} <span class=\"count\">{}</span></dt>\n<dd>",
+            escape_text(used.term),
+            used.count
+        ));
+        let links: Vec<String> = used
+            .hrefs
+            .iter()
+            .filter_map(|href| articles.iter().find(|a| &&a.href == href))
+            .map(|a| {
+                format!(
+                    r#"<a href="{}">{}</a>"#,
+                    escape_attr(&format!("{href_prefix}{}", a.href.as_str())),
+                    escape_text(&a.title)
+                )
+            })
+            .collect();
+        html.push_str(&links.join(", "));
+        html.push_str("</dd>\n");
+    }
+    html.push_str("</dl>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+
+use crate::{
+    terms::{Corpus, terms},
+    types::{Href, RelPath},
+};
+
+fn href(name: &str) -> Href {
+    Href::from_rel(&RelPath::new(PathBuf::from(format!("posts/{name}.html"))).unwrap())
+}
+
+#[test]
+fn terms_skip_short_words_numbers_and_stopwords() {
+    let found: Vec<String> =
+        terms("The Borrow checker, in 2024, rejects x86 code that it can't prove.").collect();
+
+    assert_eq!(found, ["borrow", "checker", "rejects", "code", "prove"]);
+}
+
+#[test]
+fn keywords_favour_terms_other_posts_lack() {
+    let (a, b, c) = (href("a"), href("b"), href("c"));
+    let corpus = Corpus::new([
+        (&a, "compiler compiler compiler parsing writing"),
+        (&b, "garden writing tomatoes"),
+        (&c, "garden writing"),
+    ]);
+
+    assert_eq!(corpus.keywords(&a, 2), ["compiler", "parsing"]);
+    assert_eq!(corpus.keywords(&b, 1), ["tomatoes"]);
+    assert!(corpus.keywords(&href("missing"), 3).is_empty());
+    assert_eq!(corpus.vocabulary_size(), 5);
+    assert_eq!(corpus.term_count(), 10);
+}
+
+#[test]
+fn similar_posts_share_vocabulary() {
+    let (a, b, c, d) = (href("a"), href("b"), href("c"), href("d"));
+    let corpus = Corpus::new([
+        (&a, "parsing grammars with recursive descent parsers"),
+        (&b, "recursive descent parsers for grammars"),
+        (&c, "growing tomatoes in the garden"),
+        (&d, "recursive descent into the garden"),
+    ]);
+
+    assert_eq!(corpus.similar(&a, 3, &[]), [&b, &d]);
+    assert_eq!(corpus.similar(&a, 3, &[&b]), [&d]);
+    assert_eq!(corpus.similar(&a, 1, &[]), [&b]);
+}
+
+#[test]
+fn top_terms_count_every_use_and_list_the_heaviest_users_first() {
+    let (a, b) = (href("a"), href("b"));
+    let corpus = Corpus::new([(&a, "rust rust borrow"), (&b, "rust borrow borrow borrow")]);
+
+    let top = corpus.top_terms(1);
+    assert_eq!(top.len(), 1);
+    assert_eq!(top[0].term, "borrow");
+    assert_eq!(top[0].count, 4);
+    assert_eq!(top[0].hrefs, [&b, &a]);
+}
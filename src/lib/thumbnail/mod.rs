@@ -0,0 +1,76 @@
+//! Small square thumbnails of each post's primary image, for listings and the
+//! gallery. They are cut with ImageMagick (`IMAGE_CLI`) and named after the
+//! source image's hash; finished thumbnails are kept under the cache
+//! directory, so later builds copy them instead of re-encoding.
+use std::{
+    fs,
+    path::Path,
+    process::Command,
+};
+
+use crate::{
+    config::{IMAGE_CLI, THUMBNAIL_SIZE, THUMBNAILS_DIR},
+    utils::{escape_attr, partial_path},
+};
+
+/// Directory under the cache directory holding finished thumbnails.
+pub const THUMBNAIL_CACHE_DIR: &str = "thumbs";
+
+/// Thumbnail the local image at `source` into `THUMBNAILS_DIR` under
+/// `output_dir`, returning its path relative to the site root. `None` when
+/// the image is unreadable or `IMAGE_CLI` is missing or rejects it.
+pub fn thumbnail(source: &Path, cache_dir: &Path, output_dir: &Path) -> Option<String> {
+    let bytes = fs::read(source).ok()?;
+    let name = thumbnail_name(&bytes);
+    let cached = cache_dir.join(THUMBNAIL_CACHE_DIR).join(&name);
+    if !cached.is_file() {
+        render_thumbnail(source, &cached)?;
+    }
+
+    let out_dir = output_dir.join(THUMBNAILS_DIR);
+    fs::create_dir_all(&out_dir).ok()?;
+    fs::copy(&cached, out_dir.join(&name)).ok()?;
+    Some(format!("{THUMBNAILS_DIR}/{name}"))
+}
+
+/// File name of the thumbnail of an image with these bytes.
+fn thumbnail_name(bytes: &[u8]) -> String {
+    let hash = blake3::hash(bytes).to_hex();
+    format!("{}-{THUMBNAIL_SIZE}.jpg", &hash[..16])
+}
+
+/// Scale and centre-crop `source` to a `THUMBNAIL_SIZE` square at `dest`.
+fn render_thumbnail(source: &Path, dest: &Path) -> Option<()> {
+    fs::create_dir_all(dest.parent()?).ok()?;
+    // Pages render in parallel and may share an image: write a private file
+    // and move it into place so no one copies a half-written thumbnail.
+    let partial = partial_path(dest);
+    let size = format!("{THUMBNAIL_SIZE}x{THUMBNAIL_SIZE}");
+    let status = Command::new(IMAGE_CLI)
+        .arg(source)
+        .args(["-auto-orient", "-thumbnail"])
+        .arg(format!("{size}^"))
+        .args(["-gravity", "center", "-extent"])
+        .arg(&size)
+        .args(["-strip", "-quality", "80"])
+        .arg(format!("jpg:{}", partial.display()))
+        .status()
+        .ok()?;
+    if !status.success() {
+        let _ = fs::remove_file(&partial);
+        return None;
+    }
+    fs::rename(&partial, dest).ok()
+}
+
+/// `<img>` for a thumbnail at `thumb` (relative to the site root), from a
+/// page `href_prefix` away from the root.
+pub fn thumbnail_img(href_prefix: &str, thumb: &str) -> String {
+    format!(
+        r#"<img class="thumb" src="{}" alt="" width="{THUMBNAIL_SIZE}" height="{THUMBNAIL_SIZE}" loading="lazy" decoding="async">"#,
+        escape_attr(&format!("{href_prefix}{thumb}"))
+    )
+}
+
+#[cfg(test)]
+mod tests;
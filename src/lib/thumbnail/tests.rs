@@ -0,0 +1,46 @@
+use std::fs;
+
+use tempfile::TempDir;
+
+use crate::{
+    config::{THUMBNAIL_SIZE, THUMBNAILS_DIR},
+    thumbnail::{THUMBNAIL_CACHE_DIR, thumbnail, thumbnail_img, thumbnail_name},
+};
+
+#[test]
+fn cached_thumbnails_are_copied_without_re_encoding() {
+    let tmp = TempDir::new().expect("tempdir");
+    let source = tmp.path().join("photo.jpg");
+    fs::write(&source, b"not really a jpeg").unwrap();
+    let name = thumbnail_name(b"not really a jpeg");
+    let cache_dir = tmp.path().join(".cache");
+    fs::create_dir_all(cache_dir.join(THUMBNAIL_CACHE_DIR)).unwrap();
+    fs::write(cache_dir.join(THUMBNAIL_CACHE_DIR).join(&name), b"thumb").unwrap();
+
+    let out = tmp.path().join("public");
+    let rel = thumbnail(&source, &cache_dir, &out).expect("cached thumbnail");
+
+    assert_eq!(rel, format!("{THUMBNAILS_DIR}/{name}"));
+    assert_eq!(fs::read(out.join(&rel)).unwrap(), b"thumb");
+}
+
+#[test]
+fn thumbnails_are_named_after_their_source() {
+    assert_eq!(thumbnail_name(b"a"), thumbnail_name(b"a"));
+    assert_ne!(thumbnail_name(b"a"), thumbnail_name(b"b"));
+    assert!(thumbnail_name(b"a").ends_with(&format!("-{THUMBNAIL_SIZE}.jpg")));
+}
+
+#[test]
+fn missing_images_have_no_thumbnail() {
+    let tmp = TempDir::new().expect("tempdir");
+    let missing = tmp.path().join("missing.jpg");
+    assert_eq!(thumbnail(&missing, tmp.path(), tmp.path()), None);
+}
+
+#[test]
+fn thumbnail_img_is_relative_to_the_page() {
+    let html = thumbnail_img("../", "thumbs/abc-160.jpg");
+    assert!(html.starts_with(r#"<img class="thumb" src="../thumbs/abc-160.jpg" alt=""#));
+    assert!(html.contains(&format!(r#"width="{THUMBNAIL_SIZE}""#)));
+}
@@ -0,0 +1,134 @@
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+
+use crate::{
+    transformer::Transformer,
+    utils::{AttributeBlock, escape_attr, escape_text, parse_attribute_block},
+};
+
+/// Turn a trailing `{cite="Author, Book" href="…"}` line in a blockquote into
+/// semantic `<blockquote cite=…>` / `<footer><cite>` markup.
+///
+/// The attribute block may be its own paragraph (`> {cite=…}`) or a lazy
+/// continuation line directly under the quote.
+pub struct BlockquoteCitationTransformer<'a> {
+    inner: std::vec::IntoIter<Event<'a>>,
+}
+
+impl<'a> Iterator for BlockquoteCitationTransformer<'a> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+impl<'a, I> Transformer<'a, I> for BlockquoteCitationTransformer<'a>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    fn transform(inner: I) -> Self {
+        let events: Vec<Event<'a>> = inner.collect();
+        let rewritten = attach_citations(events);
+        Self {
+            inner: rewritten.into_iter(),
+        }
+    }
+}
+
+fn attach_citations<'a>(events: Vec<Event<'a>>) -> Vec<Event<'a>> {
+    let mut out: Vec<Event<'a>> = Vec::with_capacity(events.len());
+    let mut open_quotes: Vec<usize> = Vec::new();
+
+    for event in events {
+        match event {
+            Event::Start(Tag::BlockQuote(_)) => {
+                open_quotes.push(out.len());
+                out.push(event);
+            }
+            Event::End(TagEnd::BlockQuote(_)) => {
+                let citation = open_quotes.pop().and_then(|start| {
+                    take_trailing_attributes(&mut out, start).map(|a| (start, a))
+                });
+
+                let Some((start, attrs)) = citation else {
+                    out.push(event);
+                    continue;
+                };
+
+                let href = attrs.get("href");
+                let source = attrs.get("cite").or(href).unwrap_or_default();
+
+                let mut footer = String::from("<footer><cite>");
+                match href {
+                    Some(href) => footer.push_str(&format!(
+                        r#"<a href="{}">{}</a>"#,
+                        escape_attr(href),
+                        escape_text(source)
+                    )),
+                    None => footer.push_str(&escape_text(source)),
+                }
+                footer.push_str("</cite></footer>");
+                out.push(Event::Html(CowStr::from(footer)));
+
+                if let Some(href) = href {
+                    out[start] = Event::Html(CowStr::from(format!(
+                        r#"<blockquote cite="{}">"#,
+                        escape_attr(href)
+                    )));
+                    out.push(Event::Html(CowStr::from("</blockquote>\n")));
+                } else {
+                    out.push(event);
+                }
+            }
+            other => out.push(other),
+        }
+    }
+
+    out
+}
+
+/// If the blockquote opened at `start` ends with a citation attribute block,
+/// remove it from `out` and return the parsed attributes.
+fn take_trailing_attributes(out: &mut Vec<Event<'_>>, start: usize) -> Option<AttributeBlock> {
+    if !matches!(out.last(), Some(Event::End(TagEnd::Paragraph))) {
+        return None;
+    }
+    let para_start = out[start..]
+        .iter()
+        .rposition(|e| matches!(e, Event::Start(Tag::Paragraph)))?
+        + start;
+    let content = &out[para_start + 1..out.len() - 1];
+
+    let break_pos = content
+        .iter()
+        .rposition(|e| matches!(e, Event::SoftBreak | Event::HardBreak));
+    let tail = &content[break_pos.map_or(0, |p| p + 1)..];
+
+    let mut raw = String::new();
+    for event in tail {
+        match event {
+            Event::Text(t) => raw.push_str(t),
+            _ => return None,
+        }
+    }
+
+    let attrs = parse_attribute_block(&raw)?;
+    if attrs.get("cite").is_none() && attrs.get("href").is_none() {
+        return None;
+    }
+
+    match break_pos {
+        // The attributes were the whole paragraph: drop it entirely.
+        None => out.truncate(para_start),
+        // A lazy continuation line: drop the break and the attribute text.
+        Some(pos) => {
+            out.truncate(para_start + 1 + pos);
+            out.push(Event::End(TagEnd::Paragraph));
+        }
+    }
+
+    Some(attrs)
+}
+
+#[cfg(test)]
+mod tests;
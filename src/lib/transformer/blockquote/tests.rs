@@ -0,0 +1,45 @@
+use pulldown_cmark::{Options, Parser};
+
+use crate::transformer::{WithTransformer, blockquote::BlockquoteCitationTransformer};
+
+fn render(md: &str) -> String {
+    let events = Parser::new_ext(md, Options::empty())
+        .with_transformer::<BlockquoteCitationTransformer<'_>>();
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events);
+    html
+}
+
+#[test]
+fn lazy_attribute_line_becomes_cite_footer() {
+    let html = render(
+        "> To be or not to be.\n{cite=\"Shakespeare, Hamlet\" href=\"https://example.com/hamlet?act=3&scene=1\"}\n",
+    );
+
+    assert!(html.contains(r#"<blockquote cite="https://example.com/hamlet?act=3&amp;scene=1">"#));
+    assert!(html.contains("<p>To be or not to be.</p>"));
+    assert!(html.contains(
+        r#"<footer><cite><a href="https://example.com/hamlet?act=3&amp;scene=1">Shakespeare, Hamlet</a></cite></footer></blockquote>"#
+    ));
+    assert!(!html.contains("{cite"));
+}
+
+#[test]
+fn attribute_paragraph_without_href_keeps_plain_blockquote() {
+    let html = render("> Quote.\n>\n> {cite=\"Someone\"}\n");
+
+    assert!(html.contains("<blockquote>"));
+    assert!(html.contains("<footer><cite>Someone</cite></footer>"));
+    assert!(!html.contains("<p>{"));
+}
+
+#[test]
+fn ordinary_blockquotes_are_untouched() {
+    let md = "> Just a quote {with braces}.\n";
+    let plain = {
+        let mut html = String::new();
+        pulldown_cmark::html::push_html(&mut html, Parser::new(md));
+        html
+    };
+    assert_eq!(render(md), plain);
+}
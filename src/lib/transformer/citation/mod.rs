@@ -0,0 +1,276 @@
+//! Pandoc-style citations: `[@knuth1984]`, `[see @knuth1984, p. 3]`, or
+//! several at once, `[@knuth1984; @levy1993]`. Each group becomes a sidenote
+//! (a footnote, rendered by the footnote transformers) or a parenthetical
+//! reference, per `CITATION_STYLE`, and the cited works are listed after the
+//! post.
+use std::collections::BTreeMap;
+
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+
+use crate::{
+    bibliography::{Bibliography, Entry},
+    config::{CITATION_STYLE, CitationStyle},
+    diagnostics::DocumentSink,
+    transformer::Transformer,
+    utils::{escape_attr, escape_text},
+};
+
+pub struct CitationTransformer<'a> {
+    inner: std::vec::IntoIter<Event<'a>>,
+}
+
+impl<'a> Iterator for CitationTransformer<'a> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+impl<'a> CitationTransformer<'a> {
+    /// Resolve citations against `bibliography`, warning on `sink` about
+    /// keys it does not have.
+    pub fn with_bibliography<I>(
+        inner: I,
+        sink: &DocumentSink,
+        bibliography: &Bibliography,
+        style: CitationStyle,
+    ) -> Self
+    where
+        I: Iterator<Item = Event<'a>>,
+    {
+        let events: Vec<Event<'a>> = inner.collect();
+        let rewritten = if bibliography.is_empty() {
+            events
+        } else {
+            cite(events, sink, bibliography, style)
+        };
+        Self {
+            inner: rewritten.into_iter(),
+        }
+    }
+}
+
+impl<'a, I> Transformer<'a, I> for CitationTransformer<'a>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    fn transform(inner: I) -> Self {
+        Self::with_bibliography(
+            inner,
+            &DocumentSink::default(),
+            &Bibliography::default(),
+            CITATION_STYLE,
+        )
+    }
+}
+
+/// One `@key` in a citation group, with the words around it.
+#[derive(Debug, PartialEq, Eq)]
+struct Cite<'s> {
+    prefix: &'s str,
+    key: &'s str,
+    locator: &'s str,
+}
+
+/// Replace citation groups in `events` and append the references.
+pub fn cite<'a>(
+    events: Vec<Event<'a>>,
+    sink: &DocumentSink,
+    bibliography: &Bibliography,
+    style: CitationStyle,
+) -> Vec<Event<'a>> {
+    let mut out: Vec<Event<'a>> = Vec::with_capacity(events.len());
+    let mut notes: Vec<Event<'a>> = Vec::new();
+    let mut groups: usize = 0;
+    let mut cited: BTreeMap<String, &Entry> = BTreeMap::new();
+    let mut verbatim: usize = 0;
+
+    for event in merge_text(events) {
+        match event {
+            Event::Start(Tag::CodeBlock(_) | Tag::MetadataBlock(_)) => {
+                verbatim += 1;
+                out.push(event);
+            }
+            Event::End(TagEnd::CodeBlock | TagEnd::MetadataBlock(_)) => {
+                verbatim = verbatim.saturating_sub(1);
+                out.push(event);
+            }
+            Event::Text(text) if verbatim == 0 && text.contains('@') => {
+                let mut rest: &str = &text;
+                let mut pending = String::new();
+                while let Some((start, end, group)) = next_group(rest) {
+                    pending.push_str(&rest[..start]);
+                    let entries: Option<Vec<&Entry>> = group
+                        .iter()
+                        .map(|cite| {
+                            let entry = bibliography.get(cite.key);
+                            if entry.is_none() {
+                                sink.warn(
+                                    "citation",
+                                    &format!("@{}", cite.key),
+                                    format!("no entry `{}` in the bibliography", cite.key),
+                                );
+                            }
+                            entry
+                        })
+                        .collect();
+                    let Some(entries) = entries else {
+                        pending.push_str(&rest[start..end]);
+                        rest = &rest[end..];
+                        continue;
+                    };
+
+                    if !pending.is_empty() {
+                        out.push(Event::Text(std::mem::take(&mut pending).into()));
+                    }
+                    let inline = group
+                        .iter()
+                        .zip(&entries)
+                        .map(|(cite, entry)| cite_html(cite, entry))
+                        .collect::<Vec<_>>()
+                        .join("; ");
+                    groups += 1;
+                    match style {
+                        CitationStyle::Sidenote => {
+                            let label = format!("cite-{groups}");
+                            out.push(Event::FootnoteReference(label.clone().into()));
+                            notes.extend([
+                                Event::Start(Tag::FootnoteDefinition(label.into())),
+                                Event::Start(Tag::Paragraph),
+                                Event::InlineHtml(format!("{inline}.").into()),
+                                Event::End(TagEnd::Paragraph),
+                                Event::End(TagEnd::FootnoteDefinition),
+                            ]);
+                        }
+                        CitationStyle::Bracketed => out.push(Event::InlineHtml(
+                            format!(r#"<span class="citation">({inline})</span>"#).into(),
+                        )),
+                    }
+                    for entry in entries {
+                        cited.insert(entry.key.clone(), entry);
+                    }
+                    rest = &rest[end..];
+                }
+                pending.push_str(rest);
+                if !pending.is_empty() {
+                    out.push(Event::Text(pending.into()));
+                }
+            }
+            other => out.push(other),
+        }
+    }
+
+    out.extend(notes);
+    if !cited.is_empty() {
+        out.push(Event::Html(references_html(cited.into_values()).into()));
+    }
+    out
+}
+
+/// Runs of adjacent text events as one, since the parser splits text at
+/// brackets it considered as links.
+fn merge_text(events: Vec<Event<'_>>) -> Vec<Event<'_>> {
+    let mut out: Vec<Event<'_>> = Vec::with_capacity(events.len());
+    for event in events {
+        match (out.last_mut(), event) {
+            (Some(Event::Text(previous)), Event::Text(text)) => {
+                *previous = CowStr::from(format!("{previous}{text}"));
+            }
+            (_, event) => out.push(event),
+        }
+    }
+    out
+}
+
+/// The next citation group in `text`: its byte range, brackets included,
+/// and its citations.
+fn next_group(text: &str) -> Option<(usize, usize, Vec<Cite<'_>>)> {
+    let mut from = 0;
+    while let Some(open) = text[from..].find('[').map(|at| from + at) {
+        let close = text[open..].find(']').map(|at| open + at)?;
+        if let Some(group) = parse_group(&text[open + 1..close]) {
+            return Some((open, close + 1, group));
+        }
+        from = open + 1;
+    }
+    None
+}
+
+/// The citations in the inside of a `[...]` group, if every `;`-separated
+/// part has exactly one `@key`.
+fn parse_group(inner: &str) -> Option<Vec<Cite<'_>>> {
+    inner.split(';').map(parse_cite).collect()
+}
+
+fn parse_cite(part: &str) -> Option<Cite<'_>> {
+    let at = part.find('@')?;
+    let prefix = &part[..at];
+    // An `@` right after a word is an email address or handle, not a key.
+    if prefix.ends_with(|c: char| !c.is_whitespace()) {
+        return None;
+    }
+    let rest = &part[at + 1..];
+    let len = rest
+        .find(|c: char| !(c.is_alphanumeric() || "_-:.#$%&+?<>~/".contains(c)))
+        .unwrap_or(rest.len());
+    let key = rest[..len].trim_end_matches(['.', ':']);
+    if key.is_empty() {
+        return None;
+    }
+    let locator = rest[key.len()..].trim();
+    if locator.contains('@') {
+        return None;
+    }
+    Some(Cite {
+        prefix: prefix.trim(),
+        key,
+        locator: locator.trim_start_matches(',').trim(),
+    })
+}
+
+/// A citation in the text: its prefix, the short label linked to the
+/// reference, and its locator.
+fn cite_html(cite: &Cite<'_>, entry: &Entry) -> String {
+    let mut html = String::new();
+    if !cite.prefix.is_empty() {
+        html.push_str(&escape_text(cite.prefix));
+        html.push(' ');
+    }
+    html.push_str(&format!(
+        r##"<a href="#ref-{}" class="citation-link">{}</a>"##,
+        escape_attr(&entry.key),
+        escape_text(&entry.short_label())
+    ));
+    if !cite.locator.is_empty() {
+        html.push_str(", ");
+        html.push_str(&escape_text(cite.locator));
+    }
+    html
+}
+
+/// The "References" section listing `entries`.
+fn references_html<'e>(entries: impl Iterator<Item = &'e Entry>) -> String {
+    let mut entries: Vec<&Entry> = entries.collect();
+    entries.sort_by_cached_key(|entry| {
+        let first = entry.authors.first().map(|name| name.family.to_lowercase());
+        (first, entry.year.clone(), entry.key.clone())
+    });
+    let items: String = entries
+        .iter()
+        .map(|entry| {
+            format!(
+                r#"<li id="ref-{}">{}</li>"#,
+                escape_attr(&entry.key),
+                entry.reference_html()
+            )
+        })
+        .collect();
+    format!(
+        r#"<section class="references"><h2 id="references">References</h2><ul>{items}</ul></section>
+"#
+    )
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,97 @@
+use std::path::Path;
+
+use pulldown_cmark::{Options, Parser};
+
+use crate::{
+    bibliography::{Bibliography, parse_bibtex},
+    config::CitationStyle,
+    diagnostics::{Diagnostics, DocumentSink},
+    transformer::{
+        WithTransformer, citation::CitationTransformer, footnote::PlainFootnoteTransformer,
+    },
+};
+
+const BIBTEX: &str = r#"
+@book{knuth1984, author = {Knuth, Donald}, title = {Literate Programming}, year = 1984}
+@book{levy1993, author = {Levy, Silvio and Knuth, Donald}, title = {CWEB}, year = 1993}
+@book{unused, author = {Nobody}, title = {Uncited}, year = 2001}
+"#;
+
+fn bibliography() -> Bibliography {
+    Bibliography::new(parse_bibtex(BIBTEX).unwrap())
+}
+
+fn render(md: &str, style: CitationStyle, sink: &DocumentSink) -> String {
+    let events = CitationTransformer::with_bibliography(
+        Parser::new_ext(md, Options::ENABLE_FOOTNOTES),
+        sink,
+        &bibliography(),
+        style,
+    );
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events);
+    html
+}
+
+#[test]
+fn bracketed_citations_link_to_the_references() {
+    let html = render(
+        "As shown [see @knuth1984, p. 3; @levy1993], code is prose.\n",
+        CitationStyle::Bracketed,
+        &DocumentSink::default(),
+    );
+
+    assert!(
+        html.contains(concat!(
+            r##"As shown <span class="citation">(see <a href="#ref-knuth1984" class="citation-link">Knuth 1984</a>, p. 3; "##,
+            r##"<a href="#ref-levy1993" class="citation-link">Levy and Knuth 1993</a>)</span>, code is prose."##,
+        )),
+        "{html}"
+    );
+    // Listed once each, by author, and only if cited.
+    let knuth = html.find(r#"<li id="ref-knuth1984">"#).unwrap();
+    let levy = html.find(r#"<li id="ref-levy1993">"#).unwrap();
+    assert!(knuth < levy);
+    assert!(html.contains(r#"<h2 id="references">References</h2>"#));
+    assert!(!html.contains("Uncited"));
+}
+
+#[test]
+fn sidenote_citations_become_footnotes() {
+    let md = "Prose[@knuth1984] and a note[^n].\n\n[^n]: A note.\n";
+    let events = CitationTransformer::with_bibliography(
+        Parser::new_ext(md, Options::ENABLE_FOOTNOTES),
+        &DocumentSink::default(),
+        &bibliography(),
+        CitationStyle::Sidenote,
+    )
+    .with_transformer::<PlainFootnoteTransformer<'_>>();
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events);
+
+    assert!(
+        html.contains(r##"<a href="#ref-knuth1984" class="citation-link">Knuth 1984</a>."##),
+        "{html}"
+    );
+    assert!(html.contains("A note."));
+    assert!(html.contains(r#"<li id="ref-knuth1984">"#));
+    assert!(!html.contains("[@knuth1984]"));
+}
+
+#[test]
+fn unknown_keys_warn_and_other_text_is_left_alone() {
+    let source = "Ask [me @ home] or [@nobody2020]. Mail a@b.example.\n\n```\n[@knuth1984]\n```\n";
+    let diagnostics = Diagnostics::default();
+    let sink = diagnostics.for_document(Path::new("post.md"), source, Vec::new());
+    let html = render(source, CitationStyle::Bracketed, &sink);
+
+    assert!(html.contains("Ask [me @ home] or [@nobody2020]."), "{html}");
+    assert!(html.contains("<code>[@knuth1984]\n</code>"));
+    assert!(!html.contains("References"));
+
+    let warnings: Vec<String> = diagnostics.take().iter().map(ToString::to_string).collect();
+    assert_eq!(
+        warnings,
+        ["warning: post.md:1:21: citation: no entry `nobody2020` in the bibliography"]
+    );
+}
@@ -1,20 +1,136 @@
 use std::{
     io::{BufReader, Cursor},
-    sync::OnceLock,
+    ops::RangeInclusive,
+    path::Path,
+    sync::{Arc, LazyLock, RwLock},
 };
 
+use color_eyre::Section;
+
 use pulldown_cmark::{CodeBlockKind, CowStr, Event, Tag, TagEnd};
 use syntect::{
+    easy::HighlightLines,
     highlighting::{Theme, ThemeSet},
-    html::highlighted_html_for_string,
+    html::{
+        IncludeBackground, highlighted_html_for_string, start_highlighted_html_snippet,
+        styled_line_to_highlighted_html,
+    },
     parsing::{SyntaxReference, SyntaxSet},
+    util::LinesWithEndings,
 };
 
 use crate::{
+    config::{CODE_LINE_NUMBERS, FENCE_ALIASES, HIGHLIGHT_THEME},
+    diagnostics::DocumentSink,
     transformer::Transformer,
     utils::{escape_attr, escape_html},
 };
 
+/// Parsed fence info string: the language token followed by optional flags
+/// and `key=value` attributes, separated by commas or whitespace
+/// (```` ```rs,linenos title="src/main.rs" hl_lines="1 3-5" ````).
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct FenceInfo<'a> {
+    pub lang: Option<&'a str>,
+    /// Number the lines; defaults to `CODE_LINE_NUMBERS`.
+    pub linenos: bool,
+    /// Filename or caption shown in a bar above the block.
+    pub title: Option<&'a str>,
+    /// 1-based line ranges to emphasise.
+    pub hl_lines: Vec<RangeInclusive<usize>>,
+    /// Fenced as `diff-<lang>`: lines start with a `+`/`-`/space marker and
+    /// the rest is highlighted as `lang`.
+    pub diff: bool,
+}
+
+impl<'a> FenceInfo<'a> {
+    pub fn parse(kind: &'a CodeBlockKind<'a>) -> Self {
+        let mut info = Self {
+            linenos: CODE_LINE_NUMBERS,
+            ..Self::default()
+        };
+        let CodeBlockKind::Fenced(raw) = kind else {
+            return info;
+        };
+
+        let mut tokens = fence_tokens(raw);
+        info.lang = tokens.next();
+        if let Some(lang) = info.lang.and_then(|l| l.strip_prefix("diff-"))
+            && !lang.is_empty()
+        {
+            info.lang = Some(lang);
+            info.diff = true;
+        }
+        for token in tokens {
+            match token.split_once('=') {
+                Some(("title", value)) => info.title = Some(unquote(value)),
+                Some(("hl_lines", value)) => info.hl_lines = parse_line_ranges(unquote(value)),
+                Some(_) => {}
+                None if token == "linenos" => info.linenos = true,
+                None if token == "nolinenos" => info.linenos = false,
+                None => {}
+            }
+        }
+        info
+    }
+}
+
+/// Split an info string on commas and whitespace, keeping double-quoted
+/// values (`title="a b.rs"`) in one token.
+fn fence_tokens(raw: &str) -> impl Iterator<Item = &str> {
+    let mut rest = raw;
+    std::iter::from_fn(move || {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if rest.is_empty() {
+            return None;
+        }
+        let mut in_quotes = false;
+        let end = rest
+            .char_indices()
+            .find(|&(_, c)| {
+                if c == '"' {
+                    in_quotes = !in_quotes;
+                }
+                !in_quotes && (c.is_whitespace() || c == ',')
+            })
+            .map_or(rest.len(), |(i, _)| i);
+        let (token, tail) = rest.split_at(end);
+        rest = tail;
+        Some(token)
+    })
+}
+
+/// Parse `3`, `3-5`, or a comma/space separated list of those; malformed
+/// entries are skipped.
+fn parse_line_ranges(raw: &str) -> Vec<RangeInclusive<usize>> {
+    raw.split(|c: char| c.is_whitespace() || c == ',')
+        .filter_map(|part| {
+            let (start, end) = part.split_once('-').unwrap_or((part, part));
+            let (start, end) = (start.parse().ok()?, end.parse().ok()?);
+            (start <= end).then_some(start..=end)
+        })
+        .collect()
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+/// Put a title bar above a rendered block when the fence names one.
+fn with_title(rendered: String, title: Option<&str>) -> String {
+    match title {
+        Some(title) => format!(
+            "<figure class=\"code\"><figcaption class=\"code-title\">{}</figcaption>{}</figure>\n",
+            escape_html(title),
+            rendered.trim_end()
+        ),
+        None => rendered,
+    }
+}
+
 /// An enum to keep track of the state of the highlighter in the code block.
 pub enum CodeBlockState<'a> {
     /// Not in code block, pass through the event as-is.
@@ -35,6 +151,8 @@ where
     buffer: String,
     /// Current state of the transformer; are we inside a code block?
     state: CodeBlockState<'a>,
+    /// Where unknown languages and highlighting failures are reported.
+    sink: DocumentSink,
 }
 
 impl<'a, I> Iterator for CodeHighlightTransformer<'a, I>
@@ -71,24 +189,51 @@ where
                             unreachable!()
                         };
 
-                        let language = match lang {
-                            CodeBlockKind::Fenced(ref l) => Some(l.as_ref()),
-                            CodeBlockKind::Indented => None,
-                        };
+                        let info = FenceInfo::parse(&lang);
+                        let language = info.lang;
 
-                        let syntax_set = syntax_set();
+                        let syntaxes = syntax_set();
+                        let syntax_set: &SyntaxSet = &syntaxes;
+                        let theme = theme();
 
-                        let syntax: &SyntaxReference = language
-                            .and_then(|lang| syntax_set.find_syntax_by_token(lang))
-                            .unwrap_or_else(|| syntax_set.find_syntax_plain_text());
+                        let found = language.and_then(|lang| find_syntax(syntax_set, lang));
+                        if let (Some(lang), None) = (language, found)
+                            && !is_plain(lang)
+                        {
+                            let tried = match fence_alias(lang) {
+                                Some(targets) => format!(" (tried {})", targets.join(", ")),
+                                None => String::new(),
+                            };
+                            self.sink.warn(
+                                "highlight",
+                                &self.buffer,
+                                format!("no syntax for `{lang}`{tried}; rendered as plain text"),
+                            );
+                        }
+                        let syntax: &SyntaxReference =
+                            found.unwrap_or_else(|| syntax_set.find_syntax_plain_text());
 
-                        let rendered = highlighted_html_for_string(
-                            &self.buffer,
-                            syntax_set,
-                            syntax,
-                            theme(),
-                        )
-                        .unwrap_or_else(|_| fallback_plain(&self.buffer, language));
+                        let highlighted = if info.linenos || info.diff || !info.hl_lines.is_empty()
+                        {
+                            highlighted_html_by_line(
+                                &self.buffer,
+                                syntax_set,
+                                syntax,
+                                &theme,
+                                &info,
+                            )
+                        } else {
+                            highlighted_html_for_string(&self.buffer, syntax_set, syntax, &theme)
+                        };
+                        let rendered = highlighted.unwrap_or_else(|e| {
+                            self.sink.warn(
+                                "highlight",
+                                &self.buffer,
+                                format!("highlighting failed: {e}"),
+                            );
+                            fallback_plain(&self.buffer, language)
+                        });
+                        let rendered = with_title(rendered, info.title);
 
                         return Some(Event::Html(CowStr::from(rendered)));
                     }
@@ -115,23 +260,207 @@ where
     }
 }
 
-static SYNTAX_SET: OnceLock<SyntaxSet> = OnceLock::new();
-fn syntax_set() -> &'static SyntaxSet {
-    SYNTAX_SET.get_or_init(SyntaxSet::load_defaults_newlines)
+static DEFAULT_SYNTAXES: LazyLock<Arc<SyntaxSet>> =
+    LazyLock::new(|| Arc::new(SyntaxSet::load_defaults_newlines()));
+static SYNTAX_SET: LazyLock<RwLock<Arc<SyntaxSet>>> =
+    LazyLock::new(|| RwLock::new(Arc::clone(&DEFAULT_SYNTAXES)));
+fn syntax_set() -> Arc<SyntaxSet> {
+    Arc::clone(&SYNTAX_SET.read().unwrap_or_else(|e| e.into_inner()))
 }
 
-// Parsing the theme file is moderately expensive; cache it so each code block
-// highlights without reloading the theme.
-static THEME: OnceLock<Theme> = OnceLock::new();
-fn theme() -> &'static Theme {
-    THEME.get_or_init(|| {
-        let raw_theme = include_bytes!("../../../../assets/theme.tmTheme");
-        let cursor = Cursor::new(raw_theme);
-        let mut reader = BufReader::new(cursor);
-        ThemeSet::load_from_reader(&mut reader).unwrap_or_default()
+/// Highlight with the default syntaxes plus any `.sublime-syntax` files under
+/// `dir`, replacing the set used so far. Each build calls this, so edits to
+/// the syntaxes show up on the next rebuild while serving. A missing
+/// directory just means the defaults.
+pub fn load_syntaxes(dir: &Path) -> color_eyre::Result<()> {
+    let syntaxes = if dir.is_dir() {
+        Arc::new(syntaxes_with(dir)?)
+    } else {
+        Arc::clone(&DEFAULT_SYNTAXES)
+    };
+    *SYNTAX_SET.write().unwrap_or_else(|e| e.into_inner()) = syntaxes;
+    Ok(())
+}
+
+/// The default syntaxes plus those found under `dir`.
+pub fn syntaxes_with(dir: &Path) -> color_eyre::Result<SyntaxSet> {
+    let mut builder = SyntaxSet::load_defaults_newlines().into_builder();
+    builder
+        .add_from_folder(dir, true)
+        .with_note(|| format!("While loading syntaxes from {}", dir.display()))?;
+    Ok(builder.build())
+}
+
+/// Syntax for a fence's language token: the first available syntax it is
+/// mapped to in `FENCE_ALIASES`, or else the one the token itself names.
+pub fn find_syntax<'s>(syntax_set: &'s SyntaxSet, lang: &str) -> Option<&'s SyntaxReference> {
+    fence_alias(lang)
+        .and_then(|targets| {
+            targets
+                .iter()
+                .find_map(|target| syntax_named(syntax_set, target))
+        })
+        .or_else(|| syntax_named(syntax_set, lang))
+}
+
+/// The syntaxes `FENCE_ALIASES` maps a fence token to, if any.
+fn fence_alias(lang: &str) -> Option<&'static [&'static str]> {
+    FENCE_ALIASES
+        .iter()
+        .find(|(token, _)| token.eq_ignore_ascii_case(lang))
+        .map(|(_, targets)| *targets)
+}
+
+/// Syntax named by `lang`: first by token (name or file extension), then by
+/// case-insensitive name, so user syntaxes can be named either way.
+fn syntax_named<'s>(syntax_set: &'s SyntaxSet, lang: &str) -> Option<&'s SyntaxReference> {
+    syntax_set.find_syntax_by_token(lang).or_else(|| {
+        syntax_set
+            .syntaxes()
+            .iter()
+            .find(|s| s.name.eq_ignore_ascii_case(lang))
     })
 }
 
+/// Fence languages that ask for plain text rather than naming a syntax.
+fn is_plain(lang: &str) -> bool {
+    ["text", "plain", "plaintext", "none"]
+        .iter()
+        .any(|plain| lang.eq_ignore_ascii_case(plain))
+}
+
+// Parsing the theme file is moderately expensive; keep it between code blocks
+// so each highlights without reloading the theme.
+static THEME: LazyLock<RwLock<Arc<Theme>>> =
+    LazyLock::new(|| RwLock::new(Arc::new(bundled_theme())));
+fn theme() -> Arc<Theme> {
+    Arc::clone(&THEME.read().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// Name of the theme compiled into the binary.
+pub const BUNDLED_THEME: &str = "bundled";
+
+fn bundled_theme() -> Theme {
+    let raw_theme = include_bytes!("../../../../assets/theme.tmTheme");
+    let cursor = Cursor::new(raw_theme);
+    let mut reader = BufReader::new(cursor);
+    ThemeSet::load_from_reader(&mut reader).unwrap_or_default()
+}
+
+/// Select the highlighting theme named by `HIGHLIGHT_THEME`, replacing the
+/// one used so far. Like `load_syntaxes`, each build calls this, so edits to a
+/// theme file show up on the next rebuild while serving.
+pub fn load_theme(root: &Path) -> color_eyre::Result<()> {
+    let theme = Arc::new(resolve_theme(HIGHLIGHT_THEME, root)?);
+    *THEME.write().unwrap_or_else(|e| e.into_inner()) = theme;
+    Ok(())
+}
+
+/// A theme by name: the bundled theme, one of syntect's built-ins (e.g.
+/// `InspiredGitHub`, `base16-ocean.dark`), or a `.tmTheme` path relative to
+/// `root`.
+pub fn resolve_theme(spec: &str, root: &Path) -> color_eyre::Result<Theme> {
+    if spec == BUNDLED_THEME {
+        return Ok(bundled_theme());
+    }
+    let mut builtins = ThemeSet::load_defaults().themes;
+    if let Some(theme) = builtins.remove(spec) {
+        return Ok(theme);
+    }
+
+    let path = root.join(spec);
+    ThemeSet::get_theme(&path)
+        .with_note(|| format!("While loading highlight theme {}", path.display()))
+        .with_suggestion(|| {
+            let names: Vec<&str> = builtins.keys().map(String::as_str).collect();
+            format!(
+                "Use \"{BUNDLED_THEME}\", a .tmTheme path, or one of: {}",
+                names.join(", ")
+            )
+        })
+}
+
+/// Like `highlighted_html_for_string`, but rendered line by line so lines can
+/// be numbered, emphasised, and marked as diff additions or removals.
+///
+/// Numbers sit in a gutter that is unselectable and hidden from assistive
+/// technology, so copying the block yields only the code. Lines in
+/// `hl_lines` are wrapped in a `.hl` span tinted with the theme's line
+/// highlight colour. In diff fences, `+`/`-` lines are wrapped in
+/// `.diff-add`/`.diff-del` spans and only the text after the marker is
+/// highlighted.
+pub fn highlighted_html_by_line(
+    source: &str,
+    syntax_set: &SyntaxSet,
+    syntax: &SyntaxReference,
+    theme: &Theme,
+    info: &FenceInfo<'_>,
+) -> Result<String, syntect::Error> {
+    let mut highlighter = HighlightLines::new(syntax, theme);
+    let (mut out, background) = start_highlighted_html_snippet(theme);
+    let lines: Vec<&str> = LinesWithEndings::from(source).collect();
+    let width = lines.len().to_string().len();
+    let hl_style = theme
+        .settings
+        .line_highlight
+        .map(|c| {
+            format!(
+                r#" style="display:block;background-color:#{:02x}{:02x}{:02x}{:02x}""#,
+                c.r, c.g, c.b, c.a
+            )
+        })
+        .unwrap_or_else(|| r#" style="display:block""#.to_string());
+
+    for (i, line) in lines.into_iter().enumerate() {
+        let number = i + 1;
+        let (marker, code) = match line.chars().next() {
+            Some(c @ ('+' | '-' | ' ')) if info.diff => (Some(c), &line[1..]),
+            _ => (None, line),
+        };
+        let wrapper = match marker {
+            Some('+') => Some(
+                r#"<span class="diff-add" style="display:block;background-color:#2ea04326">"#
+                    .to_string(),
+            ),
+            Some('-') => Some(
+                r#"<span class="diff-del" style="display:block;background-color:#f8514926">"#
+                    .to_string(),
+            ),
+            _ if info.hl_lines.iter().any(|r| r.contains(&number)) => {
+                Some(format!(r#"<span class="hl"{hl_style}>"#))
+            }
+            _ => None,
+        };
+
+        if let Some(open) = &wrapper {
+            out.push_str(open);
+        }
+        if info.linenos {
+            out.push_str(&format!(
+                r#"<span class="lineno" aria-hidden="true" style="user-select:none;-webkit-user-select:none;opacity:0.5;padding-right:1em">{number:>width$}</span>"#
+            ));
+        }
+        if let Some(marker) = marker {
+            out.push_str(&format!(r#"<span class="diff-marker">{marker}</span>"#));
+        }
+        let regions = highlighter.highlight_line(code, syntax_set)?;
+        let html =
+            styled_line_to_highlighted_html(&regions, IncludeBackground::IfDifferent(background))?;
+        if wrapper.is_some() {
+            // Keep the newline outside the block-level span so it does not
+            // add an empty line after it.
+            let body = html.strip_suffix('\n').unwrap_or(&html);
+            out.push_str(body);
+            out.push_str("</span>");
+        } else {
+            out.push_str(&html);
+        }
+    }
+
+    out.push_str("</pre>\n");
+    Ok(out)
+}
+
 /// Backup renderer in case syntect fails for whatever reason
 pub fn fallback_plain(source: &str, language: Option<&str>) -> String {
     let mut out = String::with_capacity(source.len() + 32);
@@ -152,10 +481,15 @@ where
     I: Iterator<Item = Event<'a>>,
 {
     fn transform(inner: I) -> Self {
+        Self::transform_with(inner, &DocumentSink::default())
+    }
+
+    fn transform_with(inner: I, sink: &DocumentSink) -> Self {
         Self {
             inner,
             buffer: String::new(),
             state: CodeBlockState::Passthrough,
+            sink: sink.clone(),
         }
     }
 }
@@ -199,10 +533,8 @@ where
                             unreachable!()
                         };
 
-                        let language = match lang {
-                            CodeBlockKind::Fenced(ref l) => Some(l.as_ref()),
-                            CodeBlockKind::Indented => None,
-                        };
+                        let info = FenceInfo::parse(&lang);
+                        let language = info.lang;
 
                         let class_attr = language
                             .map(|l| format!(" class=\"language-{}\"", escape_attr(l)))
@@ -211,9 +543,12 @@ where
                             .map(|l| format!(" data-lang=\"{}\"", escape_attr(l)))
                             .unwrap_or_default();
 
-                        let rendered = format!(
-                            "<pre><code{class_attr}{data_attr}>{}</code></pre>\n",
-                            escape_html(&self.buffer)
+                        let rendered = with_title(
+                            format!(
+                                "<pre><code{class_attr}{data_attr}>{}</code></pre>\n",
+                                escape_html(&self.buffer)
+                            ),
+                            info.title,
                         );
 
                         return Some(Event::Html(CowStr::from(rendered)));
@@ -2,11 +2,15 @@ use proptest::{
     prelude::*,
     test_runner::{Config, TestRunner},
 };
-use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Tag, TagEnd};
+use syntect::parsing::SyntaxSet;
 
 use crate::transformer::{
     WithTransformer,
-    code_block::{CodeHighlightTransformer, FeedCodeLabelTransformer},
+    code_block::{
+        BUNDLED_THEME, CodeHighlightTransformer, FeedCodeLabelTransformer, FenceInfo, find_syntax,
+        resolve_theme, syntaxes_with,
+    },
 };
 
 #[test]
@@ -61,3 +65,175 @@ fn feed_code_labels_language() {
         panic!("expected html");
     }
 }
+
+#[test]
+fn fence_info_reads_language_and_flags() {
+    let kind = CodeBlockKind::Fenced(CowStr::from("rs,linenos"));
+    let info = FenceInfo::parse(&kind);
+    assert_eq!(info.lang, Some("rs"));
+    assert!(info.linenos);
+
+    let kind = CodeBlockKind::Fenced(CowStr::from("py nolinenos"));
+    let info = FenceInfo::parse(&kind);
+    assert_eq!(info.lang, Some("py"));
+    assert!(!info.linenos);
+
+    assert_eq!(FenceInfo::parse(&CodeBlockKind::Indented).lang, None);
+
+    let kind = CodeBlockKind::Fenced(CowStr::from(r#"rs title="src/my main.rs",linenos"#));
+    let info = FenceInfo::parse(&kind);
+    assert_eq!(info.lang, Some("rs"));
+    assert_eq!(info.title, Some("src/my main.rs"));
+    assert!(info.linenos);
+}
+
+#[test]
+fn titled_fences_get_a_header_bar() {
+    let events = vec![
+        Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(CowStr::from(
+            "rs title=src/main.rs",
+        )))),
+        Event::Text(CowStr::from("fn main() {}\n")),
+        Event::End(TagEnd::CodeBlock),
+    ];
+
+    let out: Vec<_> = events
+        .into_iter()
+        .with_transformer::<CodeHighlightTransformer<_>>()
+        .collect();
+
+    let Event::Html(html) = &out[0] else {
+        panic!("expected html");
+    };
+    assert!(html.starts_with(
+        r#"<figure class="code"><figcaption class="code-title">src/main.rs</figcaption><pre"#
+    ));
+    assert!(html.trim_end().ends_with("</pre></figure>"));
+}
+
+#[test]
+fn line_numbers_are_emitted_outside_the_code() {
+    let events = vec![
+        Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(CowStr::from(
+            "rs,linenos",
+        )))),
+        Event::Text(CowStr::from("let a = 1;\nlet b = 2;\n")),
+        Event::End(TagEnd::CodeBlock),
+    ];
+
+    let out: Vec<_> = events
+        .into_iter()
+        .with_transformer::<CodeHighlightTransformer<_>>()
+        .collect();
+
+    let Event::Html(html) = &out[0] else {
+        panic!("expected html");
+    };
+    assert_eq!(html.matches(r#"class="lineno""#).count(), 2);
+    assert!(html.contains(r#"aria-hidden="true""#));
+    assert!(html.contains("user-select:none"));
+    assert!(html.contains(">2</span>"));
+}
+
+#[test]
+fn hl_lines_wrap_selected_lines() {
+    let kind = CodeBlockKind::Fenced(CowStr::from(r#"rs hl_lines="1 3-4""#));
+    assert_eq!(FenceInfo::parse(&kind).hl_lines, vec![1..=1, 3..=4]);
+
+    let events = vec![
+        Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(CowStr::from(
+            "rs,hl_lines=2-3",
+        )))),
+        Event::Text(CowStr::from(
+            "let a = 1;\nlet b = 2;\nlet c = 3;\nlet d = 4;\n",
+        )),
+        Event::End(TagEnd::CodeBlock),
+    ];
+
+    let out: Vec<_> = events
+        .into_iter()
+        .with_transformer::<CodeHighlightTransformer<_>>()
+        .collect();
+
+    let Event::Html(html) = &out[0] else {
+        panic!("expected html");
+    };
+    assert_eq!(html.matches(r#"<span class="hl""#).count(), 2);
+    assert!(!html.contains("lineno"));
+}
+
+#[test]
+fn diff_fences_mark_added_and_removed_lines() {
+    let kind = CodeBlockKind::Fenced(CowStr::from("diff-rs"));
+    let info = FenceInfo::parse(&kind);
+    assert_eq!(info.lang, Some("rs"));
+    assert!(info.diff);
+    assert!(!FenceInfo::parse(&CodeBlockKind::Fenced(CowStr::from("diff"))).diff);
+
+    let events = vec![
+        Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(CowStr::from(
+            "diff-rs",
+        )))),
+        Event::Text(CowStr::from(" fn main() {\n-    old();\n+    new();\n }\n")),
+        Event::End(TagEnd::CodeBlock),
+    ];
+
+    let out: Vec<_> = events
+        .into_iter()
+        .with_transformer::<CodeHighlightTransformer<_>>()
+        .collect();
+
+    let Event::Html(html) = &out[0] else {
+        panic!("expected html");
+    };
+    assert_eq!(html.matches(r#"<span class="diff-add""#).count(), 1);
+    assert_eq!(html.matches(r#"<span class="diff-del""#).count(), 1);
+    assert!(html.contains(r#"<span class="diff-marker">+</span>"#));
+}
+
+#[test]
+fn user_syntaxes_extend_the_defaults() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(
+        dir.path().join("widget.sublime-syntax"),
+        "%YAML 1.2\n---\nname: Widget\nfile_extensions: [wdg]\nscope: source.widget\ncontexts:\n  main:\n    - match: '\\bwidget\\b'\n      scope: keyword.control.widget\n",
+    )
+    .unwrap();
+
+    let syntaxes = syntaxes_with(dir.path()).unwrap();
+
+    assert_eq!(find_syntax(&syntaxes, "wdg").unwrap().name, "Widget");
+    assert_eq!(find_syntax(&syntaxes, "widget").unwrap().name, "Widget");
+    assert!(find_syntax(&syntaxes, "rs").is_some());
+}
+
+#[test]
+fn fence_aliases_fall_back_through_their_syntaxes() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let defaults = SyntaxSet::load_defaults_newlines();
+
+    assert_eq!(
+        find_syntax(&defaults, "console").unwrap().name,
+        "Bourne Again Shell (bash)"
+    );
+    assert_eq!(find_syntax(&defaults, "JSX").unwrap().name, "JavaScript");
+    assert!(find_syntax(&defaults, "nix").is_none());
+
+    std::fs::write(
+        dir.path().join("nix.sublime-syntax"),
+        "%YAML 1.2\n---\nname: Nix\nfile_extensions: [nixexpr]\nscope: source.nix\ncontexts:\n  main:\n    - match: '\\blet\\b'\n      scope: keyword.other.nix\n",
+    )
+    .unwrap();
+    let syntaxes = syntaxes_with(dir.path()).unwrap();
+    assert_eq!(find_syntax(&syntaxes, "nix").unwrap().name, "Nix");
+}
+
+#[test]
+fn themes_resolve_by_name_or_path() {
+    let dir = tempfile::TempDir::new().unwrap();
+
+    assert!(resolve_theme(BUNDLED_THEME, dir.path()).is_ok());
+    assert!(resolve_theme("InspiredGitHub", dir.path()).is_ok());
+
+    assert!(resolve_theme("missing.tmTheme", dir.path()).is_err());
+}
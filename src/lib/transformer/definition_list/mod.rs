@@ -0,0 +1,90 @@
+use std::collections::HashSet;
+
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+
+use crate::{transformer::Transformer, utils::slugify};
+
+/// Render definition lists (a term, then `: ` and its definition) with
+/// classes to style glossaries by, giving each term an id to link to.
+pub struct DefinitionListTransformer<'a> {
+    inner: std::vec::IntoIter<Event<'a>>,
+}
+
+impl<'a> Iterator for DefinitionListTransformer<'a> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+impl<'a, I> Transformer<'a, I> for DefinitionListTransformer<'a>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    fn transform(inner: I) -> Self {
+        let rewritten = render_definition_lists(inner.collect());
+        Self {
+            inner: rewritten.into_iter(),
+        }
+    }
+}
+
+/// Replace the definition list tags with classed HTML. Each term's id is
+/// `term-` and the slug of its text, suffixed when a term repeats.
+pub fn render_definition_lists<'a>(events: Vec<Event<'a>>) -> Vec<Event<'a>> {
+    let mut out: Vec<Event<'a>> = Vec::with_capacity(events.len());
+    let mut ids: HashSet<String> = HashSet::new();
+
+    for (i, event) in events.iter().enumerate() {
+        let html = match event {
+            Event::Start(Tag::DefinitionList) => "<dl class=\"definition-list\">\n".to_owned(),
+            Event::End(TagEnd::DefinitionList) => "</dl>\n".to_owned(),
+            Event::Start(Tag::DefinitionListTitle) => {
+                let id = unique_id(
+                    &mut ids,
+                    format!("term-{}", slugify(&term_text(&events[i + 1..]))),
+                );
+                format!(r#"<dt id="{id}" class="definition-term">"#)
+            }
+            Event::End(TagEnd::DefinitionListTitle) => "</dt>\n".to_owned(),
+            Event::Start(Tag::DefinitionListDefinition) => r#"<dd class="definition">"#.to_owned(),
+            Event::End(TagEnd::DefinitionListDefinition) => "</dd>\n".to_owned(),
+            other => {
+                out.push(other.clone());
+                continue;
+            }
+        };
+        out.push(Event::Html(CowStr::from(html)));
+    }
+
+    out
+}
+
+/// Text of the term whose events start `events`.
+fn term_text(events: &[Event<'_>]) -> String {
+    let mut text = String::new();
+    for event in events {
+        match event {
+            Event::End(TagEnd::DefinitionListTitle) => break,
+            Event::Text(t) | Event::Code(t) => text.push_str(t),
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// `base`, suffixed when a repeated term already took it.
+fn unique_id(ids: &mut HashSet<String>, base: String) -> String {
+    let mut id = base.clone();
+    let mut n = 1;
+    while !ids.insert(id.clone()) {
+        n += 1;
+        id = format!("{base}-{n}");
+    }
+    id
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,58 @@
+use pulldown_cmark::{Options, Parser};
+
+use crate::transformer::{Transformer, definition_list::DefinitionListTransformer};
+
+fn render(md: &str) -> String {
+    let events = Parser::new_ext(md, Options::ENABLE_DEFINITION_LIST);
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, DefinitionListTransformer::transform(events));
+    html
+}
+
+#[test]
+fn definition_lists_get_classes_and_term_ids() {
+    let html = render("Apple\n: A fruit.\n\nBanana\n: Another fruit.\n");
+    assert_eq!(
+        html,
+        concat!(
+            "<dl class=\"definition-list\">\n",
+            "<dt id=\"term-apple\" class=\"definition-term\">Apple</dt>\n",
+            "<dd class=\"definition\">A fruit.</dd>\n",
+            "<dt id=\"term-banana\" class=\"definition-term\">Banana</dt>\n",
+            "<dd class=\"definition\">Another fruit.</dd>\n",
+            "</dl>\n"
+        )
+    );
+}
+
+#[test]
+fn terms_keep_inline_markup_and_slug_its_text() {
+    let html = render("The *`ssg`* tool\n: Builds sites.\n");
+    assert!(html.contains(
+        r#"<dt id="term-the-ssg-tool" class="definition-term">The <em><code>ssg</code></em> tool</dt>"#
+    ));
+}
+
+#[test]
+fn terms_with_several_definitions_and_repeats() {
+    let html = render("Term\n: First.\n: Second.\n\nTerm\n: Again.\n");
+    assert_eq!(html.matches(r#"<dd class="definition">"#).count(), 3);
+    assert!(html.contains(r#"id="term-term""#));
+    assert!(html.contains(r#"id="term-term-2""#));
+}
+
+#[test]
+fn definitions_may_hold_blocks() {
+    let html = render("Term\n\n: First paragraph.\n\n    Second paragraph.\n");
+    assert!(html.contains(r#"<dd class="definition">"#));
+    assert!(html.contains("<p>First paragraph.</p>"), "{html}");
+    assert!(html.contains("<p>Second paragraph.</p>"), "{html}");
+}
+
+#[test]
+fn other_markup_is_untouched() {
+    let md = "# Title\n\nJust a paragraph: with a colon.\n";
+    let mut plain = String::new();
+    pulldown_cmark::html::push_html(&mut plain, Parser::new_ext(md, Options::empty()));
+    assert_eq!(render(md), plain);
+}
@@ -1,6 +1,11 @@
 use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
 
-use crate::transformer::Transformer;
+use crate::{
+    config::{
+        EPIGRAPH_ATTRIBUTION_MARKERS, EPIGRAPH_DETECTION, EPIGRAPH_MARKER, EpigraphDetection,
+    },
+    transformer::Transformer,
+};
 
 pub struct EpigraphTransformer<'a> {
     inner: std::vec::IntoIter<Event<'a>>,
@@ -20,15 +25,20 @@ where
 {
     fn transform(inner: I) -> Self {
         let events: Vec<Event<'a>> = inner.collect();
-        let rewritten = process_epigraphs(events);
+        let rewritten = process_epigraphs(events, EPIGRAPH_DETECTION);
         Self {
             inner: rewritten.into_iter(),
         }
     }
 }
 
-fn process_epigraphs<'a>(events: Vec<Event<'a>>) -> Vec<Event<'a>> {
+fn process_epigraphs<'a>(events: Vec<Event<'a>>, detection: EpigraphDetection) -> Vec<Event<'a>> {
     let mut out = Vec::with_capacity(events.len());
+    // Epigraphs stacked at the very top of the document are buffered so a run
+    // of several can be grouped into one container.
+    let mut leading: Vec<Vec<Event<'a>>> = Vec::new();
+    let mut at_top = true;
+    let mut in_metadata = false;
     let mut i = 0;
 
     while i < events.len() {
@@ -37,24 +47,60 @@ fn process_epigraphs<'a>(events: Vec<Event<'a>>) -> Vec<Event<'a>> {
                 let (block, consumed) = capture_blockquote(&events[i..]);
                 i += consumed;
 
-                if let Some(epigraph) = block.and_then(EpigraphBlock::from_events) {
-                    render_epigraph(&mut out, epigraph);
+                if let Some(epigraph) = block.and_then(|b| EpigraphBlock::from_events(b, detection))
+                {
+                    let mut rendered = Vec::new();
+                    render_epigraph(&mut rendered, epigraph);
+                    if at_top {
+                        leading.push(rendered);
+                    } else {
+                        out.extend(rendered);
+                    }
                 } else {
+                    flush_leading(&mut out, &mut leading);
+                    at_top = false;
                     out.push(Event::Start(Tag::BlockQuote(None)));
                     out.extend(events[i - consumed + 1..i - 1].iter().cloned());
                     out.push(Event::End(TagEnd::BlockQuote(None)));
                 }
             }
             other => {
+                match other {
+                    Event::Start(Tag::MetadataBlock(_)) => in_metadata = true,
+                    Event::End(TagEnd::MetadataBlock(_)) => in_metadata = false,
+                    _ if in_metadata => {}
+                    _ => {
+                        flush_leading(&mut out, &mut leading);
+                        at_top = false;
+                    }
+                }
                 out.push(other.clone());
                 i += 1;
             }
         }
     }
 
+    flush_leading(&mut out, &mut leading);
     out
 }
 
+/// Emit buffered leading epigraphs, wrapping runs of two or more in a shared
+/// `<div class="epigraphs">`.
+fn flush_leading<'a>(out: &mut Vec<Event<'a>>, leading: &mut Vec<Vec<Event<'a>>>) {
+    let grouped = leading.len() > 1;
+    if grouped {
+        out.push(Event::Html(CowStr::from(r#"<div class="epigraphs">"#)));
+        out.push(Event::Html(CowStr::from("\n")));
+    }
+    for rendered in leading.drain(..) {
+        out.extend(rendered);
+    }
+    if grouped {
+        out.push(Event::Html(CowStr::from("</div>")));
+        out.push(Event::Html(CowStr::from("\n")));
+    }
+}
+
 /// Captures a blockquote starting at index 0; returns its inner events and items consumed.
 fn capture_blockquote<'a>(slice: &[Event<'a>]) -> (Option<Vec<Event<'a>>>, usize) {
     if !matches!(slice.first(), Some(Event::Start(Tag::BlockQuote(_)))) {
@@ -86,7 +132,15 @@ struct EpigraphBlock<'a> {
 }
 
 impl<'a> EpigraphBlock<'a> {
-    fn from_events(events: Vec<Event<'a>>) -> Option<Self> {
+    fn from_events(events: Vec<Event<'a>>, detection: EpigraphDetection) -> Option<Self> {
+        let events = match strip_explicit_marker(events) {
+            Ok(events) => return Some(Self::explicit(events)),
+            Err(events) => events,
+        };
+        if detection == EpigraphDetection::Explicit {
+            return None;
+        }
+
         let last_para_start = events
             .iter()
             .rposition(|e| matches!(e, Event::Start(Tag::Paragraph)))?;
@@ -94,47 +148,140 @@ impl<'a> EpigraphBlock<'a> {
         let attribution = events[last_para_start..].to_vec();
         let quote = events[..last_para_start].to_vec();
 
-        if !is_epigraph(&attribution) {
+        if !is_epigraph(&quote, &attribution, detection) {
             return None;
         }
 
         Some(Self { quote, attribution })
     }
+
+    /// A marked epigraph, attributed only if its final paragraph opens with
+    /// an attribution marker.
+    fn explicit(mut events: Vec<Event<'a>>) -> Self {
+        let attributed = events
+            .iter()
+            .rposition(|e| matches!(e, Event::Start(Tag::Paragraph)))
+            .filter(|&start| {
+                start > 0
+                    && matches!(
+                        events.get(start + 1),
+                        Some(Event::Text(text)) if strip_attribution_marker(text).is_some()
+                    )
+            });
+        let attribution = match attributed {
+            Some(start) => events.split_off(start),
+            None => Vec::new(),
+        };
+        Self {
+            quote: events,
+            attribution,
+        }
+    }
 }
 
-fn is_epigraph(buffer: &[Event]) -> bool {
-    buffer.iter().any(|event| {
-        if let Event::Text(text) = event {
-            let s = text.trim();
-            s.starts_with('—') || s.starts_with("--")
-        } else {
-            false
+/// Remove `EPIGRAPH_MARKER` from the start of a blockquote's contents, along
+/// with the line break or paragraph it leaves empty. Hands the contents back
+/// untouched if they do not start with it.
+fn strip_explicit_marker(mut events: Vec<Event<'_>>) -> Result<Vec<Event<'_>>, Vec<Event<'_>>> {
+    let rest = match events.get(..2) {
+        Some([Event::Start(Tag::Paragraph), Event::Text(text)]) => {
+            match text.trim_start().strip_prefix(EPIGRAPH_MARKER) {
+                Some(rest) => rest.trim_start().to_owned(),
+                None => return Err(events),
+            }
+        }
+        _ => return Err(events),
+    };
+
+    if !rest.is_empty() {
+        events[1] = Event::Text(CowStr::from(rest));
+        return Ok(events);
+    }
+    match events.get(2) {
+        Some(Event::SoftBreak | Event::HardBreak) => {
+            events.drain(1..3);
         }
-    })
+        Some(Event::End(TagEnd::Paragraph)) => {
+            events.drain(..3);
+        }
+        _ => {
+            events.remove(1);
+        }
+    }
+    Ok(events)
 }
 
-fn render_epigraph<'a>(out: &mut Vec<Event<'a>>, block: EpigraphBlock<'a>) {
-    out.push(Event::Html(CowStr::from(r#"<div class="epigraph">"#)));
-    out.push(Event::Html(CowStr::from("\n")));
+fn is_epigraph(quote: &[Event], attribution: &[Event], detection: EpigraphDetection) -> bool {
+    match detection {
+        EpigraphDetection::Explicit => false,
+        // The attribution paragraph must open with a dash and follow an actual
+        // quote, so a lone dashed line or a dash mid-sentence does not count.
+        EpigraphDetection::LeadingDash => {
+            !quote.is_empty()
+                && matches!(
+                    attribution.get(1),
+                    Some(Event::Text(text)) if strip_attribution_marker(text).is_some()
+                )
+        }
+        EpigraphDetection::LastParagraph => attribution.iter().any(|event| {
+            if let Event::Text(text) = event {
+                strip_attribution_marker(text).is_some()
+            } else {
+                false
+            }
+        }),
+    }
+}
 
-    out.push(Event::Html(CowStr::from(r#"<blockquote>"#)));
-    out.extend(block.quote);
-    out.push(Event::Html(CowStr::from(r#"</blockquote>"#)));
+/// Strip a leading attribution dash (see `EPIGRAPH_ATTRIBUTION_MARKERS`),
+/// returning the remaining text if one was present.
+pub fn strip_attribution_marker(text: &str) -> Option<&str> {
+    let trimmed = text.trim_start();
+    EPIGRAPH_ATTRIBUTION_MARKERS
+        .iter()
+        .find_map(|marker| trimmed.strip_prefix(marker))
+}
 
-    out.push(Event::Html(CowStr::from(r#"<p class="attribution">"#)));
+/// The attribution paragraph's inline events, kept as written (emphasis,
+/// links, `<cite>`, ...) but for the first attribution marker.
+fn attribution_events<'a>(events: Vec<Event<'a>>) -> Vec<Event<'a>> {
+    let mut out = Vec::with_capacity(events.len());
+    let mut marker_seen = false;
 
-    for event in block.attribution {
+    for event in events {
         match event {
-            Event::Start(Tag::Paragraph) | Event::End(TagEnd::Paragraph) => continue,
-            Event::Text(t) => {
-                let s = t.trim_start_matches('—').trim_start_matches('-');
-                out.push(Event::Text(CowStr::from(s.to_owned())));
+            Event::Start(Tag::Paragraph) | Event::End(TagEnd::Paragraph) => {}
+            Event::Text(t) if !marker_seen => {
+                if let Some(rest) = strip_attribution_marker(&t) {
+                    marker_seen = true;
+                    let rest = rest.trim_start();
+                    if !rest.is_empty() {
+                        out.push(Event::Text(CowStr::from(rest.to_owned())));
+                    }
+                } else {
+                    out.push(Event::Text(t));
+                }
             }
             other => out.push(other),
         }
     }
 
-    out.push(Event::Html(CowStr::from(r#"</p>"#)));
+    out
+}
+
+fn render_epigraph<'a>(out: &mut Vec<Event<'a>>, block: EpigraphBlock<'a>) {
+    out.push(Event::Html(CowStr::from(r#"<div class="epigraph">"#)));
+    out.push(Event::Html(CowStr::from("\n")));
+
+    out.push(Event::Html(CowStr::from(r#"<blockquote>"#)));
+    out.extend(block.quote);
+    out.push(Event::Html(CowStr::from(r#"</blockquote>"#)));
+
+    if !block.attribution.is_empty() {
+        out.push(Event::Html(CowStr::from(r#"<p class="attribution">"#)));
+        out.extend(attribution_events(block.attribution));
+        out.push(Event::Html(CowStr::from(r#"</p>"#)));
+    }
     out.push(Event::Html(CowStr::from(r#"</div>"#)));
     out.push(Event::Html(CowStr::from("\n")));
 }
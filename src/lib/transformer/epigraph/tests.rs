@@ -1,6 +1,12 @@
 use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
 
-use crate::transformer::{Transformer, epigraph::EpigraphTransformer};
+use crate::{
+    config::EpigraphDetection,
+    transformer::{
+        Transformer,
+        epigraph::{EpigraphTransformer, process_epigraphs},
+    },
+};
 
 #[test]
 fn epigraph_transformer_detects_final_attribution() {
@@ -26,3 +32,197 @@ fn epigraph_transformer_detects_final_attribution() {
 
     assert!(html.contains(r#"<div class="epigraph">"#));
 }
+
+#[test]
+fn epigraph_accepts_ascii_and_typographic_dashes() {
+    for marker in ["--", "–", "—"] {
+        let events = vec![
+            Event::Start(Tag::BlockQuote(None)),
+            Event::Start(Tag::Paragraph),
+            Event::Text(CowStr::from("Quote.")),
+            Event::End(TagEnd::Paragraph),
+            Event::Start(Tag::Paragraph),
+            Event::Text(CowStr::from(format!("{marker} Author"))),
+            Event::End(TagEnd::Paragraph),
+            Event::End(TagEnd::BlockQuote(None)),
+        ];
+
+        let out: Vec<_> = EpigraphTransformer::transform(events.into_iter()).collect();
+
+        assert!(
+            out.iter()
+                .any(|e| matches!(e, Event::Html(h) if h.as_ref() == r#"<div class="epigraph">"#)),
+            "marker {marker} should produce an epigraph"
+        );
+        assert!(out.contains(&Event::Text(CowStr::from("Author"))));
+    }
+}
+
+fn quote_block<'a>(quote: &'a str, last: Vec<Event<'a>>) -> Vec<Event<'a>> {
+    let mut events = vec![
+        Event::Start(Tag::BlockQuote(None)),
+        Event::Start(Tag::Paragraph),
+        Event::Text(CowStr::from(quote)),
+        Event::End(TagEnd::Paragraph),
+        Event::Start(Tag::Paragraph),
+    ];
+    events.extend(last);
+    events.push(Event::End(TagEnd::Paragraph));
+    events.push(Event::End(TagEnd::BlockQuote(None)));
+    events
+}
+
+fn html_of(events: &[Event<'_>]) -> String {
+    events
+        .iter()
+        .filter_map(|e| match e {
+            Event::Html(h) => Some(h.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn leading_epigraphs_are_grouped() {
+    let mut events = quote_block("First.", vec![Event::Text(CowStr::from("— One"))]);
+    events.extend(quote_block(
+        "Second.",
+        vec![Event::Text(CowStr::from("— Two"))],
+    ));
+    events.push(Event::Start(Tag::Paragraph));
+    events.push(Event::Text(CowStr::from("Body")));
+    events.push(Event::End(TagEnd::Paragraph));
+    events.extend(quote_block(
+        "Later.",
+        vec![Event::Text(CowStr::from("— Three"))],
+    ));
+
+    let out: Vec<_> = EpigraphTransformer::transform(events.into_iter()).collect();
+    let html = html_of(&out);
+
+    assert_eq!(html.matches(r#"<div class="epigraphs">"#).count(), 1);
+    assert_eq!(html.matches(r#"<div class="epigraph">"#).count(), 3);
+
+    // The group closes before the body paragraph; the later epigraph stands alone.
+    let group_end = out
+        .iter()
+        .position(|e| matches!(e, Event::Html(h) if h.as_ref() == "</div>"))
+        .unwrap();
+    let body = out
+        .iter()
+        .position(|e| matches!(e, Event::Text(t) if t.as_ref() == "Body"))
+        .unwrap();
+    assert!(group_end < body);
+}
+
+#[test]
+fn single_leading_epigraph_is_not_grouped() {
+    let events = quote_block("Only.", vec![Event::Text(CowStr::from("— One"))]);
+    let out: Vec<_> = EpigraphTransformer::transform(events.into_iter()).collect();
+    assert!(!html_of(&out).contains("epigraphs"));
+}
+
+#[test]
+fn detection_modes_differ_on_mid_paragraph_dash() {
+    let last = vec![
+        Event::Start(Tag::Emphasis),
+        Event::Text(CowStr::from("Stop")),
+        Event::End(TagEnd::Emphasis),
+        Event::Text(CowStr::from("— he said.")),
+    ];
+    let events = quote_block("An ordinary quote.", last);
+
+    let strict = process_epigraphs(events.clone(), EpigraphDetection::LeadingDash);
+    assert!(!html_of(&strict).contains("epigraph"));
+
+    let loose = process_epigraphs(events, EpigraphDetection::LastParagraph);
+    assert!(html_of(&loose).contains(r#"<div class="epigraph">"#));
+}
+
+#[test]
+fn attribution_keeps_inline_markup() {
+    use pulldown_cmark::{Options, Parser};
+
+    let md = "> Quote.\n>\n> -- Jane Doe, *The Book* (1999), <cite>Vol. 2</cite>, [online](https://example.com/a--b)\n";
+    let out = process_epigraphs(
+        Parser::new_ext(md, Options::empty()).collect(),
+        EpigraphDetection::LeadingDash,
+    );
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, out.into_iter());
+
+    assert!(html.contains(concat!(
+        r#"<p class="attribution">Jane Doe, <em>The Book</em> (1999), "#,
+        r#"<cite>Vol. 2</cite>, <a href="https://example.com/a--b">online</a></p>"#
+    )));
+}
+
+#[test]
+fn only_the_leading_marker_is_stripped() {
+    let out = process_epigraphs(
+        quote_block(
+            "Quote.",
+            vec![
+                Event::Text(CowStr::from("-- Author, ")),
+                Event::Start(Tag::Emphasis),
+                Event::Text(CowStr::from("Work")),
+                Event::End(TagEnd::Emphasis),
+                Event::Text(CowStr::from(" -- 1999")),
+            ],
+        ),
+        EpigraphDetection::LeadingDash,
+    );
+
+    assert!(html_of(&out).contains(r#"<p class="attribution">"#));
+    assert!(out.contains(&Event::Text(CowStr::from("Author, "))));
+    assert!(out.contains(&Event::Text(CowStr::from(" -- 1999"))));
+}
+
+fn render(md: &str, detection: EpigraphDetection) -> String {
+    use pulldown_cmark::{Options, Parser};
+
+    let out = process_epigraphs(Parser::new_ext(md, Options::empty()).collect(), detection);
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, out.into_iter());
+    html
+}
+
+#[test]
+fn marked_blockquotes_are_epigraphs_in_every_mode() {
+    let md = "> {.epigraph}\n> Quote.\n>\n> -- Author\n";
+    for detection in [
+        EpigraphDetection::LeadingDash,
+        EpigraphDetection::LastParagraph,
+        EpigraphDetection::Explicit,
+    ] {
+        let html = render(md, detection);
+        assert!(html.contains(r#"<div class="epigraph">"#), "{detection:?}");
+        assert!(html.contains("<p>Quote.</p>"), "{html}");
+        assert!(
+            html.contains(r#"<p class="attribution">Author</p>"#),
+            "{html}"
+        );
+        assert!(!html.contains(".epigraph}"));
+    }
+}
+
+#[test]
+fn marked_epigraphs_need_no_attribution() {
+    let html = render(
+        "> {.epigraph}\n>\n> Quote.\n>\n> Still quoting.\n",
+        EpigraphDetection::Explicit,
+    );
+    assert!(html.contains(r#"<div class="epigraph">"#));
+    assert!(
+        html.contains("<p>Quote.</p>\n<p>Still quoting.</p>"),
+        "{html}"
+    );
+    assert!(!html.contains("attribution"));
+}
+
+#[test]
+fn explicit_mode_ignores_dashed_quotes() {
+    let html = render("> Quote.\n>\n> -- Author\n", EpigraphDetection::Explicit);
+    assert!(!html.contains("epigraph"));
+    assert!(html.contains("<blockquote>"));
+}
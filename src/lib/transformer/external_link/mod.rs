@@ -0,0 +1,160 @@
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+
+use crate::{
+    config::{
+        EXTERNAL_LINK_ICON, EXTERNAL_LINKS_NEW_TAB, EXTERNAL_LINKS_NOREFERRER, SITE_BASE_URL,
+    },
+    transformer::{Transformer, raw_html::rewrite_event},
+    utils::{escape_attr, external_host},
+};
+
+/// How links to other sites are marked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExternalLinkOptions {
+    /// Add `rel="noopener noreferrer"`.
+    pub noreferrer: bool,
+    /// Add `target="_blank"`.
+    pub new_tab: bool,
+    /// End the link text in an icon.
+    pub icon: bool,
+    /// Host of the site itself, whose absolute links are internal.
+    pub site_host: Option<String>,
+}
+
+impl Default for ExternalLinkOptions {
+    fn default() -> Self {
+        Self {
+            noreferrer: EXTERNAL_LINKS_NOREFERRER,
+            new_tab: EXTERNAL_LINKS_NEW_TAB,
+            icon: EXTERNAL_LINK_ICON,
+            site_host: external_host(SITE_BASE_URL),
+        }
+    }
+}
+
+impl ExternalLinkOptions {
+    fn marks_links(&self) -> bool {
+        self.noreferrer || self.new_tab || self.icon
+    }
+
+    fn is_external(&self, url: &str) -> bool {
+        external_host(url).is_some_and(|host| self.site_host.as_deref() != Some(host.as_str()))
+    }
+}
+
+/// Mark links to other sites (see `ExternalLinkOptions`), leaving relative
+/// links and links within the site alone.
+pub struct ExternalLinkTransformer<'a> {
+    inner: std::vec::IntoIter<Event<'a>>,
+}
+
+impl<'a> Iterator for ExternalLinkTransformer<'a> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+impl<'a> ExternalLinkTransformer<'a> {
+    pub fn with_options<I>(inner: I, options: &ExternalLinkOptions) -> Self
+    where
+        I: Iterator<Item = Event<'a>>,
+    {
+        let events: Vec<Event<'a>> = inner.collect();
+        let rewritten = if options.marks_links() {
+            mark_external_links(events, options)
+        } else {
+            events
+        };
+        Self {
+            inner: rewritten.into_iter(),
+        }
+    }
+}
+
+impl<'a, I> Transformer<'a, I> for ExternalLinkTransformer<'a>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    fn transform(inner: I) -> Self {
+        Self::with_options(inner, &ExternalLinkOptions::default())
+    }
+}
+
+/// Replace the tags of each external link with HTML carrying the extra
+/// attributes. The link text in between is untouched. External `<a>` tags in
+/// raw HTML gain the attributes they lack, but not the icon, whose place
+/// after the link text may be in a later event.
+pub fn mark_external_links<'a>(
+    events: Vec<Event<'a>>,
+    options: &ExternalLinkOptions,
+) -> Vec<Event<'a>> {
+    let mut out = Vec::with_capacity(events.len());
+    // Whether each open link was rewritten, so its end tag can follow suit.
+    let mut open: Vec<bool> = Vec::new();
+
+    for event in events {
+        match event {
+            Event::Start(Tag::Link {
+                ref dest_url,
+                ref title,
+                ..
+            }) if options.is_external(dest_url) => {
+                open.push(true);
+                out.push(Event::InlineHtml(CowStr::from(open_tag(
+                    dest_url, title, options,
+                ))));
+            }
+            Event::Start(Tag::Link { .. }) => {
+                open.push(false);
+                out.push(event);
+            }
+            Event::End(TagEnd::Link) if open.pop() == Some(true) => {
+                let icon = if options.icon {
+                    r#"<span class="external-link-icon" aria-hidden="true">↗</span>"#
+                } else {
+                    ""
+                };
+                out.push(Event::InlineHtml(CowStr::from(format!("{icon}</a>"))));
+            }
+            html @ (Event::Html(_) | Event::InlineHtml(_)) => {
+                out.push(rewrite_event(html, "a[href]", |el| {
+                    let href = el.get_attribute("href").unwrap_or_default();
+                    if !options.is_external(&href) {
+                        return false;
+                    }
+                    let mut changed = false;
+                    if options.noreferrer && !el.has_attribute("rel") {
+                        changed |= el.set_attribute("rel", "noopener noreferrer").is_ok();
+                    }
+                    if options.new_tab && !el.has_attribute("target") {
+                        changed |= el.set_attribute("target", "_blank").is_ok();
+                    }
+                    changed
+                }));
+            }
+            other => out.push(other),
+        }
+    }
+
+    out
+}
+
+fn open_tag(dest_url: &str, title: &str, options: &ExternalLinkOptions) -> String {
+    let mut tag = format!(r#"<a href="{}""#, escape_attr(dest_url));
+    if !title.is_empty() {
+        tag.push_str(&format!(r#" title="{}""#, escape_attr(title)));
+    }
+    if options.noreferrer {
+        tag.push_str(r#" rel="noopener noreferrer""#);
+    }
+    if options.new_tab {
+        tag.push_str(r#" target="_blank""#);
+    }
+    tag.push('>');
+    tag
+}
+
+#[cfg(test)]
+mod tests;
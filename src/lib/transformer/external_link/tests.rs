@@ -0,0 +1,102 @@
+use pulldown_cmark::{Options, Parser};
+
+use crate::transformer::external_link::{ExternalLinkOptions, ExternalLinkTransformer};
+
+fn options(new_tab: bool, icon: bool) -> ExternalLinkOptions {
+    ExternalLinkOptions {
+        noreferrer: true,
+        new_tab,
+        icon,
+        site_host: Some("dysthesis.com".into()),
+    }
+}
+
+fn render(md: &str, options: &ExternalLinkOptions) -> String {
+    let events = Parser::new_ext(md, Options::empty());
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(
+        &mut html,
+        ExternalLinkTransformer::with_options(events, options),
+    );
+    html
+}
+
+#[test]
+fn external_links_are_marked() {
+    let html = render(
+        r#"See [*the* spec](https://example.com/a?b=1&c=2 "Spec")."#,
+        &options(false, false),
+    );
+    assert_eq!(
+        html,
+        concat!(
+            r#"<p>See <a href="https://example.com/a?b=1&amp;c=2" title="Spec" "#,
+            r#"rel="noopener noreferrer"><em>the</em> spec</a>.</p>"#,
+            "\n"
+        )
+    );
+}
+
+#[test]
+fn raw_html_links_gain_missing_marks() {
+    let html = render(
+        concat!(
+            r#"<a href="https://example.com/">out</a> "#,
+            r#"<a href="https://example.org/" rel="me">me</a> "#,
+            r#"<a href="https://dysthesis.com/x">home</a>"#,
+        ),
+        &options(true, true),
+    );
+    assert_eq!(
+        html,
+        concat!(
+            r#"<p><a href="https://example.com/" rel="noopener noreferrer" target="_blank">out</a> "#,
+            r#"<a href="https://example.org/" rel="me" target="_blank">me</a> "#,
+            r#"<a href="https://dysthesis.com/x">home</a></p>"#,
+            "\n"
+        )
+    );
+}
+
+#[test]
+fn internal_links_are_left_alone() {
+    let html = render(
+        "[a](../posts/a.html) [b](https://dysthesis.com/b.html) [c](#c) [d](mailto:x@example.com)",
+        &options(true, true),
+    );
+    assert!(!html.contains("rel="));
+    assert!(!html.contains("target="));
+    assert!(!html.contains("external-link-icon"));
+}
+
+#[test]
+fn new_tab_and_icon_are_optional() {
+    let html = render("<https://example.com>", &options(true, true));
+    assert_eq!(
+        html,
+        concat!(
+            r#"<p><a href="https://example.com" rel="noopener noreferrer" target="_blank">"#,
+            r#"https://example.com<span class="external-link-icon" aria-hidden="true">↗</span></a></p>"#,
+            "\n"
+        )
+    );
+}
+
+#[test]
+fn nothing_changes_when_every_mark_is_off() {
+    let off = ExternalLinkOptions {
+        noreferrer: false,
+        ..options(false, false)
+    };
+    let md = "[a](https://example.com)";
+    assert_eq!(
+        render(md, &off),
+        render(
+            md,
+            &ExternalLinkOptions {
+                site_host: Some("example.com".into()),
+                ..options(true, true)
+            }
+        )
+    );
+}
@@ -1,6 +1,15 @@
-use crate::transformer::Transformer;
-use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
-use std::{collections::HashMap, fmt::Write as _};
+use crate::{
+    config::{
+        SIDENOTE_MARKERS, SIDENOTE_PLACEMENT, SIDENOTE_RESTART_PER_SECTION, SidenoteMarkers,
+        SidenotePlacement,
+    },
+    transformer::Transformer,
+};
+use pulldown_cmark::{CowStr, Event, HeadingLevel, Tag, TagEnd};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write as _,
+};
 
 pub struct FootnoteTransformer<'a> {
     inner: std::vec::IntoIter<Event<'a>>,
@@ -20,7 +29,7 @@ where
 {
     fn transform(inner: I) -> Self {
         let events: Vec<Event<'a>> = inner.collect();
-        let rewritten = convert_footnotes_to_sidenotes(events);
+        let rewritten = convert_footnotes_to_sidenotes(events, &SidenoteOptions::default());
         Self {
             inner: rewritten.into_iter(),
         }
@@ -53,12 +62,51 @@ where
     }
 }
 
-pub fn convert_footnotes_to_sidenotes<'a>(events: Vec<Event<'a>>) -> Vec<Event<'a>> {
+/// Where sidenotes go and how they are marked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SidenoteOptions {
+    pub placement: SidenotePlacement,
+    pub markers: SidenoteMarkers,
+    /// Restart markers at each `#` section.
+    pub restart_per_section: bool,
+}
+
+impl Default for SidenoteOptions {
+    fn default() -> Self {
+        Self {
+            placement: SIDENOTE_PLACEMENT,
+            markers: SIDENOTE_MARKERS,
+            restart_per_section: SIDENOTE_RESTART_PER_SECTION,
+        }
+    }
+}
+
+/// Turn footnotes into Tufte-style sidenotes placed at their references. A
+/// footnote cited again keeps its marker and links back to its sidenote
+/// rather than repeating it.
+pub fn convert_footnotes_to_sidenotes<'a>(
+    events: Vec<Event<'a>>,
+    options: &SidenoteOptions,
+) -> Vec<Event<'a>> {
     let defs = FootnoteDefinitions::collect(&events);
+    let mut citations: HashMap<&str, usize> = HashMap::new();
+    for event in &events {
+        if let Event::FootnoteReference(label) = event {
+            *citations.entry(label.as_ref()).or_default() += 1;
+        }
+    }
+    let repeated: HashSet<String> = citations
+        .into_iter()
+        .filter(|&(_, n)| n > 1)
+        .map(|(label, _)| label.to_owned())
+        .collect();
 
     let mut out: Vec<Event<'a>> = Vec::with_capacity(events.len());
     let mut skipping_definition_depth: usize = 0;
+    // Ids number every sidenote on the page; markers may restart.
     let mut sidenote_index: usize = 0;
+    let mut section_index: usize = 0;
+    let mut notes: HashMap<String, (usize, String)> = HashMap::new();
 
     for event in events {
         if skipping_definition_depth > 0 {
@@ -77,17 +125,47 @@ pub fn convert_footnotes_to_sidenotes<'a>(events: Vec<Event<'a>>) -> Vec<Event<'
                 skipping_definition_depth = 1;
             }
 
+            Event::Start(Tag::Heading {
+                level: HeadingLevel::H1,
+                ..
+            }) if options.restart_per_section => {
+                section_index = 0;
+                out.push(event);
+            }
+
             Event::FootnoteReference(label) => {
+                if let Some((first, display)) = notes.get(label.as_ref()) {
+                    let html = format!(
+                        r##"<a href="#sn-{first}-note" class="sidenote-number sidenote-repeat" data-sidenote="{display}" aria-label="See note {display}"></a>"##
+                    );
+                    out.push(Event::InlineHtml(CowStr::from(html)));
+                    continue;
+                }
+
                 sidenote_index += 1;
+                section_index += 1;
                 let id = format!("sn-{sidenote_index}");
-                let display = sidenote_index;
+                let display = sidenote_marker(section_index, options.markers);
+                notes.insert(label.to_string(), (sidenote_index, display.clone()));
+                let class = match options.placement {
+                    SidenotePlacement::Alternate if sidenote_index.is_multiple_of(2) => {
+                        "sidenote sidenote-left"
+                    }
+                    _ => "sidenote",
+                };
+                // Only notes cited again need a target for the repeats.
+                let note_id = if repeated.contains(label.as_ref()) {
+                    format!(r#" id="{id}-note""#)
+                } else {
+                    String::new()
+                };
 
                 let def_events = defs.get(label.as_ref()).unwrap_or(&[]);
 
                 let def_html = render_definition_as_inline_html(def_events);
 
                 let html = format!(
-                    r#"<label for="{id}" class="margin-toggle sidenote-number" data-sidenote="{display}"></label><input type="checkbox" id="{id}" class="margin-toggle"/><span class="sidenote" data-sidenote="{display}">{def_html}</span>"#
+                    r#"<label for="{id}" class="margin-toggle sidenote-number" data-sidenote="{display}"></label><input type="checkbox" id="{id}" class="margin-toggle"/><span class="{class}"{note_id} data-sidenote="{display}">{def_html}</span>"#
                 );
 
                 out.push(Event::InlineHtml(CowStr::from(html)));
@@ -100,6 +178,18 @@ pub fn convert_footnotes_to_sidenotes<'a>(events: Vec<Event<'a>>) -> Vec<Event<'
     out
 }
 
+/// Marker of the `n`th sidenote (from 1).
+fn sidenote_marker(n: usize, markers: SidenoteMarkers) -> String {
+    const SYMBOLS: [&str; 6] = ["*", "†", "‡", "§", "‖", "¶"];
+    match markers {
+        SidenoteMarkers::Numbers => n.to_string(),
+        SidenoteMarkers::Symbols => {
+            let i = n.saturating_sub(1);
+            SYMBOLS[i % SYMBOLS.len()].repeat(i / SYMBOLS.len() + 1)
+        }
+    }
+}
+
 /// Convert footnotes into bare HTML that reads correctly without CSS.
 pub fn convert_footnotes_to_plain_list<'a>(events: Vec<Event<'a>>) -> Vec<Event<'a>> {
     let defs = FootnoteDefinitions::collect(&events);
@@ -108,12 +198,13 @@ pub fn convert_footnotes_to_plain_list<'a>(events: Vec<Event<'a>>) -> Vec<Event<
     let mut skipping_definition_depth: usize = 0;
     let mut ordered_labels: Vec<String> = Vec::new();
 
-    let mut note_number = |label: &str| -> usize {
+    // The note's number, and whether this is its first reference.
+    let mut note_number = |label: &str| -> (usize, bool) {
         if let Some(idx) = ordered_labels.iter().position(|l| l == label) {
-            idx + 1
+            (idx + 1, false)
         } else {
             ordered_labels.push(label.to_string());
-            ordered_labels.len()
+            (ordered_labels.len(), true)
         }
     };
 
@@ -135,9 +226,16 @@ pub fn convert_footnotes_to_plain_list<'a>(events: Vec<Event<'a>>) -> Vec<Event<
             }
 
             Event::FootnoteReference(label) => {
-                let num = note_number(label.as_ref());
+                let (num, first) = note_number(label.as_ref());
+                // The back-link returns to the first reference; later ones
+                // carry no id so ids stay unique.
+                let id = if first {
+                    format!(" id=\"fnref-{num}\"")
+                } else {
+                    String::new()
+                };
                 let html = format!(
-                    "<sup id=\"fnref-{num}\" class=\"footnote-ref\"><a href=\"#fn-{num}\">{num}</a></sup>"
+                    "<sup{id} class=\"footnote-ref\"><a href=\"#fn-{num}\">{num}</a></sup>"
                 );
                 out.push(Event::InlineHtml(CowStr::from(html)));
             }
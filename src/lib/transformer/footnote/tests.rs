@@ -1,9 +1,20 @@
-use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+use pulldown_cmark::{CowStr, Event, HeadingLevel, Tag, TagEnd};
 
-use crate::transformer::footnote::{
-    convert_footnotes_to_plain_list, convert_footnotes_to_sidenotes,
+use crate::{
+    config::{SidenoteMarkers, SidenotePlacement},
+    transformer::footnote::{
+        SidenoteOptions, convert_footnotes_to_plain_list, convert_footnotes_to_sidenotes,
+    },
 };
 
+fn options() -> SidenoteOptions {
+    SidenoteOptions {
+        placement: SidenotePlacement::Right,
+        markers: SidenoteMarkers::Numbers,
+        restart_per_section: false,
+    }
+}
+
 #[test]
 fn footnote_transformer_inlines_definition() {
     let events = vec![
@@ -15,7 +26,7 @@ fn footnote_transformer_inlines_definition() {
         Event::End(TagEnd::FootnoteDefinition),
     ];
 
-    let out = convert_footnotes_to_sidenotes(events);
+    let out = convert_footnotes_to_sidenotes(events, &options());
 
     assert!(out.iter().any(|e| matches!(e, Event::InlineHtml(_))));
     assert!(!out.iter().any(|e| matches!(e, Event::FootnoteReference(_))));
@@ -53,3 +64,118 @@ fn plain_transformer_renders_ordered_list() {
     assert!(joined.contains("fnref-1"));
     assert!(!joined.contains("margin-toggle"));
 }
+
+fn cited_twice() -> Vec<Event<'static>> {
+    vec![
+        Event::FootnoteReference(CowStr::from("a")),
+        Event::FootnoteReference(CowStr::from("b")),
+        Event::FootnoteReference(CowStr::from("a")),
+        Event::Start(Tag::FootnoteDefinition(CowStr::from("a"))),
+        Event::Start(Tag::Paragraph),
+        Event::Text(CowStr::from("shared note")),
+        Event::End(TagEnd::Paragraph),
+        Event::End(TagEnd::FootnoteDefinition),
+        Event::Start(Tag::FootnoteDefinition(CowStr::from("b"))),
+        Event::Start(Tag::Paragraph),
+        Event::Text(CowStr::from("other note")),
+        Event::End(TagEnd::Paragraph),
+        Event::End(TagEnd::FootnoteDefinition),
+    ]
+}
+
+fn joined(events: &[Event<'_>]) -> String {
+    events
+        .iter()
+        .map(|e| match e {
+            Event::Html(s) | Event::InlineHtml(s) => s.to_string(),
+            _ => String::new(),
+        })
+        .collect()
+}
+
+#[test]
+fn repeated_sidenotes_link_back_to_the_first() {
+    let html = joined(&convert_footnotes_to_sidenotes(cited_twice(), &options()));
+
+    assert_eq!(html.matches("shared note").count(), 1);
+    assert!(html.contains(r#"<span class="sidenote" id="sn-1-note" data-sidenote="1">"#));
+    assert!(html.contains(r#"<span class="sidenote" data-sidenote="2">"#));
+    assert!(html.ends_with(
+        r##"<a href="#sn-1-note" class="sidenote-number sidenote-repeat" data-sidenote="1" aria-label="See note 1"></a>"##
+    ));
+    assert!(!html.contains("sn-3"));
+}
+
+#[test]
+fn repeated_plain_footnotes_keep_ids_unique() {
+    let html = joined(&convert_footnotes_to_plain_list(cited_twice()));
+
+    assert_eq!(html.matches(r#"id="fnref-1""#).count(), 1);
+    assert_eq!(html.matches(r##"href="#fn-1""##).count(), 2);
+    assert_eq!(html.matches("<li ").count(), 2);
+}
+
+fn sections() -> Vec<Event<'static>> {
+    let mut events = Vec::new();
+    for (section, labels) in [("One", ["a", "b"]), ("Two", ["c", "a"])] {
+        events.push(Event::Start(Tag::Heading {
+            level: HeadingLevel::H1,
+            id: None,
+            classes: vec![],
+            attrs: vec![],
+        }));
+        events.push(Event::Text(CowStr::from(section)));
+        events.push(Event::End(TagEnd::Heading(HeadingLevel::H1)));
+        for label in labels {
+            events.push(Event::FootnoteReference(CowStr::from(label)));
+        }
+    }
+    for label in ["a", "b", "c"] {
+        events.push(Event::Start(Tag::FootnoteDefinition(CowStr::from(label))));
+        events.push(Event::Text(CowStr::from(format!("note {label}"))));
+        events.push(Event::End(TagEnd::FootnoteDefinition));
+    }
+    events
+}
+
+#[test]
+fn sidenote_markers_can_be_symbols_restarting_per_section() {
+    let options = SidenoteOptions {
+        markers: SidenoteMarkers::Symbols,
+        restart_per_section: true,
+        ..options()
+    };
+    let html = joined(&convert_footnotes_to_sidenotes(sections(), &options));
+
+    // Markers restart under "Two"; ids stay unique across the page.
+    assert!(
+        html.contains(
+            r#"<label for="sn-1" class="margin-toggle sidenote-number" data-sidenote="*">"#
+        )
+    );
+    assert!(
+        html.contains(
+            r#"<label for="sn-2" class="margin-toggle sidenote-number" data-sidenote="†">"#
+        )
+    );
+    assert!(
+        html.contains(
+            r#"<label for="sn-3" class="margin-toggle sidenote-number" data-sidenote="*">"#
+        )
+    );
+    // A repeat keeps the marker it was first given.
+    assert!(html.ends_with(r##"<a href="#sn-1-note" class="sidenote-number sidenote-repeat" data-sidenote="*" aria-label="See note *"></a>"##));
+}
+
+#[test]
+fn alternating_sidenotes_switch_margins() {
+    let options = SidenoteOptions {
+        placement: SidenotePlacement::Alternate,
+        ..options()
+    };
+    let html = joined(&convert_footnotes_to_sidenotes(sections(), &options));
+
+    assert!(html.contains(r#"<span class="sidenote" id="sn-1-note" data-sidenote="1">"#));
+    assert!(html.contains(r#"<span class="sidenote sidenote-left" data-sidenote="2">"#));
+    assert!(html.contains(r#"<span class="sidenote" data-sidenote="3">"#));
+}
@@ -1,7 +1,21 @@
 use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
-use std::{fmt::Write, path::Path};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{LazyLock, Mutex, MutexGuard},
+};
 
-use crate::{transformer::Transformer, utils::escape_attr};
+use color_eyre::Section;
+use lol_html::html_content::Element;
+
+use crate::{
+    config::{IMAGE_CLI, IMAGE_PLACEHOLDER_COLORS, THEME},
+    transformer::{Transformer, raw_html::rewrite_event},
+    utils::escape_attr,
+};
 
 pub struct ImageCaptionTransformer<I> {
     inner: I,
@@ -56,7 +70,8 @@ where
                     }
                 }
 
-                let dimensions = image_dimensions(&dest_url);
+                let local = local_image_path(&dest_url);
+                let dimensions = local.as_deref().and_then(image_dimensions);
                 let size_attrs = dimensions
                     .map(|(w, h)| format!(r#" width="{}" height="{}""#, w, h))
                     .unwrap_or_default();
@@ -64,13 +79,20 @@ where
                 let srcset_attrs = dimensions
                     .map(|(w, _)| {
                         format!(
-                            r#" srcset="{} {}w" sizes="(max-width: 760px) 92vw, 55vw""#,
+                            r#" srcset="{} {}w" sizes="{}""#,
                             escape_attr(&dest_url),
-                            w
+                            w,
+                            THEME.image_sizes()
                         )
                     })
                     .unwrap_or_default();
 
+                let style_attr = local
+                    .as_deref()
+                    .and_then(dominant_color)
+                    .map(|color| placeholder_style(&color))
+                    .unwrap_or_default();
+
                 let loading_attr = if is_first_image { "eager" } else { "lazy" };
                 let fetchpriority_attr = if is_first_image {
                     r#" fetchpriority="high""#
@@ -81,7 +103,8 @@ where
                 let mut html = String::new();
                 let _ = write!(
                     html,
-                    r#"<figure class="image-container"><img src="{}" alt="{}" title="{}" loading="{}" decoding="async"{}{}{} /><figcaption>{}</figcaption></figure>"#,
+                    r#"<figure class="image-container"{}><img src="{}" alt="{}" title="{}" loading="{}" decoding="async"{}{}{} /><figcaption>{}</figcaption></figure>"#,
+                    style_attr,
                     escape_attr(&dest_url),
                     escape_attr(&alt_text),
                     escape_attr(&title),
@@ -94,12 +117,58 @@ where
 
                 Some(Event::Html(CowStr::from(html)))
             }
+            html @ (Event::Html(_) | Event::InlineHtml(_)) => {
+                let seen_first = &mut self.seen_first;
+                Some(rewrite_event(html, "img[src]", |el| {
+                    let is_first_image = !*seen_first;
+                    *seen_first = true;
+                    fill_raw_image(el, is_first_image)
+                }))
+            }
             other => Some(other),
         }
     }
 }
 
-fn image_dimensions(dest_url: &str) -> Option<(u32, u32)> {
+/// Give a raw HTML `<img>` the loading hints, size, and placeholder colour a
+/// Markdown image gets, keeping whatever it already sets. Returns whether
+/// anything was added.
+fn fill_raw_image(el: &mut Element<'_, '_>, is_first_image: bool) -> bool {
+    let mut added: Vec<(&str, String)> = Vec::new();
+    if !el.has_attribute("loading") {
+        let loading = if is_first_image { "eager" } else { "lazy" };
+        added.push(("loading", loading.to_owned()));
+        if is_first_image && !el.has_attribute("fetchpriority") {
+            added.push(("fetchpriority", "high".to_owned()));
+        }
+    }
+    if !el.has_attribute("decoding") {
+        added.push(("decoding", "async".to_owned()));
+    }
+    let local = el
+        .get_attribute("src")
+        .and_then(|src| local_image_path(&src));
+    if !el.has_attribute("width")
+        && !el.has_attribute("height")
+        && let Some((w, h)) = local.as_deref().and_then(image_dimensions)
+    {
+        added.push(("width", w.to_string()));
+        added.push(("height", h.to_string()));
+    }
+    if !el.has_attribute("style")
+        && let Some(color) = local.as_deref().and_then(dominant_color)
+    {
+        added.push(("style", format!("background-color: {color}")));
+    }
+    let changed = !added.is_empty();
+    for (name, value) in added {
+        let _ = el.set_attribute(name, &value);
+    }
+    changed
+}
+
+/// Where a non-remote image would be on disk.
+fn local_image_path(dest_url: &str) -> Option<PathBuf> {
     // Only attempt for local files.
     if dest_url.starts_with("http://") || dest_url.starts_with("https://") {
         return None;
@@ -109,18 +178,132 @@ fn image_dimensions(dest_url: &str) -> Option<(u32, u32)> {
     let cleaned = dest_url.trim_start_matches('/');
     let path = Path::new(cleaned);
 
-    let path = if path.exists() {
+    Some(if path.exists() {
         path.to_path_buf()
     } else {
         // Fall back to attempting the raw dest_url as given.
         Path::new(dest_url).to_path_buf()
-    };
+    })
+}
+
+/// Width and height of the image at `dest_url`, if it is a local file.
+pub fn local_image_size(dest_url: &str) -> Option<(u32, u32)> {
+    local_image_path(dest_url)
+        .as_deref()
+        .and_then(image_dimensions)
+}
 
+fn image_dimensions(path: &Path) -> Option<(u32, u32)> {
     imagesize::size(path)
         .ok()
         .map(|dim| (dim.width as u32, dim.height as u32))
 }
 
+/// ` style` attribute tinting an image's frame while the image loads.
+pub fn placeholder_style(color: &str) -> String {
+    format!(r#" style="background-color: {}""#, escape_attr(color))
+}
+
+/// File under the cache directory holding the dominant colour of images.
+pub const IMAGE_COLOR_CACHE_FILE: &str = "image-colors.json";
+
+/// Dominant colours keyed by the BLAKE3 hash of the image, so edited images
+/// are looked at again. As with rendered math, only entries some page still
+/// uses are written back.
+#[derive(Default)]
+struct ColorCache {
+    stored: HashMap<String, String>,
+    used: BTreeMap<String, String>,
+}
+
+static COLORS: LazyLock<Mutex<ColorCache>> = LazyLock::new(Mutex::default);
+
+fn colors() -> MutexGuard<'static, ColorCache> {
+    COLORS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Seed the colour cache from `path`; a missing or unreadable file is ignored.
+pub fn load_image_color_cache(path: &Path) {
+    let stored: HashMap<String, String> = fs::read_to_string(path)
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default();
+    let mut cache = colors();
+    for (key, color) in stored {
+        if !cache.used.contains_key(&key) {
+            cache.stored.insert(key, color);
+        }
+    }
+}
+
+/// Write the colours looked up during this build to `path`.
+pub fn save_image_color_cache(path: &Path) -> color_eyre::Result<()> {
+    let cache = colors();
+    if cache.used.is_empty() && !path.exists() {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string(&cache.used)?)
+        .with_note(|| format!("While writing {}", path.display()))
+}
+
+/// The most common colour of the image at `path`, as `#rrggbb`. `None` when
+/// placeholders are off, the file is unreadable, or `IMAGE_CLI` is missing or
+/// rejects it; failures are not cached, so installing the tool fills them in
+/// on the next build.
+pub fn dominant_color(path: &Path) -> Option<String> {
+    if !IMAGE_PLACEHOLDER_COLORS {
+        return None;
+    }
+    let bytes = fs::read(path).ok()?;
+    let key = blake3::hash(&bytes).to_hex().to_string();
+    {
+        let mut cache = colors();
+        if let Some(color) = cache.used.get(&key) {
+            return Some(color.clone());
+        }
+        if let Some(color) = cache.stored.remove(&key) {
+            cache.used.insert(key, color.clone());
+            return Some(color);
+        }
+    }
+
+    // Quantise a thumbnail to a few colours and take the most frequent.
+    let output = Command::new(IMAGE_CLI)
+        .arg(path)
+        .args(["-resize", "64x64", "-colors", "8", "-format", "%c"])
+        .arg("histogram:info:-")
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let color = most_common_color(&String::from_utf8_lossy(&output.stdout))?;
+    colors().used.insert(key, color.clone());
+    Some(color)
+}
+
+/// Pick the most frequent colour from ImageMagick's histogram, whose lines
+/// look like `  1234: (12,34,56) #0C2238 srgb(12,34,56)`.
+fn most_common_color(histogram: &str) -> Option<String> {
+    histogram
+        .lines()
+        .filter_map(|line| {
+            let (count, rest) = line.trim().split_once(':')?;
+            let count: u64 = count.trim().parse().ok()?;
+            // Drop any alpha channel: `#RRGGBBAA`.
+            let hex = rest.split_whitespace().find_map(|w| w.strip_prefix('#'))?;
+            let rgb = hex.get(..6)?;
+            rgb.chars()
+                .all(|c| c.is_ascii_hexdigit())
+                .then(|| (count, format!("#{}", rgb.to_ascii_lowercase())))
+        })
+        .max_by_key(|(count, _)| *count)
+        .map(|(_, color)| color)
+}
+
 impl<'a, I> Transformer<'a, I> for ImageCaptionTransformer<I>
 where
     I: Iterator<Item = Event<'a>>,
@@ -4,7 +4,10 @@ use proptest::{
 };
 use pulldown_cmark::{CowStr, Event, LinkType, Tag, TagEnd};
 
-use crate::transformer::{WithTransformer, image::ImageCaptionTransformer};
+use crate::transformer::{
+    WithTransformer,
+    image::{ImageCaptionTransformer, most_common_color},
+};
 use crate::utils::escape_attr;
 
 #[test]
@@ -90,3 +93,51 @@ fn second_image_is_lazy_and_not_high_priority() {
     assert!(second_html.contains(r#"loading="lazy""#));
     assert!(!second_html.contains(r#"fetchpriority="high""#));
 }
+
+#[test]
+fn raw_html_images_get_loading_hints_but_keep_their_own() {
+    let events = vec![
+        Event::Html(CowStr::from(r#"<p><img src="hero.jpg" alt=""></p>"#)),
+        Event::Start(Tag::Image {
+            link_type: LinkType::Inline,
+            dest_url: CowStr::from("foo.jpg"),
+            title: CowStr::from(""),
+            id: CowStr::from(""),
+        }),
+        Event::Text(CowStr::from("second")),
+        Event::End(TagEnd::Image),
+        Event::InlineHtml(CowStr::from(r#"<img src="a.jpg" loading="eager">"#)),
+    ];
+
+    let out: Vec<_> = events
+        .into_iter()
+        .with_transformer::<ImageCaptionTransformer<_>>()
+        .collect();
+    let html: Vec<String> = out
+        .iter()
+        .map(|e| match e {
+            Event::Html(html) | Event::InlineHtml(html) => html.to_string(),
+            _ => panic!("expected HTML"),
+        })
+        .collect();
+
+    assert_eq!(
+        html[0],
+        r#"<p><img src="hero.jpg" alt="" loading="eager" fetchpriority="high" decoding="async"></p>"#
+    );
+    assert!(html[1].contains(r#"loading="lazy""#), "{}", html[1]);
+    assert_eq!(
+        html[2],
+        r#"<img src="a.jpg" loading="eager" decoding="async">"#
+    );
+}
+
+#[test]
+fn most_common_color_picks_the_largest_bucket() {
+    let histogram = "      12: (250,250,250) #FAFAFA srgb(250,250,250)
+    3071: ( 18, 52, 86) #123456 srgb(18,52,86)
+     913: ( 18, 52, 86,0.5) #12345680 srgba(18,52,86,0.5)
+";
+    assert_eq!(most_common_color(histogram).as_deref(), Some("#123456"));
+    assert_eq!(most_common_color("not a histogram"), None);
+}
@@ -0,0 +1,195 @@
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+
+use crate::transformer::Transformer;
+
+/// Opens a margin note within a paragraph: `{>> aside <<}`.
+const OPEN: &str = "{>>";
+/// Closes a margin note.
+const CLOSE: &str = "<<}";
+
+/// Render `{>> … <<}` as Tufte-style margin notes: unnumbered, unlike the
+/// sidenotes footnotes become, and toggled open on narrow screens.
+pub struct MarginNoteTransformer<'a> {
+    inner: std::vec::IntoIter<Event<'a>>,
+}
+
+impl<'a> Iterator for MarginNoteTransformer<'a> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+impl<'a, I> Transformer<'a, I> for MarginNoteTransformer<'a>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    fn transform(inner: I) -> Self {
+        let rewritten = convert_margin_notes(inner.collect(), MarginNoteStyle::Margin);
+        Self {
+            inner: rewritten.into_iter(),
+        }
+    }
+}
+
+/// Render margin notes as inline asides, for feed readers without a margin.
+pub struct FeedMarginNoteTransformer<'a> {
+    inner: std::vec::IntoIter<Event<'a>>,
+}
+
+impl<'a> Iterator for FeedMarginNoteTransformer<'a> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+impl<'a, I> Transformer<'a, I> for FeedMarginNoteTransformer<'a>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    fn transform(inner: I) -> Self {
+        let rewritten = convert_margin_notes(inner.collect(), MarginNoteStyle::Inline);
+        Self {
+            inner: rewritten.into_iter(),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum MarginNoteStyle {
+    /// In the margin, behind a toggle on narrow screens.
+    Margin,
+    /// In parentheses where the note was written.
+    Inline,
+}
+
+/// Replace each `{>> … <<}` within a paragraph with a margin note. The note
+/// may hold inline markup; one left open at the end of its block is kept as
+/// written.
+pub fn convert_margin_notes<'a>(events: Vec<Event<'a>>, style: MarginNoteStyle) -> Vec<Event<'a>> {
+    let mut out: Vec<Event<'a>> = Vec::with_capacity(events.len());
+    // Content of the note being read, once its opening marker is seen.
+    let mut note: Option<Vec<Event<'a>>> = None;
+    let mut count: usize = 0;
+
+    for event in coalesce_text(events) {
+        match event {
+            Event::Text(text) if note.is_some() || text.contains(OPEN) => {
+                let mut rest: &str = &text;
+                loop {
+                    match note.as_mut() {
+                        None => {
+                            let Some(at) = rest.find(OPEN) else {
+                                push_text(&mut out, rest);
+                                break;
+                            };
+                            push_text(&mut out, &rest[..at]);
+                            note = Some(Vec::new());
+                            rest = &rest[at + OPEN.len()..];
+                        }
+                        Some(content) => {
+                            let Some(at) = rest.find(CLOSE) else {
+                                push_text(content, rest);
+                                break;
+                            };
+                            push_text(content, &rest[..at]);
+                            count += 1;
+                            let content = note.take().unwrap_or_default();
+                            let html = render_note(count, content, style);
+                            out.push(Event::InlineHtml(CowStr::from(html)));
+                            rest = &rest[at + CLOSE.len()..];
+                        }
+                    }
+                }
+            }
+            event if note.is_some() && is_inline(&event) => {
+                if let Some(content) = note.as_mut() {
+                    content.push(event);
+                }
+            }
+            other => {
+                if let Some(content) = note.take() {
+                    abandon(&mut out, content);
+                }
+                out.push(other);
+            }
+        }
+    }
+
+    if let Some(content) = note.take() {
+        abandon(&mut out, content);
+    }
+    out
+}
+
+/// Merge runs of text events, which the parser may split mid-marker.
+fn coalesce_text<'a>(events: Vec<Event<'a>>) -> Vec<Event<'a>> {
+    let mut out: Vec<Event<'a>> = Vec::with_capacity(events.len());
+    for event in events {
+        match (out.last_mut(), event) {
+            (Some(Event::Text(prev)), Event::Text(next)) => {
+                *prev = CowStr::from(format!("{prev}{next}"));
+            }
+            (_, event) => out.push(event),
+        }
+    }
+    out
+}
+
+fn push_text<'a>(out: &mut Vec<Event<'a>>, text: &str) {
+    if !text.is_empty() {
+        out.push(Event::Text(CowStr::from(text.to_owned())));
+    }
+}
+
+/// Put back an unclosed note as the text it was written as.
+fn abandon<'a>(out: &mut Vec<Event<'a>>, content: Vec<Event<'a>>) {
+    out.push(Event::Text(CowStr::Borrowed(OPEN)));
+    out.extend(content);
+}
+
+/// Events that may appear inside a margin note.
+fn is_inline(event: &Event<'_>) -> bool {
+    matches!(
+        event,
+        Event::Start(
+            Tag::Emphasis
+                | Tag::Strong
+                | Tag::Strikethrough
+                | Tag::Superscript
+                | Tag::Subscript
+                | Tag::Link { .. }
+        ) | Event::End(
+            TagEnd::Emphasis
+                | TagEnd::Strong
+                | TagEnd::Strikethrough
+                | TagEnd::Superscript
+                | TagEnd::Subscript
+                | TagEnd::Link
+        ) | Event::Text(_)
+            | Event::Code(_)
+            | Event::InlineMath(_)
+            | Event::InlineHtml(_)
+            | Event::SoftBreak
+            | Event::HardBreak
+    )
+}
+
+fn render_note(index: usize, content: Vec<Event<'_>>, style: MarginNoteStyle) -> String {
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, content.into_iter());
+    let html = html.trim();
+
+    match style {
+        MarginNoteStyle::Margin => format!(
+            r#"<label for="mn-{index}" class="margin-toggle">&#8853;</label><input type="checkbox" id="mn-{index}" class="margin-toggle"/><span class="marginnote">{html}</span>"#
+        ),
+        MarginNoteStyle::Inline => format!(r#"<small class="marginnote">({html})</small>"#),
+    }
+}
+
+#[cfg(test)]
+mod tests;
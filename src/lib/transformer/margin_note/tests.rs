@@ -0,0 +1,62 @@
+use pulldown_cmark::{Options, Parser};
+
+use crate::transformer::{
+    WithTransformer,
+    margin_note::{FeedMarginNoteTransformer, MarginNoteTransformer},
+};
+
+fn render(md: &str) -> String {
+    let events =
+        Parser::new_ext(md, Options::empty()).with_transformer::<MarginNoteTransformer<'_>>();
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events);
+    html
+}
+
+#[test]
+fn margin_notes_are_unnumbered_and_keep_inline_markup() {
+    let html = render("Main text.{>> An *aside* with `code`. <<} More text.\n");
+
+    assert_eq!(
+        html,
+        concat!(
+            "<p>Main text.",
+            r#"<label for="mn-1" class="margin-toggle">&#8853;</label>"#,
+            r#"<input type="checkbox" id="mn-1" class="margin-toggle"/>"#,
+            r#"<span class="marginnote">An <em>aside</em> with <code>code</code>.</span>"#,
+            " More text.</p>\n"
+        )
+    );
+}
+
+#[test]
+fn each_margin_note_gets_its_own_toggle() {
+    let html = render("One {>> a <<} two {>> b <<}\n\nThree {>> c <<}\n");
+
+    assert!(html.contains(r#"id="mn-1""#));
+    assert!(html.contains(r#"id="mn-3""#));
+    assert!(!html.contains("sidenote-number"));
+}
+
+#[test]
+fn unclosed_margin_notes_are_left_as_written() {
+    let html = render("Not {>> a *note*\n\nStill <<} text.\n");
+
+    assert_eq!(
+        html,
+        "<p>Not {&gt;&gt; a <em>note</em></p>\n<p>Still &lt;&lt;} text.</p>\n"
+    );
+}
+
+#[test]
+fn feed_margin_notes_sit_inline() {
+    let events = Parser::new_ext("Text.{>> Aside. <<}\n", Options::empty())
+        .with_transformer::<FeedMarginNoteTransformer<'_>>();
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events);
+
+    assert_eq!(
+        html,
+        "<p>Text.<small class=\"marginnote\">(Aside.)</small></p>\n"
+    );
+}
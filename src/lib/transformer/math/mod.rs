@@ -1,16 +1,50 @@
-use std::sync::OnceLock;
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fs,
+    marker::PhantomData,
+    path::Path,
+    sync::{LazyLock, Mutex, MutexGuard, OnceLock},
+};
 
-use katex::Opts;
+use color_eyre::Section;
+use katex::{Opts, OutputType};
 use pulldown_cmark::{CowStr, Event};
 
-use crate::transformer::Transformer;
+use crate::{
+    config::{EQUATION_NUMBERING, EquationNumbering, MATH_OUTPUT, MathOutput},
+    diagnostics::DocumentSink,
+    transformer::Transformer,
+    utils::{escape_attr, escape_text},
+};
 
-/// Render math expressions via KaTeX.
+/// Render math expressions via KaTeX, as HTML or MathML. Expressions KaTeX
+/// rejects are left as their source and reported to the sink.
+///
+/// Display math is numbered per `EQUATION_NUMBERING`; a `\label{eq:name}`
+/// inside it becomes the block's anchor, and `\eqref{eq:name}` or
+/// `\ref{eq:name}` anywhere in the text (or alone in inline math) links to
+/// it. References may come before the equation, so the whole document is
+/// read before anything is rendered.
 pub struct MathTransformer<'a, I>
 where
     I: Iterator<Item = Event<'a>>,
 {
-    inner: I,
+    inner: std::vec::IntoIter<Event<'a>>,
+    sink: DocumentSink,
+    output: MathOutput,
+    /// Number and label of each display block, in document order.
+    equations: std::vec::IntoIter<Option<Equation>>,
+    /// Equation number for each label.
+    labels: HashMap<String, usize>,
+    /// Events left over from splitting text around references.
+    pending: VecDeque<Event<'a>>,
+    _inner: PhantomData<fn() -> I>,
+}
+
+#[derive(Clone, Debug)]
+struct Equation {
+    number: usize,
+    label: Option<String>,
 }
 
 impl<'a, I> Iterator for MathTransformer<'a, I>
@@ -20,53 +54,309 @@ where
     type Item = Event<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some(event) = self.pending.pop_front() {
+            return Some(event);
+        }
         match self.inner.next()? {
             Event::InlineMath(source) => {
-                let html = render_math(source.as_ref(), false);
+                let html = match parse_reference(source.trim()) {
+                    Some((reference, "")) => self.reference_html(&reference, &source),
+                    _ => self.render(source.as_ref(), false),
+                };
                 Some(Event::InlineHtml(CowStr::from(html)))
             }
             Event::DisplayMath(source) => {
-                let html = render_math(source.as_ref(), true);
+                let (source, _) = take_label(&source);
+                let html = self.render(&source, true);
+                let html = match self.equations.next().flatten() {
+                    Some(equation) => numbered_html(&html, &equation),
+                    None => html,
+                };
                 Some(Event::Html(CowStr::from(html)))
             }
+            Event::Text(text) if text.contains("ref{") => {
+                self.pending = self.split_references(&text);
+                self.pending.pop_front()
+            }
             other => Some(other),
         }
     }
 }
 
-fn inline_opts() -> &'static Opts {
-    static INLINE: OnceLock<Opts> = OnceLock::new();
-    INLINE.get_or_init(|| {
-        let mut builder = Opts::builder();
-        builder.display_mode(false);
-        builder.build().unwrap_or_default()
-    })
+impl<'a, I> MathTransformer<'a, I>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    /// Wrap `inner`, rendering math as `output` rather than `MATH_OUTPUT`.
+    pub fn with_output(inner: I, sink: &DocumentSink, output: MathOutput) -> Self {
+        let events: Vec<Event<'a>> = inner.collect();
+        let mut labels = HashMap::new();
+        let mut equations = Vec::new();
+        let mut number = 0;
+        for event in &events {
+            let Event::DisplayMath(source) = event else {
+                continue;
+            };
+            let (_, label) = take_label(source);
+            let numbered = match EQUATION_NUMBERING {
+                EquationNumbering::Off => false,
+                EquationNumbering::Labelled => label.is_some(),
+                EquationNumbering::All => true,
+            };
+            if !numbered {
+                equations.push(None);
+                continue;
+            }
+            number += 1;
+            if let Some(label) = &label
+                && labels.insert(label.clone(), number).is_some()
+            {
+                sink.warn(
+                    "math",
+                    label,
+                    format!("equation label `{label}` is used more than once"),
+                );
+            }
+            equations.push(Some(Equation { number, label }));
+        }
+
+        Self {
+            inner: events.into_iter(),
+            sink: sink.clone(),
+            output,
+            equations: equations.into_iter(),
+            labels,
+            pending: VecDeque::new(),
+            _inner: PhantomData,
+        }
+    }
+
+    fn render(&self, source: &str, display_mode: bool) -> String {
+        render_math(source, display_mode, self.output).unwrap_or_else(|e| {
+            self.sink.warn("math", source, format!("`{source}`: {e}"));
+            source.to_string()
+        })
+    }
+
+    /// Link to a labelled equation, or `written` unchanged if there is none.
+    fn reference_html(&self, reference: &Reference<'_>, written: &str) -> String {
+        let Some(number) = self.labels.get(reference.label) else {
+            self.sink.warn(
+                "math",
+                written,
+                format!("no equation is labelled `{}`", reference.label),
+            );
+            return escape_text(written);
+        };
+        let text = if reference.parenthesised {
+            format!("({number})")
+        } else {
+            number.to_string()
+        };
+        format!(
+            r##"<a class="eqref" href="#{}">{text}</a>"##,
+            escape_attr(reference.label)
+        )
+    }
+
+    /// Split text around `\eqref{…}` / `\ref{…}`, linking each reference.
+    fn split_references(&self, text: &str) -> VecDeque<Event<'a>> {
+        let mut out = VecDeque::new();
+        let mut rest = text;
+        while let Some(start) = rest.find('\\') {
+            let Some((reference, after)) = parse_reference(&rest[start..]) else {
+                let (before, after) = rest.split_at(start + 1);
+                push_text(&mut out, before);
+                rest = after;
+                continue;
+            };
+            push_text(&mut out, &rest[..start]);
+            let written = &rest[start..rest.len() - after.len()];
+            out.push_back(Event::InlineHtml(CowStr::from(
+                self.reference_html(&reference, written),
+            )));
+            rest = after;
+        }
+        push_text(&mut out, rest);
+        out
+    }
+}
+
+fn push_text<'a>(out: &mut VecDeque<Event<'a>>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    // Merge with a preceding text event split off at a lone backslash.
+    if let Some(Event::Text(previous)) = out.back_mut() {
+        *previous = CowStr::from(format!("{previous}{text}"));
+    } else {
+        out.push_back(Event::Text(CowStr::from(text.to_owned())));
+    }
+}
+
+/// A `\eqref{label}` (parenthesised) or `\ref{label}` reference.
+struct Reference<'s> {
+    label: &'s str,
+    parenthesised: bool,
+}
+
+/// Parse a reference at the start of `s`, returning it and the text after.
+fn parse_reference(s: &str) -> Option<(Reference<'_>, &str)> {
+    let (parenthesised, rest) = match s.strip_prefix("\\eqref{") {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix("\\ref{")?),
+    };
+    let end = rest.find('}')?;
+    let label = rest[..end].trim();
+    (!label.is_empty()).then_some((
+        Reference {
+            label,
+            parenthesised,
+        },
+        &rest[end + 1..],
+    ))
+}
+
+/// Remove a `\label{…}` from display math, returning the label. KaTeX does
+/// not understand `\label` itself.
+fn take_label(source: &str) -> (std::borrow::Cow<'_, str>, Option<String>) {
+    let Some(start) = source.find("\\label{") else {
+        return (source.into(), None);
+    };
+    let after = &source[start + "\\label{".len()..];
+    let Some(end) = after.find('}') else {
+        return (source.into(), None);
+    };
+    let label = after[..end].trim().to_owned();
+    let stripped = format!("{}{}", &source[..start], &after[end + 1..]);
+    (stripped.into(), (!label.is_empty()).then_some(label))
+}
+
+fn numbered_html(html: &str, equation: &Equation) -> String {
+    let id = equation
+        .label
+        .as_deref()
+        .map(|label| format!(r#" id="{}""#, escape_attr(label)))
+        .unwrap_or_default();
+    format!(
+        r#"<div class="equation"{id}>{html}<span class="equation-number">({})</span></div>
+"#,
+        equation.number
+    )
 }
 
-fn display_opts() -> &'static Opts {
-    static DISPLAY: OnceLock<Opts> = OnceLock::new();
-    DISPLAY.get_or_init(|| {
+fn opts(display_mode: bool, output: MathOutput) -> &'static Opts {
+    static OPTS: [OnceLock<Opts>; 4] = [const { OnceLock::new() }; 4];
+    let slot = usize::from(display_mode) + 2 * usize::from(output == MathOutput::Mathml);
+    OPTS[slot].get_or_init(|| {
         let mut builder = Opts::builder();
-        builder.display_mode(true);
+        builder.display_mode(display_mode);
+        builder.output_type(match output {
+            MathOutput::Html => OutputType::HtmlAndMathml,
+            MathOutput::Mathml => OutputType::Mathml,
+        });
         builder.build().unwrap_or_default()
     })
 }
 
-fn render_math(source: &str, display_mode: bool) -> String {
-    let opts = if display_mode { display_opts() } else { inline_opts() };
+/// File under the cache directory holding rendered math.
+pub const MATH_CACHE_FILE: &str = "katex.json";
+
+/// Rendered math keyed by display mode and source, shared by every document
+/// in a build. Entries loaded from disk are only written back if some page
+/// still uses them, so the file does not grow without bound.
+#[derive(Default)]
+struct MathCache {
+    stored: HashMap<String, String>,
+    used: BTreeMap<String, String>,
+}
+
+static CACHE: LazyLock<Mutex<MathCache>> = LazyLock::new(Mutex::default);
+
+fn cache() -> MutexGuard<'static, MathCache> {
+    CACHE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl MathCache {
+    /// Start a build from the entries saved at `path`; a missing or
+    /// unreadable file is ignored. Math this process rendered for an earlier
+    /// build (e.g. before a watch rebuild) stays at hand, but only counts as
+    /// used once a page of this build asks for it again.
+    fn load(&mut self, path: &Path) {
+        let earlier = std::mem::take(&mut self.used);
+        self.stored = fs::read_to_string(path)
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default();
+        self.stored.extend(earlier);
+    }
+
+    /// The HTML rendered for `key`, marking it used by this build.
+    fn lookup(&mut self, key: &str) -> Option<String> {
+        if let Some(html) = self.used.get(key) {
+            return Some(html.clone());
+        }
+        let html = self.stored.remove(key)?;
+        self.used.insert(key.to_owned(), html.clone());
+        Some(html)
+    }
+
+    /// Write the math used during this build to `path`.
+    fn save(&self, path: &Path) -> color_eyre::Result<()> {
+        if self.used.is_empty() && !path.exists() {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string(&self.used)?)
+            .with_note(|| format!("While writing {}", path.display()))
+    }
+}
+
+/// Seed the math cache for a build from `path`.
+pub fn load_math_cache(path: &Path) {
+    cache().load(path);
+}
 
-    match katex::render_with_opts(source, opts) {
-        Ok(res) => res,
-        Err(_) => source.to_string(),
+/// Write the math rendered during this build to `path`.
+pub fn save_math_cache(path: &Path) -> color_eyre::Result<()> {
+    cache().save(path)
+}
+
+fn cache_key(source: &str, display_mode: bool, output: MathOutput) -> String {
+    let mode = if display_mode { "display" } else { "inline" };
+    match output {
+        MathOutput::Html => format!("{mode}:{source}"),
+        MathOutput::Mathml => format!("mathml-{mode}:{source}"),
     }
 }
 
+fn render_math(
+    source: &str,
+    display_mode: bool,
+    output: MathOutput,
+) -> Result<String, katex::Error> {
+    let key = cache_key(source, display_mode, output);
+    if let Some(html) = cache().lookup(&key) {
+        return Ok(html);
+    }
+
+    let html = katex::render_with_opts(source, opts(display_mode, output))?;
+    cache().used.insert(key, html.clone());
+    Ok(html)
+}
+
 impl<'a, I> Transformer<'a, I> for MathTransformer<'a, I>
 where
     I: Iterator<Item = Event<'a>>,
 {
     fn transform(inner: I) -> Self {
-        Self { inner }
+        Self::transform_with(inner, &DocumentSink::default())
+    }
+
+    fn transform_with(inner: I, sink: &DocumentSink) -> Self {
+        Self::with_output(inner, sink, MATH_OUTPUT)
     }
 }
 
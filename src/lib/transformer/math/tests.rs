@@ -1,10 +1,19 @@
+use std::collections::BTreeMap;
+
 use proptest::{
     prelude::*,
     test_runner::{Config, TestRunner},
 };
-use pulldown_cmark::{CowStr, Event};
+use pulldown_cmark::{CowStr, Event, Options, Parser};
 
-use crate::transformer::{WithTransformer, math::MathTransformer};
+use crate::{
+    config::MathOutput,
+    diagnostics::DocumentSink,
+    transformer::{
+        WithTransformer,
+        math::{MathCache, MathTransformer},
+    },
+};
 
 #[test]
 fn math_transformer_converts_math() {
@@ -29,3 +38,119 @@ fn math_transformer_converts_math() {
         })
         .unwrap();
 }
+
+#[test]
+fn rendered_math_round_trips_through_the_cache_file() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("katex.json");
+    // A previously saved entry is served without rendering.
+    std::fs::write(
+        &path,
+        r#"{"inline:\\cached-only":"<span>from cache</span>"}"#,
+    )
+    .unwrap();
+
+    // A cache of its own, as builds running alongside would reset the shared
+    // one.
+    let mut cache = MathCache::default();
+    cache.load(&path);
+    assert_eq!(
+        cache.lookup("inline:\\cached-only").unwrap(),
+        "<span>from cache</span>"
+    );
+    cache
+        .used
+        .insert("display:x^2".to_owned(), "<span>x²</span>".to_owned());
+    cache.save(&path).unwrap();
+
+    let saved = || -> BTreeMap<String, String> {
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap()
+    };
+    assert_eq!(
+        saved(),
+        BTreeMap::from([
+            (
+                "inline:\\cached-only".to_owned(),
+                "<span>from cache</span>".to_owned()
+            ),
+            ("display:x^2".to_owned(), "<span>x²</span>".to_owned()),
+        ])
+    );
+
+    // The next build starts with nothing used, so math it no longer renders
+    // is pruned, while what it does render again is served from memory.
+    cache.load(&path);
+    assert!(cache.used.is_empty());
+    assert_eq!(cache.lookup("display:x^2").unwrap(), "<span>x²</span>");
+    cache.save(&path).unwrap();
+    assert_eq!(
+        saved(),
+        BTreeMap::from([("display:x^2".to_owned(), "<span>x²</span>".to_owned())])
+    );
+}
+
+#[test]
+fn mathml_output_skips_katex_html() {
+    let events = vec![Event::InlineMath(CowStr::from("a^2"))];
+
+    let out: Vec<_> = MathTransformer::with_output(
+        events.into_iter(),
+        &DocumentSink::default(),
+        MathOutput::Mathml,
+    )
+    .collect();
+
+    let Event::InlineHtml(html) = &out[0] else {
+        panic!("expected inline HTML, got {:?}", out[0]);
+    };
+    assert!(html.contains("<math"));
+    assert!(!html.contains("katex-html"));
+}
+
+#[test]
+fn html_output_keeps_mathml_for_screen_readers() {
+    let events = vec![Event::InlineMath(CowStr::from("b_1"))];
+
+    let out: Vec<_> = events
+        .into_iter()
+        .with_transformer::<MathTransformer<_>>()
+        .collect();
+
+    let Event::InlineHtml(html) = &out[0] else {
+        panic!("expected inline HTML, got {:?}", out[0]);
+    };
+    assert!(html.contains("katex-html"));
+    assert!(html.contains("<math"));
+}
+
+fn render_doc(md: &str) -> String {
+    let events =
+        Parser::new_ext(md, Options::ENABLE_MATH).with_transformer::<MathTransformer<'_, _>>();
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events);
+    html
+}
+
+#[test]
+fn labelled_equations_are_numbered_and_linkable() {
+    let html = render_doc(
+        "See \\eqref{eq:euler} and $\\ref{eq:pyth}$.\n\n\
+         $$a^2 + b^2 = c^2 \\label{eq:pyth}$$\n\n\
+         $$x = 1$$\n\n\
+         $$e^{i\\pi} + 1 = 0 \\label{eq:euler}$$\n",
+    );
+
+    assert!(html.contains(r##"See <a class="eqref" href="#eq:euler">(2)</a> and "##));
+    assert!(html.contains(r##"<a class="eqref" href="#eq:pyth">1</a>"##));
+    assert!(html.contains(r#"<div class="equation" id="eq:pyth">"#));
+    assert!(html.contains(r#"<span class="equation-number">(2)</span></div>"#));
+    assert_eq!(html.matches("equation-number").count(), 2);
+    assert!(!html.contains("label"));
+}
+
+#[test]
+fn unknown_references_stay_as_text() {
+    let html = render_doc("A \\eqref{eq:missing} and C:\\dir.\n");
+
+    assert_eq!(html, "<p>A \\eqref{eq:missing} and C:\\dir.</p>\n");
+}
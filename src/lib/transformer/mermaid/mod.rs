@@ -0,0 +1,157 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Tag, TagEnd};
+
+use crate::{
+    config::{MERMAID_CLI, MERMAID_RENDERING, MERMAID_SCRIPT, MermaidRendering},
+    diagnostics::DocumentSink,
+    transformer::{Transformer, code_block::FenceInfo},
+    utils::{escape_attr, escape_html},
+};
+
+/// Render ```` ```mermaid ```` fences as diagrams instead of highlighted code.
+///
+/// Depending on `MERMAID_RENDERING`, a fence becomes either an inline SVG
+/// produced by the Mermaid CLI at build time, or a `<pre class="mermaid">`
+/// that the Mermaid script turns into a diagram in the browser. Must run
+/// before `CodeHighlightTransformer`, which would otherwise claim the fence.
+pub struct MermaidTransformer<'a, I>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    inner: I,
+    /// Source of the diagram being collected, if inside a mermaid fence.
+    buffer: Option<String>,
+    sink: DocumentSink,
+}
+
+impl<'a, I> Iterator for MermaidTransformer<'a, I>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let event = self.inner.next()?;
+            let Some(buffer) = self.buffer.as_mut() else {
+                match event {
+                    Event::Start(Tag::CodeBlock(ref kind)) if is_mermaid(kind) => {
+                        self.buffer = Some(String::new());
+                        continue;
+                    }
+                    other => return Some(other),
+                }
+            };
+
+            match event {
+                Event::End(TagEnd::CodeBlock) => {
+                    let source = self.buffer.take().unwrap_or_default();
+                    let html = render_mermaid(&source, MERMAID_RENDERING, &self.sink);
+                    return Some(Event::Html(CowStr::from(html)));
+                }
+                Event::Text(text) => buffer.push_str(&text),
+                Event::SoftBreak | Event::HardBreak => buffer.push('\n'),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Whether a code block is a mermaid fence; attributes after the language
+/// token are ignored.
+pub fn is_mermaid(kind: &CodeBlockKind<'_>) -> bool {
+    FenceInfo::parse(kind)
+        .lang
+        .is_some_and(|lang| lang.eq_ignore_ascii_case("mermaid"))
+}
+
+/// Whether a page with these events needs the client-side Mermaid script.
+pub fn needs_mermaid_script(events: &[Event<'_>]) -> bool {
+    MERMAID_RENDERING == MermaidRendering::Client
+        && events
+            .iter()
+            .any(|e| matches!(e, Event::Start(Tag::CodeBlock(kind)) if is_mermaid(kind)))
+}
+
+/// Head include that loads Mermaid and renders every `pre.mermaid`.
+pub fn mermaid_script() -> String {
+    format!(
+        r#"
+<script type="module">import mermaid from "{}"; mermaid.initialize({{ startOnLoad: true }});</script>"#,
+        escape_attr(MERMAID_SCRIPT)
+    )
+}
+
+/// Render one diagram. Build-time rendering falls back to the client markup
+/// (reporting a warning to `sink`) when the CLI is missing or rejects the
+/// diagram.
+pub fn render_mermaid(source: &str, mode: MermaidRendering, sink: &DocumentSink) -> String {
+    if mode == MermaidRendering::Build {
+        match render_svg(source) {
+            Ok(svg) => return format!("<figure class=\"mermaid\">{}</figure>\n", svg.trim()),
+            Err(e) => sink.warn(
+                "mermaid",
+                source,
+                format!("could not pre-render diagram: {e}"),
+            ),
+        }
+    }
+    format!("<pre class=\"mermaid\">{}</pre>\n", escape_html(source))
+}
+
+/// Pipe the diagram through the Mermaid CLI, reading SVG from its stdout.
+fn render_svg(source: &str) -> color_eyre::Result<String> {
+    let mut child = Command::new(MERMAID_CLI)
+        .args([
+            "--input",
+            "-",
+            "--output",
+            "-",
+            "--outputFormat",
+            "svg",
+            "--quiet",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .ok_or_else(|| color_eyre::eyre::eyre!("{MERMAID_CLI} has no stdin"))?
+        .write_all(source.as_bytes())?;
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        color_eyre::eyre::bail!(
+            "{MERMAID_CLI} exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+impl<'a, I> Transformer<'a, I> for MermaidTransformer<'a, I>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    fn transform(inner: I) -> Self {
+        Self::transform_with(inner, &DocumentSink::default())
+    }
+
+    fn transform_with(inner: I, sink: &DocumentSink) -> Self {
+        Self {
+            inner,
+            buffer: None,
+            sink: sink.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,57 @@
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Tag, TagEnd};
+
+use crate::{
+    config::MermaidRendering,
+    diagnostics::DocumentSink,
+    transformer::{
+        WithTransformer,
+        mermaid::{MermaidTransformer, is_mermaid, render_mermaid},
+    },
+};
+
+#[test]
+fn mermaid_fence_is_replaced() {
+    let events = vec![
+        Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(CowStr::from(
+            "mermaid",
+        )))),
+        Event::Text(CowStr::from("graph TD\n  A --> B\n")),
+        Event::End(TagEnd::CodeBlock),
+        Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(CowStr::from("rs")))),
+        Event::Text(CowStr::from("fn main() {}\n")),
+        Event::End(TagEnd::CodeBlock),
+    ];
+
+    let out: Vec<_> = events
+        .into_iter()
+        .with_transformer::<MermaidTransformer<_>>()
+        .collect();
+
+    assert_eq!(out.len(), 4);
+    assert!(matches!(&out[0], Event::Html(html) if html.contains("mermaid")));
+    assert!(matches!(out[1], Event::Start(Tag::CodeBlock(_))));
+}
+
+#[test]
+fn client_rendering_escapes_source() {
+    let html = render_mermaid(
+        "graph TD\n  A --> B\n",
+        MermaidRendering::Client,
+        &DocumentSink::default(),
+    );
+
+    assert_eq!(
+        html,
+        "<pre class=\"mermaid\">graph TD\n  A --&gt; B\n</pre>\n"
+    );
+}
+
+#[test]
+fn detects_mermaid_language_token() {
+    assert!(is_mermaid(&CodeBlockKind::Fenced(CowStr::from("mermaid"))));
+    assert!(is_mermaid(&CodeBlockKind::Fenced(CowStr::from(
+        "Mermaid title=x"
+    ))));
+    assert!(!is_mermaid(&CodeBlockKind::Fenced(CowStr::from("rs"))));
+    assert!(!is_mermaid(&CodeBlockKind::Indented));
+}
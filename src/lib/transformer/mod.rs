@@ -6,13 +6,26 @@
 //! in order to perform things such as syntax highlighting.
 use pulldown_cmark::Event;
 
+use crate::diagnostics::DocumentSink;
+
+pub mod blockquote;
+pub mod citation;
 pub mod code_block;
+pub mod definition_list;
 pub mod epigraph;
+pub mod external_link;
 pub mod footnote;
 pub mod heading;
 pub mod image;
+pub mod margin_note;
 pub mod math;
+pub mod mermaid;
+pub mod paragraph;
+pub mod punctuation;
+pub mod raw_html;
+pub mod text_macro;
 pub mod toc;
+pub mod todo;
 
 /// A transformer over events, that takes in an inner iterator and returns
 /// another iterator of events, which returns transformed events.
@@ -22,6 +35,14 @@ where
 {
     /// Wrap an inner iterator with the transformer
     fn transform(inner: I) -> Self;
+
+    /// Wrap an inner iterator, reporting anything the transformer had to
+    /// paper over (e.g. math that failed to render) to `sink`. Transformers
+    /// that cannot fail need not override this.
+    fn transform_with(inner: I, sink: &DocumentSink) -> Self {
+        let _ = sink;
+        Self::transform(inner)
+    }
 }
 
 /// Wrap an event iterator with another transformer, allowing for chaining.
@@ -30,6 +51,11 @@ pub trait WithTransformer<'a>: Iterator<Item = Event<'a>> + Sized {
     fn with_transformer<T: Transformer<'a, Self>>(self) -> T {
         T::transform(self)
     }
+
+    /// Wrap ourselves with some transformer that reports problems to `sink`
+    fn with_transformer_reporting<T: Transformer<'a, Self>>(self, sink: &DocumentSink) -> T {
+        T::transform_with(self, sink)
+    }
 }
 
 /// Blanket implementation over any event iterator
@@ -0,0 +1,129 @@
+use std::collections::HashSet;
+
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+
+use crate::{config::PARAGRAPH_PERMALINKS, transformer::Transformer};
+
+/// Give top-level paragraphs ids derived from their text, with a `¶` link to
+/// each, so readers can link to a passage. Ids survive edits elsewhere in
+/// the page; editing the paragraph itself changes its id.
+pub struct ParagraphLinkTransformer<'a> {
+    inner: std::vec::IntoIter<Event<'a>>,
+}
+
+impl<'a> Iterator for ParagraphLinkTransformer<'a> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+impl<'a> ParagraphLinkTransformer<'a> {
+    /// Wrap `inner`, linking paragraphs only when `enabled`.
+    pub fn with_enabled<I>(inner: I, enabled: bool) -> Self
+    where
+        I: Iterator<Item = Event<'a>>,
+    {
+        let events: Vec<Event<'a>> = inner.collect();
+        let rewritten = if enabled {
+            link_paragraphs(events)
+        } else {
+            events
+        };
+        Self {
+            inner: rewritten.into_iter(),
+        }
+    }
+}
+
+impl<'a, I> Transformer<'a, I> for ParagraphLinkTransformer<'a>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    fn transform(inner: I) -> Self {
+        Self::with_enabled(inner, PARAGRAPH_PERMALINKS)
+    }
+}
+
+/// Replace the tags of each top-level paragraph with ones carrying its id
+/// and ending in a link to it. Paragraphs in lists, quotes, and the like are
+/// left alone.
+pub fn link_paragraphs<'a>(events: Vec<Event<'a>>) -> Vec<Event<'a>> {
+    let mut out: Vec<Event<'a>> = Vec::with_capacity(events.len());
+    let mut ids: HashSet<String> = HashSet::new();
+    let mut depth: usize = 0;
+    // Index in `out` of the open top-level paragraph, and its text so far.
+    let mut open: Option<(usize, String)> = None;
+
+    for event in events {
+        match event {
+            Event::Start(Tag::Paragraph) if depth == 0 => {
+                depth += 1;
+                open = Some((out.len(), String::new()));
+                out.push(Event::Start(Tag::Paragraph));
+            }
+            Event::End(TagEnd::Paragraph) if depth == 1 && open.is_some() => {
+                depth -= 1;
+                let Some((start, text)) = open.take() else {
+                    continue;
+                };
+                let id = unique_id(&mut ids, paragraph_id(&text));
+                out[start] = Event::Html(CowStr::from(format!(r#"<p id="{id}">"#)));
+                out.push(Event::Html(CowStr::from(format!(
+                    r##"<a class="para-link" href="#{id}" aria-label="Link to this paragraph">¶</a></p>
+"##
+                ))));
+            }
+            Event::Start(tag) => {
+                depth += 1;
+                out.push(Event::Start(tag));
+            }
+            Event::End(tag) => {
+                depth = depth.saturating_sub(1);
+                out.push(Event::End(tag));
+            }
+            event @ (Event::Text(_) | Event::Code(_)) => {
+                if let (Some((_, text)), Event::Text(t) | Event::Code(t)) = (open.as_mut(), &event)
+                {
+                    text.push_str(t);
+                }
+                out.push(event);
+            }
+            event @ (Event::SoftBreak | Event::HardBreak) => {
+                if let Some((_, text)) = open.as_mut() {
+                    text.push(' ');
+                }
+                out.push(event);
+            }
+            other => out.push(other),
+        }
+    }
+
+    out
+}
+
+/// `p-` and a hash of the paragraph's words, ignoring case and spacing.
+fn paragraph_id(text: &str) -> String {
+    let normalized = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    let hash = blake3::hash(normalized.as_bytes()).to_hex();
+    format!("p-{}", &hash[..8])
+}
+
+/// `base`, suffixed when a repeated paragraph already took it.
+fn unique_id(ids: &mut HashSet<String>, base: String) -> String {
+    let mut id = base.clone();
+    let mut n = 1;
+    while !ids.insert(id.clone()) {
+        n += 1;
+        id = format!("{base}-{n}");
+    }
+    id
+}
+
+#[cfg(test)]
+mod tests;
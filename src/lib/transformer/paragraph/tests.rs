@@ -0,0 +1,63 @@
+use pulldown_cmark::{Options, Parser};
+
+use crate::transformer::paragraph::ParagraphLinkTransformer;
+
+fn render(md: &str, enabled: bool) -> String {
+    let events =
+        ParagraphLinkTransformer::with_enabled(Parser::new_ext(md, Options::empty()), enabled);
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events);
+    html
+}
+
+fn ids(html: &str) -> Vec<&str> {
+    html.match_indices(r#"<p id=""#)
+        .map(|(at, m)| {
+            let rest = &html[at + m.len()..];
+            &rest[..rest.find('"').unwrap()]
+        })
+        .collect()
+}
+
+#[test]
+fn paragraphs_get_ids_from_their_text() {
+    let html = render("First *passage*.\n\nSecond `one`.\n", true);
+    let found = ids(&html);
+
+    assert_eq!(found.len(), 2);
+    assert!(
+        found
+            .iter()
+            .all(|id| id.starts_with("p-") && id.len() == 10)
+    );
+    assert!(html.contains(&format!(
+        r##"<a class="para-link" href="#{}" aria-label="Link to this paragraph">¶</a></p>"##,
+        found[0]
+    )));
+    assert!(html.contains("First <em>passage</em>."));
+
+    // Ids follow the text, not the position or spacing.
+    let moved = render("New opener.\n\nfirst\n*PASSAGE*.\n", true);
+    assert_eq!(ids(&moved)[1], found[0]);
+}
+
+#[test]
+fn repeated_paragraphs_get_distinct_ids() {
+    let found = ids(&render("Same.\n\nSame.\n", true)).join(" ");
+    let (first, second) = found.split_once(' ').unwrap();
+
+    assert_eq!(second, format!("{first}-2"));
+}
+
+#[test]
+fn only_top_level_paragraphs_are_linked() {
+    let html = render("> Quoted.\n\n- Loose item.\n\n- Another.\n", true);
+
+    assert!(ids(&html).is_empty());
+    assert!(!html.contains("para-link"));
+}
+
+#[test]
+fn disabled_leaves_paragraphs_alone() {
+    assert_eq!(render("Plain.\n", false), "<p>Plain.</p>\n");
+}
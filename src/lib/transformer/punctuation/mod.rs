@@ -0,0 +1,158 @@
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+
+use crate::{config::SMART_PUNCTUATION, transformer::Transformer};
+
+/// Typographic punctuation applied to prose only.
+///
+/// This replaces pulldown-cmark's built-in smart punctuation so that it can
+/// run *after* transformers that look for the raw ASCII forms (the epigraph
+/// attribution `--`, for instance), and so that code blocks, code spans, math,
+/// raw HTML, and frontmatter are never rewritten.
+pub struct SmartPunctuationTransformer<'a, I>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    inner: I,
+    /// Depth of blocks whose text must be left verbatim (code, metadata).
+    verbatim_depth: usize,
+    /// Last character emitted in the current run of prose, used to decide
+    /// between opening and closing quotes across event boundaries.
+    prev: Option<char>,
+}
+
+impl<'a, I> Iterator for SmartPunctuationTransformer<'a, I>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let event = self.inner.next()?;
+
+        if !SMART_PUNCTUATION {
+            return Some(event);
+        }
+
+        match event {
+            Event::Start(Tag::CodeBlock(_) | Tag::MetadataBlock(_)) => {
+                self.verbatim_depth += 1;
+                Some(event)
+            }
+            Event::End(TagEnd::CodeBlock | TagEnd::MetadataBlock(_)) => {
+                self.verbatim_depth = self.verbatim_depth.saturating_sub(1);
+                self.prev = None;
+                Some(event)
+            }
+            Event::Text(text) if self.verbatim_depth == 0 => {
+                let (rewritten, last) = smarten(&text, self.prev);
+                self.prev = last.or(self.prev);
+                Some(Event::Text(rewritten.map(CowStr::from).unwrap_or(text)))
+            }
+            // Code and math behave like a word for the purpose of quoting, so
+            // `"`x`"` closes its quote after the span.
+            Event::Code(_) | Event::InlineMath(_) => {
+                self.prev = Some('x');
+                Some(event)
+            }
+            // Inline containers keep the surrounding quoting context.
+            Event::Start(
+                Tag::Emphasis
+                | Tag::Strong
+                | Tag::Strikethrough
+                | Tag::Superscript
+                | Tag::Subscript
+                | Tag::Link { .. },
+            )
+            | Event::End(
+                TagEnd::Emphasis
+                | TagEnd::Strong
+                | TagEnd::Strikethrough
+                | TagEnd::Superscript
+                | TagEnd::Subscript
+                | TagEnd::Link,
+            ) => Some(event),
+            Event::Start(_) | Event::End(_) | Event::SoftBreak | Event::HardBreak => {
+                self.prev = None;
+                Some(event)
+            }
+            other => Some(other),
+        }
+    }
+}
+
+/// Apply dash, ellipsis, and quote substitutions to a run of prose.
+///
+/// Returns `None` when nothing changed, along with the last character of the
+/// run so quoting context carries over into the next text event.
+pub fn smarten(text: &str, mut prev: Option<char>) -> (Option<String>, Option<char>) {
+    if !text.contains(['-', '.', '"', '\'']) {
+        return (None, text.chars().last());
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let ch = chars[i];
+        let replacement = match ch {
+            '-' if chars.get(i + 1) == Some(&'-') => {
+                if chars.get(i + 2) == Some(&'-') {
+                    i += 2;
+                    '—'
+                } else {
+                    i += 1;
+                    '–'
+                }
+            }
+            '.' if chars.get(i + 1) == Some(&'.') && chars.get(i + 2) == Some(&'.') => {
+                i += 2;
+                '…'
+            }
+            '"' => {
+                if opens_quote(prev) {
+                    '“'
+                } else {
+                    '”'
+                }
+            }
+            '\'' => {
+                if prev.is_some_and(char::is_alphanumeric) || !opens_quote(prev) {
+                    '’'
+                } else {
+                    '‘'
+                }
+            }
+            other => other,
+        };
+        out.push(replacement);
+        prev = Some(replacement);
+        i += 1;
+    }
+
+    let changed = out != text;
+    (changed.then_some(out), prev)
+}
+
+fn opens_quote(prev: Option<char>) -> bool {
+    match prev {
+        None => true,
+        Some(c) => c.is_whitespace() || matches!(c, '(' | '[' | '{' | '—' | '–' | '-' | '“' | '‘'),
+    }
+}
+
+impl<'a, I> Transformer<'a, I> for SmartPunctuationTransformer<'a, I>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    fn transform(inner: I) -> Self {
+        Self {
+            inner,
+            verbatim_depth: 0,
+            prev: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,69 @@
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
+
+use crate::transformer::{
+    WithTransformer,
+    punctuation::{SmartPunctuationTransformer, smarten},
+};
+
+fn render(md: &str) -> String {
+    let events = Parser::new_ext(md, Options::ENABLE_MATH)
+        .with_transformer::<SmartPunctuationTransformer<_>>();
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events);
+    html
+}
+
+#[test]
+fn smarten_converts_dashes_quotes_and_ellipses() {
+    let (out, last) = smarten(r#"He said "it's fine" -- or not --- and then..."#, None);
+    assert_eq!(
+        out.as_deref(),
+        Some("He said “it’s fine” – or not — and then…")
+    );
+    assert_eq!(last, Some('…'));
+
+    let (out, _) = smarten("plain words", None);
+    assert!(out.is_none());
+}
+
+#[test]
+fn code_and_math_are_left_verbatim() {
+    let html = render("Use `a--b \"c\"` and $x--y$ but \"quote\".\n\n```\nlet s = \"--\";\n```\n");
+
+    assert!(html.contains(r#"<code>a--b "c"</code>"#));
+    assert!(html.contains("x--y"));
+    assert!(html.contains("“quote”"));
+    assert!(html.contains(r#"let s = "--";"#));
+}
+
+#[test]
+fn quotes_close_after_inline_code() {
+    let events = vec![
+        Event::Text(CowStr::from("\"")),
+        Event::Code(CowStr::from("x")),
+        Event::Text(CowStr::from("\" done")),
+    ];
+    let out: Vec<_> = events
+        .into_iter()
+        .with_transformer::<SmartPunctuationTransformer<_>>()
+        .collect();
+
+    assert_eq!(out[0], Event::Text(CowStr::from("“")));
+    assert_eq!(out[2], Event::Text(CowStr::from("” done")));
+}
+
+#[test]
+fn fenced_code_text_is_untouched() {
+    let events = vec![
+        Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(CowStr::from("sh")))),
+        Event::Text(CowStr::from("echo 'a' -- ...")),
+        Event::End(TagEnd::CodeBlock),
+    ];
+    let out: Vec<_> = events
+        .clone()
+        .into_iter()
+        .with_transformer::<SmartPunctuationTransformer<_>>()
+        .collect();
+
+    assert_eq!(out, events);
+}
@@ -0,0 +1,87 @@
+//! Raw HTML written into Markdown (`<img src="…">`, `<a href="…">`) arrives
+//! as `Event::Html`/`Event::InlineHtml` and so skips the events the image and
+//! link passes look at. These helpers let those passes reach into it, so the
+//! same policies apply to tags however they were written.
+use std::cell::Cell;
+
+use lol_html::{HtmlRewriter, Settings, element, html_content::Element};
+use pulldown_cmark::{CowStr, Event};
+
+/// `html` with `rewrite` run on each element matching `selector`, or `None`
+/// if it changed nothing. `rewrite` returns whether it changed the element.
+pub fn rewrite_elements(
+    html: &str,
+    selector: &str,
+    mut rewrite: impl FnMut(&mut Element<'_, '_>) -> bool,
+) -> Option<String> {
+    let tag = selector.split(['[', ':']).next().unwrap_or(selector);
+    if !contains_tag(html, tag) {
+        return None;
+    }
+    let changed = Cell::new(false);
+    let mut out = Vec::with_capacity(html.len());
+    let mut rewriter = HtmlRewriter::new(
+        Settings {
+            element_content_handlers: vec![element!(selector, |el| {
+                if rewrite(el) {
+                    changed.set(true);
+                }
+                Ok(())
+            })],
+            ..Settings::new()
+        },
+        |chunk: &[u8]| out.extend_from_slice(chunk),
+    );
+    rewriter
+        .write(html.as_bytes())
+        .and_then(|()| rewriter.end())
+        .ok()?;
+    if !changed.get() {
+        return None;
+    }
+    String::from_utf8(out).ok()
+}
+
+/// The event with its HTML rewritten by [`rewrite_elements`], or unchanged
+/// if it is not raw HTML or nothing in it matched.
+pub fn rewrite_event<'a>(
+    event: Event<'a>,
+    selector: &str,
+    rewrite: impl FnMut(&mut Element<'_, '_>) -> bool,
+) -> Event<'a> {
+    match event {
+        Event::Html(html) => match rewrite_elements(&html, selector, rewrite) {
+            Some(rewritten) => Event::Html(CowStr::from(rewritten)),
+            None => Event::Html(html),
+        },
+        Event::InlineHtml(html) => match rewrite_elements(&html, selector, rewrite) {
+            Some(rewritten) => Event::InlineHtml(CowStr::from(rewritten)),
+            None => Event::InlineHtml(html),
+        },
+        other => other,
+    }
+}
+
+/// Targets of the `<a href>` tags in the raw HTML among `events`.
+pub fn raw_hrefs(events: &[Event<'_>]) -> Vec<String> {
+    let mut hrefs = Vec::new();
+    for event in events {
+        if let Event::Html(html) | Event::InlineHtml(html) = event {
+            rewrite_elements(html, "a[href]", |el| {
+                hrefs.extend(el.get_attribute("href"));
+                false
+            });
+        }
+    }
+    hrefs
+}
+
+/// Whether `html` may hold a `<tag` element, before paying for a parse.
+fn contains_tag(html: &str, tag: &str) -> bool {
+    html.as_bytes()
+        .windows(tag.len() + 1)
+        .any(|window| window[0] == b'<' && window[1..].eq_ignore_ascii_case(tag.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,40 @@
+use pulldown_cmark::{CowStr, Event, Options, Parser};
+
+use crate::transformer::raw_html::{raw_hrefs, rewrite_elements, rewrite_event};
+
+#[test]
+fn only_matching_elements_are_rewritten() {
+    let html = r#"<p><A HREF="/a.html">a</A> <img src="b.png"></p>"#;
+
+    let rewritten =
+        rewrite_elements(html, "a[href]", |el| el.set_attribute("class", "x").is_ok()).unwrap();
+
+    assert_eq!(
+        rewritten,
+        r#"<p><A HREF="/a.html" class="x">a</A> <img src="b.png"></p>"#
+    );
+    assert_eq!(rewrite_elements(html, "video", |_| true), None);
+    assert_eq!(rewrite_elements(html, "img", |_| false), None);
+}
+
+#[test]
+fn events_keep_their_kind_and_other_events_pass_through() {
+    let inline = Event::InlineHtml(CowStr::from(r#"<img src="a.png">"#));
+    let text = Event::Text(CowStr::from(r#"<img src="a.png">"#));
+    let add_alt =
+        |el: &mut lol_html::html_content::Element<'_, '_>| el.set_attribute("alt", "").is_ok();
+
+    assert_eq!(
+        rewrite_event(inline, "img", add_alt),
+        Event::InlineHtml(CowStr::from(r#"<img src="a.png" alt="">"#))
+    );
+    assert_eq!(rewrite_event(text.clone(), "img", add_alt), text);
+}
+
+#[test]
+fn hrefs_are_collected_from_block_and_inline_html() {
+    let md = "<div><a href=\"/one.html\">one</a></div>\n\nSee <a href=\"two.html#x\">two</a> and [three](three.html).\n";
+    let events: Vec<Event<'_>> = Parser::new_ext(md, Options::empty()).collect();
+
+    assert_eq!(raw_hrefs(&events), ["/one.html", "two.html#x"]);
+}
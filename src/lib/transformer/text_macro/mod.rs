@@ -0,0 +1,160 @@
+//! Site-wide text macros: `@@name@@` in prose, link targets, and link titles
+//! becomes the value `TEXT_MACROS` gives `name`, so a fact such as a handle
+//! or an affiliate ID changes in one place. Code is left alone; unknown names
+//! are reported and kept as written.
+use std::borrow::Cow;
+
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+
+use crate::{config::TEXT_MACROS, diagnostics::DocumentSink, transformer::Transformer};
+
+const DELIMITER: &str = "@@";
+
+pub struct TextMacroTransformer<'a> {
+    inner: std::vec::IntoIter<Event<'a>>,
+}
+
+impl<'a> Iterator for TextMacroTransformer<'a> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+impl<'a, I> Transformer<'a, I> for TextMacroTransformer<'a>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    fn transform(inner: I) -> Self {
+        Self::transform_with(inner, &DocumentSink::default())
+    }
+
+    fn transform_with(inner: I, sink: &DocumentSink) -> Self {
+        Self {
+            inner: expand_macros(inner, TEXT_MACROS, sink).into_iter(),
+        }
+    }
+}
+
+/// `events` with the `macros` in their text and links expanded.
+pub fn expand_macros<'a>(
+    events: impl Iterator<Item = Event<'a>>,
+    macros: &[(&str, &str)],
+    sink: &DocumentSink,
+) -> Vec<Event<'a>> {
+    let expand = |text: CowStr<'a>| match substitute(&text, macros, sink) {
+        Cow::Owned(expanded) => CowStr::from(expanded),
+        Cow::Borrowed(_) => text,
+    };
+    let mut out: Vec<Event<'a>> = Vec::new();
+    let mut verbatim: usize = 0;
+    // The parser may split a run of text, and a macro with it, at characters
+    // that could have been markup, so runs are expanded whole.
+    let mut run: Option<CowStr<'a>> = None;
+
+    for event in events {
+        if let Event::Text(text) = event {
+            if verbatim > 0 {
+                out.push(Event::Text(text));
+                continue;
+            }
+            run = Some(match run.take() {
+                Some(previous) => CowStr::from(format!("{previous}{text}")),
+                None => text,
+            });
+            continue;
+        }
+        if let Some(text) = run.take() {
+            out.push(Event::Text(expand(text)));
+        }
+        match event {
+            Event::Start(Tag::CodeBlock(_) | Tag::MetadataBlock(_)) => {
+                verbatim += 1;
+                out.push(event);
+            }
+            Event::End(TagEnd::CodeBlock | TagEnd::MetadataBlock(_)) => {
+                verbatim = verbatim.saturating_sub(1);
+                out.push(event);
+            }
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => out.push(Event::Start(Tag::Link {
+                link_type,
+                dest_url: expand(dest_url),
+                title: expand(title),
+                id,
+            })),
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => out.push(Event::Start(Tag::Image {
+                link_type,
+                dest_url: expand(dest_url),
+                title: expand(title),
+                id,
+            })),
+            other => out.push(other),
+        }
+    }
+    if let Some(text) = run {
+        out.push(Event::Text(expand(text)));
+    }
+    out
+}
+
+/// `text` with each `@@name@@` of `macros` replaced by its value.
+fn substitute<'t>(text: &'t str, macros: &[(&str, &str)], sink: &DocumentSink) -> Cow<'t, str> {
+    if !text.contains(DELIMITER) {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut changed = false;
+    while let Some(open) = rest.find(DELIMITER) {
+        let after = &rest[open + DELIMITER.len()..];
+        let Some(len) = after.find(DELIMITER) else {
+            break;
+        };
+        let name = &after[..len];
+        if !is_name(name) {
+            // Not a macro; the closing `@@` may open the next one.
+            out.push_str(&rest[..open + DELIMITER.len()]);
+            rest = after;
+            continue;
+        }
+        out.push_str(&rest[..open]);
+        let token = &rest[open..open + 2 * DELIMITER.len() + len];
+        match macros.iter().find(|(known, _)| *known == name) {
+            Some((_, value)) => {
+                out.push_str(value);
+                changed = true;
+            }
+            None => {
+                sink.warn("macros", token, format!("no text macro named `{name}`"));
+                out.push_str(token);
+            }
+        }
+        rest = &after[len + DELIMITER.len()..];
+    }
+    if !changed {
+        return Cow::Borrowed(text);
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+fn is_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod tests;
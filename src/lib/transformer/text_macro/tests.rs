@@ -0,0 +1,71 @@
+use std::path::Path;
+
+use pulldown_cmark::{Options, Parser};
+
+use crate::{
+    diagnostics::{Diagnostics, DocumentSink},
+    transformer::text_macro::expand_macros,
+};
+
+const MACROS: &[(&str, &str)] = &[
+    ("handle", "@me@example.social"),
+    ("shop", "https://shop.example/?ref=abc"),
+];
+
+fn render(md: &str, sink: &DocumentSink) -> String {
+    let events = expand_macros(Parser::new_ext(md, Options::empty()), MACROS, sink);
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events.into_iter());
+    html
+}
+
+#[test]
+fn macros_expand_in_text_and_links() {
+    let md =
+        "Find me at @@handle@@.\n\n[Buy it](@@shop@@ \"picked by @@handle@@\") [sic] @@handle@@\n";
+    let html = render(md, &DocumentSink::default());
+
+    assert!(
+        html.contains("<p>Find me at @me@example.social.</p>"),
+        "{html}"
+    );
+    assert!(
+        html.contains(
+            r#"<a href="https://shop.example/?ref=abc" title="picked by @me@example.social">"#
+        ),
+        "{html}"
+    );
+    assert!(html.contains("[sic] @me@example.social</p>"), "{html}");
+    assert!(!html.contains("@@"), "{html}");
+}
+
+#[test]
+fn code_is_left_alone() {
+    let md = "Write `@@handle@@`.\n\n```\n@@handle@@\n```\n";
+    let html = render(md, &DocumentSink::default());
+
+    assert!(html.contains("<code>@@handle@@</code>"), "{html}");
+    assert!(
+        html.contains("<pre><code>@@handle@@\n</code></pre>"),
+        "{html}"
+    );
+}
+
+#[test]
+fn unknown_macros_are_kept_and_reported() {
+    let md = "Email @@email@@ or mail@@host, or @@ nothing @@.\n";
+    let diagnostics = Diagnostics::default();
+    let sink = diagnostics.for_document(Path::new("post.md"), md, Vec::new());
+
+    let html = render(md, &sink);
+
+    assert!(
+        html.contains("<p>Email @@email@@ or mail@@host, or @@ nothing @@.</p>"),
+        "{html}"
+    );
+    let reported: Vec<String> = diagnostics.take().iter().map(ToString::to_string).collect();
+    assert_eq!(
+        reported,
+        ["warning: post.md:1:7: macros: no text macro named `email`"]
+    );
+}
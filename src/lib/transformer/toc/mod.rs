@@ -1,6 +1,9 @@
 use pulldown_cmark::{CowStr, Event, HeadingLevel, Tag, TagEnd};
 
 use crate::{
+    config::{HEADING_SLUG_COLLISION, TOC_COLLAPSIBLE, TOC_DEPTH, TOC_NUMBERING, TocNumbering},
+    diagnostics::{DocumentSink, Severity},
+    slug::{Claim, Slugs},
     transformer::Transformer,
     utils::{escape_attr, escape_text, slugify},
 };
@@ -29,16 +32,76 @@ impl<'a> Iterator for FeedTocTransformer<'a> {
     }
 }
 
+/// How a page's TOC is built: the site config, overridden by frontmatter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TocOptions {
+    /// Insert a TOC at all (`toc: false` opts out); heading ids are assigned
+    /// either way.
+    pub enabled: bool,
+    /// Deepest heading level listed, e.g. `3` for h2–h3.
+    pub depth: u8,
+    pub numbering: TocNumbering,
+    /// Add a collapsed inline copy for narrow viewports.
+    pub collapsible: bool,
+}
+
+impl Default for TocOptions {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            depth: TOC_DEPTH,
+            numbering: TOC_NUMBERING,
+            collapsible: TOC_COLLAPSIBLE,
+        }
+    }
+}
+
+impl<'a> TocTransformer<'a> {
+    /// Wrap `inner`, building the TOC per `options`.
+    pub fn with_options<I>(inner: I, sink: &DocumentSink, options: TocOptions) -> Self
+    where
+        I: Iterator<Item = Event<'a>>,
+    {
+        let events: Vec<Event<'a>> = inner.collect();
+        let rewritten = if options.enabled {
+            insert_toc_and_heading_ids(events, sink, &options)
+        } else {
+            assign_heading_ids(events, sink)
+        };
+        Self {
+            inner: rewritten.into_iter(),
+        }
+    }
+}
+
+impl<'a> FeedTocTransformer<'a> {
+    /// Wrap `inner`, building the TOC per `options`.
+    pub fn with_options<I>(inner: I, options: TocOptions) -> Self
+    where
+        I: Iterator<Item = Event<'a>>,
+    {
+        let events: Vec<Event<'a>> = inner.collect();
+        let rewritten = if options.enabled {
+            insert_feed_toc_and_heading_ids(events, deepest_level(options.depth))
+        } else {
+            assign_heading_ids(events, &DocumentSink::default())
+        };
+        Self {
+            inner: rewritten.into_iter(),
+        }
+    }
+}
+
 impl<'a, I> Transformer<'a, I> for TocTransformer<'a>
 where
     I: Iterator<Item = Event<'a>>,
 {
     fn transform(inner: I) -> Self {
-        let events: Vec<Event<'a>> = inner.collect();
-        let rewritten = insert_toc_and_heading_ids(events);
-        Self {
-            inner: rewritten.into_iter(),
-        }
+        Self::transform_with(inner, &DocumentSink::default())
+    }
+
+    fn transform_with(inner: I, sink: &DocumentSink) -> Self {
+        Self::with_options(inner, sink, TocOptions::default())
     }
 }
 
@@ -47,38 +110,53 @@ where
     I: Iterator<Item = Event<'a>>,
 {
     fn transform(inner: I) -> Self {
-        let events: Vec<Event<'a>> = inner.collect();
-        let rewritten = insert_feed_toc_and_heading_ids(events);
-        Self {
-            inner: rewritten.into_iter(),
-        }
+        Self::with_options(inner, TocOptions::default())
     }
 }
 
-/// Insert a margin TOC (based on h2 and h3) and assign ids to headings when absent.
-pub fn insert_toc_and_heading_ids<'a>(events: Vec<Event<'a>>) -> Vec<Event<'a>> {
+/// Heading level for a TOC depth, clamped to h2–h6.
+fn deepest_level(depth: u8) -> HeadingLevel {
+    HeadingLevel::try_from(usize::from(depth.clamp(2, 6))).unwrap_or(HeadingLevel::H3)
+}
+
+/// Insert a margin TOC (headings from h2 down to the options' depth) and
+/// assign ids to headings when absent. Clashing ids are resolved per
+/// `HEADING_SLUG_COLLISION` and reported to `sink`.
+pub fn insert_toc_and_heading_ids<'a>(
+    events: Vec<Event<'a>>,
+    sink: &DocumentSink,
+    options: &TocOptions,
+) -> Vec<Event<'a>> {
     let TocExtraction {
         events: body,
         headings,
-    } = extract_headings(events);
+    } = extract_headings(events, sink, deepest_level(options.depth));
 
     if headings.is_empty() {
         return body;
     }
 
-    let toc_html = build_toc_html(&headings);
+    let toc_html = build_toc_html(&headings, options);
     let mut final_out: Vec<Event<'a>> = Vec::with_capacity(body.len() + 1);
     final_out.push(Event::Html(CowStr::from(toc_html)));
     final_out.extend(body);
     final_out
 }
 
+/// Assign ids to headings without inserting a TOC.
+pub fn assign_heading_ids<'a>(events: Vec<Event<'a>>, sink: &DocumentSink) -> Vec<Event<'a>> {
+    extract_headings(events, sink, HeadingLevel::H2).events
+}
+
 /// Feed-friendly TOC: no numbering, simple lists, and a proper heading.
-pub fn insert_feed_toc_and_heading_ids<'a>(events: Vec<Event<'a>>) -> Vec<Event<'a>> {
+pub fn insert_feed_toc_and_heading_ids<'a>(
+    events: Vec<Event<'a>>,
+    deepest: HeadingLevel,
+) -> Vec<Event<'a>> {
     let TocExtraction {
         events: body,
         headings,
-    } = extract_headings(events);
+    } = extract_headings(events, &DocumentSink::default(), deepest);
 
     if headings.is_empty() {
         return body;
@@ -91,174 +169,176 @@ pub fn insert_feed_toc_and_heading_ids<'a>(events: Vec<Event<'a>>) -> Vec<Event<
     final_out
 }
 
-fn build_toc_html(headings: &[HeadingEntry]) -> String {
-    use std::fmt::Write as _;
-
-    let mut h2_n: usize = 0;
-    let mut h3_n: usize = 0;
-
-    let mut li_open = false;
-
-    let mut sub_open = false;
-
+fn build_toc_html(headings: &[HeadingEntry], options: &TocOptions) -> String {
     let mut s = String::new();
     s.push_str(r#"<div class="toc-anchor">"#);
 
     s.push_str(r#"<nav class="toc marginnote" aria-label="Contents">"#);
     s.push_str(r#"<p class="toc-title">Contents</p>"#);
-    s.push_str(r#"<ol class="toc-list">"#);
-    for (i, entry) in headings.iter().enumerate() {
-        let next_level = headings.get(i + 1).map(|h| h.level);
-
-        if matches!(entry.level, HeadingLevel::H2) {
-            if li_open {
-                if sub_open {
-                    s.push_str("</ol>");
-                    sub_open = false;
-                }
-                s.push_str("</li>");
-            }
+    push_toc_list(&mut s, headings, options.numbering);
+    s.push_str("</nav>");
+    s.push_str("</div>");
+
+    if options.collapsible {
+        s.push_str(r#"<details class="toc-inline">"#);
+        s.push_str(r#"<summary class="toc-title">Contents</summary>"#);
+        push_toc_list(&mut s, headings, options.numbering);
+        s.push_str("</details>");
+    }
+    s
+}
 
-            li_open = true;
-            h2_n += 1;
-            h3_n = 0;
+/// The numbered `<ol class="toc-list">` shared by both copies of the TOC.
+fn push_toc_list(s: &mut String, headings: &[HeadingEntry], numbering: TocNumbering) {
+    use std::fmt::Write as _;
 
-            let num = format!("{:02}", h2_n);
+    s.push_str(r#"<ol class="toc-list">"#);
+    push_nested_items(
+        s,
+        headings,
+        (r#"<ol class="toc-sub">"#, "</ol>"),
+        |s, entry, numbers| {
             let href_id = escape_attr(&entry.id);
             let text = escape_text(&entry.title);
 
-            s.push_str(r#"<li class="toc-l1">"#);
-            write!(&mut s, r##"<a href="#{}">"##, href_id).unwrap();
-            s.push_str(r#"<span class="toc-num">"#);
-            s.push_str(&num);
-            s.push_str(r#"</span>"#);
-            s.push_str(r#"<span class="toc-text">"#);
-            s.push_str(&text);
-            s.push_str(r#"</span><span class="toc-leader" aria-hidden="true"></span></a>"#);
-
-            if matches!(next_level, Some(HeadingLevel::H3)) {
-                s.push_str(r#"<ol class="toc-sub">"#);
-                sub_open = true;
-            }
-        } else if matches!(entry.level, HeadingLevel::H3) {
-            if !li_open {
-                h2_n += 1;
-                h3_n = 0;
-
-                let num = format!("{:02}", h2_n);
-                let href_id = escape_attr(&entry.id);
-                let text = escape_text(&entry.title);
-
-                s.push_str(r#"<li class="toc-l1">"#);
-                write!(&mut s, r##"<a href="#{}">"##, href_id).unwrap();
+            write!(s, r#"<li class="toc-l{}">"#, numbers.len()).unwrap();
+            write!(s, r##"<a href="#{}">"##, href_id).unwrap();
+            if let Some(num) = toc_number(numbers, numbering) {
                 s.push_str(r#"<span class="toc-num">"#);
                 s.push_str(&num);
                 s.push_str(r#"</span>"#);
-                s.push_str(r#"<span class="toc-text">"#);
-                s.push_str(&text);
-                s.push_str(
-                    r#"</span><span class="toc-leader" aria-hidden="true"></span></a></li>"#,
-                );
-                continue;
             }
-
-            h3_n += 1;
-            let num = format!("{:02}.{}", h2_n, h3_n);
-
-            let href_id = escape_attr(&entry.id);
-            let text = escape_text(&entry.title);
-
-            s.push_str(r#"<li class="toc-l2">"#);
-            write!(&mut s, r##"<a href="#{}">"##, href_id).unwrap();
-            s.push_str(r#"<span class="toc-num">"#);
-            s.push_str(&num);
-            s.push_str(r#"</span>"#);
             s.push_str(r#"<span class="toc-text">"#);
             s.push_str(&text);
-            s.push_str(r#"</span><span class="toc-leader" aria-hidden="true"></span></a></li>"#);
-        }
-    }
+            s.push_str(r#"</span><span class="toc-leader" aria-hidden="true"></span></a>"#);
+        },
+    );
+    s.push_str("</ol>");
+}
+
+/// Label of the entry at `numbers` (its position among its ancestors).
+fn toc_number(numbers: &[usize], numbering: TocNumbering) -> Option<String> {
+    let parts: Vec<String> = match numbering {
+        TocNumbering::Off => return None,
+        // 01, 01.1, 01.1.1, ...
+        TocNumbering::Decimal => numbers
+            .iter()
+            .enumerate()
+            .map(|(i, n)| {
+                if i == 0 {
+                    format!("{n:02}")
+                } else {
+                    n.to_string()
+                }
+            })
+            .collect(),
+        // I, I.i, I.i.i, ...
+        TocNumbering::Roman => numbers
+            .iter()
+            .enumerate()
+            .map(|(i, n)| {
+                let numeral = roman(*n);
+                if i == 0 {
+                    numeral
+                } else {
+                    numeral.to_lowercase()
+                }
+            })
+            .collect(),
+    };
+    Some(parts.join("."))
+}
 
-    if li_open {
-        if sub_open {
-            s.push_str("</ol>");
+/// Upper-case Roman numeral for `n` (at least 1).
+fn roman(mut n: usize) -> String {
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut out = String::new();
+    for (value, numeral) in NUMERALS {
+        while n >= value {
+            out.push_str(numeral);
+            n -= value;
         }
-        s.push_str("</li>");
     }
-
-    s.push_str("</ol></nav>");
-    s.push_str("</div>");
-    s
+    out
 }
 
 fn build_feed_toc_html(headings: &[HeadingEntry]) -> String {
     use std::fmt::Write as _;
 
-    let mut h2_open = false;
-    let mut sub_open = false;
-
     let mut s = String::new();
     s.push_str(r#"<h1 id="contents">Contents</h1>"#);
     s.push_str("<ul>");
+    push_nested_items(&mut s, headings, ("<ul>", "</ul>"), |s, entry, _| {
+        write!(
+            s,
+            "<li><a href=\"#{id}\">{title}</a>",
+            id = escape_attr(&entry.id),
+            title = escape_text(&entry.title)
+        )
+        .unwrap();
+    });
+    s.push_str("</ul>");
+    s
+}
 
-    for (i, entry) in headings.iter().enumerate() {
-        let next_level = headings.get(i + 1).map(|h| h.level);
-
-        if matches!(entry.level, HeadingLevel::H2) {
-            if h2_open {
-                if sub_open {
-                    s.push_str("</ul>");
-                    sub_open = false;
-                }
-                s.push_str("</li>");
-            }
-
-            h2_open = true;
-            write!(
-                &mut s,
-                "<li><a href=\"#{id}\">",
-                id = escape_attr(&entry.id)
-            )
-            .unwrap();
-            s.push_str(&escape_text(&entry.title));
-            s.push_str("</a>");
-
-            if matches!(next_level, Some(HeadingLevel::H3)) {
-                s.push_str("<ul>");
-                sub_open = true;
-            }
-        } else if matches!(entry.level, HeadingLevel::H3) {
-            if !h2_open {
-                // stray h3, render as h2-level item
-                write!(
-                    &mut s,
-                    "<li><a href=\"#{id}\">{title}</a></li>",
-                    id = escape_attr(&entry.id),
-                    title = escape_text(&entry.title)
-                )
-                .unwrap();
-                continue;
-            }
-
-            write!(
-                &mut s,
-                "<li><a href=\"#{id}\">{title}</a></li>",
-                id = escape_attr(&entry.id),
-                title = escape_text(&entry.title)
-            )
-            .unwrap();
+/// Write `headings` into an already opened list, nesting a sub-list (opened
+/// and closed with `sub_list`) under an item for each deeper level. `item`
+/// writes an entry's opening `<li>` and link, given its position among its
+/// ancestors (e.g. `[2, 1]` for the first h3 under the second h2). A heading
+/// that skips a level, or a deeper one with no parent, nests only one level
+/// below the previous entry.
+fn push_nested_items(
+    s: &mut String,
+    headings: &[HeadingEntry],
+    sub_list: (&str, &str),
+    mut item: impl FnMut(&mut String, &HeadingEntry, &[usize]),
+) {
+    let (open, close) = sub_list;
+    // Items written so far in each open list, outermost first.
+    let mut counters: Vec<usize> = vec![0];
+
+    for entry in headings {
+        let deepest_open = counters.len() - usize::from(counters[counters.len() - 1] == 0);
+        let depth = (entry.level as usize - 2).min(deepest_open);
+
+        while counters.len() > depth + 1 {
+            s.push_str("</li>");
+            s.push_str(close);
+            counters.pop();
+        }
+        if depth == counters.len() {
+            s.push_str(open);
+            counters.push(0);
+        } else if counters[depth] > 0 {
+            s.push_str("</li>");
         }
+
+        counters[depth] += 1;
+        item(s, entry, &counters);
     }
 
-    if h2_open {
-        if sub_open {
-            s.push_str("</ul>");
-        }
+    while counters.len() > 1 {
+        s.push_str("</li>");
+        s.push_str(close);
+        counters.pop();
+    }
+    if counters[0] > 0 {
         s.push_str("</li>");
     }
-
-    s.push_str("</ul>");
-    s
 }
 
 #[derive(Debug)]
@@ -273,11 +353,15 @@ struct TocExtraction<'a> {
     headings: Vec<HeadingEntry>,
 }
 
-fn extract_headings<'a>(events: Vec<Event<'a>>) -> TocExtraction<'a> {
+/// Assign ids to every h2–h6, collecting those down to `deepest` for the TOC.
+fn extract_headings<'a>(
+    events: Vec<Event<'a>>,
+    sink: &DocumentSink,
+    deepest: HeadingLevel,
+) -> TocExtraction<'a> {
     let mut out: Vec<Event<'a>> = Vec::with_capacity(events.len() + 1);
     let mut headings: Vec<HeadingEntry> = Vec::new();
-    let mut slug_counts: std::collections::HashMap<String, usize> =
-        std::collections::HashMap::new();
+    let mut slugs = Slugs::new(HEADING_SLUG_COLLISION);
 
     let mut in_heading: Option<(HeadingLevel, usize, String, Option<String>)> = None;
 
@@ -291,7 +375,7 @@ fn extract_headings<'a>(events: Vec<Event<'a>>) -> TocExtraction<'a> {
                     classes,
                     attrs,
                 }),
-            ) if matches!(level, HeadingLevel::H2 | HeadingLevel::H3) => {
+            ) if level >= HeadingLevel::H2 => {
                 let start_index = out.len();
                 let existing_id = id.as_ref().map(|c| c.to_string());
 
@@ -322,7 +406,15 @@ fn extract_headings<'a>(events: Vec<Event<'a>>) -> TocExtraction<'a> {
                 let title = title_buf.trim().to_string();
 
                 let base = existing_id.clone().unwrap_or_else(|| slugify(&title));
-                let unique = uniquify_slug(base, &mut slug_counts);
+                let claim = slugs.claim(base);
+                if let Some(message) = claim.describe("heading id") {
+                    let severity = match claim {
+                        Claim::Collided(_) => Severity::Error,
+                        _ => Severity::Warning,
+                    };
+                    sink.report(severity, "slug", &title, message);
+                }
+                let unique = claim.slug().to_owned();
 
                 let old = std::mem::replace(&mut out[*start_index], Event::Text(CowStr::from("")));
                 out[*start_index] = match old {
@@ -340,11 +432,13 @@ fn extract_headings<'a>(events: Vec<Event<'a>>) -> TocExtraction<'a> {
                     other => other,
                 };
 
-                headings.push(HeadingEntry {
-                    level: *level,
-                    id: unique,
-                    title,
-                });
+                if *level <= deepest {
+                    headings.push(HeadingEntry {
+                        level: *level,
+                        id: unique,
+                        title,
+                    });
+                }
 
                 out.push(Event::End(TagEnd::Heading(*level)));
                 in_heading = None;
@@ -361,16 +455,6 @@ fn extract_headings<'a>(events: Vec<Event<'a>>) -> TocExtraction<'a> {
         headings,
     }
 }
-fn uniquify_slug(base: String, counts: &mut std::collections::HashMap<String, usize>) -> String {
-    let n = counts.entry(base.clone()).or_insert(0);
-    *n += 1;
-
-    if *n == 1 {
-        base
-    } else {
-        format!("{base}-{}", *n)
-    }
-}
 
 #[cfg(test)]
 mod tests;
@@ -6,7 +6,14 @@ use proptest::{
 };
 use pulldown_cmark::{CowStr, Event, HeadingLevel, Tag, TagEnd};
 
-use crate::{transformer::toc::insert_toc_and_heading_ids, utils::slugify};
+use crate::{
+    config::TocNumbering,
+    diagnostics::DocumentSink,
+    transformer::toc::{
+        TocOptions, TocTransformer, insert_feed_toc_and_heading_ids, insert_toc_and_heading_ids,
+    },
+    utils::slugify,
+};
 
 #[test]
 fn toc_assigns_unique_ids() {
@@ -31,7 +38,7 @@ fn toc_assigns_unique_ids() {
                     events.push(Event::End(TagEnd::Heading(HeadingLevel::H2)));
                 }
 
-                let out = insert_toc_and_heading_ids(events);
+                let out = insert_toc_and_heading_ids(events, &DocumentSink::default(), &options(3));
 
                 let mut ids = Vec::new();
                 for ev in &out {
@@ -68,3 +75,142 @@ fn toc_assigns_unique_ids() {
         )
         .unwrap();
 }
+
+fn options(depth: u8) -> TocOptions {
+    TocOptions {
+        depth,
+        numbering: TocNumbering::Decimal,
+        ..TocOptions::default()
+    }
+}
+
+fn headings(levels: &[HeadingLevel]) -> Vec<Event<'static>> {
+    let mut events = Vec::new();
+    for (i, level) in levels.iter().enumerate() {
+        events.push(Event::Start(Tag::Heading {
+            level: *level,
+            id: None,
+            classes: vec![],
+            attrs: vec![],
+        }));
+        events.push(Event::Text(CowStr::from(format!("Part {i}"))));
+        events.push(Event::End(TagEnd::Heading(*level)));
+    }
+    events
+}
+
+fn toc_html(out: &[Event<'_>]) -> String {
+    match out.first() {
+        Some(Event::Html(html)) => html.to_string(),
+        other => panic!("expected a TOC, got {other:?}"),
+    }
+}
+
+#[test]
+fn toc_depth_limits_listed_levels_but_not_ids() {
+    use HeadingLevel::{H2, H3, H4};
+    let levels = [H2, H3, H4, H3, H2];
+
+    let shallow =
+        insert_toc_and_heading_ids(headings(&levels), &DocumentSink::default(), &options(2));
+    let toc = toc_html(&shallow);
+    assert!(toc.contains("#part-0") && toc.contains("#part-4"));
+    assert!(!toc.contains("#part-1") && !toc.contains("toc-sub"));
+    let ids = shallow
+        .iter()
+        .filter(|ev| matches!(ev, Event::Start(Tag::Heading { id: Some(_), .. })))
+        .count();
+    assert_eq!(ids, levels.len());
+
+    let deep = insert_toc_and_heading_ids(headings(&levels), &DocumentSink::default(), &options(4));
+    let toc = toc_html(&deep);
+    assert!(
+        toc.contains(
+            r##"<li class="toc-l3"><a href="#part-2"><span class="toc-num">01.1.1</span>"##
+        )
+    );
+    assert!(toc.contains(r#"<span class="toc-num">01.2</span>"#));
+    assert!(toc.contains(r#"<span class="toc-num">02</span>"#));
+    assert_eq!(toc.matches("<li").count(), toc.matches("</li>").count());
+    assert_eq!(toc.matches("<ol").count(), toc.matches("</ol>").count());
+}
+
+#[test]
+fn feed_toc_nests_skipped_levels_one_deep() {
+    use HeadingLevel::{H2, H4};
+
+    let out = insert_feed_toc_and_heading_ids(headings(&[H4, H2, H4]), H4);
+    assert_eq!(
+        toc_html(&out),
+        concat!(
+            r#"<h1 id="contents">Contents</h1><ul>"#,
+            r##"<li><a href="#part-0">Part 0</a></li>"##,
+            r##"<li><a href="#part-1">Part 1</a><ul><li><a href="#part-2">Part 2</a></li></ul></li>"##,
+            "</ul>"
+        )
+    );
+}
+
+#[test]
+fn disabled_toc_still_assigns_ids() {
+    use HeadingLevel::{H2, H3};
+
+    let options = TocOptions {
+        enabled: false,
+        ..TocOptions::default()
+    };
+    let out: Vec<_> = TocTransformer::with_options(
+        headings(&[H2, H3]).into_iter(),
+        &DocumentSink::default(),
+        options,
+    )
+    .collect();
+    assert!(!out.iter().any(|ev| matches!(ev, Event::Html(_))));
+    let ids: Vec<_> = out
+        .iter()
+        .filter_map(|ev| match ev {
+            Event::Start(Tag::Heading { id: Some(id), .. }) => Some(id.to_string()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(ids, ["part-0", "part-1"]);
+}
+
+#[test]
+fn toc_numbering_schemes() {
+    use HeadingLevel::{H2, H3};
+    let levels = [H2, H2, H2, H2, H3, H3];
+
+    let mut roman = options(3);
+    roman.numbering = TocNumbering::Roman;
+    let out = insert_toc_and_heading_ids(headings(&levels), &DocumentSink::default(), &roman);
+    let toc = toc_html(&out);
+    assert!(toc.contains(r#"<span class="toc-num">IV</span>"#));
+    assert!(toc.contains(r#"<span class="toc-num">IV.ii</span>"#));
+
+    let mut off = options(3);
+    off.numbering = TocNumbering::Off;
+    let out = insert_toc_and_heading_ids(headings(&levels), &DocumentSink::default(), &off);
+    assert!(!toc_html(&out).contains("toc-num"));
+}
+
+#[test]
+fn collapsible_toc_adds_a_closed_inline_copy() {
+    use HeadingLevel::{H2, H3};
+    let levels = [H2, H3];
+
+    let mut collapsible = options(3);
+    collapsible.collapsible = true;
+    let out = insert_toc_and_heading_ids(headings(&levels), &DocumentSink::default(), &collapsible);
+    let toc = toc_html(&out);
+    let (margin, inline) = toc.split_once(r#"<details class="toc-inline">"#).unwrap();
+    assert!(margin.contains(r#"<nav class="toc marginnote""#));
+    assert!(inline.starts_with(r#"<summary class="toc-title">Contents</summary>"#));
+    assert!(inline.ends_with("</details>"));
+    assert_eq!(margin.matches("<li").count(), inline.matches("<li").count());
+
+    let mut fixed = options(3);
+    fixed.collapsible = false;
+    let out = insert_toc_and_heading_ids(headings(&levels), &DocumentSink::default(), &fixed);
+    assert!(!toc_html(&out).contains("<details"));
+}
@@ -0,0 +1,198 @@
+//! Unfinished spots the author marked: `TODO:` or `FIXME:` in the text, up
+//! to the end of its line, and HTML comments starting with `todo` or `fixme`
+//! (`<!-- todo: cite this -->`). They are cut from the page, so they never
+//! ship, and reported as `todo` diagnostics, which `--strict-todos` makes
+//! fatal. Code is left alone.
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+
+use crate::{
+    diagnostics::{DocumentSink, Severity},
+    transformer::Transformer,
+};
+
+const MARKERS: [&str; 2] = ["TODO:", "FIXME:"];
+/// Comment openings, matched case-insensitively, and the marker each reports.
+const COMMENT_MARKERS: [(&str, &str); 2] = [("todo", "TODO"), ("fixme", "FIXME")];
+
+pub struct TodoTransformer<'a> {
+    inner: std::vec::IntoIter<Event<'a>>,
+}
+
+impl<'a> Iterator for TodoTransformer<'a> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+impl<'a, I> Transformer<'a, I> for TodoTransformer<'a>
+where
+    I: Iterator<Item = Event<'a>>,
+{
+    fn transform(inner: I) -> Self {
+        Self::transform_with(inner, &DocumentSink::default())
+    }
+
+    fn transform_with(inner: I, sink: &DocumentSink) -> Self {
+        Self {
+            inner: strip_todos(inner, sink).into_iter(),
+        }
+    }
+}
+
+/// `events` without their TODO markers, each reported to `sink`. Paragraphs
+/// and list items left empty are dropped with them.
+fn strip_todos<'a>(events: impl Iterator<Item = Event<'a>>, sink: &DocumentSink) -> Vec<Event<'a>> {
+    let mut out: Vec<Event<'a>> = Vec::new();
+    let mut verbatim: usize = 0;
+    // While cutting the rest of a marked line: how many tags opened since.
+    let mut cutting: Option<usize> = None;
+
+    for event in merge_html(events) {
+        if let Some(depth) = cutting.as_mut() {
+            match event {
+                Event::Start(_) => *depth += 1,
+                Event::End(_) if *depth > 0 => *depth -= 1,
+                // Closes a tag opened before the marker, so it stays.
+                Event::End(_) => {
+                    cutting = None;
+                    push(&mut out, event);
+                }
+                Event::SoftBreak | Event::HardBreak if *depth == 0 => cutting = None,
+                _ => {}
+            }
+            continue;
+        }
+        match event {
+            Event::Start(Tag::CodeBlock(_) | Tag::MetadataBlock(_)) => {
+                verbatim += 1;
+                out.push(event);
+            }
+            Event::End(TagEnd::CodeBlock | TagEnd::MetadataBlock(_)) => {
+                verbatim = verbatim.saturating_sub(1);
+                out.push(event);
+            }
+            Event::Text(text) if verbatim == 0 => match find_marker(&text) {
+                Some((at, marker)) => {
+                    let note = text[at + marker.len()..].trim();
+                    report(sink, &text[at..], marker.trim_end_matches(':'), note);
+                    let before = text[..at].trim_end();
+                    if !before.is_empty() {
+                        out.push(Event::Text(before.to_owned().into()));
+                    }
+                    cutting = Some(0);
+                }
+                None => out.push(Event::Text(text)),
+            },
+            Event::Html(ref html) | Event::InlineHtml(ref html)
+                if verbatim == 0 && html.contains("<!--") =>
+            {
+                let kept = strip_comments(html, sink);
+                if kept.trim().is_empty() {
+                    continue;
+                }
+                out.push(match event {
+                    Event::Html(_) => Event::Html(kept.into()),
+                    _ => Event::InlineHtml(kept.into()),
+                });
+            }
+            other => push(&mut out, other),
+        }
+    }
+    out
+}
+
+/// Push `event`, dropping the paragraph, item, or list it closes if that
+/// was left empty.
+fn push<'a>(out: &mut Vec<Event<'a>>, event: Event<'a>) {
+    let emptied = matches!(
+        (&event, out.last()),
+        (
+            Event::End(TagEnd::Paragraph),
+            Some(Event::Start(Tag::Paragraph))
+        ) | (Event::End(TagEnd::Item), Some(Event::Start(Tag::Item)))
+            | (
+                Event::End(TagEnd::List(_)),
+                Some(Event::Start(Tag::List(_)))
+            )
+    );
+    if emptied {
+        out.pop();
+    } else {
+        out.push(event);
+    }
+}
+
+/// Runs of adjacent HTML as one, since a comment in an HTML block spans
+/// one event per line.
+fn merge_html<'a>(events: impl Iterator<Item = Event<'a>>) -> Vec<Event<'a>> {
+    let mut out: Vec<Event<'a>> = Vec::new();
+    for event in events {
+        match (out.last_mut(), event) {
+            (Some(Event::Html(previous)), Event::Html(html)) => {
+                *previous = CowStr::from(format!("{previous}{html}"));
+            }
+            (_, event) => out.push(event),
+        }
+    }
+    out
+}
+
+/// Byte offset and text of the first marker in `text` that starts a word.
+fn find_marker(text: &str) -> Option<(usize, &'static str)> {
+    MARKERS
+        .iter()
+        .filter_map(|marker| {
+            text.match_indices(marker)
+                .find(|(at, _)| {
+                    !text[..*at]
+                        .chars()
+                        .next_back()
+                        .is_some_and(|c| c.is_alphanumeric() || c == '_')
+                })
+                .map(|(at, _)| (at, *marker))
+        })
+        .min_by_key(|(at, _)| *at)
+}
+
+/// `html` without its TODO comments, each reported to `sink`.
+fn strip_comments(html: &str, sink: &DocumentSink) -> String {
+    let mut kept = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(open) = rest.find("<!--") {
+        let Some(len) = rest[open..].find("-->").map(|at| at + "-->".len()) else {
+            break;
+        };
+        let comment = &rest[open..open + len];
+        let body = comment["<!--".len()..comment.len() - "-->".len()].trim();
+        let marker = COMMENT_MARKERS.into_iter().find(|(opening, _)| {
+            body.get(..opening.len())
+                .is_some_and(|start| start.eq_ignore_ascii_case(opening))
+                && !body[opening.len()..].starts_with(char::is_alphanumeric)
+        });
+        kept.push_str(&rest[..open]);
+        match marker {
+            Some((opening, marker)) => {
+                let note = body[opening.len()..].trim_start_matches(':').trim();
+                report(sink, comment, marker, note);
+            }
+            None => kept.push_str(comment),
+        }
+        rest = &rest[open + len..];
+    }
+    kept.push_str(rest);
+    kept
+}
+
+fn report(sink: &DocumentSink, snippet: &str, marker: &'static str, note: &str) {
+    let message = if note.is_empty() {
+        "unfinished".to_owned()
+    } else {
+        note.to_owned()
+    };
+    sink.report(Severity::Todo, marker, snippet, message);
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,68 @@
+use std::path::Path;
+
+use pulldown_cmark::{Options, Parser};
+
+use crate::{
+    diagnostics::{Diagnostics, DocumentSink, Severity, parse_with_spans},
+    transformer::{WithTransformer, todo::TodoTransformer},
+};
+
+fn render(md: &str, sink: &DocumentSink) -> String {
+    let events = Parser::new_ext(md, Options::empty())
+        .with_transformer_reporting::<TodoTransformer<'_>>(sink);
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events);
+    html
+}
+
+#[test]
+fn marked_lines_and_comments_are_cut_and_reported() {
+    let md = "Intro.\nTODO: cite the survey\nMore text.\n\nFIXME: numbers are from 2019\n\n- kept\n- TODO: a third item\n\nSee *TODO: wording* here.\n\n<!-- todo: add a diagram -->\n\nDone <!-- FIXME --> now. <!-- a plain comment -->\n";
+    let diagnostics = Diagnostics::default();
+    let (_, spans) = parse_with_spans(md, Options::empty());
+    let sink = diagnostics.for_document(Path::new("post.md"), md, spans);
+
+    let html = render(md, &sink);
+
+    assert!(!html.contains("TODO") && !html.contains("FIXME"), "{html}");
+    assert!(!html.contains("cite the survey") && !html.contains("diagram"));
+    assert!(html.contains("<p>Intro.\nMore text.</p>"), "{html}");
+    assert!(html.contains("<li>kept</li>") && html.matches("<li>").count() == 1);
+    assert!(html.contains("<p>See <em></em> here.</p>"), "{html}");
+    assert!(html.contains("Done  now."), "{html}");
+    assert!(html.contains("<!-- a plain comment -->"));
+    assert_eq!(html.matches("<p>").count(), 3, "{html}");
+
+    let reported: Vec<String> = diagnostics.take().iter().map(ToString::to_string).collect();
+    assert_eq!(
+        reported,
+        [
+            "todo: post.md:2:1: TODO: cite the survey",
+            "todo: post.md:5:1: FIXME: numbers are from 2019",
+            "todo: post.md:8:3: TODO: a third item",
+            "todo: post.md:10:6: TODO: wording",
+            "todo: post.md:12:1: TODO: add a diagram",
+            "todo: post.md:14:6: FIXME: unfinished",
+        ]
+    );
+}
+
+#[test]
+fn code_and_words_merely_containing_markers_are_left_alone() {
+    let md = "Use `TODO:` comments.\n\n```rust\n// TODO: later\n```\n\nMY_TODO: is a constant. <!-- todos are fine -->\n";
+    let diagnostics = Diagnostics::default();
+    let sink = diagnostics.for_document(Path::new("post.md"), md, Vec::new());
+
+    let html = render(md, &sink);
+
+    assert!(html.contains("<code>TODO:</code>"));
+    assert!(html.contains("// TODO: later"));
+    assert!(html.contains("MY_TODO: is a constant."), "{html}");
+    assert!(html.contains("<!-- todos are fine -->"));
+    assert!(
+        diagnostics
+            .take()
+            .iter()
+            .all(|d| d.severity != Severity::Todo)
+    );
+}
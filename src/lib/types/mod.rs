@@ -8,6 +8,8 @@ use std::{
 
 use time::{Date, format_description};
 
+use crate::config::PRETTY_URLS;
+
 /// Date format used for mtime and ctime.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct IsoDate(Date);
@@ -28,11 +30,22 @@ impl IsoDate {
         self.0.year()
     }
 
+    /// Month of the year, 1 to 12.
+    pub fn month(&self) -> u8 {
+        self.0.month().into()
+    }
+
     pub fn as_date(&self) -> Date {
         self.0
     }
 }
 
+impl From<Date> for IsoDate {
+    fn from(date: Date) -> Self {
+        Self(date)
+    }
+}
+
 impl fmt::Display for IsoDate {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str(&self.as_str())
@@ -74,6 +87,50 @@ impl fmt::Display for Tag {
     }
 }
 
+/// Kind of post, chosen with `type:` in frontmatter.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PostKind {
+    /// A titled essay, listed on the main index.
+    #[default]
+    Article,
+    /// A short, untitled post shown in full on the notes stream.
+    Note,
+    /// A post built around one full-bleed image, collected on the gallery.
+    Photo,
+    /// Commentary on an outbound `link:`, which listings point at directly.
+    Link,
+    /// A recipe whose frontmatter renders as a recipe card and JSON-LD.
+    Recipe,
+    /// A standalone page, such as an about page, published at the site root
+    /// and left out of listings, tag pages, and feeds.
+    Page,
+}
+
+impl PostKind {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "article" | "post" | "essay" => Some(Self::Article),
+            "note" => Some(Self::Note),
+            "photo" => Some(Self::Photo),
+            "link" => Some(Self::Link),
+            "recipe" => Some(Self::Recipe),
+            "page" => Some(Self::Page),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Article => "article",
+            Self::Note => "note",
+            Self::Photo => "photo",
+            Self::Link => "link",
+            Self::Recipe => "recipe",
+            Self::Page => "page",
+        }
+    }
+}
+
 /// The collection of tags for each article.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Tags(pub Vec<Tag>);
@@ -116,7 +173,16 @@ pub struct Href(String);
 
 impl Href {
     pub fn from_rel(rel: &RelPath) -> Self {
+        Self::from_rel_with(rel, PRETTY_URLS)
+    }
+
+    /// Build an href, dropping a trailing `index.html` when `pretty` is set so
+    /// directory-style pages are linked as `posts/foo/`.
+    pub fn from_rel_with(rel: &RelPath, pretty: bool) -> Self {
         let s = rel.as_path().to_string_lossy().replace('\\', "/");
+        if pretty && let Some(dir) = s.strip_suffix("/index.html") {
+            return Self(format!("{dir}/"));
+        }
         Self(s)
     }
 
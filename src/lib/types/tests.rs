@@ -152,3 +152,16 @@ fn href_uses_forward_slashes() {
         })
         .unwrap();
 }
+
+#[test]
+fn pretty_href_drops_index_file() {
+    let rel = RelPath::new(PathBuf::from("posts/foo/index.html")).unwrap();
+    assert_eq!(Href::from_rel_with(&rel, true).as_str(), "posts/foo/");
+    assert_eq!(
+        Href::from_rel_with(&rel, false).as_str(),
+        "posts/foo/index.html"
+    );
+
+    let root = RelPath::new(PathBuf::from("index.html")).unwrap();
+    assert_eq!(Href::from_rel_with(&root, true).as_str(), "index.html");
+}
@@ -1,3 +1,33 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    thread,
+};
+
+use crate::{
+    config::{PRETTY_URLS, TAGS_DIR},
+    types::{Href, RelPath, Tag},
+};
+
+/// A file next to `dest` private to this process and thread, to write before
+/// moving it into place, so no concurrent build, render, or server ever sees
+/// `dest` half written.
+pub fn partial_path(dest: &Path) -> PathBuf {
+    let thread = format!("{:?}", thread::current().id());
+    let id: String = thread.chars().filter(char::is_ascii_digit).collect();
+    dest.with_extension(format!("{}.{id}.partial", std::process::id()))
+}
+
+/// Write `data` to `path` through a [`partial_path`], replacing any file
+/// there in one step.
+pub fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    let partial = partial_path(path);
+    fs::write(&partial, data)?;
+    fs::rename(&partial, path).inspect_err(|_| {
+        let _ = fs::remove_file(&partial);
+    })
+}
+
 /// HTML-escape text content.
 pub fn escape_text(s: &str) -> String {
     let mut out = String::with_capacity(s.len());
@@ -63,8 +93,107 @@ pub fn slugify(input: &str) -> String {
     }
 }
 
+/// Attributes written as `{#id .class key="value"}` alongside a Markdown block.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct AttributeBlock {
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+    pub pairs: Vec<(String, String)>,
+}
+
+impl AttributeBlock {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn has_class(&self, class: &str) -> bool {
+        self.classes.iter().any(|c| c == class)
+    }
+}
+
+/// Parse a brace-delimited attribute block. Returns `None` unless the whole
+/// (trimmed) input is a well-formed, non-empty block.
+pub fn parse_attribute_block(input: &str) -> Option<AttributeBlock> {
+    let inner = input.trim().strip_prefix('{')?.strip_suffix('}')?;
+    let mut block = AttributeBlock::default();
+    let mut chars = inner.chars().peekable();
+
+    let is_name = |c: &char| c.is_alphanumeric() || *c == '-' || *c == '_';
+
+    while let Some(&ch) = chars.peek() {
+        if ch.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        match ch {
+            '#' | '.' => {
+                chars.next();
+                let mut name = String::new();
+                while let Some(c) = chars.next_if(is_name) {
+                    name.push(c);
+                }
+                if name.is_empty() {
+                    return None;
+                }
+                if ch == '#' {
+                    block.id = Some(name);
+                } else {
+                    block.classes.push(name);
+                }
+            }
+            _ => {
+                let mut key = String::new();
+                while let Some(c) = chars.next_if(is_name) {
+                    key.push(c);
+                }
+                if key.is_empty() || chars.next() != Some('=') {
+                    return None;
+                }
+
+                let mut value = String::new();
+                match chars.peek() {
+                    Some(&quote @ ('"' | '\'')) => {
+                        chars.next();
+                        loop {
+                            match chars.next() {
+                                Some(c) if c == quote => break,
+                                Some(c) => value.push(c),
+                                None => return None,
+                            }
+                        }
+                    }
+                    _ => {
+                        while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                            value.push(c);
+                        }
+                    }
+                }
+                block.pairs.push((key, value));
+            }
+        }
+    }
+
+    let empty = block.id.is_none() && block.classes.is_empty() && block.pairs.is_empty();
+    (!empty).then_some(block)
+}
+
+/// Lower-cased host of an absolute or protocol-relative URL.
+pub fn external_host(url: &str) -> Option<String> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .or_else(|| url.strip_prefix("//"))?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?.split(':').next()?;
+    (!host.is_empty()).then(|| host.to_lowercase())
+}
+
 /// Prefix needed to navigate from a relative output path back to the root.
-pub fn prefix_to_root(rel_out: &std::path::Path) -> String {
+pub fn prefix_to_root(rel_out: &Path) -> String {
     let depth = rel_out
         .parent()
         .map(|p| p.components().count())
@@ -72,5 +201,33 @@ pub fn prefix_to_root(rel_out: &std::path::Path) -> String {
     "../".repeat(depth)
 }
 
+/// Output location for a page. With `pretty` set, `posts/foo.html` becomes
+/// `posts/foo/index.html`; pages already named `index.html` are left alone.
+pub fn page_out_path(rel_out: &Path, pretty: bool) -> PathBuf {
+    if !pretty || rel_out.file_stem().is_some_and(|stem| stem == "index") {
+        return rel_out.to_path_buf();
+    }
+    rel_out.with_extension("").join("index.html")
+}
+
+/// Output location of a tag's listing page, relative to the site root.
+pub fn tag_page_path(tag: &Tag) -> PathBuf {
+    page_out_path(
+        &Path::new(TAGS_DIR).join(format!("{tag}.html")),
+        PRETTY_URLS,
+    )
+}
+
+/// Output location of the page listing every tag, relative to the site root.
+pub fn tags_index_path() -> PathBuf {
+    Path::new(TAGS_DIR).join("index.html")
+}
+
+/// Root-relative link to a tag's listing page.
+pub fn tag_href(tag: &Tag) -> Href {
+    let rel = RelPath::new(tag_page_path(tag)).expect("tag page path is relative");
+    Href::from_rel(&rel)
+}
+
 #[cfg(test)]
 mod tests;
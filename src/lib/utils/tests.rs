@@ -3,7 +3,27 @@ use proptest::{
     test_runner::{Config, TestRunner},
 };
 
-use super::{escape_text, prefix_to_root, slugify};
+use std::fs;
+
+use tempfile::TempDir;
+
+use super::{
+    escape_text, page_out_path, parse_attribute_block, partial_path, prefix_to_root, slugify,
+    write_atomic,
+};
+
+#[test]
+fn atomic_writes_replace_the_file_and_leave_no_partial() {
+    let tmp = TempDir::new().expect("tempdir");
+    let path = tmp.path().join("page.html");
+    fs::write(&path, "old").unwrap();
+
+    write_atomic(&path, b"new").unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+    assert!(!partial_path(&path).exists());
+    assert_eq!(fs::read_dir(tmp.path()).unwrap().count(), 1);
+}
 
 #[test]
 fn escape_text_removes_angle_and_quotes() {
@@ -77,3 +97,37 @@ fn prefix_to_root_matches_depth() {
         )
         .unwrap();
 }
+
+#[test]
+fn page_out_path_nests_pages_when_pretty() {
+    use std::path::Path;
+
+    let rel = Path::new("posts/foo.html");
+    assert_eq!(page_out_path(rel, false), Path::new("posts/foo.html"));
+    assert_eq!(page_out_path(rel, true), Path::new("posts/foo/index.html"));
+    assert_eq!(
+        page_out_path(Path::new("posts/bar/index.html"), true),
+        Path::new("posts/bar/index.html")
+    );
+    assert_eq!(prefix_to_root(&page_out_path(rel, true)), "../../");
+}
+
+#[test]
+fn attribute_blocks_parse_ids_classes_and_pairs() {
+    let block = parse_attribute_block(
+        r#" {#intro .epigraph cite="Author, Book" href='https://a.b/c' n=3} "#,
+    )
+    .unwrap();
+    assert_eq!(block.id.as_deref(), Some("intro"));
+    assert!(block.has_class("epigraph"));
+    assert_eq!(block.get("cite"), Some("Author, Book"));
+    assert_eq!(block.get("href"), Some("https://a.b/c"));
+    assert_eq!(block.get("n"), Some("3"));
+
+    for bad in ["{}", "cite=\"x\"", "{cite=\"open}", "{=x}", "{.}", "{cite}"] {
+        assert!(
+            parse_attribute_block(bad).is_none(),
+            "{bad} should not parse"
+        );
+    }
+}
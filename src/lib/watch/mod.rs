@@ -0,0 +1,138 @@
+//! File watching for `serve`. Platform backends differ in what they report
+//! for one save (several writes, a rename over the file, a swap file coming
+//! and going) and in whether they work at all (network drives, exhausted
+//! inotify watches). This wraps them so that changes to `WATCH_IGNORE` paths
+//! are dropped, a burst of events becomes one batch of changed paths, and
+//! polling takes over when native notifications are unavailable.
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    time::Duration,
+};
+
+use notify::{Event, EventKind, PollWatcher, RecursiveMode, Watcher};
+
+use crate::{
+    config::{WATCH_DEBOUNCE, WATCH_IGNORE, WATCH_POLL_INTERVAL, WATCH_POLLING},
+    ignore::IgnoreRules,
+};
+
+type Changes = notify::Result<Event>;
+
+/// Watches the files of the site at a root, reporting changes in batches.
+pub struct SiteWatcher {
+    root: PathBuf,
+    backend: Box<dyn Watcher + Send>,
+    polling: bool,
+    sender: Sender<Changes>,
+    changes: Receiver<Changes>,
+    /// Everything watched so far, to carry over to a polling backend.
+    watched: Vec<(PathBuf, RecursiveMode)>,
+    ignore: IgnoreRules,
+}
+
+impl SiteWatcher {
+    /// A watcher for the site at `root`, using the platform's notifications
+    /// unless `WATCH_POLLING` is set or they cannot be had.
+    pub fn new(root: &Path) -> notify::Result<Self> {
+        let (sender, changes) = mpsc::channel();
+        let (backend, polling) = match WATCH_POLLING {
+            true => (polling_backend(&sender)?, true),
+            false => match notify::recommended_watcher(sender.clone()) {
+                Ok(native) => (Box::new(native) as Box<dyn Watcher + Send>, false),
+                Err(e) => {
+                    eprintln!("File notifications unavailable ({e}); polling instead");
+                    (polling_backend(&sender)?, true)
+                }
+            },
+        };
+        Ok(Self {
+            root: root.to_path_buf(),
+            backend,
+            polling,
+            sender,
+            changes,
+            watched: Vec::new(),
+            ignore: IgnoreRules::new(WATCH_IGNORE),
+        })
+    }
+
+    /// Start watching `path`. If the platform's notifications refuse it
+    /// (say, out of watches), everything is watched by polling from then on.
+    pub fn watch(&mut self, path: &Path, mode: RecursiveMode) -> notify::Result<()> {
+        if let Err(e) = self.backend.watch(path, mode) {
+            if self.polling {
+                return Err(e);
+            }
+            eprintln!(
+                "Cannot watch {} for notifications ({e}); polling instead",
+                path.display()
+            );
+            self.backend = polling_backend(&self.sender)?;
+            self.polling = true;
+            for (watched, mode) in &self.watched {
+                self.backend.watch(watched, *mode)?;
+            }
+            self.backend.watch(path, mode)?;
+        }
+        self.watched.push((path.to_path_buf(), mode));
+        Ok(())
+    }
+
+    /// Wait for a change, then for the burst it belongs to to settle, and
+    /// return the paths that changed. `None` once no more changes can come.
+    pub fn next_batch(&self) -> Option<BTreeSet<PathBuf>> {
+        collect_batch(&self.changes, WATCH_DEBOUNCE, |path| {
+            !is_ignored(&self.ignore, &self.root, path)
+        })
+    }
+}
+
+fn polling_backend(sender: &Sender<Changes>) -> notify::Result<Box<dyn Watcher + Send>> {
+    let config = notify::Config::default().with_poll_interval(WATCH_POLL_INTERVAL);
+    Ok(Box::new(PollWatcher::new(sender.clone(), config)?))
+}
+
+/// Block for the first change `keep` accepts, then gather the rest until
+/// none arrives for `settle`. Reads of files are not changes. An event
+/// asking for a rescan, which names no paths, counts as a change to all of
+/// them and is reported as the empty path.
+pub fn collect_batch(
+    changes: &Receiver<Changes>,
+    settle: Duration,
+    keep: impl Fn(&Path) -> bool,
+) -> Option<BTreeSet<PathBuf>> {
+    let mut batch = BTreeSet::new();
+    loop {
+        let received = if batch.is_empty() {
+            changes.recv().ok()?
+        } else {
+            match changes.recv_timeout(settle) {
+                Ok(received) => received,
+                Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => {
+                    return Some(batch);
+                }
+            }
+        };
+        match received {
+            Ok(event) if event.need_rescan() => {
+                batch.insert(PathBuf::new());
+            }
+            Ok(event) if matches!(event.kind, EventKind::Access(_)) => {}
+            Ok(event) => batch.extend(event.paths.into_iter().filter(|path| keep(path))),
+            Err(e) => eprintln!("Watch error: {e}"),
+        }
+    }
+}
+
+/// Whether `path`, or a directory it is in, matches `rules` below `root`.
+pub fn is_ignored(rules: &IgnoreRules, root: &Path, path: &Path) -> bool {
+    let rel = path.strip_prefix(root).unwrap_or(path);
+    rel.ancestors()
+        .filter(|ancestor| !ancestor.as_os_str().is_empty())
+        .any(|ancestor| rules.is_ignored(ancestor, ancestor != rel || path.is_dir()))
+}
+
+#[cfg(test)]
+mod tests;
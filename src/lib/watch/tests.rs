@@ -0,0 +1,71 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+use notify::{
+    Event, EventKind,
+    event::{AccessKind, CreateKind, Flag, ModifyKind},
+};
+
+use crate::{
+    config::WATCH_IGNORE,
+    ignore::IgnoreRules,
+    watch::{collect_batch, is_ignored},
+};
+
+fn event(kind: EventKind, path: &str) -> notify::Result<Event> {
+    Ok(Event::new(kind).add_path(PathBuf::from(path)))
+}
+
+#[test]
+fn editor_litter_and_git_are_ignored() {
+    let rules = IgnoreRules::new(WATCH_IGNORE);
+    let root = Path::new("/site");
+    let ignored = |path: &str| is_ignored(&rules, root, Path::new(path));
+
+    assert!(ignored("/site/contents/.post.md.swp"));
+    assert!(ignored("/site/contents/post.md~"));
+    assert!(ignored("/site/contents/4913"));
+    assert!(ignored("/site/contents/.git/index"));
+    assert!(ignored("/site/contents/post.md.123.4.partial"));
+    assert!(!ignored("/site/contents/post.md"));
+    assert!(!ignored("/site/style.css"));
+}
+
+#[test]
+fn a_burst_of_events_is_one_batch_per_path() {
+    let (sender, changes) = mpsc::channel();
+    let modify = EventKind::Modify(ModifyKind::Any);
+    sender.send(event(modify, "/site/a.md")).unwrap();
+    sender.send(event(modify, "/site/a.md")).unwrap();
+    sender
+        .send(event(EventKind::Access(AccessKind::Any), "/site/read.md"))
+        .unwrap();
+    sender
+        .send(event(
+            EventKind::Create(CreateKind::File),
+            "/site/.a.md.swp",
+        ))
+        .unwrap();
+    sender.send(event(modify, "/site/b.md")).unwrap();
+
+    let batch = collect_batch(&changes, Duration::from_millis(20), |path| {
+        !path.to_string_lossy().ends_with(".swp")
+    })
+    .unwrap();
+
+    assert_eq!(
+        batch.into_iter().collect::<Vec<_>>(),
+        [PathBuf::from("/site/a.md"), PathBuf::from("/site/b.md")]
+    );
+
+    sender
+        .send(Ok(Event::new(EventKind::Other).set_flag(Flag::Rescan)))
+        .unwrap();
+    drop(sender);
+    let batch = collect_batch(&changes, Duration::from_millis(20), |_| true).unwrap();
+    assert!(batch.contains(Path::new("")));
+    assert_eq!(collect_batch(&changes, Duration::ZERO, |_| true), None);
+}
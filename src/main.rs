@@ -1,78 +1,208 @@
-use std::env::{self, current_dir};
+use std::{
+    collections::BTreeSet,
+    env::{self, current_dir},
+    path::{Path, PathBuf},
+    thread,
+    time::SystemTime,
+};
 
 use axum::Router;
-use color_eyre::Section;
-use notify::{EventKind, RecursiveMode, Watcher};
+use color_eyre::{Section, eyre::eyre};
+use notify::RecursiveMode;
 use ssg::{
-    config::{INPUT_DIR, OUTPUT_DIR},
-    pipeline::build_once,
+    cache::{self, CacheLimits},
+    config::{
+        AUTHORS_FILE, BIBLIOGRAPHY, CACHE_DIR, CONTENT_ROOTS, HIGHLIGHT_THEME, OUTPUT_DIR,
+        PROJECTS_DIR, PROJECTS_FILE, SYNTAXES_DIR, TAGS_FILE, TEMPLATES_DIR,
+    },
+    diagnostics::format::ReportFormat,
+    pipeline::{BuildOptions, BuildReport, build_once_with},
+    report::render_report,
+    watch::SiteWatcher,
 };
-use tower_http::services::ServeDir;
+use tower_http::services::{ServeDir, ServeFile};
 use tower_livereload::LiveReloadLayer;
 
 #[tokio::main]
 async fn main() -> color_eyre::Result<()> {
     color_eyre::install()?;
 
-    if env::args().any(|a| a == "serve") {
-        serve().await?;
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = BuildOptions {
+        strict: args.iter().any(|a| a == "--strict"),
+        strict_todos: args.iter().any(|a| a == "--strict-todos"),
+        future: args.iter().any(|a| a == "--future"),
+        progress: args.iter().any(|a| a == "--progress"),
+        report_format: match args.iter().position(|a| a == "--report-format") {
+            Some(i) => args
+                .get(i + 1)
+                .ok_or_else(|| eyre!("--report-format needs a format"))
+                .with_note(|| "Use one of text, github, json, or sarif")?
+                .parse()?,
+            None => ReportFormat::Text,
+        },
+        report_file: match args.iter().position(|a| a == "--report-file") {
+            Some(i) => Some(
+                args.get(i + 1)
+                    .ok_or_else(|| eyre!("--report-file needs a path"))?
+                    .into(),
+            ),
+            None => None,
+        },
+        ..BuildOptions::default()
+    };
+    if args.first().is_some_and(|a| a == "cache") {
+        cache_command(args.get(1).map(String::as_str))?;
+    } else if args.first().is_some_and(|a| a == "report") {
+        if options.report_format != ReportFormat::Text && options.report_file.is_none() {
+            return Err(eyre!(
+                "`report` prints pages to stdout, so diagnostics need a file"
+            ))
+            .with_note(|| "Pass --report-file <path> along with --report-format");
+        }
+        let report = build_once_with(options)?;
+        print!("{}", render_report(&report.pages));
+    } else if args.iter().any(|a| a == "serve") {
+        serve(options).await?;
     } else {
-        build_once()?;
+        build_once_with(options)?;
     }
 
     Ok(())
 }
 
-async fn serve() -> color_eyre::Result<()> {
+/// `ssg cache gc|clear|stats`
+fn cache_command(action: Option<&str>) -> color_eyre::Result<()> {
+    let cache_dir = current_dir()
+        .with_note(|| "While getting the current working directory")?
+        .join(CACHE_DIR);
+
+    match action {
+        Some("gc") => {
+            let report = cache::gc(&cache_dir, CacheLimits::default(), SystemTime::now())?;
+            println!(
+                "Removed {} cache entries, freeing {}",
+                report.removed,
+                cache::format_bytes(report.freed)
+            );
+        }
+        Some("clear") => {
+            cache::clear(&cache_dir)?;
+            println!("Cleared {}", cache_dir.display());
+        }
+        Some("stats") => {
+            let stats = cache::stats(&cache_dir);
+            println!(
+                "{}: {} files, {}",
+                cache_dir.display(),
+                stats.files,
+                cache::format_bytes(stats.bytes)
+            );
+            if let Some(age) = stats
+                .oldest
+                .and_then(|t| SystemTime::now().duration_since(t).ok())
+            {
+                println!("Oldest entry: {} days old", age.as_secs() / (24 * 60 * 60));
+            }
+        }
+        _ => {
+            return Err(color_eyre::eyre::eyre!("usage: ssg cache <gc|clear|stats>"));
+        }
+    }
+
+    Ok(())
+}
+
+async fn serve(options: BuildOptions) -> color_eyre::Result<()> {
     // Initial build
     println!("Building site...");
-    build_once()?;
+    let report = build_once_with(options.clone())?;
 
     let current_dir = current_dir().with_note(|| "While getting the current working directory")?;
     let public_dir = current_dir.join(OUTPUT_DIR);
-    let contents_dir = current_dir.join(INPUT_DIR);
     let css_src = current_dir.join("style.css");
+    let templates_dir = current_dir.join(TEMPLATES_DIR);
+    let projects_file = current_dir.join(PROJECTS_FILE);
+    let projects_dir = current_dir.join(PROJECTS_DIR);
+    let authors_file = current_dir.join(AUTHORS_FILE);
+    let tags_file = current_dir.join(TAGS_FILE);
+    let bibliography = current_dir.join(BIBLIOGRAPHY);
+    let syntaxes_dir = current_dir.join(SYNTAXES_DIR);
+    let theme_file = current_dir.join(HIGHLIGHT_THEME);
 
     // Setup live reload
     let livereload = LiveReloadLayer::new();
     let reloader = livereload.reloader();
 
-    // Setup file watcher
-    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
-        match res {
-            Ok(event) => {
-                // Ignore Access events (triggered when reading files) to
-                // prevent infinite loops
-                if matches!(event.kind, EventKind::Access(_)) {
-                    return;
-                }
+    // Setup file watcher. Changes are handled on their own thread, which owns
+    // the watcher so it can start watching files newly pulled in by includes.
+    let mut watcher = SiteWatcher::new(&current_dir)?;
 
-                println!("Change detected, rebuilding...");
-                // We ignore build errors during watch mode to keep the server
-                // alive
-                if let Err(e) = build_once() {
-                    eprintln!("Build failed: {}", e);
-                } else {
+    // Watch the content roots, the style.css file, any user templates, the
+    // projects data, the author and tag descriptions, the bibliography, and
+    // any extra highlighting syntaxes and theme file
+    for root in CONTENT_ROOTS {
+        watcher.watch(&current_dir.join(root.dir), RecursiveMode::Recursive)?;
+    }
+    if css_src.exists() {
+        watcher.watch(&css_src, RecursiveMode::NonRecursive)?;
+    }
+    if templates_dir.exists() {
+        watcher.watch(&templates_dir, RecursiveMode::Recursive)?;
+    }
+    if projects_file.exists() {
+        watcher.watch(&projects_file, RecursiveMode::NonRecursive)?;
+    }
+    if projects_dir.exists() {
+        watcher.watch(&projects_dir, RecursiveMode::Recursive)?;
+    }
+    if authors_file.exists() {
+        watcher.watch(&authors_file, RecursiveMode::NonRecursive)?;
+    }
+    if tags_file.exists() {
+        watcher.watch(&tags_file, RecursiveMode::NonRecursive)?;
+    }
+    if bibliography.exists() {
+        watcher.watch(&bibliography, RecursiveMode::NonRecursive)?;
+    }
+    if syntaxes_dir.exists() {
+        watcher.watch(&syntaxes_dir, RecursiveMode::Recursive)?;
+    }
+    // Only when `HIGHLIGHT_THEME` names a file rather than a built-in.
+    if theme_file.is_file() {
+        watcher.watch(&theme_file, RecursiveMode::NonRecursive)?;
+    }
+    let mut included = BTreeSet::new();
+    watch_included(&mut watcher, &current_dir, &report, &mut included);
+
+    thread::spawn(move || {
+        while let Some(changed) = watcher.next_batch() {
+            println!(
+                "Change detected in {} file(s), rebuilding...",
+                changed.len()
+            );
+            // We ignore build errors during watch mode to keep the server
+            // alive
+            match build_once_with(options.clone()) {
+                Ok(report) => {
+                    watch_included(&mut watcher, &current_dir, &report, &mut included);
                     println!("Rebuild complete.");
                     reloader.reload();
                 }
+                Err(e) => eprintln!("Build failed: {}", e),
             }
-            Err(e) => eprintln!("Watch error: {}", e),
         }
-    })?;
-
-    // Watch contents directory and the style.css file
-    watcher.watch(&contents_dir, RecursiveMode::Recursive)?;
-    if css_src.exists() {
-        watcher.watch(&css_src, RecursiveMode::NonRecursive)?;
-    }
+    });
 
-    // Setup Axum router
+    // Setup Axum router; missing paths get the site's 404 page, as they
+    // would from a static host
+    let not_found = ServeFile::new(public_dir.join("404.html"));
     let app = Router::new()
         .fallback_service(
             ServeDir::new(public_dir)
                 .precompressed_br()
-                .precompressed_gzip(),
+                .precompressed_gzip()
+                .not_found_service(not_found),
         )
         .layer(livereload);
 
@@ -82,3 +212,27 @@ async fn serve() -> color_eyre::Result<()> {
 
     Ok(())
 }
+
+/// Watch files spliced in by include directives that are not yet watched.
+/// Those inside a content root already are.
+fn watch_included(
+    watcher: &mut SiteWatcher,
+    current_dir: &Path,
+    report: &BuildReport,
+    watched: &mut BTreeSet<PathBuf>,
+) {
+    for path in &report.included {
+        let in_root = CONTENT_ROOTS
+            .iter()
+            .any(|root| path.starts_with(current_dir.join(root.dir)));
+        if in_root || watched.contains(path) {
+            continue;
+        }
+        match watcher.watch(path, RecursiveMode::NonRecursive) {
+            Ok(()) => {
+                watched.insert(path.clone());
+            }
+            Err(e) => eprintln!("Cannot watch {}: {e}", path.display()),
+        }
+    }
+}